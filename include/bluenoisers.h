@@ -0,0 +1,84 @@
+#ifndef BLUENOISERS_H
+#define BLUENOISERS_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// An iterator generating samples on demand, created by [`bn_iter_new`] and
+// freed with [`bn_iter_free`].
+typedef struct BnIterator BnIterator;
+
+// Samples returned by [`bn_generate`], `count` × `dimension` coordinates,
+// sample by sample. Free them with [`bn_points_free`].
+typedef struct BnPoints {
+  // The coordinates, or null if there are no samples.
+  double *coordinates;
+  // The number of samples.
+  size_t count;
+  // The number of coordinates per sample.
+  size_t dimension;
+} BnPoints;
+
+// Generates all samples in a domain of `dimension` axes with the sizes
+// `dimensions`, see `blue_noise` in Rust. The same seed yields the same
+// samples.
+//
+// Gives no samples if `dimensions` is null, a size or `min_distance` is not
+// positive or `k_abort` is zero.
+//
+// # Safety
+//
+// `dimensions` must point to `dimension` values.
+struct BnPoints bn_generate(const double *dimensions,
+                            size_t dimension,
+                            double min_distance,
+                            size_t k_abort,
+                            uint64_t seed);
+
+// Frees samples returned by [`bn_generate`].
+//
+// # Safety
+//
+// `points` must have been returned by [`bn_generate`] and not been freed
+// before.
+void bn_points_free(struct BnPoints points);
+
+// Creates an iterator generating the samples of [`bn_generate`] one by
+// one, in the same order.
+//
+// Returns null for the arguments for which [`bn_generate`] gives no
+// samples.
+//
+// # Safety
+//
+// `dimensions` must point to `dimension` values.
+struct BnIterator *bn_iter_new(const double *dimensions,
+                               size_t dimension,
+                               double min_distance,
+                               size_t k_abort,
+                               uint64_t seed);
+
+// Generates the next sample and writes its coordinates to `out`.
+//
+// Returns false, without writing anything, when all samples have been
+// generated or if `iterator` or `out` is null.
+//
+// # Safety
+//
+// `iterator` must have been returned by [`bn_iter_new`] and not been freed
+// and `out` must have room for as many values as the iterator has
+// dimensions.
+bool bn_iter_next(struct BnIterator *iterator, double *out);
+
+// Frees an iterator created by [`bn_iter_new`]. Null is ignored.
+//
+// # Safety
+//
+// `iterator` must have been returned by [`bn_iter_new`] and not been freed
+// before.
+void bn_iter_free(struct BnIterator *iterator);
+
+#endif  /* BLUENOISERS_H */
@@ -0,0 +1,887 @@
+//! Statistics to verify the blue noise characteristics of point sets.
+//!
+//! The functions take any point sets, not only generated ones, so patterns
+//! from other sources can be compared with the same measures.
+
+use crate::PoissonDiskSet;
+use std::f64::consts::PI;
+
+/// The power spectrum of a 2D point set at integer frequencies, see
+/// [`periodogram`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrum {
+    /// The number of frequencies along each axis.
+    pub size: usize,
+    /// The size of the domain of the points.
+    pub dimensions: [f64; 2],
+    /// The power at each frequency, `size × size` values with the first axis
+    /// changing fastest. The zero frequency, the DC term, is at
+    /// `(size / 2, size / 2)`.
+    pub power: Vec<f64>,
+}
+
+/// Computes the periodogram of the 2D `points` in a domain of size
+/// `dimensions`: for the frequencies `k`, which are integer multiples of one
+/// over the size of the domain along each axis, the power
+///
+/// *|∑ⱼ e^(−2πi k·xⱼ)|² / n*
+///
+/// where *xⱼ* are the *n* points. The frequencies from `-size / 2` to
+/// `(size - 1) / 2` times the base frequency are computed along each axis.
+///
+/// Uncorrelated (white) noise has an expected power of one at all
+/// frequencies except zero. Blue noise has little power at low frequencies,
+/// up to about *1 / `min_distance`*, then a peak and a flat tail.
+///
+/// The Fourier transform is evaluated directly, which takes
+/// *n × `size`²* steps.
+///
+/// ```
+/// use bluenoisers::BlueNoiseConfig;
+/// use bluenoisers::analysis::periodogram;
+///
+/// let samples = BlueNoiseConfig::new(vec![32., 32.], 1.).seed(1).generate();
+/// let spectrum = periodogram(&samples, [32., 32.], 64);
+/// // the DC term is the number of points
+/// let dc = spectrum.power[32 * 64 + 32];
+/// assert!((dc - samples.len() as f64).abs() < 1e-6);
+/// // little power at the lowest frequencies
+/// assert!(spectrum.power[32 * 64 + 33] < 0.5);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a point is not 2-dimensional.
+pub fn periodogram<P: AsRef<[f64]>>(points: &[P], dimensions: [f64; 2], size: usize) -> Spectrum {
+    let first = -(size as isize / 2);
+    // the real and imaginary parts of the sums
+    let mut re = vec![0_f64; size * size];
+    let mut im = vec![0_f64; size * size];
+    let mut wave_x = vec![(0_f64, 0_f64); size];
+    let mut wave_y = vec![(0_f64, 0_f64); size];
+    for point in points {
+        let point = point.as_ref();
+        assert_eq!(point.len(), 2, "the periodogram needs 2-dimensional points");
+        // e^(-2πi k·x) factors into one wave per axis
+        for (waves, (x, dim)) in [&mut wave_x, &mut wave_y]
+            .into_iter()
+            .zip(point.iter().zip(dimensions.iter()))
+        {
+            for (i, wave) in waves.iter_mut().enumerate() {
+                let angle = -2_f64 * PI * (first + i as isize) as f64 * x / dim;
+                *wave = (angle.cos(), angle.sin());
+            }
+        }
+        for (y, (wy_re, wy_im)) in wave_y.iter().enumerate() {
+            let row = y * size;
+            for (x, (wx_re, wx_im)) in wave_x.iter().enumerate() {
+                re[row + x] += wx_re * wy_re - wx_im * wy_im;
+                im[row + x] += wx_re * wy_im + wx_im * wy_re;
+            }
+        }
+    }
+    let n = points.len().max(1) as f64;
+    Spectrum {
+        size,
+        dimensions,
+        power: re
+            .iter()
+            .zip(im.iter())
+            .map(|(re, im)| (re * re + im * im) / n)
+            .collect(),
+    }
+}
+
+impl Spectrum {
+    /// The frequency at the pixel `(x, y)`.
+    pub fn frequency(&self, x: usize, y: usize) -> [f64; 2] {
+        let center = (self.size / 2) as f64;
+        [
+            (x as f64 - center) / self.dimensions[0],
+            (y as f64 - center) / self.dimensions[1],
+        ]
+    }
+
+    /// The power spectrum as an image with values in `[0, 1]`, for display.
+    ///
+    /// The DC term, which only depends on the number of points and would
+    /// outshine everything else, is set to zero, and the rest is divided by
+    /// the highest remaining power.
+    pub fn image(&self) -> Vec<f64> {
+        let mut image = self.power.clone();
+        if let Some(dc) = image.get_mut((self.size / 2) * self.size + self.size / 2) {
+            *dc = 0_f64;
+        }
+        let max = image.iter().copied().fold(0_f64, f64::max);
+        if max > 0_f64 {
+            image.iter_mut().for_each(|p| *p /= max);
+        }
+        image
+    }
+}
+
+impl Spectrum {
+    /// Averages the spectra of several realizations of a pattern, which
+    /// reduces the noise of single periodograms.
+    ///
+    /// ```
+    /// use bluenoisers::BlueNoiseConfig;
+    /// use bluenoisers::analysis::{Spectrum, periodogram};
+    ///
+    /// let spectra: Vec<Spectrum> = (0..4)
+    ///     .map(|seed| {
+    ///         let samples = BlueNoiseConfig::new(vec![16., 16.], 1.).seed(seed).generate();
+    ///         periodogram(&samples, [16., 16.], 32)
+    ///     })
+    ///     .collect();
+    /// let profile = Spectrum::average(&spectra).radial(1. / 16.);
+    /// // the peak is near the inverse of the minimal distance
+    /// let peak = (0..profile.power.len())
+    ///     .max_by(|a, b| profile.power[*a].total_cmp(&profile.power[*b]))
+    ///     .unwrap();
+    /// assert!(profile.frequency[peak] > 0.5);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no spectra or if they have different sizes or
+    /// domains.
+    pub fn average(spectra: &[Spectrum]) -> Spectrum {
+        let first = spectra.first().expect("no spectra to average");
+        let mut power = vec![0_f64; first.power.len()];
+        for spectrum in spectra {
+            assert!(
+                spectrum.size == first.size && spectrum.dimensions == first.dimensions,
+                "the spectra must have the same size and domain"
+            );
+            power
+                .iter_mut()
+                .zip(spectrum.power.iter())
+                .for_each(|(sum, p)| *sum += p);
+        }
+        power.iter_mut().for_each(|p| *p /= spectra.len() as f64);
+        Spectrum {
+            size: first.size,
+            dimensions: first.dimensions,
+            power,
+        }
+    }
+
+    /// Averages the power over annuli of frequencies, which gives the
+    /// radially averaged power spectrum and the anisotropy of Ulichney.
+    ///
+    /// The annuli are `bin_width` wide, in cycles per unit of the domain,
+    /// and reach up to the highest frequency computed along both axes. The
+    /// DC term and annuli without frequencies are left out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bin_width` is not positive.
+    pub fn radial(&self, bin_width: f64) -> RadialProfile {
+        assert!(bin_width > 0_f64, "the bin width must be positive");
+        let half = (self.size / 2) as f64;
+        let max = (half / self.dimensions[0]).min(half / self.dimensions[1]);
+        let bins = (max / bin_width).ceil() as usize;
+        let mut sums = vec![(0_usize, 0_f64, 0_f64); bins];
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let [u, v] = self.frequency(x, y);
+                let f = (u * u + v * v).sqrt();
+                if f == 0_f64 || f >= max {
+                    continue;
+                }
+                let p = self.power[y * self.size + x];
+                let bin = &mut sums[((f / bin_width) as usize).min(bins - 1)];
+                bin.0 += 1;
+                bin.1 += p;
+                bin.2 += p * p;
+            }
+        }
+        let mut profile = RadialProfile {
+            frequency: Vec::new(),
+            power: Vec::new(),
+            anisotropy: Vec::new(),
+        };
+        for (i, (count, sum, sum_sqr)) in sums.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let mean = sum / count as f64;
+            let variance = if count > 1 {
+                (sum_sqr - sum * mean) / (count - 1) as f64
+            } else {
+                0_f64
+            };
+            profile.frequency.push((i as f64 + 0.5) * bin_width);
+            profile.power.push(mean);
+            profile.anisotropy.push(variance.max(0_f64) / (mean * mean));
+        }
+        profile
+    }
+}
+
+/// A [`Spectrum`] averaged over annuli of frequencies, see
+/// [`Spectrum::radial`]. The vectors have one entry per annulus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialProfile {
+    /// The middle frequency of each annulus.
+    pub frequency: Vec<f64>,
+    /// The mean power in each annulus.
+    pub power: Vec<f64>,
+    /// The variance of the power in each annulus divided by the square of
+    /// its mean. Isotropic patterns are close to the value of white noise,
+    /// which is about one; it is usually plotted in decibels,
+    /// *10 log₁₀(anisotropy)*.
+    pub anisotropy: Vec<f64>,
+}
+
+/// How close the spectrum of a 2D point set comes to ideal blue noise, see
+/// [`quality`]. The frequencies are relative to *f₀ = √(n / area)*, the
+/// frequency of a pattern with `n` points at one point per unit cell.
+///
+/// Ideal blue noise has no power up to some frequency and a flat spectrum of
+/// power one above it, like white noise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quality {
+    /// The highest frequency below which the radially averaged power stays
+    /// below one half, relative to *f₀*. Higher means less low frequency
+    /// noise; Poisson disk sets reach about 0.7, white noise 0.
+    ///
+    /// Heck et al. use a threshold of 0.1 on spectra averaged over many
+    /// periodic realizations; single sets in a box have too much noise and
+    /// leakage from the borders at low frequencies for that.
+    pub effective_nyquist: f64,
+    /// The mean radially averaged power below *f₀ / 2*, which should be
+    /// close to zero. It is about one for white noise.
+    pub low_frequency_energy: f64,
+    /// The highest radially averaged power. Peaks much above one cause
+    /// aliasing which shows as regular structure.
+    pub peak: f64,
+}
+
+impl Quality {
+    /// Combines the measures into one number, higher is better:
+    /// [`effective_nyquist`](Quality::effective_nyquist) divided by the
+    /// excess [`peak`](Quality::peak), which trades low frequency noise off
+    /// against aliasing like Heck et al.
+    pub fn score(&self) -> f64 {
+        self.effective_nyquist / self.peak.max(1_f64)
+    }
+}
+
+/// Measures the [`Quality`] of the 2D `points` in a domain of size
+/// `dimensions`, e.g. to pick the best of several seeds.
+///
+/// This computes the [`periodogram`] up to *2.5 f₀*, which takes about
+/// *25 n²* steps, so it is meant for sets of up to some ten thousand points.
+/// Without points, there is no *f₀* to measure against and all the measures
+/// are NaN.
+///
+/// ```
+/// use bluenoisers::BlueNoiseConfig;
+/// use bluenoisers::analysis::quality;
+///
+/// let best = (0..4)
+///     .map(|seed| BlueNoiseConfig::new(vec![20., 20.], 1.).seed(seed).generate())
+///     .max_by(|a, b| {
+///         let score = |samples: &Vec<_>| quality(samples, [20., 20.]).score();
+///         score(a).total_cmp(&score(b))
+///     })
+///     .unwrap();
+/// assert!(quality(&best, [20., 20.]).effective_nyquist > 0.3);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a point is not 2-dimensional.
+pub fn quality<P: AsRef<[f64]>>(points: &[P], dimensions: [f64; 2]) -> Quality {
+    if points.is_empty() {
+        return Quality {
+            effective_nyquist: f64::NAN,
+            low_frequency_energy: f64::NAN,
+            peak: f64::NAN,
+        };
+    }
+    let f0 = (points.len() as f64 / (dimensions[0] * dimensions[1])).sqrt();
+    // frequencies up to 2.5 f0 along the longer axis
+    let size = 2 * (2.5 * f0 * dimensions[0].max(dimensions[1])).ceil() as usize + 1;
+    let profile = periodogram(points, dimensions, size).radial(f0 / 8_f64);
+    let effective_nyquist = profile
+        .frequency
+        .iter()
+        .zip(profile.power.iter())
+        .find(|(_, power)| **power >= 0.5)
+        .map_or(f64::INFINITY, |(f, _)| f - f0 / 16_f64)
+        / f0;
+    let low: Vec<f64> = profile
+        .frequency
+        .iter()
+        .zip(profile.power.iter())
+        .filter(|(f, _)| **f < f0 / 2_f64)
+        .map(|(_, power)| *power)
+        .collect();
+    Quality {
+        effective_nyquist: effective_nyquist.max(0_f64),
+        low_frequency_energy: low.iter().sum::<f64>() / low.len().max(1) as f64,
+        peak: profile.power.iter().copied().fold(0_f64, f64::max),
+    }
+}
+
+/// How [`rdf`] accounts for the pairs of points which are cut off by the
+/// borders of the domain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeCorrection {
+    /// No correction, so *g(r)* drops with growing *r* as more of the shells
+    /// around points near the borders are outside.
+    None,
+    /// Ohser's translation correction: each pair is weighted by the inverse
+    /// volume of the part of the domain which still contains the pair when
+    /// translated. Unbiased for any domain.
+    Translation,
+    /// The domain wraps around, as for tileable sets: distances are measured
+    /// to the nearest periodic copy of each point. `max_distance` must be at
+    /// most half the size of the domain.
+    Periodic,
+}
+
+/// The radial distribution function of a point set, see [`rdf`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialDistribution {
+    /// The middle distance of each bin.
+    pub radius: Vec<f64>,
+    /// The value of *g(r)* in each bin.
+    pub g: Vec<f64>,
+}
+
+/// Computes the radial distribution function, or pair correlation function,
+/// *g(r)* of `points` in a domain of size `dimensions`, in bins of
+/// `bin_width` up to `max_distance`.
+///
+/// *g(r)* is the density of points at distance *r* from a point, relative to
+/// the average density, so it is one for uncorrelated points. Poisson disk
+/// sets have *g(r) = 0* below the minimal distance and a peak right above it.
+///
+/// Only the pairs of points closer than `max_distance` are visited, using a
+/// grid of cells of that size, so small distances are fast to compute even
+/// for large sets.
+///
+/// ```
+/// use bluenoisers::analysis::{EdgeCorrection, rdf};
+///
+/// let samples = bluenoisers::blue_noise(vec![30., 30.], 1., 30);
+/// let g = rdf(&samples, &[30., 30.], 0.1, 5., EdgeCorrection::Translation);
+/// assert_eq!(g.g.len(), 50);
+/// assert!(g.g[..10].iter().all(|g| *g == 0.));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bin_width` or `max_distance` are not positive, if a point
+/// doesn't have one coordinate per dimension, or if `max_distance` is more
+/// than half the size of the domain with [`EdgeCorrection::Periodic`].
+pub fn rdf<P: AsRef<[f64]>>(
+    points: &[P],
+    dimensions: &[f64],
+    bin_width: f64,
+    max_distance: f64,
+    correction: EdgeCorrection,
+) -> RadialDistribution {
+    assert!(
+        bin_width > 0_f64 && max_distance > 0_f64,
+        "the bin width and the maximal distance must be positive"
+    );
+    let periodic = correction == EdgeCorrection::Periodic;
+    assert!(
+        !periodic || dimensions.iter().all(|x| 2_f64 * max_distance <= *x),
+        "periodic distances are only unique up to half the domain"
+    );
+    let bins = (max_distance / bin_width).ceil() as usize;
+    let volume: f64 = dimensions.iter().product();
+    // cells at least `max_distance` wide, so that all close pairs are in
+    // neighboring cells
+    let cell_count: Vec<usize> = dimensions
+        .iter()
+        .map(|x| ((x / max_distance).floor() as usize).max(1))
+        .collect();
+    let cell_of = |point: &[f64]| -> Vec<usize> {
+        point
+            .iter()
+            .zip(dimensions.iter().zip(cell_count.iter()))
+            .map(|(x, (dim, count))| ((x / dim * *count as f64).max(0_f64) as usize).min(count - 1))
+            .collect()
+    };
+    let linear = |cell: &[usize]| {
+        cell.iter()
+            .zip(cell_count.iter())
+            .rev()
+            .fold(0, |accu, (c, count)| accu * count + c)
+    };
+    let mut cells: Vec<Vec<usize>> = vec![Vec::new(); cell_count.iter().product()];
+    for (i, point) in points.iter().enumerate() {
+        let point = point.as_ref();
+        assert_eq!(
+            point.len(),
+            dimensions.len(),
+            "the points must have one coordinate per dimension"
+        );
+        cells[linear(&cell_of(point))].push(i);
+    }
+    let mut weights = vec![0_f64; bins];
+    let mut neighbors: Vec<usize> = Vec::new();
+    for (i, point) in points.iter().enumerate() {
+        let point = point.as_ref();
+        let center = cell_of(point);
+        // the distinct cells around the one of the point
+        neighbors.clear();
+        let mut offset = vec![-1_isize; center.len()];
+        loop {
+            let cell: Option<Vec<usize>> = center
+                .iter()
+                .zip(offset.iter().zip(cell_count.iter()))
+                .map(|(c, (o, count))| {
+                    let c = *c as isize + o;
+                    let count = *count as isize;
+                    if periodic {
+                        Some(c.rem_euclid(count) as usize)
+                    } else {
+                        (0..count).contains(&c).then_some(c as usize)
+                    }
+                })
+                .collect();
+            if let Some(cell) = cell {
+                neighbors.push(linear(&cell));
+            }
+            // iterate offsets
+            let mut done = true;
+            for o in offset.iter_mut() {
+                if *o == 1 {
+                    *o = -1;
+                } else {
+                    *o += 1;
+                    done = false;
+                    break;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        for j in neighbors.iter().flat_map(|cell| cells[*cell].iter()) {
+            if *j == i {
+                continue;
+            }
+            let mut dst_sqr = 0_f64;
+            let mut overlap = 1_f64;
+            for ((x, y), dim) in point.iter().zip(points[*j].as_ref()).zip(dimensions) {
+                let mut d = (x - y).abs();
+                if periodic {
+                    d = d.min(dim - d);
+                }
+                dst_sqr += d * d;
+                overlap *= dim - d;
+            }
+            let distance = dst_sqr.sqrt();
+            if distance >= max_distance {
+                continue;
+            }
+            let bin = ((distance / bin_width) as usize).min(bins - 1);
+            weights[bin] += match correction {
+                EdgeCorrection::None | EdgeCorrection::Periodic => 1_f64 / volume,
+                EdgeCorrection::Translation => 1_f64 / overlap,
+            };
+        }
+    }
+    // the volume of the unit ball
+    let dimension = dimensions.len();
+    let mut unit_ball = [1_f64, 2_f64];
+    for d in 2..=dimension {
+        unit_ball[d % 2] *= 2_f64 * PI / d as f64;
+    }
+    let unit_ball = unit_ball[dimension % 2];
+    let n = points.len() as f64;
+    let pairs = (n * (n - 1_f64)).max(1_f64);
+    let (radius, g) = weights
+        .iter()
+        .enumerate()
+        .map(|(bin, weight)| {
+            let (inner, outer) = (
+                bin as f64 * bin_width,
+                ((bin + 1) as f64 * bin_width).min(max_distance),
+            );
+            let shell = unit_ball * (outer.powi(dimension as i32) - inner.powi(dimension as i32));
+            (
+                (inner + outer) / 2_f64,
+                volume * volume / pairs * weight / shell,
+            )
+        })
+        .unzip();
+    RadialDistribution { radius, g }
+}
+
+/// A summary of the spacing of the samples of a set, see
+/// [`PoissonDiskSet::spacing_stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpacingStats {
+    /// The number of samples.
+    pub count: usize,
+    /// The number of samples per unit of volume.
+    pub density: f64,
+    /// The smallest distance of a sample to its nearest neighbor, at least
+    /// the minimal distance for valid sets.
+    pub min_nearest: f64,
+    /// The mean distance of the samples to their nearest neighbors.
+    pub mean_nearest: f64,
+    /// The largest distance of a sample to its nearest neighbor.
+    pub max_nearest: f64,
+    /// The radius of the largest empty ball, see
+    /// [`PoissonDiskSet::largest_empty_ball`].
+    pub coverage_radius: f64,
+}
+
+impl PoissonDiskSet {
+    /// Summarizes the spacing of the samples, e.g. to track the quality of
+    /// generated assets over time.
+    ///
+    /// The nearest neighbors are found with the background grid. The
+    /// distances are infinite if there are less than two samples, the
+    /// coverage radius if there are none.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![30., 30.], 1., 30);
+    /// let stats = set.spacing_stats();
+    /// assert_eq!(stats.count, set.len());
+    /// assert!(stats.min_nearest >= 1.);
+    /// assert!(stats.max_nearest < 2.);
+    /// println!("{stats:?}");
+    /// ```
+    pub fn spacing_stats(&self) -> SpacingStats {
+        let nearest: Vec<f64> = self
+            .samples()
+            .iter()
+            .map(|sample| {
+                self.knn(sample, 2)
+                    .get(1)
+                    .map_or(f64::INFINITY, |(_, d)| *d)
+            })
+            .collect();
+        let volume: f64 = self.dimensions().iter().product();
+        let (min_nearest, max_nearest) = nearest
+            .iter()
+            .fold((f64::INFINITY, 0_f64), |(min, max), d| {
+                (min.min(*d), max.max(*d))
+            });
+        SpacingStats {
+            count: self.len(),
+            density: self.len() as f64 / volume,
+            min_nearest,
+            mean_nearest: if nearest.is_empty() {
+                f64::INFINITY
+            } else {
+                nearest.iter().sum::<f64>() / nearest.len() as f64
+            },
+            max_nearest: if nearest.is_empty() {
+                f64::INFINITY
+            } else {
+                max_nearest
+            },
+            coverage_radius: self
+                .largest_empty_ball()
+                .map_or(f64::INFINITY, |(_, radius)| radius),
+        }
+    }
+}
+
+/// Scales `points` into the unit cube, for the discrepancies.
+fn unit_coordinates<P: AsRef<[f64]>>(points: &[P], dimensions: &[f64]) -> Vec<Vec<f64>> {
+    points
+        .iter()
+        .map(|point| {
+            let point = point.as_ref();
+            assert_eq!(
+                point.len(),
+                dimensions.len(),
+                "the points must have one coordinate per dimension"
+            );
+            point
+                .iter()
+                .zip(dimensions)
+                .map(|(x, dim)| x / dim)
+                .collect()
+        })
+        .collect()
+}
+
+/// Computes the L2 star discrepancy of `points` in a domain of size
+/// `dimensions`: the root mean square, over all boxes with one corner at the
+/// origin, of the difference between the volume of the box and the fraction
+/// of points inside, with the domain scaled to the unit cube.
+///
+/// Lower is more uniform; for `n` random points it is about
+/// *√((2⁻ᵈ − 3⁻ᵈ) / n)*, while low discrepancy sequences get close to
+/// *log(n)ᵈ / n*. Uses Warnock's formula, which takes *n² d* steps.
+///
+/// ```
+/// use bluenoisers::analysis::l2_star_discrepancy;
+///
+/// let samples = bluenoisers::blue_noise(vec![20., 20.], 1., 30);
+/// let random = ((0.25_f64 - 1. / 9.) / samples.len() as f64).sqrt();
+/// assert!(l2_star_discrepancy(&samples, &[20., 20.]) < random);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a point doesn't have one coordinate per dimension.
+pub fn l2_star_discrepancy<P: AsRef<[f64]>>(points: &[P], dimensions: &[f64]) -> f64 {
+    let points = unit_coordinates(points, dimensions);
+    let n = points.len() as f64;
+    let d = dimensions.len() as i32;
+    if points.is_empty() {
+        return 3_f64.powi(-d).sqrt();
+    }
+    let single: f64 = points
+        .iter()
+        .map(|x| x.iter().map(|x| 1_f64 - x * x).product::<f64>())
+        .sum();
+    let pairs: f64 = points
+        .iter()
+        .map(|x| {
+            points
+                .iter()
+                .map(|y| {
+                    x.iter()
+                        .zip(y)
+                        .map(|(x, y)| 1_f64 - x.max(*y))
+                        .product::<f64>()
+                })
+                .sum::<f64>()
+        })
+        .sum();
+    let squared = 3_f64.powi(-d) - 2_f64.powi(1 - d) / n * single + pairs / (n * n);
+    squared.max(0_f64).sqrt()
+}
+
+/// Estimates the star discrepancy of `points` in a domain of size
+/// `dimensions`: the largest difference, over all boxes with one corner at
+/// the origin, between the volume of the box and the fraction of points
+/// inside, with the domain scaled to the unit cube.
+///
+/// Finding the worst box exactly is NP-hard in the dimension, so this only
+/// checks the boxes whose far corner is at a point, or at the far corner of
+/// the domain, with and without the points on their border. That gives a
+/// lower bound which is exact in 1D and takes *n² d* steps.
+///
+/// ```
+/// use bluenoisers::analysis::star_discrepancy;
+///
+/// // the points of a regular grid are at most half a cell off
+/// let points: Vec<[f64; 1]> = (0..10).map(|i| [i as f64 + 0.5]).collect();
+/// assert!((star_discrepancy(&points, &[10.]) - 0.05).abs() < 1e-12);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a point doesn't have one coordinate per dimension.
+pub fn star_discrepancy<P: AsRef<[f64]>>(points: &[P], dimensions: &[f64]) -> f64 {
+    let points = unit_coordinates(points, dimensions);
+    let n = points.len().max(1) as f64;
+    let far = vec![1_f64; dimensions.len()];
+    points
+        .iter()
+        .chain(std::iter::once(&far))
+        .map(|corner| {
+            let volume: f64 = corner.iter().product();
+            let (mut open, mut closed) = (0_usize, 0_usize);
+            for point in points.iter() {
+                if point.iter().zip(corner).all(|(x, c)| x <= c) {
+                    closed += 1;
+                    if point.iter().zip(corner).all(|(x, c)| x < c) {
+                        open += 1;
+                    }
+                }
+            }
+            (closed as f64 / n - volume).max(volume - open as f64 / n)
+        })
+        .fold(0_f64, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlueNoiseConfig;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn periodogram_of_white_and_blue_noise() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let white: Vec<[f64; 2]> = (0..1000)
+            .map(|_| [rng.gen_range(0_f64..30.), rng.gen_range(0_f64..30.)])
+            .collect();
+        let white = periodogram(&white, [30., 30.], 32);
+        let mean = |spectrum: &Spectrum, low: bool| {
+            let (mut sum, mut count) = (0_f64, 0);
+            for y in 0..spectrum.size {
+                for x in 0..spectrum.size {
+                    let [u, v] = spectrum.frequency(x, y);
+                    let f = (u * u + v * v).sqrt();
+                    if f > 0. && (f < 0.3) == low {
+                        sum += spectrum.power[y * spectrum.size + x];
+                        count += 1;
+                    }
+                }
+            }
+            sum / count as f64
+        };
+        assert!((mean(&white, true) - 1.).abs() < 0.5);
+        let blue = BlueNoiseConfig::new(vec![30., 30.], 1.).seed(1).generate();
+        let blue = periodogram(&blue, [30., 30.], 32);
+        assert!(mean(&blue, true) < 0.2);
+
+        // symmetric, since the points are real
+        let k = |x: usize, y: usize| blue.power[y * 32 + x];
+        assert!((k(20, 7) - k(32 - 20, 32 - 7)).abs() < 1e-6);
+        let image = blue.image();
+        assert_eq!(image[16 * 32 + 16], 0.);
+        assert_eq!(image.iter().copied().fold(0., f64::max), 1.);
+    }
+
+    #[test]
+    fn radial_distribution() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let white: Vec<[f64; 2]> = (0..2000)
+            .map(|_| [rng.gen_range(0_f64..40.), rng.gen_range(0_f64..20.)])
+            .collect();
+        let mean = |g: &[f64]| g.iter().sum::<f64>() / g.len() as f64;
+        for correction in [EdgeCorrection::Translation, EdgeCorrection::Periodic] {
+            let g = rdf(&white, &[40., 20.], 0.5, 8., correction);
+            assert_eq!(g.radius.len(), 16);
+            assert_eq!(g.radius[3], 1.75);
+            assert!((mean(&g.g[2..]) - 1.).abs() < 0.05);
+        }
+        // without a correction, the points near the borders miss neighbors
+        let g = rdf(&white, &[40., 20.], 0.5, 8., EdgeCorrection::None);
+        assert!(g.g[15] < 0.8);
+
+        let blue = BlueNoiseConfig::new(vec![40., 40.], 1.).seed(2).generate();
+        let g = rdf(&blue, &[40., 40.], 0.25, 4., EdgeCorrection::Translation);
+        assert!(g.g[..4].iter().all(|g| *g == 0.));
+        assert!(g.g[4] > 1.2);
+        assert!((mean(&g.g[10..]) - 1.).abs() < 0.1);
+    }
+
+    #[test]
+    fn discrepancy() {
+        // a single point in the middle of the unit square
+        let middle = [[0.5, 0.5]];
+        let expected: f64 = 1. / 9. - 0.5 * 0.75 * 0.75 + 0.25;
+        assert!((l2_star_discrepancy(&middle, &[1., 1.]) - expected.sqrt()).abs() < 1e-12);
+        // the box up to the point holds it but has a volume of a quarter
+        assert_eq!(star_discrepancy(&middle, &[1., 1.]), 0.75);
+
+        let blue = BlueNoiseConfig::new(vec![20., 20.], 1.).seed(3).generate();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let random: Vec<[f64; 2]> = (0..blue.len())
+            .map(|_| [rng.gen_range(0_f64..20.), rng.gen_range(0_f64..20.)])
+            .collect();
+        assert!(
+            l2_star_discrepancy(&blue, &[20., 20.]) < l2_star_discrepancy(&random, &[20., 20.])
+        );
+        assert!(star_discrepancy(&blue, &[20., 20.]) < star_discrepancy(&random, &[20., 20.]));
+        // scaling the domain doesn't matter
+        let scaled: Vec<Vec<f64>> = blue.iter().map(|p| vec![p[0] / 20., p[1] * 3.]).collect();
+        assert!(
+            (star_discrepancy(&scaled, &[1., 60.]) - star_discrepancy(&blue, &[20., 20.])).abs()
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn spacing_stats() {
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let stats = set.spacing_stats();
+        let nearest: Vec<f64> = set
+            .samples()
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                set.samples()
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| i != *j)
+                    .map(|(_, b)| crate::grid::BackgroundGrid::dst_sqr(a, b).sqrt())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+        assert_eq!(stats.count, set.len());
+        assert_eq!(stats.density, set.len() as f64 / 200.);
+        assert_eq!(
+            stats.min_nearest,
+            nearest.iter().copied().fold(f64::INFINITY, f64::min)
+        );
+        assert_eq!(
+            stats.max_nearest,
+            nearest.iter().copied().fold(0., f64::max)
+        );
+        assert!(
+            (stats.mean_nearest - nearest.iter().sum::<f64>() / nearest.len() as f64).abs() < 1e-12
+        );
+        assert_eq!(stats.coverage_radius, set.largest_empty_ball().unwrap().1);
+
+        let single = PoissonDiskSet::new(vec![1., 1.], 5., 30).spacing_stats();
+        assert_eq!(single.count, 1);
+        assert_eq!(single.min_nearest, f64::INFINITY);
+        assert_eq!(single.mean_nearest, f64::INFINITY);
+        assert!(single.coverage_radius.is_finite());
+    }
+
+    #[test]
+    fn quality_of_white_and_blue_noise() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let white: Vec<[f64; 2]> = (0..300)
+            .map(|_| [rng.gen_range(0_f64..20.), rng.gen_range(0_f64..20.)])
+            .collect();
+        let white = quality(&white, [20., 20.]);
+        assert!(white.effective_nyquist < 0.15);
+        assert!((white.low_frequency_energy - 1.).abs() < 0.4);
+        let blue = BlueNoiseConfig::new(vec![20., 20.], 1.).seed(4).generate();
+        let blue = quality(&blue, [20., 20.]);
+        assert!(blue.effective_nyquist > 0.4);
+        assert!(blue.low_frequency_energy < 0.3);
+        assert!(blue.peak > 1.);
+        assert!(blue.score() > white.score());
+        let empty = quality(&[] as &[[f64; 2]], [20., 20.]);
+        assert!(empty.score().is_nan());
+    }
+
+    #[test]
+    fn radial_profile() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let spectra: Vec<Spectrum> = (0..8)
+            .map(|_| {
+                let white: Vec<[f64; 2]> = (0..500)
+                    .map(|_| [rng.gen_range(0_f64..20.), rng.gen_range(0_f64..20.)])
+                    .collect();
+                periodogram(&white, [20., 20.], 32)
+            })
+            .collect();
+        let average = Spectrum::average(&spectra);
+        assert_eq!(
+            average.power[5],
+            spectra.iter().map(|s| s.power[5]).sum::<f64>() / 8.
+        );
+        let profile = average.radial(0.1);
+        assert_eq!(profile.frequency.len(), profile.power.len());
+        assert_eq!(profile.frequency.len(), profile.anisotropy.len());
+        assert!(profile.frequency.iter().all(|f| *f < 0.8));
+        // flat, and averaging 8 realizations reduces the anisotropy of white
+        // noise to about one eighth
+        for (power, anisotropy) in profile.power.iter().zip(profile.anisotropy.iter()).skip(1) {
+            assert!((power - 1.).abs() < 0.3);
+            assert!(*anisotropy < 0.5);
+        }
+    }
+}
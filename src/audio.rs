@@ -0,0 +1,129 @@
+//! One-dimensional blue noise at audio rates.
+
+use crate::BlueNoiseConfig;
+
+/// Events in time with blue noise spacing, at least a minimal interval
+/// apart, for dithering and noise shaping experiments.
+///
+/// The events can be had as times in seconds or rendered as a train of unit
+/// impulses at a sample rate. The spectrum of the impulse train has little
+/// power below the frequency `1 / min_interval`, which is what makes it
+/// blue.
+///
+/// ```
+/// use bluenoisers::AudioNoise;
+///
+/// // events at least 20 frames apart at 48 kHz
+/// let noise = AudioNoise::new(48_000., 20. / 48_000.).seed(5);
+/// let buffer = noise.render(4800);
+/// let times: Vec<f64> = noise.times(0.1).collect();
+/// assert_eq!(buffer.iter().filter(|x| **x == 1.).count(), times.len());
+/// assert!(times.windows(2).all(|t| t[1] - t[0] >= 20. / 48_000.));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioNoise {
+    sample_rate: f64,
+    min_interval: f64,
+    seed: Option<u64>,
+}
+
+impl AudioNoise {
+    /// Creates events at least `min_interval` seconds apart, rendered at
+    /// `sample_rate` frames per second. Without a seed, the random number
+    /// generator is seeded from the operating system.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sample rate is not positive or the minimal interval is
+    /// shorter than one frame.
+    pub fn new(sample_rate: f64, min_interval: f64) -> AudioNoise {
+        assert!(sample_rate > 0., "the sample rate must be positive");
+        assert!(
+            min_interval * sample_rate >= 1.,
+            "the minimal interval must be at least one frame"
+        );
+        AudioNoise {
+            sample_rate,
+            min_interval,
+            seed: None,
+        }
+    }
+
+    /// Makes the events reproducible: the same seed yields the same events.
+    pub fn seed(mut self, seed: u64) -> AudioNoise {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The number of frames per second.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The minimal interval between two events, in seconds.
+    pub fn min_interval(&self) -> f64 {
+        self.min_interval
+    }
+
+    /// Generates the times of the events in the first `duration` seconds, in
+    /// ascending order.
+    pub fn times(&self, duration: f64) -> impl Iterator<Item = f64> + use<> {
+        let mut config = BlueNoiseConfig::new(vec![duration], self.min_interval);
+        if let Some(seed) = self.seed {
+            config = config.seed(seed);
+        }
+        let mut times: Vec<f64> = config.generate().iter().map(|sample| sample[0]).collect();
+        times.sort_by(f64::total_cmp);
+        times.into_iter()
+    }
+
+    /// Renders the events of the first `frames` frames as unit impulses at
+    /// the frames they fall in, with all other frames zero.
+    ///
+    /// Since the minimal interval is at least a frame, every event gets its
+    /// own frame. For dithering, subtract the mean to remove the DC term and
+    /// scale the result to the step size of the quantizer.
+    pub fn render(&self, frames: usize) -> Vec<f32> {
+        let mut buffer = vec![0_f32; frames];
+        if frames == 0 {
+            return buffer;
+        }
+        for time in self.times(frames as f64 / self.sample_rate) {
+            // the time is less than the duration, but may be rounded up to
+            // it when converted to frames
+            let frame = ((time * self.sample_rate) as usize).min(frames - 1);
+            buffer[frame] = 1.;
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let noise = AudioNoise::new(1000., 0.01).seed(3);
+        let times: Vec<f64> = noise.times(2.).collect();
+        assert_eq!(times, noise.times(2.).collect::<Vec<_>>());
+        assert!(times.windows(2).all(|t| t[1] - t[0] >= 0.01));
+        // the events cover the whole duration, in both directions from the
+        // first one
+        assert!(times[0] < 0.02 && times[times.len() - 1] > 1.98);
+        let mean = 2. / times.len() as f64;
+        assert!((0.01..0.02).contains(&mean));
+
+        let buffer = noise.render(2000);
+        assert_eq!(buffer.iter().filter(|x| **x == 1.).count(), times.len());
+        assert!(buffer.iter().all(|x| *x == 0. || *x == 1.));
+        assert_eq!(buffer[(times[7] * 1000.) as usize], 1.);
+        assert!(noise.render(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn interval_below_frame() {
+        AudioNoise::new(44_100., 1. / 48_000.);
+    }
+}
@@ -0,0 +1,422 @@
+//! The `analyze` subcommand.
+
+use crate::Result;
+use bluenoisers::analysis::{self, EdgeCorrection, Quality, RadialDistribution, SpacingStats};
+use bluenoisers::{Point, PoissonDiskSet, Violation};
+use clap::ValueEnum;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The RDF is computed up to this many times the radius.
+const RDF_RANGE: f64 = 5.;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The points to analyze, as CSV, PLY or JSON written by `generate`.
+    input: PathBuf,
+    /// The input format. Defaults to the extension of the input file.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+    /// The size of the domain along each axis, e.g. `64,64`. Taken from JSON
+    /// input if not given.
+    #[arg(short, long, value_delimiter = ',')]
+    dimensions: Option<Vec<f64>>,
+    /// The minimal distance the points should keep, to count the violations.
+    /// Taken from JSON input if not given, otherwise estimated from the
+    /// spacing.
+    #[arg(short, long)]
+    radius: Option<f64>,
+    /// Treats the domain as periodic for the radial distribution function,
+    /// which is the default for JSON input that wraps around all axes.
+    #[arg(long)]
+    periodic: bool,
+    /// The file to write the JSON report to. Without, it is written to
+    /// standard output.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Plots the 2D points, as PNG or SVG by the extension.
+    #[arg(long, value_name = "PATH")]
+    plot_points: Option<PathBuf>,
+    /// Plots the power spectrum of 2D points.
+    #[arg(long, value_name = "PATH")]
+    plot_spectrum: Option<PathBuf>,
+    /// The number of frequencies along each axis of the plotted spectrum.
+    #[arg(long, default_value_t = 128)]
+    spectrum_size: usize,
+    /// Plots the radial distribution function.
+    #[arg(long, value_name = "PATH")]
+    plot_rdf: Option<PathBuf>,
+    /// Fails if points are outside of the domain or closer than the radius,
+    /// after writing the report.
+    #[arg(long)]
+    strict: bool,
+    /// Fails if the spectral quality score of 2D points is below this,
+    /// after writing the report.
+    #[arg(long, value_name = "SCORE")]
+    min_score: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Csv,
+    Ply,
+    Json,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+}
+
+/// Everything which goes into the report.
+struct Report {
+    dimensions: Vec<f64>,
+    points: usize,
+    radius: f64,
+    outside: usize,
+    too_close: usize,
+    duplicates: usize,
+    spacing: SpacingStats,
+    quality: Option<Quality>,
+    correction: EdgeCorrection,
+    rdf: RadialDistribution,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| Format::of(&args.input))
+        .ok_or("unknown input format, use --format")?;
+    let reader = BufReader::new(File::open(&args.input)?);
+    let (config, points) = match format {
+        Format::Csv => (None, bluenoisers::read_csv(reader)?),
+        Format::Ply => (None, bluenoisers::read_ply(reader)?),
+        Format::Json => {
+            let (config, points) = bluenoisers::read_json(reader)?;
+            (Some(config), points)
+        }
+    };
+    let dimensions = match (&args.dimensions, &config) {
+        (Some(dimensions), _) => dimensions.clone(),
+        (None, Some(config)) => config.dimensions().to_vec(),
+        (None, None) => return Err("the size of the domain is needed, use --dimensions".into()),
+    };
+    if dimensions.is_empty() || dimensions.iter().any(|d| !(d.is_finite() && *d > 0.)) {
+        return Err("the dimensions must be positive".into());
+    }
+    if points.iter().any(|p| p.len() != dimensions.len()) {
+        return Err(format!("the points must have {} coordinates", dimensions.len()).into());
+    }
+    let radius = args
+        .radius
+        .or(config.as_ref().map(|config| config.min_distance()));
+    if radius.is_some_and(|r| !(r.is_finite() && r > 0.)) {
+        return Err("the radius must be positive".into());
+    }
+    let periodic = args.periodic
+        || config
+            .as_ref()
+            .is_some_and(|config| config.wrapped_axes().iter().all(|wrap| *wrap));
+    let report = analyze(&points, dimensions, radius, periodic)?;
+    match &args.output {
+        Some(path) => write_report(&mut File::create(path)?, &report)?,
+        None => write_report(&mut io::stdout().lock(), &report)?,
+    }
+
+    if let Some(path) = &args.plot_points {
+        bluenoisers::plot_points(&points, planar(&report.dimensions)?, path)?;
+    }
+    if let Some(path) = &args.plot_spectrum {
+        let spectrum =
+            analysis::periodogram(&points, planar(&report.dimensions)?, args.spectrum_size);
+        bluenoisers::plot_spectrum(&spectrum, path)?;
+    }
+    if let Some(path) = &args.plot_rdf {
+        bluenoisers::plot_rdf(&report.rdf, path)?;
+    }
+    if args.strict && report.outside + report.too_close > 0 {
+        return Err(format!(
+            "{} points are outside and {} pairs closer than {}",
+            report.outside, report.too_close, report.radius
+        )
+        .into());
+    }
+    if let Some(min_score) = args.min_score {
+        let quality = report
+            .quality
+            .ok_or("the quality score is only computed for 2D points")?;
+        if quality.score() < min_score {
+            return Err(
+                format!("the quality score {} is below {min_score}", quality.score()).into(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The dimensions of a 2D domain, for the plots.
+fn planar(dimensions: &[f64]) -> Result<[f64; 2]> {
+    match dimensions {
+        &[width, height] => Ok([width, height]),
+        _ => Err("only 2D points can be plotted".into()),
+    }
+}
+
+fn analyze(
+    points: &[Point],
+    dimensions: Vec<f64>,
+    radius: Option<f64>,
+    periodic: bool,
+) -> Result<Report> {
+    let (spacing, duplicates) = spacing(points, &dimensions);
+    if spacing.count < 2 {
+        return Err("there must be at least two distinct points inside of the domain".into());
+    }
+    // without a radius, the points are expected to keep the distance of
+    // their closest distinct pair, which is a little less for the rounding
+    // of the square root
+    let (radius, checked) = match radius {
+        Some(radius) => (radius, radius),
+        None => (spacing.min_nearest, spacing.min_nearest * (1. - 1e-9)),
+    };
+    let (outside, too_close) = match bluenoisers::validate(points, checked, &dimensions) {
+        Ok(()) => (0, 0),
+        Err(violations) => violations
+            .iter()
+            .fold((0, 0), |(outside, too_close), violation| match violation {
+                Violation::Outside(_) => (outside + 1, too_close),
+                Violation::TooClose { .. } => (outside, too_close + 1),
+                Violation::InvalidMinDistance(radius) => {
+                    unreachable!("the radius {radius} is checked to be positive and finite")
+                }
+                Violation::NoDimensions
+                | Violation::InvalidDimension { .. }
+                | Violation::DomainTooLarge { .. } => {
+                    unreachable!("the dimensions are checked to be positive")
+                }
+            }),
+    };
+    let quality = match dimensions[..] {
+        [width, height] => Some(analysis::quality(points, [width, height])),
+        _ => None,
+    };
+    // the periodic correction only reaches half way across the domain
+    let half = dimensions.iter().copied().fold(f64::INFINITY, f64::min) / 2.;
+    let max_distance = if periodic {
+        (RDF_RANGE * radius).min(half)
+    } else {
+        RDF_RANGE * radius
+    };
+    let correction = if periodic {
+        EdgeCorrection::Periodic
+    } else {
+        EdgeCorrection::Translation
+    };
+    let inside: Vec<&Point> = points
+        .iter()
+        .filter(|p| is_inside(p, &dimensions))
+        .collect();
+    let rdf = analysis::rdf(&inside, &dimensions, radius / 10., max_distance, correction);
+    Ok(Report {
+        dimensions,
+        points: points.len(),
+        radius,
+        outside,
+        too_close,
+        duplicates,
+        spacing,
+        quality,
+        correction,
+        rdf,
+    })
+}
+
+fn is_inside(point: &[f64], dimensions: &[f64]) -> bool {
+    point
+        .iter()
+        .zip(dimensions)
+        .all(|(x, dim)| (0. ..*dim).contains(x))
+}
+
+/// The spacing statistics of the points inside of the domain, leaving out
+/// exact duplicates, and the number of duplicates.
+fn spacing(points: &[Point], dimensions: &[f64]) -> (SpacingStats, usize) {
+    let mut points: Vec<Point> = points
+        .iter()
+        .filter(|p| is_inside(p, dimensions))
+        .cloned()
+        .collect();
+    let volume: f64 = dimensions.iter().product();
+    // about the spacing of a pattern with this many points; all pairs closer
+    // than that are found, so the closest one is among them
+    let typical = (volume / points.len().max(1) as f64).powf(1. / dimensions.len() as f64);
+    let mut duplicates = 0;
+    loop {
+        let conflicts = bluenoisers::find_conflicts(&points, typical);
+        let pair_distance = |(i, j): (usize, usize)| distance(&points[i], &points[j]);
+        let closest = conflicts
+            .iter()
+            .map(|pair| pair_distance(*pair))
+            .fold(typical, f64::min);
+        if closest > 0. {
+            // see the rounding in `analyze`
+            let min_distance = closest * (1. - 1e-9);
+            let set = PoissonDiskSet::from_points(dimensions.to_vec(), min_distance, points)
+                .expect("no points are outside or closer than the closest pair");
+            return (set.spacing_stats(), duplicates);
+        }
+        let mut duplicate = vec![false; points.len()];
+        for (_, j) in conflicts
+            .into_iter()
+            .filter(|pair| pair_distance(*pair) == 0.)
+        {
+            duplicate[j] = true;
+        }
+        duplicates += duplicate.iter().filter(|d| **d).count();
+        points = points
+            .into_iter()
+            .zip(duplicate)
+            .filter_map(|(point, duplicate)| (!duplicate).then_some(point))
+            .collect();
+    }
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn write_report<W: Write>(writer: &mut W, report: &Report) -> io::Result<()> {
+    // JSON has no infinity
+    let number = |x: f64| {
+        if x.is_finite() {
+            x.to_string()
+        } else {
+            "null".to_string()
+        }
+    };
+    let array = |values: &[f64]| {
+        let values: Vec<String> = values.iter().map(|x| number(*x)).collect();
+        format!("[{}]", values.join(", "))
+    };
+    let spacing = &report.spacing;
+    writeln!(writer, "{{")?;
+    writeln!(writer, r#"  "dimensions": {},"#, array(&report.dimensions))?;
+    writeln!(writer, r#"  "points": {},"#, report.points)?;
+    writeln!(
+        writer,
+        r#"  "violations": {{"min_distance": {}, "outside": {}, "too_close": {}, "duplicates": {}}},"#,
+        number(report.radius),
+        report.outside,
+        report.too_close,
+        report.duplicates,
+    )?;
+    writeln!(
+        writer,
+        r#"  "spacing": {{"density": {}, "min_nearest": {}, "mean_nearest": {}, "max_nearest": {}, "coverage_radius": {}}},"#,
+        number(spacing.density),
+        number(spacing.min_nearest),
+        number(spacing.mean_nearest),
+        number(spacing.max_nearest),
+        number(spacing.coverage_radius),
+    )?;
+    match &report.quality {
+        Some(quality) => writeln!(
+            writer,
+            r#"  "spectrum": {{"effective_nyquist": {}, "low_frequency_energy": {}, "peak": {}, "score": {}}},"#,
+            number(quality.effective_nyquist),
+            number(quality.low_frequency_energy),
+            number(quality.peak),
+            number(quality.score()),
+        )?,
+        None => writeln!(writer, r#"  "spectrum": null,"#)?,
+    }
+    let correction = match report.correction {
+        EdgeCorrection::None => "none",
+        EdgeCorrection::Translation => "translation",
+        EdgeCorrection::Periodic => "periodic",
+    };
+    writeln!(
+        writer,
+        r#"  "rdf": {{"correction": "{correction}", "radius": {}, "g": {}}}"#,
+        array(&report.rdf.radius),
+        array(&report.rdf.g),
+    )?;
+    writeln!(writer, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bluenoisers::BlueNoiseConfig;
+    use clap::Parser;
+
+    fn args(line: &str) -> Args {
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            args: Args,
+        }
+        Command::parse_from(line.split(' ')).args
+    }
+
+    #[test]
+    fn spacing() {
+        let mut points: Vec<Point> = BlueNoiseConfig::new(vec![20., 20.], 1.).seed(2).generate();
+        let set = PoissonDiskSet::from_points(vec![20., 20.], 1., points.clone()).unwrap();
+        let expected = set.spacing_stats();
+        assert_eq!(super::spacing(&points, &[20., 20.]), (expected, 0));
+        // duplicates and points outside are left out
+        points.push(points[3].clone());
+        points.push(Point::from_slice(&[21., 3.]));
+        assert_eq!(super::spacing(&points, &[20., 20.]), (expected, 1));
+    }
+
+    #[test]
+    fn report() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("samples.json");
+        let config = BlueNoiseConfig::new(vec![24., 16.], 1.)
+            .seed(5)
+            .wrap(vec![true, true]);
+        bluenoisers::export_json(&json, &config.generate(), &config).unwrap();
+        let report = dir.path().join("report.json");
+        let plots = ["points.png", "spectrum.png", "rdf.svg"].map(|name| dir.path().join(name));
+        let line = format!(
+            "analyze {} --strict --min-score 0.1 -o {} --plot-points {} --plot-spectrum {} --plot-rdf {}",
+            json.display(),
+            report.display(),
+            plots[0].display(),
+            plots[1].display(),
+            plots[2].display(),
+        );
+        run(&args(&line)).unwrap();
+        let values: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        assert_eq!(values["violations"]["too_close"], 0);
+        assert_eq!(values["rdf"]["correction"], "periodic");
+        assert!(values["spacing"]["min_nearest"].as_f64().unwrap() >= 1.);
+        assert!(values["spectrum"]["effective_nyquist"].as_f64().unwrap() > 0.3);
+        assert!(plots.iter().all(|plot| plot.exists()));
+
+        // too close for a larger radius, and without dimensions from CSV
+        let csv = dir.path().join("samples.csv");
+        bluenoisers::export_csv(&csv, &config.generate(), &bluenoisers::CsvFormat::new(','))
+            .unwrap();
+        let line = format!(
+            "analyze {} -d 24,16 -r 1.5 -o {}",
+            csv.display(),
+            report.display()
+        );
+        run(&args(&line)).unwrap();
+        assert!(run(&args(&format!("{line} --strict"))).is_err());
+        assert!(run(&args(&format!("analyze {}", csv.display()))).is_err());
+        assert!(run(&args(&format!("analyze {} -d 24,16,1", csv.display()))).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! The `generate` subcommand.
+
+use crate::Result;
+use bluenoisers::{BlueNoiseConfig, CsvFormat, PngStyle, Point, PoissonDiskSet};
+use clap::ValueEnum;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How many times the radius is adjusted to generate a number of samples.
+const COUNT_ITERATIONS: usize = 12;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The size of the domain along each axis, e.g. `64,64`.
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    dimensions: Vec<f64>,
+    /// The minimal distance between samples.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "count",
+        conflicts_with = "count"
+    )]
+    radius: Option<f64>,
+    /// The number of samples, instead of a radius. The radius is chosen to
+    /// fit a few more and the closest ones are removed, so the samples can't
+    /// be written as JSON, which records how to generate them.
+    #[arg(short = 'n', long)]
+    count: Option<usize>,
+    /// Makes the pattern reproducible. Without, a random seed is used and
+    /// recorded in JSON output.
+    #[arg(short, long)]
+    seed: Option<u64>,
+    /// How often a neighbor of a sample is tried before giving up on it.
+    #[arg(short, long, default_value_t = 30)]
+    k_abort: usize,
+    /// Makes the pattern periodic along the axes, e.g. `x,y` or `0,2`, or
+    /// along all axes without a value.
+    #[arg(short, long, value_name = "AXES", num_args = 0..=1, default_missing_value = "all")]
+    wrap: Option<String>,
+    /// The output format. Defaults to the extension of the output file or
+    /// CSV.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+    /// The output file. Without, CSV is written to standard output.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// The width of PNG images in pixels. The height follows from the
+    /// dimensions.
+    #[arg(long, default_value_t = 512)]
+    width: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+    Ply,
+    Png,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| args.output.as_deref().and_then(Format::of))
+        .unwrap_or(Format::Csv);
+    if args.output.is_none() && format != Format::Csv {
+        return Err("only CSV can be written to standard output, use --output".into());
+    }
+    if args.dimensions.iter().any(|d| !(d.is_finite() && *d > 0.)) {
+        return Err("the dimensions must be positive".into());
+    }
+    if format == Format::Json && args.count.is_some() {
+        return Err("JSON can't record how to thin out to a count, use --radius".into());
+    }
+    if format == Format::Ply && !(2..=3).contains(&args.dimensions.len()) {
+        return Err("only 2D and 3D samples can be written as PLY".into());
+    }
+    if format == Format::Png && args.dimensions.len() != 2 {
+        return Err("only 2D samples can be written as PNG".into());
+    }
+    if args.k_abort == 0 {
+        return Err("k-abort must be positive".into());
+    }
+    let wrap = match &args.wrap {
+        Some(axes) => wrapped_axes(axes, args.dimensions.len())?,
+        None => vec![false; args.dimensions.len()],
+    };
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let (config, samples) = match (args.radius, args.count) {
+        (Some(radius), _) => {
+            let config = configure(args, radius, &wrap, seed)?;
+            let samples = config.generate();
+            (config, samples)
+        }
+        (None, Some(count)) => with_count(args, count, &wrap, seed)?,
+        (None, None) => unreachable!("clap requires a radius or a count"),
+    };
+    let Some(path) = &args.output else {
+        return Ok(CsvFormat::new(',').write(io::stdout().lock(), &samples)?);
+    };
+    match format {
+        Format::Csv => bluenoisers::export_csv(path, &samples, &CsvFormat::new(','))?,
+        Format::Json => bluenoisers::export_json(path, &samples, &config)?,
+        Format::Ply => bluenoisers::export_ply(path, &samples)?,
+        Format::Png => {
+            let [width, height] = [args.dimensions[0], args.dimensions[1]];
+            let rows = (f64::from(args.width) * height / width).round().max(1.) as u32;
+            let style =
+                PngStyle::new([width, height], [args.width, rows]).tileable(wrap[0] && wrap[1]);
+            bluenoisers::export_png(path, &samples, &style)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the axes given to `--wrap`: `all` or a list of axis names or
+/// indices.
+fn wrapped_axes(axes: &str, dimension: usize) -> Result<Vec<bool>> {
+    let mut wrap = vec![axes == "all"; dimension];
+    if axes == "all" {
+        return Ok(wrap);
+    }
+    for axis in axes.split(',') {
+        let index = match axis.trim() {
+            "x" => 0,
+            "y" => 1,
+            "z" => 2,
+            "w" => 3,
+            index => index
+                .parse()
+                .map_err(|_| format!("unknown axis {index:?}"))?,
+        };
+        *wrap
+            .get_mut(index)
+            .ok_or_else(|| format!("there is no axis {axis} in {dimension} dimensions"))? = true;
+    }
+    Ok(wrap)
+}
+
+fn configure(args: &Args, radius: f64, wrap: &[bool], seed: u64) -> Result<BlueNoiseConfig> {
+    if !(radius.is_finite() && radius > 0.) {
+        return Err("the radius must be positive".into());
+    }
+    if wrap
+        .iter()
+        .zip(args.dimensions.iter())
+        .any(|(wrap, dim)| *wrap && *dim < 2. * radius)
+    {
+        return Err("a wrapped axis must be at least twice the radius long".into());
+    }
+    let config = BlueNoiseConfig::new(args.dimensions.clone(), radius)
+        .k_abort(args.k_abort)
+        .seed(seed)
+        .wrap(wrap.to_vec());
+    // e.g. a background grid which doesn't fit into memory
+    config.check()?;
+    Ok(config)
+}
+
+/// Generates exactly `count` samples: the radius is adjusted until a few
+/// more are generated, then the closest ones are removed.
+fn with_count(
+    args: &Args,
+    count: usize,
+    wrap: &[bool],
+    seed: u64,
+) -> Result<(BlueNoiseConfig, Vec<Point>)> {
+    if count == 0 {
+        return Err("the count must be positive".into());
+    }
+    let dimension = args.dimensions.len() as f64;
+    let volume: f64 = args.dimensions.iter().product();
+    // the number of samples is roughly proportional to radius^-dimension
+    let mut radius = (volume / count as f64).powf(1. / dimension);
+    let mut best: Option<(BlueNoiseConfig, Vec<Point>)> = None;
+    for _ in 0..COUNT_ITERATIONS {
+        let config = configure(args, radius, wrap, seed)?;
+        let samples = config.generate();
+        let generated = samples.len();
+        let enough = generated >= count;
+        if enough && best.as_ref().is_none_or(|(_, best)| generated < best.len()) {
+            best = Some((config, samples));
+        }
+        if enough && generated <= count + count / 50 {
+            break;
+        }
+        // aim at 1% more than needed
+        let target = count as f64 * 1.01 + 1.;
+        radius *= (generated.max(1) as f64 / target).powf(1. / dimension);
+    }
+    let (config, samples) = best.ok_or("the count is too large for the dimensions")?;
+    let samples = thin_out(samples, &config, count);
+    Ok((config, samples))
+}
+
+/// Removes samples until `count` are left, one of every closest pair.
+fn thin_out(mut samples: Vec<Point>, config: &BlueNoiseConfig, count: usize) -> Vec<Point> {
+    while samples.len() > count {
+        let set = PoissonDiskSet::from_points(
+            config.dimensions().to_vec(),
+            config.min_distance(),
+            samples,
+        )
+        .expect("the samples were generated");
+        // the nearest neighbor of every sample, besides itself
+        let mut nearest: Vec<(usize, usize, f64)> = (0..set.len())
+            .map(|i| {
+                let (j, distance) = set.knn(&set.samples()[i], 2)[1];
+                (i, j, distance)
+            })
+            .collect();
+        nearest.sort_by(|a, b| a.2.total_cmp(&b.2));
+        let mut removed = vec![false; set.len()];
+        let mut excess = set.len() - count;
+        for (i, j, _) in nearest {
+            if excess == 0 {
+                break;
+            }
+            // the neighbor may have been the one removed from this pair
+            if !removed[j] {
+                removed[i] = true;
+                excess -= 1;
+            }
+        }
+        samples = set
+            .into_samples()
+            .into_iter()
+            .zip(removed)
+            .filter_map(|(sample, removed)| (!removed).then_some(sample))
+            .collect();
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(line: &str) -> Args {
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            args: Args,
+        }
+        Command::parse_from(line.split(' ')).args
+    }
+
+    #[test]
+    fn wrapped_axes() {
+        assert_eq!(super::wrapped_axes("all", 2).unwrap(), [true, true]);
+        assert_eq!(super::wrapped_axes("x,2", 3).unwrap(), [true, false, true]);
+        assert!(super::wrapped_axes("z", 2).is_err());
+        assert!(super::wrapped_axes("u", 2).is_err());
+        assert_eq!(
+            args("generate -d 8,8 -r 1 --wrap").wrap.as_deref(),
+            Some("all")
+        );
+    }
+
+    #[test]
+    fn count() {
+        let args = args("generate -d 30,20 -n 200 -s 3");
+        let (config, samples) = with_count(&args, 200, &[false, false], 3).unwrap();
+        assert_eq!(samples.len(), 200);
+        assert!(bluenoisers::validate(&samples, config.min_distance(), &[30., 20.]).is_ok());
+        // evenly spread: no gap much larger than the radius
+        let set =
+            PoissonDiskSet::from_points(vec![30., 20.], config.min_distance(), samples).unwrap();
+        assert!(set.largest_empty_ball().unwrap().1 < 2.5 * config.min_distance());
+        assert!(with_count(&args, 0, &[false, false], 3).is_err());
+    }
+
+    #[test]
+    fn output() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in ["csv", "json", "ply", "png"] {
+            let path = dir.path().join(format!("samples.{format}"));
+            let line = format!("generate -d 16,9 -r 1 -s 1 -w y -o {}", path.display());
+            run(&args(&line)).unwrap();
+            assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        }
+        let json = dir.path().join("samples.json");
+        let (config, samples) = bluenoisers::read_json(std::fs::File::open(json).unwrap()).unwrap();
+        assert_eq!(config.wrapped_axes(), [false, true]);
+        assert_eq!(config.generate(), samples);
+        assert!(run(&args("generate -d 16,9 -r 1 -f png")).is_err());
+        assert!(run(&args("generate -d 16,9,3 -r 1 -f png -o x")).is_err());
+        assert!(run(&args("generate -d 16,1 -r 1 -w -o x.csv")).is_err());
+        assert!(run(&args("generate -d 16,9 -n 20 -o x.json")).is_err());
+        let error = run(&args("generate -d 1e9,1e9 -r 0.001 -o x.csv")).unwrap_err();
+        assert!(error.to_string().contains("too large"));
+    }
+}
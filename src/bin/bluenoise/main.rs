@@ -0,0 +1,64 @@
+//! The `bluenoise` command line tool, to generate blue noise without writing
+//! Rust. See `bluenoise help` for the subcommands.
+//!
+//! Requires the `cli` feature:
+//!
+//! ```text
+//! cargo install bluenoisers --features cli
+//! bluenoise generate --dimensions 64,64 --radius 1 --seed 7 > samples.csv
+//! bluenoise analyze samples.csv --dimensions 64,64 --radius 1 --strict
+//! bluenoise mask --size 128 --channels 4 --output mask.png
+//! bluenoise stipple portrait.jpg --dots 20000 --output portrait.svg
+//! ```
+
+mod analyze;
+mod generate;
+mod mask;
+mod stipple;
+
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::process::ExitCode;
+
+/// What goes wrong in a subcommand, reported on standard error.
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Blue noise point patterns using Fast Poisson Disk Sampling.
+#[derive(Debug, Parser)]
+#[command(name = "bluenoise", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Generates a pattern of samples which are at least a radius apart.
+    Generate(generate::Args),
+    /// Reports the spacing, spectrum and radial distribution function of a
+    /// point set as JSON, optionally with plots. The spectrum takes time
+    /// quadratic in the number of points.
+    Analyze(analyze::Args),
+    /// Generates a tileable blue noise threshold texture for dithering, as
+    /// PNG or EXR.
+    Mask(mask::Args),
+    /// Draws an image with dots which are denser where it is darker, as SVG
+    /// or PNG.
+    Stipple(stipple::Args),
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Generate(args) => generate::run(&args),
+        Command::Analyze(args) => analyze::run(&args),
+        Command::Mask(args) => mask::run(&args),
+        Command::Stipple(args) => stipple::run(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("bluenoise: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
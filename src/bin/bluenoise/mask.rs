@@ -0,0 +1,201 @@
+//! The `mask` subcommand.
+
+use crate::Result;
+use bluenoisers::DitherMask;
+use clap::ValueEnum;
+use image::ColorType;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The size of the texture in pixels, e.g. `128` or `256x64`. Generating
+    /// takes time quadratic in the number of pixels.
+    #[arg(long, default_value = "128", value_parser = size)]
+    size: [usize; 2],
+    /// The number of channels, each an independent mask: 1 for gray, 2 for
+    /// gray and alpha, 3 for RGB and 4 for RGBA.
+    #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=4))]
+    channels: u8,
+    /// The bits per channel, 8 or 16 for PNG and 32 for EXR, which is the
+    /// default for EXR.
+    #[arg(short, long)]
+    bits: Option<u8>,
+    /// Makes the texture reproducible. Channel `i` uses the seed plus `i`.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+    /// The output format. Defaults to the extension of the output file.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+    /// The output file.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Png,
+    Exr,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+}
+
+/// Parses `N` as a square size or `WxH`.
+fn size(size: &str) -> std::result::Result<[usize; 2], String> {
+    let parse = |side: &str| match side.trim().parse() {
+        Ok(side) if side > 0 => Ok(side),
+        _ => Err(format!("invalid size {side:?}, e.g. 128 or 256x64")),
+    };
+    match size.split_once(['x', 'X']) {
+        Some((width, height)) => Ok([parse(width)?, parse(height)?]),
+        None => parse(size).map(|side| [side, side]),
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| Format::of(&args.output))
+        .ok_or("unknown output format, use --format")?;
+    let bits = match (format, args.bits) {
+        (Format::Png, None) => 8,
+        (Format::Exr, None) => 32,
+        (Format::Png, Some(bits @ (8 | 16))) | (Format::Exr, Some(bits @ 32)) => bits,
+        (Format::Png, Some(_)) => return Err("PNG textures have 8 or 16 bits".into()),
+        (Format::Exr, Some(_)) => return Err("EXR textures have 32 bits".into()),
+    };
+    let [width, height] = args.size;
+    let (Ok(width_u32), Ok(height_u32)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err("the size is too large".into());
+    };
+    if width
+        .checked_mul(height)
+        .is_none_or(|count| count > u32::MAX as usize)
+    {
+        return Err("the texture must have at most 2^32 pixels".into());
+    }
+    let channels = usize::from(args.channels);
+    let masks: Vec<DitherMask> = (0..channels)
+        .map(|i| DitherMask::void_and_cluster(width, height, args.seed.wrapping_add(i as u64)))
+        .collect();
+    let (buffer, color): (Vec<u8>, ColorType) = match bits {
+        8 => {
+            let values: Vec<Vec<u8>> = masks.iter().map(DitherMask::to_u8).collect();
+            let color = [
+                ColorType::L8,
+                ColorType::La8,
+                ColorType::Rgb8,
+                ColorType::Rgba8,
+            ];
+            (interleave(&values), color[channels - 1])
+        }
+        16 => {
+            let values: Vec<Vec<u16>> = masks.iter().map(DitherMask::to_u16).collect();
+            let color = [
+                ColorType::L16,
+                ColorType::La16,
+                ColorType::Rgb16,
+                ColorType::Rgba16,
+            ];
+            // the encoder takes the samples in native byte order
+            let buffer = interleave(&values)
+                .into_iter()
+                .flat_map(|v| v.to_ne_bytes());
+            (buffer.collect(), color[channels - 1])
+        }
+        _ => {
+            let mut values: Vec<Vec<f32>> = masks.iter().map(DitherMask::to_f32).collect();
+            // EXR has RGB and RGBA only: gray is repeated and a second
+            // channel goes to green, with blue zero
+            match channels {
+                1 => values = vec![values[0].clone(); 3],
+                2 => values.push(vec![0.; width * height]),
+                _ => {}
+            }
+            let color = if channels == 4 {
+                ColorType::Rgba32F
+            } else {
+                ColorType::Rgb32F
+            };
+            let buffer = interleave(&values)
+                .into_iter()
+                .flat_map(|v| v.to_ne_bytes());
+            (buffer.collect(), color)
+        }
+    };
+    image::save_buffer(&args.output, &buffer, width_u32, height_u32, color)?;
+    Ok(())
+}
+
+/// The values of all channels, pixel by pixel.
+fn interleave<T: Copy>(channels: &[Vec<T>]) -> Vec<T> {
+    let pixels = channels[0].len();
+    (0..pixels)
+        .flat_map(|pixel| channels.iter().map(move |channel| channel[pixel]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(line: &str) -> Args {
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            args: Args,
+        }
+        Command::parse_from(line.split(' ')).args
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(super::size("16").unwrap(), [16, 16]);
+        assert_eq!(super::size("32x8").unwrap(), [32, 8]);
+        assert!(super::size("0").is_err());
+        assert!(super::size("8x").is_err());
+    }
+
+    #[test]
+    fn mask() {
+        let dir = tempfile::tempdir().unwrap();
+        let gray = dir.path().join("gray.png");
+        run(&args(&format!(
+            "mask --size 16x8 -s 3 -o {}",
+            gray.display()
+        )))
+        .unwrap();
+        let image = image::open(&gray).unwrap();
+        assert_eq!(image.color(), ColorType::L8);
+        let expected = DitherMask::void_and_cluster(16, 8, 3).to_gray_image();
+        assert_eq!(image.into_luma8(), expected);
+
+        let rgba = dir.path().join("rgba.png");
+        let line = format!("mask --size 16 -c 4 -b 16 -o {}", rgba.display());
+        run(&args(&line)).unwrap();
+        let image = image::open(&rgba).unwrap().into_rgba16();
+        assert_eq!(image.dimensions(), (16, 16));
+        let alpha = DitherMask::void_and_cluster(16, 16, 3).to_u16();
+        assert_eq!(image.get_pixel(5, 2)[3], alpha[2 * 16 + 5]);
+        // the channels are independent
+        assert!(image.pixels().any(|p| p[0] != p[1]));
+
+        for channels in [1, 2, 4] {
+            let exr = dir.path().join(format!("mask{channels}.exr"));
+            let line = format!("mask --size 16 -c {channels} -o {}", exr.display());
+            run(&args(&line)).unwrap();
+            let image = image::open(&exr).unwrap().into_rgba32f();
+            let red = DitherMask::void_and_cluster(16, 16, 0).to_f32();
+            assert_eq!(image.get_pixel(3, 4)[0], red[4 * 16 + 3]);
+        }
+
+        assert!(run(&args("mask -b 32 -o mask.png")).is_err());
+        assert!(run(&args("mask -b 8 -o mask.exr")).is_err());
+        assert!(run(&args("mask -o mask.tiff")).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! The `stipple` subcommand.
+
+use crate::Result;
+use bluenoisers::{PngStyle, Point, Splat, SvgStyle};
+use clap::ValueEnum;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::path::{Path, PathBuf};
+
+/// How many times the radii are scaled to fit a number of dots.
+const BUDGET_ITERATIONS: usize = 12;
+
+/// The ratio of the largest to the smallest spacing if only a number of
+/// dots is given.
+const DEFAULT_RANGE: f64 = 8.;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The image to stipple, PNG or JPEG. Colors are converted to gray.
+    input: PathBuf,
+    /// The spacing of the dots in black and in white areas, in pixels of the
+    /// input, e.g. `2,16`.
+    #[arg(
+        short,
+        long,
+        value_name = "MIN,MAX",
+        value_delimiter = ',',
+        required_unless_present = "dots"
+    )]
+    radius: Option<Vec<f64>>,
+    /// The largest number of dots. The spacing is scaled to fit, keeping the
+    /// ratio of the radii, or a ratio of 8 without them.
+    #[arg(short = 'n', long)]
+    dots: Option<usize>,
+    /// The radius of the drawn dots in pixels of the input. Defaults to 40%
+    /// of the smallest spacing.
+    #[arg(long)]
+    dot_radius: Option<f64>,
+    /// Places the dots where the image is light instead of dark, for light
+    /// dots on a dark background.
+    #[arg(long)]
+    invert: bool,
+    /// Makes the dots reproducible.
+    #[arg(short, long)]
+    seed: Option<u64>,
+    /// How often a neighbor of a dot is tried before giving up on it.
+    #[arg(short, long, default_value_t = 30)]
+    k_abort: usize,
+    /// The output format. Defaults to the extension of the output file.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+    /// The output file.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The width of the output in pixels, by default that of the input.
+    #[arg(long)]
+    width: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| Format::of(&args.output))
+        .ok_or("unknown output format, use --format")?;
+    if args.k_abort == 0 {
+        return Err("k-abort must be positive".into());
+    }
+    let mut image = image::open(&args.input)?.into_luma8();
+    if image.width() == 0 || image.height() == 0 {
+        return Err("the image is empty".into());
+    }
+    if args.invert {
+        image::imageops::invert(&mut image);
+    }
+    let [min_radius, max_radius] = match args.radius.as_deref() {
+        Some(&[min, max]) => [min, max],
+        Some(_) => return Err("the radius takes two values, e.g. 2,16".into()),
+        None => [1., DEFAULT_RANGE],
+    };
+    if !(min_radius.is_finite() && min_radius > 0. && max_radius.is_finite()) {
+        return Err("the radii must be positive".into());
+    }
+    if max_radius < min_radius {
+        return Err("the second radius must be at least the first".into());
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let (min_radius, dots) = match args.dots {
+        Some(budget) => within_budget(args, &image, [min_radius, max_radius], budget, seed)?,
+        None => (
+            min_radius,
+            stipple(args, &image, [min_radius, max_radius], seed),
+        ),
+    };
+    let dimensions = [f64::from(image.width()), f64::from(image.height())];
+    let dot_radius = args.dot_radius.unwrap_or(0.4 * min_radius);
+    if !(dot_radius.is_finite() && dot_radius >= 0.) {
+        return Err("the dot radius must not be negative".into());
+    }
+    let width = args.width.unwrap_or(image.width());
+    let scale = f64::from(width) / dimensions[0];
+    match format {
+        Format::Svg => {
+            let color = if args.invert { "white" } else { "black" };
+            let style = SvgStyle::new(dimensions)
+                .width(f64::from(width))
+                .dot_radius(dot_radius)
+                .color(color);
+            bluenoisers::export_svg(&args.output, &dots, &style)?;
+        }
+        Format::Png => {
+            let rows = (dimensions[1] * scale).round().max(1.) as u32;
+            let style = PngStyle::new(dimensions, [width, rows]).splat(Splat::Dot {
+                radius: dot_radius * scale,
+            });
+            let mut pixels = style.render(&dots);
+            // rendered light on dark
+            if !args.invert {
+                pixels.iter_mut().for_each(|p| *p = 255 - *p);
+            }
+            image::save_buffer(&args.output, &pixels, width, rows, image::ColorType::L8)?;
+        }
+    }
+    Ok(())
+}
+
+fn stipple(args: &Args, image: &image::GrayImage, [min, max]: [f64; 2], seed: u64) -> Vec<Point> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    bluenoisers::stipple(image, min, max, args.k_abort, &mut rng)
+}
+
+/// Scales the radii until at most `budget` dots, but not many fewer, are
+/// placed. Returns the scaled minimal radius and the dots.
+fn within_budget(
+    args: &Args,
+    image: &image::GrayImage,
+    [min, max]: [f64; 2],
+    budget: usize,
+    seed: u64,
+) -> Result<(f64, Vec<Point>)> {
+    if budget == 0 {
+        return Err("the number of dots must be positive".into());
+    }
+    // the darkness sets the number of dots per area at the minimal radius
+    let darkness: f64 = image
+        .pixels()
+        .map(|p| 1. - f64::from(p[0]) / 255.)
+        .sum::<f64>()
+        .max(f64::from(image.width() * image.height()) * (min / max).powi(2));
+    // the number of dots is roughly proportional to radius^-2
+    let mut scale = (darkness / budget as f64).sqrt() / min;
+    let mut best: Option<(f64, Vec<Point>)> = None;
+    for _ in 0..BUDGET_ITERATIONS {
+        let dots = stipple(args, image, [min * scale, max * scale], seed);
+        let placed = dots.len();
+        let fits = placed <= budget;
+        if fits && best.as_ref().is_none_or(|(_, best)| placed > best.len()) {
+            best = Some((min * scale, dots));
+        }
+        if fits && placed + budget / 50 >= budget {
+            break;
+        }
+        // aim at 1% fewer than allowed
+        let target = budget as f64 * 0.99;
+        scale *= (placed as f64 / target).sqrt();
+    }
+    best.ok_or_else(|| "no spacing fits the number of dots".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(line: &str) -> Args {
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            args: Args,
+        }
+        Command::parse_from(line.split(' ')).args
+    }
+
+    #[test]
+    fn stipple() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("gradient.png");
+        image::GrayImage::from_fn(120, 60, |x, _| image::Luma([(x * 2) as u8]))
+            .save(&input)
+            .unwrap();
+
+        let svg = dir.path().join("dots.svg");
+        let line = format!(
+            "stipple {} -n 500 -s 4 --width 240 -o {}",
+            input.display(),
+            svg.display()
+        );
+        run(&args(&line)).unwrap();
+        let dots = std::fs::read_to_string(&svg)
+            .unwrap()
+            .matches("<circle")
+            .count();
+        assert!((490..=500).contains(&dots));
+
+        let png = dir.path().join("dots.png");
+        let line = format!(
+            "stipple {} -r 1,6 -s 4 -o {}",
+            input.display(),
+            png.display()
+        );
+        run(&args(&line)).unwrap();
+        let output = image::open(&png).unwrap().into_luma8();
+        assert_eq!(output.dimensions(), (120, 60));
+        // black dots on white, more on the dark left
+        let black = |x: u32| {
+            output
+                .enumerate_pixels()
+                .filter(|(px, _, p)| px / 60 == x && p[0] == 0)
+                .count()
+        };
+        assert!(black(0) > 2 * black(1));
+
+        for radius in ["6,1", "1", "0,1"] {
+            let line = format!(
+                "stipple {} -r {radius} -o {}",
+                input.display(),
+                png.display()
+            );
+            assert!(run(&args(&line)).is_err());
+        }
+        let line = format!("stipple {} -r 1,6 -o dots.txt", input.display());
+        assert!(run(&args(&line)).is_err());
+        let line = format!("stipple {} -n 0 -o {}", input.display(), svg.display());
+        assert!(run(&args(&line)).is_err());
+    }
+}
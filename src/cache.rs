@@ -0,0 +1,299 @@
+//! Patterns generated ahead of time, for applications which can't wait for
+//! them.
+
+use crate::{BlueNoiseConfig, Point};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// A reservoir of patterns which background threads keep filled, so that a
+/// real time application can get a new pattern in the middle of a frame
+/// without generating it.
+///
+/// There is one thread per configuration, which generates patterns until
+/// `patterns` of them are ready and then waits until one is taken. A
+/// configuration with a seed gives the same sequence of patterns every time,
+/// each generated with a seed drawn from it; without a seed, the patterns are
+/// random.
+///
+/// [`take`](PatternCache::take) never blocks. If no pattern is ready, it
+/// returns `None`, or with [`toroidal_shifts`](PatternCache::toroidal_shifts)
+/// the last pattern it handed out, moved by a random offset along the
+/// wrapped axes. Since a pattern generated with
+/// [`wrap`](BlueNoiseConfig::wrap) tiles the domain, the moved pattern keeps
+/// the minimal distance, but it is recognizably the same pattern when seen
+/// next to the original.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, PatternCache};
+///
+/// let config = BlueNoiseConfig::new(vec![32., 32.], 1.).wrap(vec![true, true]);
+/// let cache = PatternCache::new(vec![config.clone()], 4).toroidal_shifts(true);
+/// // e.g. while loading, to be sure there is a pattern
+/// let first = cache.wait(&config);
+/// // every frame, new or moved but never none
+/// let pattern = cache.take(&config).unwrap();
+/// assert!(first.len() > 500 && pattern.len() > 500);
+/// ```
+///
+/// Dropping the cache stops the threads once they finish the pattern they
+/// are generating.
+#[derive(Debug)]
+pub struct PatternCache {
+    pools: Vec<Arc<Pool>>,
+    workers: Vec<JoinHandle<()>>,
+    shifts: bool,
+}
+
+/// The patterns of one configuration.
+#[derive(Debug)]
+struct Pool {
+    config: BlueNoiseConfig,
+    capacity: usize,
+    state: Mutex<PoolState>,
+    /// Signaled when a pattern was added.
+    added: Condvar,
+    /// Signaled when a pattern was taken or the cache is dropped.
+    taken: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    ready: VecDeque<Vec<Point>>,
+    /// The pattern handed out last, for toroidal shifts.
+    last: Option<Vec<Point>>,
+    stop: bool,
+}
+
+impl PatternCache {
+    /// Starts keeping `patterns` patterns ready for each of `configs`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `patterns` is zero or a configuration is not valid, see
+    /// [`BlueNoiseConfig::check`].
+    pub fn new(configs: Vec<BlueNoiseConfig>, patterns: usize) -> PatternCache {
+        assert!(patterns > 0, "the number of patterns must be positive");
+        for config in &configs {
+            if let Err(error) = config.check() {
+                panic!("{error}");
+            }
+        }
+        let pools: Vec<Arc<Pool>> = configs
+            .into_iter()
+            .map(|config| {
+                Arc::new(Pool {
+                    config,
+                    capacity: patterns,
+                    state: Mutex::new(PoolState::default()),
+                    added: Condvar::new(),
+                    taken: Condvar::new(),
+                })
+            })
+            .collect();
+        let workers = pools
+            .iter()
+            .map(|pool| {
+                let pool = Arc::clone(pool);
+                thread::spawn(move || pool.fill())
+            })
+            .collect();
+        PatternCache {
+            pools,
+            workers,
+            shifts: false,
+        }
+    }
+
+    /// Sets whether [`take`](PatternCache::take) moves the last pattern by a
+    /// random offset along the wrapped axes instead of returning `None` if no
+    /// new pattern is ready. Off by default.
+    pub fn toroidal_shifts(mut self, shifts: bool) -> PatternCache {
+        self.shifts = shifts;
+        self
+    }
+
+    /// Takes a new pattern of `config` if one is ready, without waiting, see
+    /// [`PatternCache`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` is not one of the configurations of the cache.
+    pub fn take(&self, config: &BlueNoiseConfig) -> Option<Vec<Point>> {
+        let pool = self.pool(config);
+        let mut state = pool.state.lock().unwrap();
+        if let Some(pattern) = state.ready.pop_front() {
+            pool.taken.notify_one();
+            if self.shifts {
+                state.last = Some(pattern.clone());
+            }
+            return Some(pattern);
+        }
+        let last = state.last.as_ref()?;
+        Some(shifted(last, config, &mut rand::thread_rng()))
+    }
+
+    /// Takes the next new pattern of `config`, waiting until one is ready,
+    /// e.g. while loading a level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` is not one of the configurations of the cache.
+    pub fn wait(&self, config: &BlueNoiseConfig) -> Vec<Point> {
+        let pool = self.pool(config);
+        let mut state = pool
+            .added
+            .wait_while(pool.state.lock().unwrap(), |state| state.ready.is_empty())
+            .unwrap();
+        let pattern = state.ready.pop_front().unwrap();
+        pool.taken.notify_one();
+        if self.shifts {
+            state.last = Some(pattern.clone());
+        }
+        pattern
+    }
+
+    /// The number of patterns of `config` which are ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config` is not one of the configurations of the cache.
+    pub fn ready(&self, config: &BlueNoiseConfig) -> usize {
+        self.pool(config).state.lock().unwrap().ready.len()
+    }
+
+    fn pool(&self, config: &BlueNoiseConfig) -> &Pool {
+        self.pools
+            .iter()
+            .find(|pool| pool.config == *config)
+            .expect("the configuration is not cached")
+    }
+}
+
+impl Drop for PatternCache {
+    fn drop(&mut self) {
+        for pool in &self.pools {
+            pool.state.lock().unwrap().stop = true;
+            pool.taken.notify_one();
+        }
+        for worker in self.workers.drain(..) {
+            // a panic while generating has been reported already
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Pool {
+    /// Generates patterns whenever there are less than `capacity`, until the
+    /// cache is dropped.
+    fn fill(&self) {
+        let mut seeds = self.config.seed.map(ChaCha8Rng::seed_from_u64);
+        loop {
+            {
+                let state = self
+                    .taken
+                    .wait_while(self.state.lock().unwrap(), |state| {
+                        !state.stop && state.ready.len() >= self.capacity
+                    })
+                    .unwrap();
+                if state.stop {
+                    return;
+                }
+            }
+            let pattern = match &mut seeds {
+                Some(seeds) => self.config.clone().seed(seeds.next_u64()).generate(),
+                None => self.config.generate(),
+            };
+            self.state.lock().unwrap().ready.push_back(pattern);
+            self.added.notify_all();
+        }
+    }
+}
+
+/// `pattern` moved by a random offset along the wrapped axes of `config`,
+/// wrapping around at the borders.
+fn shifted<R: Rng>(pattern: &[Point], config: &BlueNoiseConfig, rng: &mut R) -> Vec<Point> {
+    let offsets: Vec<f64> = config
+        .dimensions
+        .iter()
+        .zip(config.wrapped_axes())
+        .map(|(dim, wrap)| {
+            if wrap {
+                rng.gen_range(0_f64..*dim)
+            } else {
+                0_f64
+            }
+        })
+        .collect();
+    pattern
+        .iter()
+        .map(|sample| {
+            sample
+                .iter()
+                .zip(offsets.iter().zip(config.dimensions.iter()))
+                .map(|(x, (offset, dim))| {
+                    let x = x + offset;
+                    if x >= *dim { x - dim } else { x }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_conflicts;
+
+    #[test]
+    fn cache() {
+        let config = BlueNoiseConfig::new(vec![16., 16.], 1.).seed(3);
+        let other = BlueNoiseConfig::new(vec![8.; 3], 1.);
+        let cache = PatternCache::new(vec![config.clone(), other.clone()], 2);
+        let first = cache.wait(&config);
+        let second = cache.wait(&config);
+        assert_ne!(first, second);
+        // patterns with seeds drawn from the seed of the configuration
+        let mut seeds = ChaCha8Rng::seed_from_u64(3);
+        assert_eq!(first, config.clone().seed(seeds.next_u64()).generate());
+        assert_eq!(second, config.clone().seed(seeds.next_u64()).generate());
+        assert_eq!(cache.wait(&other)[0].len(), 3);
+        // the threads fill the reservoir again
+        while cache.ready(&config) < 2 {
+            thread::yield_now();
+        }
+        assert!(cache.take(&config).is_some());
+        assert!(cache.take(&config).is_some());
+    }
+
+    #[test]
+    fn toroidal_shifts() {
+        let config = BlueNoiseConfig::new(vec![20., 10.], 1.).wrap(vec![true, false]);
+        let pattern = config.generate();
+        let moved = shifted(&pattern, &config, &mut rand::thread_rng());
+        assert_eq!(moved.len(), pattern.len());
+        for (moved, sample) in moved.iter().zip(pattern.iter()) {
+            assert!((0. ..20.).contains(&moved[0]));
+            assert_eq!(moved[1], sample[1]);
+        }
+        // closer only by rounding errors, also across the wrapped border
+        let tiled: Vec<Point> = moved
+            .iter()
+            .flat_map(|sample| {
+                [0., 20.]
+                    .into_iter()
+                    .map(|offset| Point::from_slice(&[sample[0] + offset, sample[1]]))
+            })
+            .collect();
+        assert!(find_conflicts(&tiled, 1. - 1e-9).is_empty());
+
+        let cache = PatternCache::new(vec![config.clone()], 1).toroidal_shifts(true);
+        cache.wait(&config);
+        // a new pattern or the last one moved, but never nothing
+        for _ in 0..10 {
+            assert!(cache.take(&config).is_some());
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! A compact spatial index of a finished set, for loading into other
+//! programs.
+
+use crate::{Point, PoissonDiskSet};
+use std::io::{self, Read, Write};
+
+/// The samples of a set bucketed into a regular grid of cells, stored as
+/// compressed sparse rows: the samples of cell `c` are
+/// `indices[offsets[c]..offsets[c + 1]]`. Cells are numbered with the first
+/// axis changing fastest.
+///
+/// Unlike the background grid, cells may hold any number of samples, so the
+/// cell size can be chosen to suit the queries at runtime. With a cell size
+/// of `min_distance`, the samples closer than that to a position are in its
+/// cell and the ones around it.
+///
+/// [`write_to`](CellLists::write_to) stores it in a binary layout which can
+/// be used without rebuilding anything, all values little endian:
+///
+/// | field         | type    | count               |
+/// |---------------|---------|---------------------|
+/// | magic `BNCL`  | bytes   | 4                   |
+/// | version, `1`  | `u32`   | 1                   |
+/// | dimension `d` | `u32`   | 1                   |
+/// | samples `n`   | `u32`   | 1                   |
+/// | min distance  | `f64`   | 1                   |
+/// | cell size     | `f64`   | 1                   |
+/// | domain size   | `f64`   | `d`                 |
+/// | cell counts   | `u32`   | `d`                 |
+/// | samples       | `f64`   | `n × d`             |
+/// | offsets       | `u32`   | number of cells + 1 |
+/// | indices       | `u32`   | `n`                 |
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellLists {
+    /// The size of the domain.
+    pub dimensions: Vec<f64>,
+    /// The minimal distance between samples.
+    pub min_distance: f64,
+    /// The edge length of the cells.
+    pub cell_size: f64,
+    /// The number of cells along each axis.
+    pub cell_count: Vec<usize>,
+    /// The samples, in order of generation.
+    pub samples: Vec<Point>,
+    /// Where the samples of each cell start in `indices`, and the end.
+    pub offsets: Vec<u32>,
+    /// The indices of the samples, grouped by cell.
+    pub indices: Vec<u32>,
+}
+
+const MAGIC: &[u8; 4] = b"BNCL";
+const VERSION: u32 = 1;
+
+impl PoissonDiskSet {
+    /// Builds the [`CellLists`] of the set with cells of edge length
+    /// `cell_size`.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+    /// let lists = set.cell_lists(1.);
+    /// let mut bytes = Vec::new();
+    /// lists.write_to(&mut bytes).unwrap();
+    /// // ... and in the engine
+    /// let loaded = bluenoisers::CellLists::read_from(&bytes[..]).unwrap();
+    /// for index in loaded.cell(&[3, 4]) {
+    ///     let sample = &loaded.samples[*index as usize];
+    ///     assert!((3. ..4.).contains(&sample[0]) && (4. ..5.).contains(&sample[1]));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` is not positive or if there are more samples or
+    /// cells than fit into a `u32`.
+    pub fn cell_lists(&self, cell_size: f64) -> CellLists {
+        assert!(cell_size > 0_f64, "the cell size must be positive");
+        let cell_count: Vec<usize> = self
+            .dimensions()
+            .iter()
+            .map(|x| ((x / cell_size).ceil() as usize).max(1))
+            .collect();
+        let total = cell_count
+            .iter()
+            .try_fold(1_usize, |accu, count| accu.checked_mul(*count))
+            .filter(|total| *total < u32::MAX as usize)
+            .expect("too many cells");
+        assert!(self.len() <= u32::MAX as usize, "too many samples");
+        let cells: Vec<usize> = self
+            .samples()
+            .iter()
+            .map(|sample| linear_cell(sample, cell_size, &cell_count))
+            .collect();
+        // counting sort by cell
+        let mut offsets = vec![0_u32; total + 1];
+        for cell in cells.iter() {
+            offsets[cell + 1] += 1;
+        }
+        for c in 0..total {
+            offsets[c + 1] += offsets[c];
+        }
+        let mut next = offsets.clone();
+        let mut indices = vec![0_u32; self.len()];
+        for (index, cell) in cells.iter().enumerate() {
+            indices[next[*cell] as usize] = index as u32;
+            next[*cell] += 1;
+        }
+        CellLists {
+            dimensions: self.dimensions().to_vec(),
+            min_distance: self.min_distance(),
+            cell_size,
+            cell_count,
+            samples: self.samples().to_vec(),
+            offsets,
+            indices,
+        }
+    }
+}
+
+/// The index of the cell containing `position`, clamped to the grid.
+fn linear_cell(position: &[f64], cell_size: f64, cell_count: &[usize]) -> usize {
+    position
+        .iter()
+        .zip(cell_count.iter())
+        .rev()
+        .fold(0, |accu, (x, count)| {
+            let c = ((x / cell_size).max(0_f64) as usize).min(count - 1);
+            accu * count + c
+        })
+}
+
+impl CellLists {
+    /// The indices of the samples in the cell with the coordinates `cell`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell` is outside of the grid.
+    pub fn cell(&self, cell: &[usize]) -> &[u32] {
+        assert!(
+            cell.len() == self.cell_count.len()
+                && cell.iter().zip(self.cell_count.iter()).all(|(c, n)| c < n),
+            "the cell is outside of the grid"
+        );
+        let c = cell
+            .iter()
+            .zip(self.cell_count.iter())
+            .rev()
+            .fold(0, |accu, (c, count)| accu * count + c);
+        &self.indices[self.offsets[c] as usize..self.offsets[c + 1] as usize]
+    }
+
+    /// Writes the binary layout described at [`CellLists`].
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            self.dimensions.len() as u32,
+            self.samples.len() as u32,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&self.min_distance.to_le_bytes())?;
+        writer.write_all(&self.cell_size.to_le_bytes())?;
+        for x in self.dimensions.iter() {
+            writer.write_all(&x.to_le_bytes())?;
+        }
+        for count in self.cell_count.iter() {
+            writer.write_all(&(*count as u32).to_le_bytes())?;
+        }
+        for x in self.samples.iter().flatten() {
+            writer.write_all(&x.to_le_bytes())?;
+        }
+        for value in self.offsets.iter().chain(self.indices.iter()) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads the binary layout written by [`write_to`](CellLists::write_to).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the data is not a valid
+    /// layout.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<CellLists> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut magic = [0_u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a cell list file"));
+        }
+        let read_u32 = |reader: &mut R| -> io::Result<u32> {
+            let mut bytes = [0_u8; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        if read_u32(&mut reader)? != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let dimension = read_u32(&mut reader)? as usize;
+        let count = read_u32(&mut reader)? as usize;
+        let read_f64 = |reader: &mut R| -> io::Result<f64> {
+            let mut bytes = [0_u8; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(f64::from_le_bytes(bytes))
+        };
+        let min_distance = read_f64(&mut reader)?;
+        let cell_size = read_f64(&mut reader)?;
+        let dimensions = (0..dimension)
+            .map(|_| read_f64(&mut reader))
+            .collect::<io::Result<Vec<f64>>>()?;
+        let cell_count = (0..dimension)
+            .map(|_| read_u32(&mut reader).map(|c| c as usize))
+            .collect::<io::Result<Vec<usize>>>()?;
+        let total = cell_count
+            .iter()
+            .try_fold(1_usize, |accu, count| accu.checked_mul(*count))
+            .filter(|total| *total < u32::MAX as usize)
+            .ok_or_else(|| invalid("too many cells"))?;
+        let samples = (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| read_f64(&mut reader))
+                    .collect::<io::Result<Point>>()
+            })
+            .collect::<io::Result<Vec<Point>>>()?;
+        let offsets = (0..=total)
+            .map(|_| read_u32(&mut reader))
+            .collect::<io::Result<Vec<u32>>>()?;
+        let indices = (0..count)
+            .map(|_| read_u32(&mut reader))
+            .collect::<io::Result<Vec<u32>>>()?;
+        if offsets[0] != 0
+            || offsets.windows(2).any(|w| w[0] > w[1])
+            || offsets[total] as usize != count
+            || indices.iter().any(|i| *i as usize >= count)
+        {
+            return Err(invalid("inconsistent cell lists"));
+        }
+        Ok(CellLists {
+            dimensions,
+            min_distance,
+            cell_size,
+            cell_count,
+            samples,
+            offsets,
+            indices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_lists() {
+        for dimensions in [vec![20., 10.], vec![5., 6., 4.]] {
+            let set = PoissonDiskSet::new(dimensions.clone(), 1., 30);
+            let lists = set.cell_lists(1.5);
+            assert_eq!(lists.indices.len(), set.len());
+            let mut seen = vec![false; set.len()];
+            for (c, window) in lists.offsets.windows(2).enumerate() {
+                for index in &lists.indices[window[0] as usize..window[1] as usize] {
+                    let sample = &set.samples()[*index as usize];
+                    assert_eq!(linear_cell(sample, 1.5, &lists.cell_count), c);
+                    seen[*index as usize] = true;
+                }
+            }
+            assert!(seen.iter().all(|s| *s));
+
+            let mut bytes = Vec::new();
+            lists.write_to(&mut bytes).unwrap();
+            assert_eq!(CellLists::read_from(&bytes[..]).unwrap(), lists);
+            let error = CellLists::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+            bytes[0] = b'X';
+            let error = CellLists::read_from(&bytes[..]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
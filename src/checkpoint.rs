@@ -0,0 +1,191 @@
+//! Saving the state of a generation in progress, to resume it later.
+
+use crate::grid::BackgroundGrid;
+use crate::{BlueNoiseIterator, GenerationStats, Interval, NeighborSearch, Point, Separation};
+use rand_chacha::ChaCha8Rng;
+
+/// The serialized form of a [`BlueNoiseIterator`], without the grid, which is
+/// rebuilt when deserializing.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct IteratorState<S, L, R> {
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    samples: S,
+    active: L,
+    active_idx: usize,
+    next_active: L,
+    region: Option<(Vec<f64>, Vec<f64>)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    wrap: Vec<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bounds: Vec<Interval>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    search: Option<NeighborSearch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    separation: Option<Separation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_distance: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gap_cell: Option<usize>,
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    reproducible: bool,
+    rng: R,
+    stats: GenerationStats,
+}
+
+/// Serializes everything needed to continue generating exactly the same
+/// samples: the samples so far, the active lists, the region, the wrapped
+/// axes, the bounds, the neighbor search, the separation, the maximal
+/// distance and the gaps left to fill, the reproducible mode, the state of
+/// the random number generator and the statistics.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, BlueNoiseIterator};
+///
+/// let config = BlueNoiseConfig::new(vec![20., 20.], 1.).seed(3);
+/// let mut it = config.iter();
+/// let first: Vec<_> = it.by_ref().take(50).collect();
+/// let checkpoint = serde_json::to_string(&it).unwrap();
+/// // ... after a restart
+/// let resumed: BlueNoiseIterator = serde_json::from_str(&checkpoint).unwrap();
+/// let rest: Vec<_> = resumed.collect();
+/// assert_eq!([first, rest].concat(), config.generate());
+/// ```
+///
+/// The background grid is rebuilt from the samples, on the heap, even if
+/// the iterator kept it in a file. Whether candidates were checked in
+/// parallel is not stored, so `parallel_candidates` has to be called again
+/// after resuming to get the same samples.
+///
+/// Requires the `serde` feature.
+impl serde::Serialize for BlueNoiseIterator {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        IteratorState {
+            dimensions: self.dimensions.clone(),
+            min_distance: self.min_distance,
+            k_abort: self.k_abort,
+            samples: &self.samples,
+            active: &self.active,
+            active_idx: self.active_idx,
+            next_active: &self.next_active,
+            region: self.region.clone(),
+            wrap: self.wrap.clone(),
+            bounds: self.bggrid.bounds.clone(),
+            search: (self.bggrid.search != NeighborSearch::Compact).then_some(self.bggrid.search),
+            separation: (self.bggrid.separation != Separation::AtLeast)
+                .then_some(self.bggrid.separation),
+            max_distance: (self.max_distance != 2_f64 * self.min_distance)
+                .then_some(self.max_distance),
+            gap_cell: self.gap_cell,
+            reproducible: self.reproducible,
+            rng: &self.rng,
+            stats: self.stats,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Restores an iterator serialized at any point during generation.
+///
+/// Fails if the samples violate the minimal distance or the active lists
+/// refer to samples which don't exist.
+///
+/// Requires the `serde` feature.
+impl<'de> serde::Deserialize<'de> for BlueNoiseIterator {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let state = IteratorState::<Vec<Point>, Vec<usize>, ChaCha8Rng>::deserialize(deserializer)?;
+        let mut bggrid = BackgroundGrid::new(state.dimensions.clone(), state.min_distance);
+        if !state.bounds.is_empty() && state.bounds.len() != state.dimensions.len() {
+            return Err(D::Error::custom("there must be an interval per dimension"));
+        }
+        bggrid.bounds = state.bounds;
+        bggrid.search = state.search.unwrap_or_default();
+        bggrid.separate(state.separation.unwrap_or_default());
+        let mut samples = Vec::with_capacity(state.samples.len());
+        for sample in state.samples {
+            if sample.len() != state.dimensions.len() {
+                return Err(D::Error::custom("a sample has the wrong dimension"));
+            }
+            bggrid
+                .insert(sample, &mut samples)
+                .map_err(|()| D::Error::custom("the samples are too close"))?;
+        }
+        // ids start at one
+        let valid = |id: &usize| (1..=samples.len()).contains(id);
+        if !state
+            .active
+            .iter()
+            .chain(state.next_active.iter())
+            .all(valid)
+        {
+            return Err(D::Error::custom("an active sample doesn't exist"));
+        }
+        let mut it = BlueNoiseIterator::with_grid(
+            state.dimensions,
+            state.min_distance,
+            state.k_abort,
+            bggrid,
+            Vec::new(),
+        );
+        it.samples = samples;
+        it.active = state.active;
+        it.active_idx = state.active_idx;
+        it.next_active = state.next_active;
+        if !state.wrap.is_empty() && state.wrap.len() != it.dimensions.len() {
+            return Err(D::Error::custom("there must be a wrap flag per dimension"));
+        }
+        it.region = state.region;
+        it.wrap = state.wrap;
+        if let Some(max_distance) = state.max_distance {
+            if !(max_distance.is_finite() && max_distance > it.min_distance) {
+                return Err(D::Error::custom(
+                    "the maximal distance must be more than the minimal distance",
+                ));
+            }
+            it.max_distance = max_distance;
+        }
+        it.gap_cell = state.gap_cell;
+        it.reproducible = state.reproducible;
+        it.rng = state.rng;
+        it.stats = state.stats;
+        Ok(it)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlueNoiseConfig, BlueNoiseIterator};
+
+    #[test]
+    fn resume() {
+        let config = BlueNoiseConfig::new(vec![15., 10., 5.], 1.).seed(11);
+        let wrapped = config.clone().wrap(vec![true, false, true]);
+        let bounded = config.clone().max_distance(1.1);
+        for config in [config, wrapped, bounded] {
+            let expected = config.generate();
+            for split in [0, 1, 2, 100, expected.len()] {
+                let mut it = config.iter();
+                let mut samples: Vec<_> = it.by_ref().take(split).collect();
+                let checkpoint = serde_json::to_string(&it).unwrap();
+                let resumed: BlueNoiseIterator = serde_json::from_str(&checkpoint).unwrap();
+                let stats = it.stats();
+                assert_eq!(resumed.stats(), stats);
+                samples.extend(resumed);
+                assert_eq!(samples, expected);
+            }
+        }
+
+        let config = BlueNoiseConfig::new(vec![15., 10., 5.], 1.).seed(11);
+        let mut it = config.iter();
+        it.by_ref().take(10).for_each(drop);
+        let mut checkpoint: serde_json::Value = serde_json::to_value(&it).unwrap();
+        checkpoint["active"] = serde_json::json!([11]);
+        assert!(serde_json::from_value::<BlueNoiseIterator>(checkpoint.clone()).is_err());
+        checkpoint["active"] = serde_json::json!([1]);
+        checkpoint["samples"][1] = checkpoint["samples"][0].clone();
+        assert!(serde_json::from_value::<BlueNoiseIterator>(checkpoint).is_err());
+    }
+}
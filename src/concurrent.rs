@@ -0,0 +1,346 @@
+//! A background grid which can be filled from several threads at once.
+
+use crate::Point;
+use crate::config::{ConfigError, Interval, check_domain};
+use crate::grid::{BackgroundGrid, check_size};
+use smallvec::SmallVec;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+const EMPTY: u8 = 0;
+/// The cell was claimed and its coordinates are being written.
+const WRITING: u8 = 1;
+/// The coordinates are written and the neighbors are being checked.
+const PENDING: u8 = 2;
+const COMMITTED: u8 = 3;
+
+/// A background grid which allows inserting samples concurrently through a
+/// shared reference, without locks.
+///
+/// This is the building block for generating blue noise on several threads
+/// in shared memory. Each cell holds at most one sample, together with its
+/// coordinates. Inserting a sample
+///
+/// 1. claims its cell, which fails if the cell is taken already,
+/// 2. publishes the coordinates,
+/// 3. checks all the neighboring cells for samples which are too close and
+/// 4. either commits the sample or rolls the claim back on a conflict.
+///
+/// Since every insertion publishes its sample before looking at the
+/// neighbors, of two conflicting insertions running at the same time at
+/// least one sees the other and backs off. It can happen that both back off.
+///
+/// ```
+/// use bluenoisers::ConcurrentGrid;
+///
+/// let grid = ConcurrentGrid::new(vec![10., 10.], 1.);
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| assert!(grid.try_insert(&[2., 2.])));
+///     scope.spawn(|| assert!(grid.try_insert(&[8., 8.])));
+/// });
+/// assert!(!grid.try_insert(&[2.5, 2.]));
+/// assert_eq!(grid.into_samples().len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentGrid {
+    /// Only used for its layout, its cells stay empty.
+    layout: BackgroundGrid,
+    /// How many cells in each direction have to be checked for conflicts.
+    cell_offs: usize,
+    states: Vec<AtomicU8>,
+    /// The bits of the coordinates of the sample in each cell.
+    coordinates: Vec<AtomicU64>,
+}
+
+impl ConcurrentGrid {
+    /// Creates an empty grid for a domain of size `dimensions` (see
+    /// [`blue_noise`](crate::blue_noise)) in which samples are at least
+    /// `min_distance` apart.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domain is not valid, see [`ConcurrentGrid::try_new`].
+    pub fn new(dimensions: Vec<f64>, min_distance: f64) -> ConcurrentGrid {
+        ConcurrentGrid::try_new(dimensions, min_distance).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates an empty grid, or fails if there are no dimensions, a size is
+    /// negative or not finite, `min_distance` is not positive and finite, or
+    /// the cells don't fit into memory. Unlike the background grid of the
+    /// generator, this grid is dense in any number of dimensions.
+    pub fn try_new(dimensions: Vec<f64>, min_distance: f64) -> Result<ConcurrentGrid, ConfigError> {
+        check_domain(&dimensions, min_distance)?;
+        check_size(&dimensions, min_distance)?;
+        let layout = BackgroundGrid::without_storage(dimensions, min_distance);
+        let cell_count = layout.data_size();
+        let dimension = layout.dimensions.len();
+        // each cell holds a state and the coordinates of its sample
+        let too_large = || ConfigError::DomainTooLarge {
+            cells: cell_count as f64,
+            bytes: cell_count as f64 * (1 + dimension * size_of::<u64>()) as f64,
+        };
+        let coordinate_count = cell_count.checked_mul(dimension).ok_or_else(too_large)?;
+        let mut states = Vec::new();
+        let mut coordinates = Vec::new();
+        // the size may still be more than the system can allocate
+        states
+            .try_reserve_exact(cell_count)
+            .and_then(|_| coordinates.try_reserve_exact(coordinate_count))
+            .map_err(|_| too_large())?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            cells = cell_count,
+            cell_size = layout.cell_size,
+            "allocating concurrent grid"
+        );
+        states.extend((0..cell_count).map(|_| AtomicU8::new(EMPTY)));
+        coordinates.extend((0..coordinate_count).map(|_| AtomicU64::new(0)));
+        Ok(ConcurrentGrid {
+            cell_offs: (min_distance / layout.cell_size).ceil() as usize,
+            states,
+            coordinates,
+            layout,
+        })
+    }
+
+    /// The size of the domain.
+    pub fn dimensions(&self) -> &[f64] {
+        &self.layout.dimensions
+    }
+
+    /// Tries to insert a sample at `position`.
+    ///
+    /// Returns `false` if `position` is outside of the domain or too close to
+    /// another sample, including ones being inserted at the same time.
+    pub fn try_insert(&self, position: &[f64]) -> bool {
+        let dimensions = &self.layout.dimensions;
+        let dimension = dimensions.len();
+        debug_assert_eq!(position.len(), dimension);
+        if position
+            .iter()
+            .zip(dimensions.iter())
+            .any(|(x, dim)| !Interval::ClosedOpen.contains(*x, *dim))
+        {
+            return false;
+        }
+        let cell_id: SmallVec<[usize; 4]> = position
+            .iter()
+            .map(|x| (*x / self.layout.cell_size) as usize)
+            .collect();
+        let own_idx = self.layout.calc_idx(&cell_id);
+        // two samples in one cell are always too close
+        if self.states[own_idx]
+            .compare_exchange(EMPTY, WRITING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+        let own_coordinates = &self.coordinates[own_idx * dimension..(own_idx + 1) * dimension];
+        for (slot, x) in own_coordinates.iter().zip(position.iter()) {
+            slot.store(x.to_bits(), Ordering::Relaxed);
+        }
+        self.states[own_idx].store(PENDING, Ordering::SeqCst);
+        let min_cell: SmallVec<[usize; 4]> = cell_id
+            .iter()
+            .map(|x| x.saturating_sub(self.cell_offs))
+            .collect();
+        let max_cell: SmallVec<[usize; 4]> = cell_id
+            .iter()
+            .zip(self.layout.cell_count.iter())
+            .map(|(x, size_x)| (x + self.cell_offs).min(size_x - 1))
+            .collect();
+        let mut indices = min_cell.clone();
+        loop {
+            let idx = self.layout.calc_idx(&indices);
+            // A cell which is still being written will check against us once
+            // it is pending, since we are pending already.
+            if idx != own_idx && self.states[idx].load(Ordering::SeqCst) >= PENDING {
+                let other = &self.coordinates[idx * dimension..(idx + 1) * dimension];
+                let dst_sqr = other
+                    .iter()
+                    .zip(position.iter())
+                    .fold(0_f64, |accu, (o, x)| {
+                        let diff = f64::from_bits(o.load(Ordering::Relaxed)) - x;
+                        accu + diff * diff
+                    });
+                if dst_sqr < self.layout.min_dst_sqr {
+                    self.states[own_idx].store(EMPTY, Ordering::SeqCst);
+                    return false;
+                }
+            }
+            // loop exit check
+            if indices == max_cell {
+                break;
+            }
+            // iterate indices
+            for i in 0..dimension {
+                if indices[i] == max_cell[i] {
+                    indices[i] = min_cell[i];
+                } else {
+                    indices[i] += 1;
+                    break;
+                }
+            }
+        }
+        self.states[own_idx].store(COMMITTED, Ordering::SeqCst);
+        true
+    }
+
+    /// The samples committed so far, in the order of their cells.
+    pub fn samples(&self) -> Vec<Point> {
+        let dimension = self.layout.dimensions.len();
+        self.states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| state.load(Ordering::SeqCst) == COMMITTED)
+            .map(|(idx, _)| {
+                self.coordinates[idx * dimension..(idx + 1) * dimension]
+                    .iter()
+                    .map(|x| f64::from_bits(x.load(Ordering::Relaxed)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Iterates over the committed samples on the threads of the current
+    /// rayon thread pool, in no particular order and without collecting them
+    /// first.
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_samples(&self) -> impl rayon::iter::ParallelIterator<Item = Point> + '_ {
+        use rayon::prelude::*;
+        let dimension = self.layout.dimensions.len();
+        self.states
+            .par_iter()
+            .enumerate()
+            .filter(|(_, state)| state.load(Ordering::SeqCst) == COMMITTED)
+            .map(move |(idx, _)| {
+                self.coordinates[idx * dimension..(idx + 1) * dimension]
+                    .iter()
+                    .map(|x| f64::from_bits(x.load(Ordering::Relaxed)))
+                    .collect()
+            })
+    }
+
+    /// The committed samples inside the half-open box from `lower` to
+    /// `upper`. The box may reach outside of the domain.
+    pub fn samples_in(&self, lower: &[f64], upper: &[f64]) -> Vec<Point> {
+        let dimension = self.layout.dimensions.len();
+        let cell_of = |x: f64, size_x: usize| {
+            ((x.max(0_f64) / self.layout.cell_size) as usize).min(size_x - 1)
+        };
+        let min_cell: SmallVec<[usize; 4]> = lower
+            .iter()
+            .zip(self.layout.cell_count.iter())
+            .map(|(x, size_x)| cell_of(*x, *size_x))
+            .collect();
+        let max_cell: SmallVec<[usize; 4]> = upper
+            .iter()
+            .zip(self.layout.cell_count.iter())
+            .map(|(x, size_x)| cell_of(*x, *size_x))
+            .collect();
+        let mut samples = Vec::new();
+        if self.states.is_empty() {
+            return samples;
+        }
+        let mut indices = min_cell.clone();
+        loop {
+            let idx = self.layout.calc_idx(&indices);
+            if self.states[idx].load(Ordering::SeqCst) == COMMITTED {
+                let sample: Point = self.coordinates[idx * dimension..(idx + 1) * dimension]
+                    .iter()
+                    .map(|x| f64::from_bits(x.load(Ordering::Relaxed)))
+                    .collect();
+                if sample
+                    .iter()
+                    .zip(lower.iter().zip(upper.iter()))
+                    .all(|(x, (lo, hi))| lo <= x && x < hi)
+                {
+                    samples.push(sample);
+                }
+            }
+            // loop exit check
+            if indices == max_cell {
+                break;
+            }
+            // iterate indices
+            for i in 0..dimension {
+                if indices[i] == max_cell[i] {
+                    indices[i] = min_cell[i];
+                } else {
+                    indices[i] += 1;
+                    break;
+                }
+            }
+        }
+        samples
+    }
+
+    /// Consumes the grid and returns its samples, see
+    /// [`samples`](ConcurrentGrid::samples).
+    pub fn into_samples(self) -> Vec<Point> {
+        self.samples()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn concurrent_darts() {
+        let radius = 1.;
+        let grid = ConcurrentGrid::new(vec![30., 20.], radius);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut rng = rand::thread_rng();
+                    for _ in 0..20000 {
+                        grid.try_insert(&[rng.gen_range(0_f64..30.), rng.gen_range(0_f64..20.)]);
+                    }
+                });
+            }
+        });
+        let samples = grid.into_samples();
+        assert!(samples.len() > 100);
+        for (i, s1) in samples.iter().enumerate() {
+            for s2 in samples.iter().skip(i + 1) {
+                assert!(BackgroundGrid::dst_sqr(s1, s2) >= radius * radius);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            ConcurrentGrid::try_new(vec![f64::NAN, 4.], 1.),
+            Err(ConfigError::InvalidDimension { axis: 0, .. })
+        ));
+        assert_eq!(
+            ConcurrentGrid::try_new(vec![4.], 0.).err(),
+            Some(ConfigError::InvalidMinDistance(0.))
+        );
+        assert!(matches!(
+            ConcurrentGrid::try_new(vec![1e12; 3], 1e-3),
+            Err(ConfigError::DomainTooLarge { .. })
+        ));
+        // addressable, but far more than any system can allocate
+        assert!(matches!(
+            ConcurrentGrid::try_new(vec![266.; 3], 1e-3),
+            Err(ConfigError::DomainTooLarge { .. })
+        ));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_samples() {
+        use rayon::prelude::*;
+        let grid = ConcurrentGrid::new(vec![10., 10.], 1.);
+        assert!(grid.try_insert(&[2., 2.]));
+        assert!(grid.try_insert(&[8., 3.]));
+        let mut samples: Vec<Point> = grid.par_samples().collect();
+        samples.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        assert_eq!(samples, grid.samples());
+    }
+}
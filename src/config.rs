@@ -0,0 +1,1014 @@
+//! A reusable description of what to generate.
+
+#[cfg(feature = "std")]
+use crate::PoissonDiskSet;
+use crate::grid::{SPARSE_DIMENSION, check_size};
+use crate::{BlueNoiseIterator, Point};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The parameters of a blue noise pattern.
+///
+/// This bundles the arguments of [`blue_noise`](crate::blue_noise) with
+/// further options, so patterns can be described once and generated
+/// repeatedly, e.g. in a batch with `blue_noise_batch` (`rayon` feature).
+///
+/// ```
+/// use bluenoisers::BlueNoiseConfig;
+///
+/// let config = BlueNoiseConfig::new(vec![64., 64.], 4.).seed(7);
+/// assert_eq!(config.generate(), config.generate());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlueNoiseConfig {
+    pub(crate) dimensions: Vec<f64>,
+    pub(crate) min_distance: f64,
+    pub(crate) k_abort: usize,
+    pub(crate) seed: Option<u64>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) wrap: Vec<bool>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) bounds: Vec<Interval>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) search: Option<NeighborSearch>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) separation: Option<Separation>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub(crate) border_correction: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub(crate) reproducible: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) max_distance: Option<f64>,
+}
+
+/// Which of the two borders of an axis belong to the domain, see
+/// [`BlueNoiseConfig::bounds`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interval {
+    /// `[0, size)`, the default, so that copies of the domain can be put
+    /// side by side without sharing a border.
+    #[default]
+    ClosedOpen,
+    /// `[0, size]`
+    Closed,
+    /// `(0, size)`
+    Open,
+    /// `(0, size]`
+    OpenClosed,
+}
+
+impl Interval {
+    /// Whether `x` is in the interval along an axis of length `size`. An axis
+    /// of length 0 is flat and only contains 0, whatever the interval.
+    pub fn contains(self, x: f64, size: f64) -> bool {
+        if size == 0_f64 {
+            return x == 0_f64;
+        }
+        let (lower, upper) = match self {
+            Interval::ClosedOpen => (0_f64 <= x, x < size),
+            Interval::Closed => (0_f64 <= x, x <= size),
+            Interval::Open => (0_f64 < x, x < size),
+            Interval::OpenClosed => (0_f64 < x, x <= size),
+        };
+        lower && upper
+    }
+}
+
+/// How the generator looks for samples closer than the minimal distance to a
+/// candidate, see [`BlueNoiseConfig::neighbor_search`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NeighborSearch {
+    /// Visits the cells at most `ceil(sqrt(n))` cells away along each axis
+    /// in `n` dimensions, and in high dimensions only the adjacent cells
+    /// which are closer than the minimal distance. The fastest, and the
+    /// default.
+    #[default]
+    Compact,
+    /// Visits all the cells which the box around the minimal distance
+    /// overlaps, computed the same way as the cells of the samples, so that
+    /// no conflict is missed regardless of rounding. A little slower, for
+    /// uses which need the guarantee.
+    Conservative,
+    /// Compares the candidate with every sample. Takes quadratic time, to
+    /// check the other strategies on small domains.
+    Exhaustive,
+}
+
+/// Whether samples may be exactly the minimal distance apart, see
+/// [`BlueNoiseConfig::separation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Separation {
+    /// At least the minimal distance apart, so discs of that diameter may
+    /// touch. The default.
+    #[default]
+    AtLeast,
+    /// More than the minimal distance apart, so discs of that diameter never
+    /// touch.
+    MoreThan,
+}
+
+/// Why a configuration can't be generated, see
+/// [`BlueNoiseConfig::try_new`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// There are no dimensions.
+    NoDimensions,
+    /// The size of the domain along an axis is negative or not finite. An
+    /// axis can be thinner than the minimal distance or even flat, see
+    /// [`BlueNoiseConfig::new`].
+    InvalidDimension {
+        /// The index of the axis.
+        axis: usize,
+        /// Its size.
+        size: f64,
+    },
+    /// The minimal distance is not positive and finite.
+    InvalidMinDistance(f64),
+    /// `k_abort` is zero, so no sample would get neighbors.
+    ZeroKAbort,
+    /// The maximal distance to the closest sample is not finite and more
+    /// than the minimal distance, see [`BlueNoiseConfig::max_distance`].
+    InvalidMaxDistance(f64),
+    /// The background grid would take more memory than can be allocated,
+    /// since the domain is too large for the minimal distance.
+    DomainTooLarge {
+        /// The number of cells the grid would have.
+        cells: f64,
+        /// The number of bytes they would take.
+        bytes: f64,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NoDimensions => write!(f, "the domain needs at least one dimension"),
+            ConfigError::InvalidDimension { axis, size } => write!(
+                f,
+                "the size of the domain along axis {axis} must be finite and not negative, not {size}"
+            ),
+            ConfigError::InvalidMinDistance(min_distance) => write!(
+                f,
+                "the minimal distance must be positive and finite, not {min_distance}"
+            ),
+            ConfigError::ZeroKAbort => write!(f, "k_abort must be at least one"),
+            ConfigError::InvalidMaxDistance(max_distance) => write!(
+                f,
+                "the maximal distance must be finite and more than the minimal distance, not \
+                 {max_distance}"
+            ),
+            ConfigError::DomainTooLarge { cells, bytes } => write!(
+                f,
+                "the domain is too large for the minimal distance: the background grid would \
+                 need {cells:.3e} cells taking {bytes:.3e} bytes"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConfigError {}
+
+/// Checks that a domain of size `dimensions` can be filled with samples at
+/// least `min_distance` apart.
+pub(crate) fn check_domain(dimensions: &[f64], min_distance: f64) -> Result<(), ConfigError> {
+    if dimensions.is_empty() {
+        return Err(ConfigError::NoDimensions);
+    }
+    if let Some((axis, size)) = dimensions
+        .iter()
+        .enumerate()
+        .find(|(_, size)| !(size.is_finite() && **size >= 0_f64))
+    {
+        return Err(ConfigError::InvalidDimension { axis, size: *size });
+    }
+    if !(min_distance.is_finite() && min_distance > 0_f64) {
+        return Err(ConfigError::InvalidMinDistance(min_distance));
+    }
+    Ok(())
+}
+
+impl BlueNoiseConfig {
+    /// Creates a configuration for samples at least `min_distance` apart in
+    /// a domain of size `dimensions`, see [`blue_noise`](crate::blue_noise).
+    ///
+    /// `k_abort` defaults to 30 and the random number generator is seeded
+    /// from the operating system. Without the `std` feature, there is none,
+    /// so a [seed](BlueNoiseConfig::seed) or a random number generator has to
+    /// be given, see [`iter_with_rng`](BlueNoiseConfig::iter_with_rng).
+    ///
+    /// Axes shorter than `min_distance`, like the thickness of a thin slab,
+    /// leave no room to keep samples apart along them. The samples are then
+    /// spread out along the other axes only, as in a domain of fewer
+    /// dimensions, and placed at random along the short ones. Along an axis
+    /// of size 0, all samples are at 0, so a domain of size `[50, 50, 0]` is
+    /// a plane in 3D.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, find_conflicts};
+    ///
+    /// let samples = BlueNoiseConfig::new(vec![50., 50., 0.5], 1.).generate();
+    /// assert!(samples.iter().all(|s| s[2] < 0.5));
+    /// assert!(find_conflicts(&samples, 1.).is_empty());
+    /// ```
+    pub fn new(dimensions: Vec<f64>, min_distance: f64) -> BlueNoiseConfig {
+        BlueNoiseConfig {
+            dimensions,
+            min_distance,
+            k_abort: 30,
+            seed: None,
+            wrap: Vec::new(),
+            bounds: Vec::new(),
+            search: None,
+            separation: None,
+            border_correction: false,
+            reproducible: false,
+            max_distance: None,
+        }
+    }
+
+    /// Like [`new`](BlueNoiseConfig::new), but fails if there are no
+    /// dimensions, a size is negative or not finite, or `min_distance` is not
+    /// positive and finite, e.g. for parameters coming from users.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, ConfigError};
+    ///
+    /// assert!(BlueNoiseConfig::try_new(vec![64., 64.], 4.).is_ok());
+    /// assert_eq!(
+    ///     BlueNoiseConfig::try_new(vec![64., f64::NAN], 4.).unwrap_err().to_string(),
+    ///     "the size of the domain along axis 1 must be finite and not negative, not NaN"
+    /// );
+    /// assert_eq!(
+    ///     BlueNoiseConfig::try_new(Vec::new(), 4.),
+    ///     Err(ConfigError::NoDimensions)
+    /// );
+    /// ```
+    pub fn try_new(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+    ) -> Result<BlueNoiseConfig, ConfigError> {
+        check_domain(&dimensions, min_distance)?;
+        Ok(BlueNoiseConfig::new(dimensions, min_distance))
+    }
+
+    /// Checks all the parameters, as [`try_new`](BlueNoiseConfig::try_new)
+    /// does, that `k_abort` is not zero and that the background grid can be
+    /// addressed, without allocating it.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        check_domain(&self.dimensions, self.min_distance)?;
+        if self.k_abort == 0 {
+            return Err(ConfigError::ZeroKAbort);
+        }
+        self.check_max_distance()?;
+        if self.dimensions.len() <= SPARSE_DIMENSION {
+            check_size(&self.dimensions, self.min_distance)?;
+        }
+        Ok(())
+    }
+
+    fn check_max_distance(&self) -> Result<(), ConfigError> {
+        match self.max_distance {
+            Some(max_distance)
+                if !(max_distance.is_finite() && max_distance > self.min_distance) =>
+            {
+                Err(ConfigError::InvalidMaxDistance(max_distance))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets how often the generator tries to find a new neighbor of a sample
+    /// before giving up on it, see [`blue_noise`](crate::blue_noise).
+    pub fn k_abort(mut self, k_abort: usize) -> BlueNoiseConfig {
+        self.k_abort = k_abort;
+        self
+    }
+
+    /// Makes generation reproducible: the same seed yields the same samples.
+    pub fn seed(mut self, seed: u64) -> BlueNoiseConfig {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Like [`seed`](BlueNoiseConfig::seed), but also makes the samples
+    /// bit-identical on every operating system and architecture.
+    ///
+    /// By default, candidates are placed at random angles around a sample,
+    /// computed with `sin` and `cos` of the platform's math library, which
+    /// may differ in the last bit between platforms, and every difference
+    /// changes all the following samples. In this mode, candidates are
+    /// instead drawn uniformly from the spherical shell by rejection, using
+    /// only additions, multiplications and comparisons, which IEEE 754
+    /// defines exactly. The random numbers come from ChaCha8, whose output is
+    /// specified, and no entropy of the operating system is used. With
+    /// [`parallel_candidates`](crate::BlueNoiseIterator::parallel_candidates),
+    /// the samples don't depend on the number of threads either.
+    ///
+    /// The samples differ from the ones of the same seed without this mode.
+    /// Since the shell fills less and less of the cube, drawing candidates
+    /// gets slow above about eight dimensions.
+    ///
+    /// ```
+    /// use bluenoisers::BlueNoiseConfig;
+    ///
+    /// let config = BlueNoiseConfig::new(vec![8., 8.], 1.).reproducible(42);
+    /// assert!(config.is_reproducible());
+    /// assert_eq!(config.generate(), config.generate());
+    /// ```
+    pub fn reproducible(mut self, seed: u64) -> BlueNoiseConfig {
+        self.seed = Some(seed);
+        self.reproducible = true;
+        self
+    }
+
+    /// Whether the samples are bit-identical on all platforms, see
+    /// [`reproducible`](BlueNoiseConfig::reproducible).
+    pub fn is_reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// Makes the pattern periodic along the axes for which `wrap` is true:
+    /// samples close to one border keep the minimal distance to the ones at
+    /// the opposite border, as if the domain wrapped around, so copies of the
+    /// pattern can be tiled side by side without visible seams.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, Point, find_conflicts};
+    ///
+    /// let samples = BlueNoiseConfig::new(vec![16., 16.], 1.)
+    ///     .wrap(vec![true, true])
+    ///     .generate();
+    /// // the pattern next to a copy of itself
+    /// let shifted = samples.iter().map(|s| Point::from_slice(&[s[0] + 16., s[1]]));
+    /// let tiled: Vec<_> = samples.iter().cloned().chain(shifted).collect();
+    /// assert!(find_conflicts(&tiled, 1.).is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `wrap` doesn't have an entry per dimension or a wrapped axis
+    /// is shorter than twice the minimal distance.
+    pub fn wrap(mut self, wrap: Vec<bool>) -> BlueNoiseConfig {
+        assert_eq!(
+            wrap.len(),
+            self.dimensions.len(),
+            "there must be a wrap flag per dimension"
+        );
+        assert!(
+            wrap.iter()
+                .zip(self.dimensions.iter())
+                .all(|(wrap, dim)| !wrap || *dim >= 2_f64 * self.min_distance),
+            "a wrapped axis must be at least twice the minimal distance long"
+        );
+        assert!(
+            self.bounds.is_empty()
+                || wrap
+                    .iter()
+                    .zip(self.bounds.iter())
+                    .all(|(wrap, bounds)| !wrap || *bounds == Interval::ClosedOpen),
+            "a wrapped axis must be half-open"
+        );
+        self.wrap = if wrap.contains(&true) {
+            wrap
+        } else {
+            Vec::new()
+        };
+        self
+    }
+
+    /// Sets which borders of each axis belong to the domain. By default,
+    /// samples may lie on the lower border but not on the upper one, see
+    /// [`Interval`]. Closing the upper border e.g. lets samples which were
+    /// snapped to a pixel grid be [inserted](PoissonDiskSet::try_insert) at
+    /// the far edge.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, Interval, Placement};
+    ///
+    /// let config = BlueNoiseConfig::new(vec![16., 16.], 1.).seed(2);
+    /// let corner = [16., 16.];
+    /// assert_eq!(config.generate_set().placement(&corner), Placement::Outside);
+    /// let closed = config.bounds(vec![Interval::Closed; 2]).generate_set();
+    /// assert_ne!(closed.placement(&corner), Placement::Outside);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bounds` doesn't have an entry per dimension or a
+    /// [wrapped](BlueNoiseConfig::wrap) axis is not half-open, since its
+    /// upper border is the lower one.
+    pub fn bounds(mut self, bounds: Vec<Interval>) -> BlueNoiseConfig {
+        assert_eq!(
+            bounds.len(),
+            self.dimensions.len(),
+            "there must be an interval per dimension"
+        );
+        assert!(
+            self.wrap.is_empty()
+                || bounds
+                    .iter()
+                    .zip(self.wrap.iter())
+                    .all(|(bounds, wrap)| !wrap || *bounds == Interval::ClosedOpen),
+            "a wrapped axis must be half-open"
+        );
+        self.bounds = if bounds.iter().all(|b| *b == Interval::ClosedOpen) {
+            Vec::new()
+        } else {
+            bounds
+        };
+        self
+    }
+
+    /// Makes the density near the borders of the domain match the one
+    /// inside.
+    ///
+    /// Samples next to a border have no neighbors beyond it, so more of them
+    /// fit there: a plain pattern is about twice as dense within a quarter of
+    /// the minimal distance from a border, and slightly sparser right after.
+    /// With the correction, the pattern is generated as if the domain wrapped
+    /// around along every axis which is half-open and at least twice the
+    /// minimal distance long, so samples near a border see neighbors beyond
+    /// it just like the ones inside. The samples are a valid pattern which
+    /// is also [periodic](BlueNoiseConfig::wrap), but
+    /// [`wrapped_axes`](BlueNoiseConfig::wrapped_axes) only reports the axes
+    /// set to wrap.
+    ///
+    /// ```
+    /// use bluenoisers::BlueNoiseConfig;
+    ///
+    /// let config = BlueNoiseConfig::new(vec![32., 32.], 1.).seed(8);
+    /// let at_border = |samples: Vec<bluenoisers::Point>| {
+    ///     samples.iter().filter(|s| s[0] < 0.25).count()
+    /// };
+    /// let corrected = config.clone().border_correction(true).generate();
+    /// assert!(at_border(corrected) < at_border(config.generate()));
+    /// ```
+    pub fn border_correction(mut self, correct: bool) -> BlueNoiseConfig {
+        self.border_correction = correct;
+        self
+    }
+
+    /// The axes along which the generator wraps around, which are more
+    /// than [`wrapped_axes`](BlueNoiseConfig::wrapped_axes) with the
+    /// [border correction](BlueNoiseConfig::border_correction).
+    fn generated_wrap(&self) -> Vec<bool> {
+        if !self.border_correction {
+            return self.wrap.clone();
+        }
+        let wrap: Vec<bool> = self
+            .intervals()
+            .iter()
+            .zip(self.dimensions.iter())
+            .zip(self.wrapped_axes())
+            .map(|((bounds, dim), wrap)| {
+                wrap || (*bounds == Interval::ClosedOpen && *dim >= 2_f64 * self.min_distance)
+            })
+            .collect();
+        if wrap.contains(&true) {
+            wrap
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Sets how candidates are checked against the samples nearby, see
+    /// [`NeighborSearch`]. All strategies give the same samples unless
+    /// rounding makes the compact one miss a conflict.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, NeighborSearch};
+    ///
+    /// let config = BlueNoiseConfig::new(vec![16., 16.], 1.).seed(4);
+    /// let conservative = config.clone().neighbor_search(NeighborSearch::Conservative);
+    /// assert_eq!(conservative.generate(), config.generate());
+    /// ```
+    pub fn neighbor_search(mut self, search: NeighborSearch) -> BlueNoiseConfig {
+        self.search = (search != NeighborSearch::Compact).then_some(search);
+        self
+    }
+
+    /// Sets whether samples may be exactly the minimal distance apart, see
+    /// [`Separation`]. Generated samples hardly ever are, but positions
+    /// [inserted](PoissonDiskSet::try_insert) into a set, e.g. snapped to a
+    /// grid, often are.
+    ///
+    /// ```
+    /// use bluenoisers::{BlueNoiseConfig, Separation};
+    ///
+    /// let samples = BlueNoiseConfig::new(vec![8., 8.], 1.)
+    ///     .separation(Separation::MoreThan)
+    ///     .generate();
+    /// for (i, a) in samples.iter().enumerate() {
+    ///     for b in &samples[..i] {
+    ///         let dst_sqr: f64 = a.iter().zip(b.iter()).map(|(a, b)| (a - b).powi(2)).sum();
+    ///         assert!(dst_sqr > 1.);
+    ///     }
+    /// }
+    /// ```
+    pub fn separation(mut self, separation: Separation) -> BlueNoiseConfig {
+        self.separation = (separation != Separation::AtLeast).then_some(separation);
+        self
+    }
+
+    /// Sets how far every position of the domain may be from its closest
+    /// sample, for meshes which need an upper bound on the spacing as well
+    /// as the lower one.
+    ///
+    /// New samples are placed at least the minimal distance and less than
+    /// `max_distance` away from a sample, instead of up to twice the minimal
+    /// distance, so they pack more tightly: with 1.2 times the minimal
+    /// distance, a pattern in 2D has about 19 % more samples. Once growth
+    /// stops, the gaps farther than `max_distance` from all samples get a
+    /// sample each, counted as [`filled`](crate::GenerationStats::filled),
+    /// and growth continues from there, so no ball larger than
+    /// `max_distance` with its center in the domain is empty, up to six
+    /// dimensions. The gaps are probed more finely the closer
+    /// `max_distance` is to the minimal distance: in 3D, 1.02 times takes
+    /// over a hundred times as long as 1.2 times.
+    ///
+    /// ```
+    /// use bluenoisers::BlueNoiseConfig;
+    ///
+    /// let set = BlueNoiseConfig::new(vec![20., 20.], 1.)
+    ///     .seed(1)
+    ///     .max_distance(1.2)
+    ///     .generate_set();
+    /// assert!(set.largest_empty_ball().unwrap().1 <= 1.2);
+    /// ```
+    ///
+    /// This is checked by [`check`](BlueNoiseConfig::check); a
+    /// `max_distance` which is not more than the minimal distance makes
+    /// [`iter`](BlueNoiseConfig::iter) and
+    /// [`generate`](BlueNoiseConfig::generate) panic.
+    pub fn max_distance(mut self, max_distance: f64) -> BlueNoiseConfig {
+        self.max_distance = Some(max_distance);
+        self
+    }
+
+    /// The size of the domain.
+    pub fn dimensions(&self) -> &[f64] {
+        &self.dimensions
+    }
+
+    /// The minimal distance between samples.
+    pub fn min_distance(&self) -> f64 {
+        self.min_distance
+    }
+
+    /// Whether the domain wraps around along each axis, see
+    /// [`wrap`](BlueNoiseConfig::wrap).
+    pub fn wrapped_axes(&self) -> Vec<bool> {
+        if self.wrap.is_empty() {
+            vec![false; self.dimensions.len()]
+        } else {
+            self.wrap.clone()
+        }
+    }
+
+    /// Which borders of each axis belong to the domain, see
+    /// [`bounds`](BlueNoiseConfig::bounds).
+    pub fn intervals(&self) -> Vec<Interval> {
+        if self.bounds.is_empty() {
+            vec![Interval::ClosedOpen; self.dimensions.len()]
+        } else {
+            self.bounds.clone()
+        }
+    }
+
+    fn iter_with_seed(&self, seed: Option<u64>) -> BlueNoiseIterator {
+        self.try_iter_with_seed(seed)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_iter_with_seed(&self, seed: Option<u64>) -> Result<BlueNoiseIterator, ConfigError> {
+        self.check_max_distance()?;
+        let mut it =
+            BlueNoiseIterator::try_new(self.dimensions.clone(), self.min_distance, self.k_abort)?;
+        if let Some(seed) = seed {
+            it.rng = ChaCha8Rng::seed_from_u64(seed);
+        }
+        it.wrap = self.generated_wrap();
+        it.bggrid.bounds = self.bounds.clone();
+        it.bggrid.search = self.search.unwrap_or_default();
+        it.bggrid.separate(self.separation.unwrap_or_default());
+        it.reproducible = self.reproducible;
+        if let Some(max_distance) = self.max_distance {
+            it.max_distance = max_distance;
+            it.gap_cell = Some(0);
+        }
+        Ok(it)
+    }
+
+    /// Creates an iterator generating the samples on demand.
+    ///
+    /// # Panics
+    ///
+    /// Without the `std` feature, panics if there is no seed.
+    pub fn iter(&self) -> BlueNoiseIterator {
+        #[cfg(not(feature = "std"))]
+        assert!(
+            self.seed.is_some(),
+            "without the std feature, a seed or a random number generator is needed"
+        );
+        self.iter_with_seed(self.seed)
+    }
+
+    /// Like [`iter`](BlueNoiseConfig::iter), but fails instead of panicking
+    /// or generating nonsense if the parameters are not valid, see
+    /// [`check`](BlueNoiseConfig::check).
+    ///
+    /// # Panics
+    ///
+    /// Without the `std` feature, panics if there is no seed.
+    pub fn try_iter(&self) -> Result<BlueNoiseIterator, ConfigError> {
+        self.check()?;
+        #[cfg(not(feature = "std"))]
+        assert!(
+            self.seed.is_some(),
+            "without the std feature, a seed or a random number generator is needed"
+        );
+        self.try_iter_with_seed(self.seed)
+    }
+
+    /// Like [`iter`](BlueNoiseConfig::iter), but if there is no seed, draws
+    /// one from `rng`, e.g. a hardware random number generator on a
+    /// microcontroller.
+    ///
+    /// ```
+    /// use bluenoisers::BlueNoiseConfig;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+    /// let config = BlueNoiseConfig::new(vec![64., 64.], 4.);
+    /// assert!(config.iter_with_rng(&mut rng).count() > 100);
+    /// ```
+    pub fn iter_with_rng<R: RngCore>(&self, rng: &mut R) -> BlueNoiseIterator {
+        self.iter_with_seed(Some(self.seed.unwrap_or_else(|| rng.next_u64())))
+    }
+
+    /// Generates all the samples, in order of generation.
+    ///
+    /// # Panics
+    ///
+    /// Without the `std` feature, panics if there is no seed.
+    pub fn generate(&self) -> Vec<Point> {
+        let mut it = self.iter();
+        while it.next_ref().is_some() {}
+        it.samples
+    }
+
+    /// Like [`generate`](BlueNoiseConfig::generate), but if there is no seed,
+    /// draws one from `rng`.
+    pub fn generate_with_rng<R: RngCore>(&self, rng: &mut R) -> Vec<Point> {
+        let mut it = self.iter_with_rng(rng);
+        while it.next_ref().is_some() {}
+        it.samples
+    }
+
+    /// Generates all the samples as a [`PoissonDiskSet`], which supports
+    /// spatial queries.
+    #[cfg(feature = "std")]
+    pub fn generate_set(&self) -> PoissonDiskSet {
+        PoissonDiskSet::generate(self)
+    }
+}
+
+/// Generates many independent patterns on the threads of the current rayon
+/// thread pool.
+///
+/// The patterns are returned in the order of `configs`. Each one has its own
+/// random number generator, so seeded configurations yield the same result as
+/// [`BlueNoiseConfig::generate`].
+///
+/// To create variations of one pattern, derive the configurations from seeds:
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, blue_noise_batch};
+///
+/// let base = BlueNoiseConfig::new(vec![32., 32.], 2.);
+/// let configs: Vec<_> = (0..8).map(|seed| base.clone().seed(seed)).collect();
+/// let patterns = blue_noise_batch(&configs);
+/// assert_eq!(patterns[3], configs[3].generate());
+/// ```
+///
+/// Requires the `rayon` feature.
+#[cfg(feature = "rayon")]
+pub fn blue_noise_batch(configs: &[BlueNoiseConfig]) -> Vec<Vec<Point>> {
+    use rayon::prelude::*;
+    configs.par_iter().map(BlueNoiseConfig::generate).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        let config = BlueNoiseConfig::new(vec![40., 30.], 2.).k_abort(20);
+        let first = config.clone().seed(1).generate();
+        assert_eq!(first, config.clone().seed(1).generate());
+        assert_ne!(first, config.clone().seed(2).generate());
+
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let drawn = config.generate_with_rng(&mut rng);
+        assert_ne!(drawn, config.generate_with_rng(&mut rng));
+        // a seed takes precedence over the random number generator
+        assert_eq!(config.clone().seed(1).generate_with_rng(&mut rng), first);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn wrap() {
+        use crate::find_conflicts;
+
+        for wrap in [vec![true, true], vec![false, true]] {
+            let config = BlueNoiseConfig::new(vec![20., 12.], 1.)
+                .seed(4)
+                .wrap(wrap.clone());
+            let samples = config.generate();
+            assert!(samples.iter().all(|s| s[0] < 20. && s[1] < 12.));
+            // 3 x 3 copies, across all borders and corners
+            let tiled: Vec<Point> = (0..9)
+                .flat_map(|copy| {
+                    let shift = [(copy % 3) as f64 * 20., (copy / 3) as f64 * 12.];
+                    samples
+                        .iter()
+                        .map(move |s| Point::from_slice(&[s[0] + shift[0], s[1] + shift[1]]))
+                })
+                .collect();
+            let conflicts = find_conflicts(&tiled, 1.);
+            if wrap[0] {
+                assert!(conflicts.is_empty());
+            } else {
+                // only across the borders along x, which doesn't wrap
+                assert!(!conflicts.is_empty());
+                assert!(
+                    conflicts
+                        .iter()
+                        .all(|(a, b)| a / samples.len() % 3 != b / samples.len() % 3)
+                );
+            }
+            assert_eq!(config.wrapped_axes(), wrap);
+        }
+        assert_eq!(
+            BlueNoiseConfig::new(vec![3., 3.], 1.).wrap(vec![false, false]),
+            BlueNoiseConfig::new(vec![3., 3.], 1.)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reproducible() {
+        use crate::find_conflicts;
+
+        // FNV-1a over the bits of all coordinates
+        let hash = |samples: &[Point]| {
+            samples
+                .iter()
+                .flat_map(|s| s.iter())
+                .fold(0xcbf2_9ce4_8422_2325_u64, |hash, x| {
+                    (hash ^ x.to_bits()).wrapping_mul(0x0100_0000_01b3)
+                })
+        };
+        for (dimensions, expected) in [
+            (vec![16., 16.], 0xff9c_675b_b531_0ed3),
+            (vec![5., 4., 3.], 0xc473_c3f9_bbb5_bc7c),
+        ] {
+            let default = BlueNoiseConfig::new(dimensions, 1.).seed(42);
+            let config = default.clone().reproducible(42);
+            let samples = config.generate();
+            assert!(find_conflicts(&samples, 1.).is_empty());
+            // the same on every platform
+            assert_eq!(hash(&samples), expected, "{}", hash(&samples));
+            assert_ne!(samples, default.generate());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn thin() {
+        use crate::find_conflicts;
+
+        let plane = BlueNoiseConfig::new(vec![40., 40.], 1.).seed(3).generate();
+        for thickness in [0.5, 1e-6] {
+            let slab = BlueNoiseConfig::new(vec![40., 40., thickness], 1.)
+                .seed(3)
+                .generate();
+            assert!(slab.iter().all(|s| (0. ..thickness).contains(&s[2])));
+            assert!(find_conflicts(&slab, 1.).is_empty());
+            // as dense as the plane, or denser since samples can also be
+            // apart across the slab
+            assert!(slab.len() as f64 > 0.98 * plane.len() as f64);
+        }
+        // a flat axis, all samples are at 0 along it
+        let flat = BlueNoiseConfig::new(vec![40., 40., 0.], 1.).seed(3);
+        assert!(flat.check().is_ok());
+        let samples = flat.generate();
+        assert!(samples.iter().all(|s| s[2] == 0.));
+        assert!(find_conflicts(&samples, 1.).is_empty());
+        assert!(samples.len() as f64 > 0.98 * plane.len() as f64);
+        assert!(crate::PoissonDiskSet::from_points(vec![40., 40., 0.], 1., samples).is_ok());
+        let point = BlueNoiseConfig::new(vec![0.5, 0.5], 1.).seed(3).generate();
+        assert_eq!(point.len(), 1);
+        let point = BlueNoiseConfig::new(vec![0., 0.], 1.).seed(3).generate();
+        assert_eq!(point, [Point::from_slice(&[0., 0.])]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn bounds() {
+        use crate::Placement;
+
+        // a whole number of grid cells along x
+        let width = 10. * (1. / 2_f64.sqrt());
+        let config = BlueNoiseConfig::new(vec![width, 6.], 1.).seed(5);
+        let borders = [[width, 3.], [0., 3.], [4., 6.], [4., 0.]];
+        let outside = |config: &BlueNoiseConfig| {
+            let set = config.generate_set();
+            borders.map(|border| set.placement(&border) == Placement::Outside)
+        };
+        assert_eq!(outside(&config), [true, false, true, false]);
+        let bounds = vec![Interval::OpenClosed, Interval::Open];
+        let half_open = config.clone().bounds(bounds.clone());
+        assert_eq!(half_open.intervals(), bounds);
+        assert_eq!(outside(&half_open), [false, true, true, true]);
+
+        let closed = config.bounds(vec![Interval::Closed; 2]);
+        assert_eq!(outside(&closed), [false; 4]);
+        let mut set = closed.generate_set();
+        let inserted = (0..=60)
+            .filter(|y| set.try_insert(Point::from_slice(&[width, f64::from(*y) / 10.])))
+            .count();
+        assert!(inserted > 0);
+        // the samples on the closed borders are valid for the bounds only
+        let samples = set.samples().to_vec();
+        assert!(crate::validate(&samples, 1., &[width, 6.]).is_err());
+        let loaded = PoissonDiskSet::from_points_with_bounds(
+            vec![width, 6.],
+            1.,
+            closed.intervals(),
+            samples.clone(),
+        )
+        .unwrap();
+        assert_eq!(loaded.placement(&[width, 3.]), set.placement(&[width, 3.]));
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&loaded).unwrap();
+            let loaded: PoissonDiskSet = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.samples(), samples);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn border_correction() {
+        use crate::find_conflicts;
+
+        // the density within a quarter of the minimal distance from the
+        // borders, relative to the whole domain
+        let at_border = |config: BlueNoiseConfig| {
+            let (mut border, mut all) = (0, 0);
+            for seed in 0..4 {
+                let samples = config.clone().seed(seed).generate();
+                assert!(find_conflicts(&samples, 1.).is_empty());
+                all += samples.len();
+                border += samples
+                    .iter()
+                    .filter(|s| s.iter().any(|x| *x < 0.25 || *x >= 39.75))
+                    .count();
+            }
+            let area = 40_f64 * 40. - 39.5 * 39.5;
+            border as f64 / area / (all as f64 / 1600.)
+        };
+        let config = BlueNoiseConfig::new(vec![40., 40.], 1.);
+        assert!(at_border(config.clone()) > 1.5);
+        let corrected = at_border(config.clone().border_correction(true));
+        assert!((0.8..1.2).contains(&corrected));
+        assert_eq!(
+            config.border_correction(true).wrapped_axes(),
+            [false, false]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let error = |dimensions: Vec<f64>, min_distance| {
+            BlueNoiseConfig::try_new(dimensions, min_distance).unwrap_err()
+        };
+        assert_eq!(error(vec![], 1.), ConfigError::NoDimensions);
+        assert_eq!(
+            error(vec![4., f64::INFINITY], 1.),
+            ConfigError::InvalidDimension {
+                axis: 1,
+                size: f64::INFINITY
+            }
+        );
+        assert_eq!(
+            error(vec![-1., 4.], 1.),
+            ConfigError::InvalidDimension { axis: 0, size: -1. }
+        );
+        assert_eq!(error(vec![4.], 0.), ConfigError::InvalidMinDistance(0.));
+        assert!(matches!(
+            error(vec![4.], f64::NAN),
+            ConfigError::InvalidMinDistance(d) if d.is_nan()
+        ));
+        let config = BlueNoiseConfig::try_new(vec![4., 4.], 1.).unwrap().seed(1);
+        assert!(config.try_iter().is_ok());
+        assert_eq!(
+            config.k_abort(0).try_iter().err(),
+            Some(ConfigError::ZeroKAbort)
+        );
+        assert!(crate::grid::BackgroundGrid::try_new(vec![1.; 8], -1.).is_err());
+
+        let huge = BlueNoiseConfig::new(vec![1e12; 3], 1e-3);
+        let Err(ConfigError::DomainTooLarge { cells, bytes }) = huge.check() else {
+            panic!("the grid of {huge:?} is too large");
+        };
+        assert!(cells > 1e45 && bytes == cells * size_of::<usize>() as f64);
+        assert!(huge.try_iter().is_err());
+        // sparse grids have no cells up front
+        assert!(BlueNoiseConfig::new(vec![1e12; 7], 1e-3).check().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn max_distance() {
+        let config = BlueNoiseConfig::new(vec![30., 20.], 1.).seed(6);
+        for max_distance in [1.1, 1.5] {
+            let bounded = config.clone().max_distance(max_distance);
+            assert!(bounded.check().is_ok());
+            let samples = bounded.generate();
+            assert!(samples.len() > config.generate().len());
+            for (i, a) in samples.iter().enumerate() {
+                let closest = samples
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, b)| crate::grid::BackgroundGrid::dst_sqr(a, b))
+                    .fold(f64::INFINITY, f64::min);
+                assert!((1. ..max_distance * max_distance).contains(&closest));
+            }
+        }
+        // the gaps are filled, also across thin axes
+        let mut filled = 0;
+        for dimensions in [vec![30., 20.], vec![8., 6., 5.], vec![20., 10., 0.5]] {
+            for max_distance in [1.05, 1.3] {
+                let bounded = BlueNoiseConfig::new(dimensions.clone(), 1.)
+                    .seed(7)
+                    .max_distance(max_distance);
+                let set = bounded.generate_set();
+                assert!(set.largest_empty_ball().unwrap().1 <= max_distance);
+                let mut it = bounded.iter();
+                while it.next_ref().is_some() {}
+                filled += it.stats().filled;
+            }
+        }
+        assert!(filled > 0);
+        for invalid in [1., 0.5, f64::NAN] {
+            let invalid = config.clone().max_distance(invalid);
+            assert!(matches!(
+                invalid.check(),
+                Err(ConfigError::InvalidMaxDistance(_))
+            ));
+            assert!(matches!(
+                invalid.try_iter_with_seed(None),
+                Err(ConfigError::InvalidMaxDistance(_))
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrap_too_short() {
+        BlueNoiseConfig::new(vec![10., 1.5], 1.).wrap(vec![false, true]);
+    }
+}
@@ -0,0 +1,125 @@
+//! How well a set covers its domain.
+
+use crate::raster::pixel_centers;
+use crate::{Point, PoissonDiskSet};
+
+impl PoissonDiskSet {
+    /// Finds the largest ball without samples whose center lies in the
+    /// domain, and returns its center and radius. The radius is also known as
+    /// the coverage radius: every point of the domain is at most that far
+    /// from a sample.
+    ///
+    /// A set is maximal, i.e. no sample can be added anymore, if the radius
+    /// is less than `min_distance`. Returns `None` for empty sets.
+    ///
+    /// In 2D, the result is exact: the center is the corner of a Voronoi cell
+    /// which is farthest from its sample. In other dimensions, the distance
+    /// to the nearest sample is first evaluated on a raster with a spacing of
+    /// half the minimal distance and then maximized locally around the best
+    /// raster points, so the radius may be slightly too small.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![30., 30.], 1., 30);
+    /// let (center, radius) = set.largest_empty_ball().unwrap();
+    /// // Bridson's algorithm leaves only small gaps
+    /// assert!(radius < 2.);
+    /// assert_eq!(set.nearest(&center).unwrap().1, radius);
+    /// ```
+    pub fn largest_empty_ball(&self) -> Option<(Point, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.dimensions().len() == 2 {
+            return (0..self.len())
+                .flat_map(|index| self.voronoi_cell(index))
+                .map(|corner| {
+                    let corner = Point::from_slice(&corner);
+                    let radius = self.nearest(&corner).unwrap().1;
+                    (corner, radius)
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        }
+        let spacing = self.min_distance() / 2_f64;
+        let resolution: Vec<usize> = self
+            .dimensions()
+            .iter()
+            .map(|x| ((x / spacing).ceil() as usize).max(1))
+            .collect();
+        let mut candidates: Vec<(Point, f64)> = pixel_centers(self.dimensions(), &resolution)
+            .map(|center| {
+                let radius = self.nearest(&center).unwrap().1;
+                (center, radius)
+            })
+            .collect();
+        candidates.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        candidates.truncate(16);
+        candidates
+            .into_iter()
+            .map(|(center, radius)| self.climb(center, radius, spacing))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Moves `center` inside the domain as long as that increases its
+    /// distance `radius` to the nearest sample, with steps starting at `step`
+    /// and getting smaller.
+    fn climb(&self, mut center: Point, mut radius: f64, mut step: f64) -> (Point, f64) {
+        let min_step = self.min_distance() * 1e-9;
+        while step > min_step {
+            let mut improved = false;
+            for axis in 0..center.len() {
+                for direction in [-1_f64, 1_f64] {
+                    let mut moved = center.clone();
+                    moved[axis] =
+                        (moved[axis] + direction * step).clamp(0_f64, self.dimensions()[axis]);
+                    let moved_radius = self.nearest(&moved).unwrap().1;
+                    if moved_radius > radius {
+                        center = moved;
+                        radius = moved_radius;
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                step /= 2_f64;
+            }
+        }
+        (center, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn largest_empty_ball() {
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let (center, radius) = set.largest_empty_ball().unwrap();
+        assert!(radius < 2.);
+        // no point on a fine raster is farther away from the samples
+        let field = set.rasterize_distance_field(&[400, 200]);
+        assert!(field.iter().all(|distance| *distance <= radius));
+        assert!(field.iter().any(|distance| *distance > 0.9 * radius));
+        assert!(center.iter().all(|x| *x >= 0.));
+
+        // a single sample leaves the farthest corner of the domain empty
+        let single = PoissonDiskSet::new(vec![2., 3., 2.], 100., 30);
+        assert_eq!(single.len(), 1);
+        let (center, radius) = single.largest_empty_ball().unwrap();
+        let expected = single.samples()[0]
+            .iter()
+            .zip([2., 3., 2.].iter())
+            .map(|(s, x)| s.max(x - s).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!((radius - expected).abs() < 1e-6);
+        assert!(
+            center
+                .iter()
+                .zip([2., 3., 2.].iter())
+                .all(|(c, x)| *c == 0. || c == x)
+        );
+    }
+}
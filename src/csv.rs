@@ -0,0 +1,236 @@
+//! Delimited text files of point sets, for spreadsheets and data analysis.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// How [`export_csv`] writes a point set, with one line per sample and one
+/// column per dimension.
+///
+/// Optionally, there are further columns: the index of the sample first and
+/// a radius and a class per sample after the coordinates.
+///
+/// ```
+/// use bluenoisers::CsvFormat;
+///
+/// let samples = [[1., 2.], [3.25, 4.]];
+/// let format = CsvFormat::new('\t').precision(2).ids(true).classes(vec![0, 1]);
+/// let mut tsv = Vec::new();
+/// format.write(&mut tsv, &samples).unwrap();
+/// assert_eq!(
+///     String::from_utf8(tsv).unwrap(),
+///     "id\tx\ty\tclass\n0\t1.00\t2.00\t0\n1\t3.25\t4.00\t1\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvFormat {
+    delimiter: char,
+    precision: Option<usize>,
+    header: bool,
+    ids: bool,
+    radii: Option<Vec<f64>>,
+    classes: Option<Vec<usize>>,
+}
+
+impl CsvFormat {
+    /// Creates a format with columns separated by `delimiter`, e.g. `','`
+    /// for CSV or `'\t'` for TSV.
+    ///
+    /// By default, there is a header line, numbers are written with as many
+    /// digits as needed to read them back exactly and there are no further
+    /// columns.
+    pub fn new(delimiter: char) -> CsvFormat {
+        CsvFormat {
+            delimiter,
+            precision: None,
+            header: true,
+            ids: false,
+            radii: None,
+            classes: None,
+        }
+    }
+
+    /// Writes coordinates and radii with `digits` digits after the decimal
+    /// point.
+    pub fn precision(mut self, digits: usize) -> CsvFormat {
+        self.precision = Some(digits);
+        self
+    }
+
+    /// Sets whether the first line names the columns. The coordinates are
+    /// named `x`, `y` and `z` up to three dimensions and `x0`, `x1`, … above.
+    pub fn header(mut self, header: bool) -> CsvFormat {
+        self.header = header;
+        self
+    }
+
+    /// Sets whether the first column is the index of the sample, named `id`.
+    pub fn ids(mut self, ids: bool) -> CsvFormat {
+        self.ids = ids;
+        self
+    }
+
+    /// Adds a column `radius` with one value per sample.
+    pub fn radii(mut self, radii: Vec<f64>) -> CsvFormat {
+        self.radii = Some(radii);
+        self
+    }
+
+    /// Adds a column `class` with one value per sample.
+    pub fn classes(mut self, classes: Vec<usize>) -> CsvFormat {
+        self.classes = Some(classes);
+        self
+    }
+
+    /// Writes `samples` to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the samples don't all have the same number of coordinates
+    /// or if the radii or classes are not one per sample.
+    pub fn write<P: AsRef<[f64]>, W: Write>(&self, mut writer: W, samples: &[P]) -> io::Result<()> {
+        for (name, len) in [
+            ("radii", self.radii.as_ref().map(Vec::len)),
+            ("classes", self.classes.as_ref().map(Vec::len)),
+        ] {
+            if let Some(len) = len {
+                assert_eq!(
+                    len,
+                    samples.len(),
+                    "there must be one of the {name} per sample"
+                );
+            }
+        }
+        let dimension = samples.first().map_or(0, |s| s.as_ref().len());
+        let number = |x: f64| match self.precision {
+            Some(digits) => format!("{x:.digits$}"),
+            None => x.to_string(),
+        };
+        let delimiter = self.delimiter.to_string();
+        let end_line = |writer: &mut W, line: &mut Vec<String>| {
+            writeln!(writer, "{}", line.join(&delimiter))?;
+            line.clear();
+            io::Result::Ok(())
+        };
+        let mut line: Vec<String> = Vec::new();
+        if self.header {
+            if self.ids {
+                line.push("id".to_string());
+            }
+            for axis in 0..dimension {
+                line.push(if dimension <= 3 {
+                    ["x", "y", "z"][axis].to_string()
+                } else {
+                    format!("x{axis}")
+                });
+            }
+            if self.radii.is_some() {
+                line.push("radius".to_string());
+            }
+            if self.classes.is_some() {
+                line.push("class".to_string());
+            }
+            end_line(&mut writer, &mut line)?;
+        }
+        for (index, sample) in samples.iter().enumerate() {
+            let sample = sample.as_ref();
+            assert_eq!(
+                sample.len(),
+                dimension,
+                "all samples must have the same number of coordinates"
+            );
+            if self.ids {
+                line.push(index.to_string());
+            }
+            line.extend(sample.iter().map(|x| number(*x)));
+            if let Some(radii) = &self.radii {
+                line.push(number(radii[index]));
+            }
+            if let Some(classes) = &self.classes {
+                line.push(classes[index].to_string());
+            }
+            end_line(&mut writer, &mut line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `samples` as delimited text to `path`, see [`CsvFormat`].
+///
+/// ```no_run
+/// use bluenoisers::{CsvFormat, export_csv};
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 10.], 1., 30);
+/// export_csv("samples.csv", &samples, &CsvFormat::new(',').precision(4)).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if the samples don't all have the same number of coordinates or
+/// if the radii or classes are not one per sample.
+pub fn export_csv<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    format: &CsvFormat,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    format.write(&mut writer, samples)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples as delimited text, see [`export_csv`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radii or classes of `format` are not one per sample.
+    pub fn export_csv(&self, path: impl AsRef<Path>, format: &CsvFormat) -> io::Result<()> {
+        export_csv(path, self.samples(), format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write() {
+        let write = |format: &CsvFormat, samples: &[[f64; 4]]| {
+            let mut text = Vec::new();
+            format.write(&mut text, samples).unwrap();
+            String::from_utf8(text).unwrap()
+        };
+        let samples = [[0.1, 2., 3., 4.5], [1. / 3., 0., 0., 1e-7]];
+        assert_eq!(
+            write(&CsvFormat::new(','), &samples),
+            "x0,x1,x2,x3\n0.1,2,3,4.5\n0.3333333333333333,0,0,0.0000001\n"
+        );
+        let format = CsvFormat::new(';')
+            .precision(1)
+            .header(false)
+            .radii(vec![0.75, 1.]);
+        assert_eq!(
+            write(&format, &samples),
+            "0.1;2.0;3.0;4.5;0.8\n0.3;0.0;0.0;0.0;1.0\n"
+        );
+    }
+
+    #[test]
+    fn export_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.csv");
+        let set = PoissonDiskSet::new(vec![8., 6., 4.], 1., 30);
+        let format = CsvFormat::new(',').radii(vec![set.min_distance(); set.len()]);
+        set.export_csv(&path, &format).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("x,y,z,radius"));
+        for (line, sample) in lines.zip(set.samples()) {
+            let values: Vec<f64> = line.split(',').map(|x| x.parse().unwrap()).collect();
+            assert_eq!(values[..3], sample[..]);
+            assert_eq!(values[3], 1.);
+        }
+        assert_eq!(text.lines().count(), set.len() + 1);
+    }
+}
@@ -0,0 +1,96 @@
+//! Delaunay triangulations of 2D sets.
+
+use crate::PoissonDiskSet;
+use spade::{DelaunayTriangulation, HasPosition, Point2, Triangulation};
+
+/// A sample as a vertex of the triangulation, remembering its index.
+struct Vertex {
+    position: Point2<f64>,
+    index: usize,
+}
+
+impl HasPosition for Vertex {
+    type Scalar = f64;
+
+    fn position(&self) -> Point2<f64> {
+        self.position
+    }
+}
+
+impl PoissonDiskSet {
+    /// Computes the Delaunay triangulation of the set.
+    ///
+    /// Returns the triangles as the indices of their corners in the samples,
+    /// in counter-clockwise order. The triangles cover the convex hull of the
+    /// samples.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+    /// for [a, b, c] in set.delaunay() {
+    ///     let (a, b, c) = (&set.samples()[a], &set.samples()[b], &set.samples()[c]);
+    ///     // counter-clockwise
+    ///     assert!((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) > 0.);
+    /// }
+    /// ```
+    ///
+    /// Requires the `delaunay` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2-dimensional.
+    pub fn delaunay(&self) -> Vec<[usize; 3]> {
+        assert_eq!(
+            self.dimensions().len(),
+            2,
+            "Delaunay triangulations are only supported in 2D"
+        );
+        let mut triangulation: DelaunayTriangulation<Vertex> = DelaunayTriangulation::new();
+        for (index, sample) in self.samples().iter().enumerate() {
+            triangulation
+                .insert(Vertex {
+                    position: Point2::new(sample[0], sample[1]),
+                    index,
+                })
+                .expect("samples are finite and distinct");
+        }
+        triangulation
+            .inner_faces()
+            .map(|face| face.vertices().map(|vertex| vertex.data().index))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::BackgroundGrid;
+
+    #[test]
+    fn empty_circumcircles() {
+        let set = PoissonDiskSet::new(vec![20., 15.], 1., 30);
+        let triangles = set.delaunay();
+        // Euler: a triangulation of n points with h of them on the hull has
+        // 2n - 2 - h triangles
+        assert!(triangles.len() > set.len());
+        assert!(triangles.len() < 2 * set.len());
+        for [a, b, c] in triangles {
+            let p = |i: usize| &set.samples()[i];
+            let (ax, ay, bx, by, cx, cy) = (p(a)[0], p(a)[1], p(b)[0], p(b)[1], p(c)[0], p(c)[1]);
+            let d = 2. * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+            let center = [
+                ((ax * ax + ay * ay) * (by - cy)
+                    + (bx * bx + by * by) * (cy - ay)
+                    + (cx * cx + cy * cy) * (ay - by))
+                    / d,
+                ((ax * ax + ay * ay) * (cx - bx)
+                    + (bx * bx + by * by) * (ax - cx)
+                    + (cx * cx + cy * cy) * (bx - ax))
+                    / d,
+            ];
+            let radius = BackgroundGrid::dst_sqr(&center, p(a)).sqrt();
+            assert_eq!(set.within(&center, radius * (1. - 1e-9)).count(), 0);
+        }
+    }
+}
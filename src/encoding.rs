@@ -0,0 +1,245 @@
+//! Compact encodings of the sample coordinates, e.g. for GPU upload.
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use alloc::vec::Vec;
+
+/// A compact representation of a single coordinate.
+///
+/// See [`blue_noise_encoded`](crate::blue_noise_encoded).
+pub trait Encoding {
+    /// The type a coordinate is encoded as.
+    type Scalar: Copy;
+
+    /// Encodes coordinate `x` of an axis which is `extent` long, i.e.
+    /// `0 <= x < extent`.
+    fn encode(x: f64, extent: f64) -> Self::Scalar;
+}
+
+/// Single precision floats, in domain units.
+#[derive(Clone, Copy, Debug)]
+pub struct F32;
+
+impl Encoding for F32 {
+    type Scalar = f32;
+
+    fn encode(x: f64, _extent: f64) -> f32 {
+        x as f32
+    }
+}
+
+/// Half precision floats, in domain units.
+///
+/// Note that half floats only have 11 significant bits, so this is only
+/// sensible for small domains. Requires the `half` feature.
+#[cfg(feature = "half")]
+#[derive(Clone, Copy, Debug)]
+pub struct F16;
+
+#[cfg(feature = "half")]
+impl Encoding for F16 {
+    type Scalar = half::f16;
+
+    fn encode(x: f64, _extent: f64) -> half::f16 {
+        half::f16::from_f64(x)
+    }
+}
+
+/// Unsigned 16 bit integers normalized to the domain, i.e. `0` is the lower
+/// bound and `65535` the upper bound of each axis.
+#[derive(Clone, Copy, Debug)]
+pub struct UNorm16;
+
+impl Encoding for UNorm16 {
+    type Scalar = u16;
+
+    fn encode(x: f64, extent: f64) -> u16 {
+        ((x / extent).clamp(0_f64, 1_f64) * u16::MAX as f64).round() as u16
+    }
+}
+
+/// One part of a record in a [`VertexLayout`].
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    /// The coordinates of the sample.
+    Position,
+    /// The same value for every sample.
+    Constant(f32),
+    /// One value per sample.
+    Values(Vec<f32>),
+    /// This many zeros.
+    Padding(usize),
+}
+
+/// The layout of an interleaved `f32` buffer with one record per sample,
+/// matching the vertex or storage buffer layouts of shaders, so that the
+/// result can be copied to the GPU as is.
+///
+/// The fields are written in the order they were added. For example, a
+/// `vec4<f32>` per sample with the position in `xyz` and the radius in `w`,
+/// followed by the class as a `f32`, padded to 8 floats:
+///
+/// ```
+/// use bluenoisers::VertexLayout;
+///
+/// let samples = [[1., 2., 3.], [4., 5., 6.]];
+/// let layout = VertexLayout::new()
+///     .position()
+///     .constant(0.5)
+///     .values(vec![0., 1.])
+///     .stride(8);
+/// assert_eq!(layout.record_size(3), 8);
+/// assert_eq!(
+///     layout.interleave(&samples),
+///     [
+///         1., 2., 3., 0.5, 0., 0., 0., 0., //
+///         4., 5., 6., 0.5, 1., 0., 0., 0.,
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VertexLayout {
+    fields: Vec<Field>,
+    stride: Option<usize>,
+}
+
+impl VertexLayout {
+    /// Creates a layout without fields.
+    pub fn new() -> VertexLayout {
+        VertexLayout::default()
+    }
+
+    /// Adds the coordinates of the sample, as single precision floats in
+    /// domain units.
+    pub fn position(mut self) -> VertexLayout {
+        self.fields.push(Field::Position);
+        self
+    }
+
+    /// Adds `value` to every record, e.g. the minimal distance as radius.
+    pub fn constant(mut self, value: f32) -> VertexLayout {
+        self.fields.push(Field::Constant(value));
+        self
+    }
+
+    /// Adds one of `values` to each record, in order of the samples, e.g. a
+    /// radius or a class per sample.
+    pub fn values(mut self, values: Vec<f32>) -> VertexLayout {
+        self.fields.push(Field::Values(values));
+        self
+    }
+
+    /// Adds `count` zeros, e.g. to align the next field.
+    pub fn padding(mut self, count: usize) -> VertexLayout {
+        self.fields.push(Field::Padding(count));
+        self
+    }
+
+    /// Pads each record with zeros to `stride` floats. Without it, records
+    /// are tightly packed.
+    pub fn stride(mut self, stride: usize) -> VertexLayout {
+        self.stride = Some(stride);
+        self
+    }
+
+    /// The number of floats per record for samples with `dimension`
+    /// coordinates. The stride in bytes is four times that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fields don't fit into the stride.
+    pub fn record_size(&self, dimension: usize) -> usize {
+        let packed = self
+            .fields
+            .iter()
+            .map(|field| match field {
+                Field::Position => dimension,
+                Field::Constant(_) | Field::Values(_) => 1,
+                Field::Padding(count) => *count,
+            })
+            .sum();
+        match self.stride {
+            Some(stride) => {
+                assert!(packed <= stride, "the fields don't fit into the stride");
+                stride
+            }
+            None => packed,
+        }
+    }
+
+    /// Writes one record per sample.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the samples don't all have the same number of coordinates,
+    /// if there are not as many values as samples or if the fields don't fit
+    /// into the stride.
+    pub fn interleave<P: AsRef<[f64]>>(&self, samples: &[P]) -> Vec<f32> {
+        for field in &self.fields {
+            if let Field::Values(values) = field {
+                assert_eq!(
+                    values.len(),
+                    samples.len(),
+                    "there must be one value per sample"
+                );
+            }
+        }
+        let dimension = samples.first().map_or(0, |s| s.as_ref().len());
+        let size = self.record_size(dimension);
+        let mut buffer = Vec::with_capacity(size * samples.len());
+        for (index, sample) in samples.iter().enumerate() {
+            let sample = sample.as_ref();
+            assert_eq!(
+                sample.len(),
+                dimension,
+                "all samples must have the same number of coordinates"
+            );
+            let start = buffer.len();
+            for field in &self.fields {
+                match field {
+                    Field::Position => buffer.extend(sample.iter().map(|x| *x as f32)),
+                    Field::Constant(value) => buffer.push(*value),
+                    Field::Values(values) => buffer.push(values[index]),
+                    Field::Padding(count) => buffer.extend(core::iter::repeat_n(0_f32, *count)),
+                }
+            }
+            buffer.resize(start + size, 0_f32);
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unorm16() {
+        assert_eq!(UNorm16::encode(0., 10.), 0);
+        assert_eq!(UNorm16::encode(5., 10.), 32768);
+        assert_eq!(UNorm16::encode(9.99999999, 10.), u16::MAX);
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn f16() {
+        assert_eq!(F16::encode(1.5, 10.), half::f16::from_f32(1.5));
+    }
+
+    #[test]
+    fn vertex_layout() {
+        let samples = [[1., 2.], [3., 4.]];
+        let layout = VertexLayout::new().padding(1).position().constant(7.);
+        assert_eq!(layout.record_size(2), 4);
+        assert_eq!(
+            layout.interleave(&samples),
+            [0., 1., 2., 7., 0., 3., 4., 7.]
+        );
+        let padded = layout.stride(6);
+        assert_eq!(padded.record_size(2), 6);
+        assert_eq!(padded.interleave(&samples).len(), 12);
+        assert_eq!(padded.interleave(&samples)[6..10], [0., 3., 4., 7.]);
+        let empty: [[f64; 2]; 0] = [];
+        assert!(VertexLayout::new().position().interleave(&empty).is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+//! Timestamps of events with blue noise spacing.
+
+use crate::BlueNoiseIterator;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Generates the times of events in `[0, duration)`, at least `min_gap`
+/// apart, in ascending order, e.g. when to spawn raindrops or sparks or how
+/// to jitter notifications.
+///
+/// Without a `count`, the events fill the duration, so there is no gap of
+/// more than about twice `min_gap`. With a `count`, events are removed from
+/// that, always one from the closest pair, until `count` are left, which
+/// keeps them evenly spread. If `count` events don't fit, all that fit are
+/// returned.
+///
+/// ```
+/// let mut rng = rand::thread_rng();
+/// // 20 raindrops in the next 10 seconds, at least 0.1 seconds apart
+/// let drops = bluenoisers::event_times(10., 0.1, Some(20), &mut rng);
+/// assert_eq!(drops.len(), 20);
+/// assert!(drops.windows(2).all(|t| t[1] - t[0] >= 0.1));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `duration` or `min_gap` are not positive.
+pub fn event_times<R: Rng>(
+    duration: f64,
+    min_gap: f64,
+    count: Option<usize>,
+    rng: &mut R,
+) -> Vec<f64> {
+    assert!(duration > 0., "the duration must be positive");
+    assert!(min_gap > 0., "the minimal gap must be positive");
+    let mut it = BlueNoiseIterator::new(vec![duration], min_gap, 30);
+    it.rng = ChaCha8Rng::seed_from_u64(rng.next_u64());
+    let mut times: Vec<f64> = it.map(|sample| sample[0]).collect();
+    times.sort_by(f64::total_cmp);
+    let count = count.unwrap_or(times.len());
+    while times.len() > count.max(1) {
+        let gap = |i: usize| times[i + 1] - times[i];
+        let closest = (0..times.len() - 1)
+            .min_by(|a, b| gap(*a).total_cmp(&gap(*b)))
+            .expect("there are two events");
+        // of the closest pair, remove the one closer to its other neighbor
+        let before = closest.checked_sub(1).map_or(f64::INFINITY, gap);
+        let after = if closest + 2 < times.len() {
+            gap(closest + 1)
+        } else {
+            f64::INFINITY
+        };
+        times.remove(if before < after { closest } else { closest + 1 });
+    }
+    times.truncate(count);
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_times() {
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let all = super::event_times(10., 0.1, None, &mut rng);
+        assert!(all.windows(2).all(|t| t[1] - t[0] >= 0.1));
+        assert!((50..100).contains(&all.len()));
+        assert!(all[0] >= 0. && all[all.len() - 1] < 10.);
+
+        let few = super::event_times(10., 0.1, Some(10), &mut rng);
+        assert_eq!(few.len(), 10);
+        // evenly spread, without long pauses
+        assert!(few.windows(2).all(|t| t[1] - t[0] < 2.5));
+        // more events than fit
+        let seeded = || ChaCha8Rng::seed_from_u64(5);
+        assert_eq!(
+            super::event_times(1., 0.4, Some(10), &mut seeded()),
+            super::event_times(1., 0.4, None, &mut seeded())
+        );
+        assert!(super::event_times(1., 0.1, Some(0), &mut rng).is_empty());
+        assert_eq!(super::event_times(1., 0.1, Some(1), &mut rng).len(), 1);
+    }
+}
@@ -0,0 +1,225 @@
+//! A C interface to the generator, for engines and plugins in other
+//! languages. The declarations are in `include/bluenoisers.h`, which is
+//! generated with `cbindgen --output include/bluenoisers.h`. Build the
+//! shared library with
+//!
+//! ```text
+//! cargo rustc --release --lib --crate-type cdylib --features ffi
+//! ```
+//!
+//! Invalid arguments never abort the caller: they give empty results.
+
+use crate::{BlueNoiseConfig, BlueNoiseIterator};
+
+/// Samples returned by [`bn_generate`], `count` × `dimension` coordinates,
+/// sample by sample. Free them with [`bn_points_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct BnPoints {
+    /// The coordinates, or null if there are no samples.
+    pub coordinates: *mut f64,
+    /// The number of samples.
+    pub count: usize,
+    /// The number of coordinates per sample.
+    pub dimension: usize,
+}
+
+impl BnPoints {
+    fn empty(dimension: usize) -> BnPoints {
+        BnPoints {
+            coordinates: std::ptr::null_mut(),
+            count: 0,
+            dimension,
+        }
+    }
+}
+
+/// An iterator generating samples on demand, created by [`bn_iter_new`] and
+/// freed with [`bn_iter_free`].
+pub struct BnIterator {
+    inner: BlueNoiseIterator,
+    dimension: usize,
+}
+
+/// The configuration for the arguments shared by [`bn_generate`] and
+/// [`bn_iter_new`], if they are valid.
+///
+/// # Safety
+///
+/// `dimensions` must point to `dimension` values if `dimension` is not zero.
+unsafe fn config(
+    dimensions: *const f64,
+    dimension: usize,
+    min_distance: f64,
+    k_abort: usize,
+    seed: u64,
+) -> Option<BlueNoiseConfig> {
+    if dimensions.is_null() || dimension == 0 {
+        return None;
+    }
+    // SAFETY: guaranteed by the caller
+    let dimensions = unsafe { std::slice::from_raw_parts(dimensions, dimension) };
+    let valid = dimensions.iter().all(|d| d.is_finite() && *d > 0.)
+        && min_distance.is_finite()
+        && min_distance > 0.
+        && k_abort > 0;
+    valid.then(|| {
+        BlueNoiseConfig::new(dimensions.to_vec(), min_distance)
+            .k_abort(k_abort)
+            .seed(seed)
+    })
+}
+
+/// Generates all samples in a domain of `dimension` axes with the sizes
+/// `dimensions`, see `blue_noise` in Rust. The same seed yields the same
+/// samples.
+///
+/// Gives no samples if `dimensions` is null, a size or `min_distance` is not
+/// positive or `k_abort` is zero.
+///
+/// # Safety
+///
+/// `dimensions` must point to `dimension` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bn_generate(
+    dimensions: *const f64,
+    dimension: usize,
+    min_distance: f64,
+    k_abort: usize,
+    seed: u64,
+) -> BnPoints {
+    // SAFETY: guaranteed by the caller
+    let Some(config) = (unsafe { config(dimensions, dimension, min_distance, k_abort, seed) })
+    else {
+        return BnPoints::empty(dimension);
+    };
+    let samples = config.generate();
+    if samples.is_empty() {
+        return BnPoints::empty(dimension);
+    }
+    let coordinates: Box<[f64]> = samples.iter().flatten().copied().collect();
+    BnPoints {
+        coordinates: Box::into_raw(coordinates) as *mut f64,
+        count: samples.len(),
+        dimension,
+    }
+}
+
+/// Frees samples returned by [`bn_generate`].
+///
+/// # Safety
+///
+/// `points` must have been returned by [`bn_generate`] and not been freed
+/// before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bn_points_free(points: BnPoints) {
+    if points.coordinates.is_null() {
+        return;
+    }
+    let len = points.count * points.dimension;
+    // SAFETY: the coordinates were a boxed slice of this length, see
+    // `bn_generate`
+    drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(points.coordinates, len)) });
+}
+
+/// Creates an iterator generating the samples of [`bn_generate`] one by
+/// one, in the same order.
+///
+/// Returns null for the arguments for which [`bn_generate`] gives no
+/// samples.
+///
+/// # Safety
+///
+/// `dimensions` must point to `dimension` values.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bn_iter_new(
+    dimensions: *const f64,
+    dimension: usize,
+    min_distance: f64,
+    k_abort: usize,
+    seed: u64,
+) -> *mut BnIterator {
+    // SAFETY: guaranteed by the caller
+    match unsafe { config(dimensions, dimension, min_distance, k_abort, seed) } {
+        Some(config) => Box::into_raw(Box::new(BnIterator {
+            inner: config.iter(),
+            dimension,
+        })),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Generates the next sample and writes its coordinates to `out`.
+///
+/// Returns false, without writing anything, when all samples have been
+/// generated or if `iterator` or `out` is null.
+///
+/// # Safety
+///
+/// `iterator` must have been returned by [`bn_iter_new`] and not been freed
+/// and `out` must have room for as many values as the iterator has
+/// dimensions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bn_iter_next(iterator: *mut BnIterator, out: *mut f64) -> bool {
+    if iterator.is_null() || out.is_null() {
+        return false;
+    }
+    // SAFETY: guaranteed by the caller
+    let iterator = unsafe { &mut *iterator };
+    let Some(sample) = iterator.inner.next_ref() else {
+        return false;
+    };
+    // SAFETY: guaranteed by the caller
+    let out = unsafe { std::slice::from_raw_parts_mut(out, iterator.dimension) };
+    out.copy_from_slice(sample);
+    true
+}
+
+/// Frees an iterator created by [`bn_iter_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `iterator` must have been returned by [`bn_iter_new`] and not been freed
+/// before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bn_iter_free(iterator: *mut BnIterator) {
+    if !iterator.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { Box::from_raw(iterator) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_and_iterate() {
+        let dimensions = [8., 6.];
+        let config = BlueNoiseConfig::new(dimensions.to_vec(), 1.).seed(9);
+        let expected = config.generate();
+        unsafe {
+            let points = bn_generate(dimensions.as_ptr(), 2, 1., 30, 9);
+            assert_eq!((points.count, points.dimension), (expected.len(), 2));
+            let coordinates = std::slice::from_raw_parts(points.coordinates, 2 * points.count);
+            assert_eq!(&coordinates[2..4], &expected[1][..]);
+            bn_points_free(points);
+
+            let iterator = bn_iter_new(dimensions.as_ptr(), 2, 1., 30, 9);
+            let mut sample = [0.; 2];
+            for expected in &expected {
+                assert!(bn_iter_next(iterator, sample.as_mut_ptr()));
+                assert_eq!(sample[..], expected[..]);
+            }
+            assert!(!bn_iter_next(iterator, sample.as_mut_ptr()));
+            bn_iter_free(iterator);
+
+            let invalid = bn_generate(dimensions.as_ptr(), 2, -1., 30, 9);
+            assert!(invalid.coordinates.is_null());
+            assert_eq!(invalid.count, 0);
+            bn_points_free(invalid);
+            assert!(bn_iter_new(std::ptr::null(), 2, 1., 30, 9).is_null());
+            bn_iter_free(std::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Blue noise on an integer lattice, without floating point arithmetic.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::Rng;
+use smallvec::SmallVec;
+
+/// A sample of [`blue_noise_fixed`], with integer coordinates.
+pub type FixedPoint = SmallVec<[u32; 4]>;
+
+/// Generates blue noise samples with integer coordinates in
+/// `[0, dimensions[i])`, which are at least `min_distance` apart.
+///
+/// All computations are exact integer arithmetic: distances are compared
+/// squared in 128 bits and candidates are drawn uniformly by rejection from
+/// the lattice points between one and two times `min_distance` away from a
+/// sample. So there are no rounding errors, e.g. near the borders of grid
+/// cells, and the samples are identical on every platform, as needed for
+/// lockstep simulations. The units are up to the
+/// caller: to generate in the unit square with a resolution of `2^-32`, use
+/// a domain of `u32::MAX` and divide by `2^32`.
+///
+/// The random numbers are drawn from `rng`, so a seeded generator whose
+/// output is specified, like ChaCha8, always gives the same samples.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// // millimeters on a 10 x 5 m plot, at least 20 cm apart
+/// let samples = bluenoisers::blue_noise_fixed(&[10_000, 5_000], 200, 30, &mut rng);
+/// let (a, b) = (&samples[0], &samples[1]);
+/// let dx = u64::from(a[0].abs_diff(b[0]));
+/// let dy = u64::from(a[1].abs_diff(b[1]));
+/// assert!(dx * dx + dy * dy >= 200 * 200);
+/// ```
+///
+/// # Panics
+///
+/// Panics if there are no dimensions, a dimension is zero, `min_distance`
+/// is smaller than the square root of the number of dimensions or the grid
+/// of the domain would have more than `usize::MAX` cells.
+pub fn blue_noise_fixed<R: Rng>(
+    dimensions: &[u32],
+    min_distance: u32,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<FixedPoint> {
+    assert!(!dimensions.is_empty(), "there must be a dimension");
+    assert!(
+        dimensions.iter().all(|dim| *dim > 0),
+        "the dimensions must be positive"
+    );
+    let r_sqr = u128::from(min_distance) * u128::from(min_distance);
+    let dimension = dimensions.len() as u128;
+    assert!(
+        r_sqr >= dimension,
+        "the minimal distance must be at least the square root of the dimension"
+    );
+    // two samples in a cell are closer than side * sqrt(dimension), which is
+    // at most the minimal distance, so every cell holds one sample at most
+    let side = (r_sqr / dimension).isqrt() as u64;
+    let cells: Vec<u64> = dimensions
+        .iter()
+        .map(|dim| u64::from(*dim).div_ceil(side))
+        .collect();
+    let cell_count = cells
+        .iter()
+        .try_fold(1_usize, |count, cells| {
+            count.checked_mul(usize::try_from(*cells).ok()?)
+        })
+        .expect("the domain has too many cells");
+    // the ids of the samples plus one, zero for an empty cell
+    let mut grid = vec![0_usize; cell_count];
+    // closer samples are at most this many cells away along each axis
+    let reach = (u64::from(min_distance) - 1) / side + 1;
+    let cell_of =
+        |point: &[u32]| -> Vec<u64> { point.iter().map(|x| u64::from(*x) / side).collect() };
+    let index_of = |cell: &[u64]| {
+        cell.iter()
+            .zip(cells.iter())
+            .rev()
+            .fold(0_usize, |index, (c, cells)| {
+                index * *cells as usize + *c as usize
+            })
+    };
+    let conflicts = |point: &[u32], grid: &[usize], samples: &[FixedPoint]| {
+        let center = cell_of(point);
+        let lower: Vec<u64> = center.iter().map(|c| c.saturating_sub(reach)).collect();
+        let upper: Vec<u64> = center
+            .iter()
+            .zip(cells.iter())
+            .map(|(c, cells)| (c + reach).min(cells - 1))
+            .collect();
+        // all the cells from lower to upper, like an odometer
+        let mut cell = lower.clone();
+        loop {
+            let id = grid[index_of(&cell)];
+            if id != 0 && dst_sqr(point, &samples[id - 1]) < r_sqr {
+                return true;
+            }
+            let Some(axis) = (0..cell.len()).find(|axis| cell[*axis] < upper[*axis]) else {
+                return false;
+            };
+            cell[axis] += 1;
+            cell[..axis].copy_from_slice(&lower[..axis]);
+        }
+    };
+
+    let mut samples: Vec<FixedPoint> = Vec::new();
+    let first: FixedPoint = dimensions
+        .iter()
+        .map(|dim| rng.gen_range(0..*dim))
+        .collect();
+    grid[index_of(&cell_of(&first))] = 1;
+    samples.push(first);
+    let mut active = vec![0];
+    let outer = 2 * i64::from(min_distance);
+    while !active.is_empty() {
+        // drawn as u64, since the bits used for usize depend on the platform
+        let active_idx = rng.gen_range(0..active.len() as u64) as usize;
+        let current = samples[active[active_idx]].clone();
+        let mut found = false;
+        for _ in 0..k_abort {
+            // offsets outside of the annulus are drawn again instead of
+            // counting as tries, so that every try is a candidate
+            let offset = loop {
+                let offset: SmallVec<[i64; 4]> = current
+                    .iter()
+                    .map(|_| rng.gen_range(-outer..=outer))
+                    .collect();
+                let squared: u128 = offset
+                    .iter()
+                    .map(|o| u128::from(o.unsigned_abs()).pow(2))
+                    .sum();
+                if (r_sqr..4 * r_sqr).contains(&squared) {
+                    break offset;
+                }
+            };
+            let samp: Option<FixedPoint> = current
+                .iter()
+                .zip(offset.iter())
+                .zip(dimensions.iter())
+                .map(|((x, o), dim)| u32::try_from(i64::from(*x) + o).ok().filter(|x| x < dim))
+                .collect();
+            let Some(samp) = samp else {
+                continue;
+            };
+            if conflicts(&samp, &grid, &samples) {
+                continue;
+            }
+            grid[index_of(&cell_of(&samp))] = samples.len() + 1;
+            samples.push(samp);
+            active.push(samples.len() - 1);
+            found = true;
+            break;
+        }
+        if !found {
+            active.swap_remove(active_idx);
+        }
+    }
+    samples
+}
+
+/// The exact squared distance between two points.
+fn dst_sqr(a: &[u32], b: &[u32]) -> u128 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| u128::from(a.abs_diff(*b)).pow(2))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn fixed() {
+        let generate = |dimensions: &[u32], min_distance| {
+            let mut rng = ChaCha8Rng::seed_from_u64(8);
+            blue_noise_fixed(dimensions, min_distance, 30, &mut rng)
+        };
+        for (dimensions, min_distance) in [
+            (vec![400, 300], 10),
+            (vec![60, 50, 40], 7),
+            (vec![u32::MAX, u32::MAX], u32::MAX / 20),
+            // as small as it gets, a cell per lattice point
+            (vec![30, 30], 2),
+        ] {
+            let samples = generate(&dimensions, min_distance);
+            assert_eq!(samples, generate(&dimensions, min_distance));
+            assert!(samples.len() > 100);
+            let r_sqr = u128::from(min_distance).pow(2);
+            for (i, a) in samples.iter().enumerate() {
+                assert!(a.iter().zip(dimensions.iter()).all(|(x, dim)| x < dim));
+                assert!(samples[..i].iter().all(|b| dst_sqr(a, b) >= r_sqr));
+            }
+        }
+        // every try is a candidate, so the samples are as dense as those of
+        // the floating point generation
+        let fixed: usize = (0..4)
+            .map(|seed| {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                blue_noise_fixed(&[60, 50, 40], 7, 30, &mut rng).len()
+            })
+            .sum();
+        let float: usize = (0..4)
+            .map(|seed| {
+                crate::BlueNoiseConfig::new(vec![60., 50., 40.], 7.)
+                    .seed(seed)
+                    .generate()
+                    .len()
+            })
+            .sum();
+        assert!(fixed as f64 > 0.98 * float as f64);
+    }
+}
@@ -0,0 +1,239 @@
+//! glTF 2.0 files of 2D and 3D point sets.
+
+use crate::PoissonDiskSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Writes the 2D or 3D `samples` as a glTF 2.0 scene with a single mesh
+/// whose primitive has the `POINTS` mode. 2D samples get a z coordinate of
+/// zero.
+///
+/// With `colors`, which must have one linear RGB color per sample, the
+/// points get a `COLOR_0` attribute, e.g. to tell classes of samples apart.
+///
+/// If `path` ends with `.glb`, the file is a binary glTF, otherwise the JSON
+/// form with the data embedded as base64.
+///
+/// ```no_run
+/// use bluenoisers::export_gltf;
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 10., 10.], 1., 30);
+/// // color by height
+/// let colors: Vec<[f32; 3]> = samples
+///     .iter()
+///     .map(|s| if s[2] < 5. { [1., 0., 0.] } else { [0., 0., 1.] })
+///     .collect();
+/// export_gltf("samples.glb", &samples, Some(&colors)).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if there are no samples, if a sample has less than two or more
+/// than three coordinates or if the number of colors differs from the
+/// number of samples.
+pub fn export_gltf<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    colors: Option<&[[f32; 3]]>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let binary = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("glb"));
+    fs::write(path, gltf(samples, colors, binary))
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D or 3D set as a glTF 2.0 file, see
+    /// [`export_gltf`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is empty or not 2- or 3-dimensional, or if the
+    /// number of colors differs from the number of samples.
+    pub fn export_gltf(
+        &self,
+        path: impl AsRef<Path>,
+        colors: Option<&[[f32; 3]]>,
+    ) -> io::Result<()> {
+        assert!(
+            (2..=3).contains(&self.dimensions().len()),
+            "only 2D and 3D sets can be exported"
+        );
+        export_gltf(path, self.samples(), colors)
+    }
+}
+
+/// The bytes of a glTF file, see [`export_gltf`].
+fn gltf<P: AsRef<[f64]>>(samples: &[P], colors: Option<&[[f32; 3]]>, binary: bool) -> Vec<u8> {
+    // glTF doesn't allow empty accessors
+    assert!(!samples.is_empty(), "there must be samples to export");
+    if let Some(colors) = colors {
+        assert_eq!(
+            colors.len(),
+            samples.len(),
+            "there must be one color per sample"
+        );
+    }
+    let mut buffer = Vec::new();
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for sample in samples {
+        let sample = sample.as_ref();
+        assert!(
+            (2..=3).contains(&sample.len()),
+            "only 2D and 3D samples can be exported"
+        );
+        let position = [
+            sample[0],
+            sample[1],
+            sample.get(2).copied().unwrap_or(0_f64),
+        ];
+        for axis in 0..3 {
+            let x = position[axis] as f32;
+            min[axis] = min[axis].min(x);
+            max[axis] = max[axis].max(x);
+            buffer.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    for x in colors.iter().flat_map(|colors| colors.iter().flatten()) {
+        buffer.extend_from_slice(&x.to_le_bytes());
+    }
+
+    let count = samples.len();
+    let size = 12 * count;
+    let vec3 = |values: [f32; 3]| format!("[{},{},{}]", values[0], values[1], values[2]);
+    let uri = if binary {
+        String::new()
+    } else {
+        format!(
+            r#","uri":"data:application/octet-stream;base64,{}""#,
+            base64(&buffer)
+        )
+    };
+    let (color_view, color_accessor, color_attribute) = if colors.is_some() {
+        (
+            format!(r#",{{"buffer":0,"byteOffset":{size},"byteLength":{size},"target":34962}}"#),
+            format!(r#",{{"bufferView":1,"componentType":5126,"count":{count},"type":"VEC3"}}"#),
+            r#","COLOR_0":1"#.to_string(),
+        )
+    } else {
+        Default::default()
+    };
+    let mut json = format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"bluenoisers"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+            r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0{}}},"mode":0}}]}}],"#,
+            r#""accessors":[{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","min":{},"max":{}}}{}],"#,
+            r#""bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{},"target":34962}}{}],"#,
+            r#""buffers":[{{"byteLength":{}{}}}]}}"#
+        ),
+        color_attribute,
+        count,
+        vec3(min),
+        vec3(max),
+        color_accessor,
+        size,
+        color_view,
+        buffer.len(),
+        uri,
+    )
+    .into_bytes();
+    if !binary {
+        return json;
+    }
+
+    // chunks are padded to four bytes, JSON with spaces
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    for value in [2, length as u32, json.len() as u32] {
+        glb.extend_from_slice(&value.to_le_bytes());
+    }
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&buffer);
+    glb
+}
+
+/// Encodes `bytes` as base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let word = chunk.iter().enumerate().fold(0_u32, |accu, (i, byte)| {
+            accu | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(word >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foo"), "Zm9v");
+        assert_eq!(super::base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn export_gltf() {
+        let dir = tempfile::tempdir().unwrap();
+        let set = PoissonDiskSet::new(vec![6., 4.], 1., 30);
+        let colors = vec![[0.5_f32, 0., 1.]; set.len()];
+
+        let path = dir.path().join("samples.glb");
+        set.export_gltf(&path, Some(&colors)).unwrap();
+        let glb = fs::read(&path).unwrap();
+        let word = |at: usize| u32::from_le_bytes(glb[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(word(4), 2);
+        assert_eq!(word(8), glb.len());
+        let json_length = word(12);
+        assert_eq!(json_length % 4, 0);
+        let json = std::str::from_utf8(&glb[20..20 + json_length]).unwrap();
+        assert!(json.contains(r#""COLOR_0":1"#));
+        assert!(json.contains(r#""mode":0"#));
+        assert!(!json.contains("uri"));
+        let bin = &glb[20 + json_length + 8..];
+        assert_eq!(word(20 + json_length), 24 * set.len());
+        assert_eq!(bin.len(), 24 * set.len());
+        let float = |at: usize| f32::from_le_bytes(bin[at..at + 4].try_into().unwrap());
+        let last = &set.samples()[set.len() - 1];
+        let at = 12 * (set.len() - 1);
+        assert_eq!(
+            [float(at), float(at + 4), float(at + 8)],
+            [last[0] as f32, last[1] as f32, 0.]
+        );
+        assert_eq!(float(12 * set.len()), 0.5);
+
+        let path = dir.path().join("samples.gltf");
+        set.export_gltf(&path, None).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(!json.contains("COLOR_0"));
+        let encoded = super::base64(&bin[..12 * set.len()]);
+        assert!(json.contains(&format!(
+            r#""uri":"data:application/octet-stream;base64,{encoded}""#
+        )));
+        assert!(json.contains(&format!(r#""count":{}"#, set.len())));
+    }
+}
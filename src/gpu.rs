@@ -0,0 +1,459 @@
+//! Blue noise generation on the GPU with [`wgpu`].
+//!
+//! This implements parallel dart throwing in phase groups, see
+//! [*Parallel Poisson Disk Sampling*](https://doi.org/10.1145/1360612.1360619)
+//! by Li-Yi Wei. The domain is covered by a grid with cells of edge length
+//! *`min_distance` / √n*, so each cell holds at most one sample. Cells whose
+//! coordinates are congruent modulo 3 form a phase group; they are far enough
+//! apart that all the cells of a group can throw a dart at the same time,
+//! without any synchronization. Cycling through all the groups a few times
+//! yields a nearly maximal set.
+
+use crate::Point;
+use std::fmt;
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = r#"
+struct Params {
+    cell_count: vec3<u32>,
+    dimension: u32,
+    extent: vec3<f32>,
+    cell_size: f32,
+    phase: vec3<u32>,
+    round: u32,
+    min_dst_sqr: f32,
+    seed: u32,
+    reach: u32,
+    padding: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> cells: array<vec4<f32>>;
+
+fn hash(x: u32) -> u32 {
+    let state = x * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(state: ptr<function, u32>) -> f32 {
+    *state = hash(*state);
+    return f32(*state >> 8u) / 16777216.0;
+}
+
+fn cell_index(cell: vec3<u32>) -> u32 {
+    return cell.x + params.cell_count.x * (cell.y + params.cell_count.y * cell.z);
+}
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let per_axis = (params.cell_count - params.phase + vec3<u32>(2u)) / 3u;
+    let linear = id.x + id.y * groups.x * 64u;
+    if (linear >= per_axis.x * per_axis.y * per_axis.z) {
+        return;
+    }
+    let local = vec3<u32>(
+        linear % per_axis.x,
+        (linear / per_axis.x) % per_axis.y,
+        linear / (per_axis.x * per_axis.y),
+    );
+    let cell = local * 3u + params.phase;
+    let idx = cell_index(cell);
+    if (cells[idx].w > 0.0) {
+        return;
+    }
+    var state = hash(idx ^ hash(params.round ^ hash(params.seed)));
+    var position = (vec3<f32>(cell)
+        + vec3<f32>(random(&state), random(&state), random(&state))) * params.cell_size;
+    if (params.dimension == 2u) {
+        position.z = 0.0;
+    }
+    if (any(position >= params.extent)) {
+        return;
+    }
+    let reach = i32(params.reach);
+    let reach_z = select(0, reach, params.dimension == 3u);
+    for (var dz = -reach_z; dz <= reach_z; dz++) {
+        for (var dy = -reach; dy <= reach; dy++) {
+            for (var dx = -reach; dx <= reach; dx++) {
+                let other_cell = vec3<i32>(cell) + vec3<i32>(dx, dy, dz);
+                if (any(other_cell < vec3<i32>(0))
+                    || any(other_cell >= vec3<i32>(params.cell_count))) {
+                    continue;
+                }
+                let other = cells[cell_index(vec3<u32>(other_cell))];
+                let diff = other.xyz - position;
+                if (other.w > 0.0 && dot(diff, diff) < params.min_dst_sqr) {
+                    return;
+                }
+            }
+        }
+    }
+    cells[idx] = vec4<f32>(position, 1.0);
+}
+"#;
+
+/// The size of [`Params`](SHADER) in bytes.
+const PARAMS_SIZE: u64 = 64;
+const WORKGROUP_SIZE: u32 = 64;
+const MAX_WORKGROUPS: u32 = 65535;
+
+/// Errors of the GPU backend.
+#[derive(Debug)]
+pub enum GpuError {
+    /// No suitable adapter was found.
+    Adapter(wgpu::RequestAdapterError),
+    /// The device could not be created.
+    Device(wgpu::RequestDeviceError),
+    /// Reading the samples back failed.
+    Map(wgpu::BufferAsyncError),
+    /// Only 2- and 3-dimensional domains are supported.
+    UnsupportedDimension(usize),
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuError::Adapter(e) => write!(f, "no GPU adapter: {e}"),
+            GpuError::Device(e) => write!(f, "no GPU device: {e}"),
+            GpuError::Map(e) => write!(f, "reading samples from the GPU failed: {e}"),
+            GpuError::UnsupportedDimension(dimension) => {
+                write!(f, "{dimension} dimensions are not supported on the GPU")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+/// Generates blue noise on a GPU.
+///
+/// ```no_run
+/// use bluenoisers::GpuSampler;
+///
+/// let sampler = GpuSampler::with_default_adapter()?;
+/// let samples = sampler.generate(&[3840., 2160.], 2., 8, 42)?;
+/// // either use `samples.buffer()` on the GPU or read them back
+/// let samples = sampler.read(&samples)?;
+/// # Ok::<(), bluenoisers::GpuError>(())
+/// ```
+///
+/// Requires the `wgpu` feature.
+#[derive(Debug)]
+pub struct GpuSampler {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+/// Samples which were generated by [`GpuSampler::generate`] and still reside
+/// on the GPU.
+#[derive(Debug)]
+pub struct GpuSamples {
+    buffer: wgpu::Buffer,
+    cell_count: [u32; 3],
+    dimension: usize,
+}
+
+impl GpuSamples {
+    /// The storage buffer with the samples.
+    ///
+    /// It contains one `vec4<f32>` per grid cell, the first axis changing
+    /// fastest. If the cell holds a sample, `w` is `1` and `xyz` are its
+    /// coordinates (`z` is `0` in 2D), otherwise `w` is `0`.
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// The number of grid cells along each axis.
+    pub fn cell_count(&self) -> [u32; 3] {
+        self.cell_count
+    }
+}
+
+impl GpuSampler {
+    /// Creates a sampler on an existing device.
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> GpuSampler {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bluenoisers"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bluenoisers"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(PARAMS_SIZE),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bluenoisers"),
+            bind_group_layouts: &[Some(&layout)],
+            immediate_size: 0,
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bluenoisers"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        GpuSampler {
+            device,
+            queue,
+            layout,
+            pipeline,
+        }
+    }
+
+    /// Creates a sampler on the default adapter, blocking until it is ready.
+    pub fn with_default_adapter() -> Result<GpuSampler, GpuError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .map_err(GpuError::Adapter)?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+                .map_err(GpuError::Device)?;
+        Ok(GpuSampler::new(device, queue))
+    }
+
+    /// Generates samples in a 2- or 3-dimensional domain.
+    ///
+    /// `dimensions` and `min_distance` are the same as for
+    /// [`blue_noise`](crate::blue_noise). Each of the `rounds` visits every
+    /// cell once; eight rounds already come close to a maximal set. The
+    /// `seed` determines the result.
+    ///
+    /// Coordinates are computed in single precision, so very large domains
+    /// lose accuracy.
+    pub fn generate(
+        &self,
+        dimensions: &[f64],
+        min_distance: f64,
+        rounds: u32,
+        seed: u32,
+    ) -> Result<GpuSamples, GpuError> {
+        let dimension = dimensions.len();
+        if !(2..=3).contains(&dimension) {
+            return Err(GpuError::UnsupportedDimension(dimension));
+        }
+        assert!(min_distance > 0.0);
+        let cell_size = min_distance / (dimension as f64).sqrt();
+        let mut cell_count = [1_u32; 3];
+        let mut extent = [1_f32; 3];
+        for (i, x) in dimensions.iter().enumerate() {
+            cell_count[i] = (x / cell_size).ceil() as u32;
+            extent[i] = *x as f32;
+        }
+        let cells: u64 = cell_count.iter().map(|c| *c as u64).product();
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bluenoisers samples"),
+            size: (cells * 16).max(16),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let samples = GpuSamples {
+            buffer,
+            cell_count,
+            dimension,
+        };
+        if cells == 0 {
+            return Ok(samples);
+        }
+        // the parameters of all dispatches, each one at an aligned offset
+        let stride =
+            (self.device.limits().min_uniform_buffer_offset_alignment as u64).max(PARAMS_SIZE);
+        let phases: Vec<[u32; 3]> = (0..3_u32.pow(dimension as u32))
+            .map(|phase| [phase % 3, phase / 3 % 3, phase / 9])
+            .filter(|phase| phase.iter().zip(cell_count.iter()).all(|(p, c)| p < c))
+            .collect();
+        let mut params = Vec::new();
+        for round in 0..rounds {
+            for phase in phases.iter() {
+                let start = params.len();
+                for c in cell_count {
+                    params.extend_from_slice(&c.to_le_bytes());
+                }
+                params.extend_from_slice(&(dimension as u32).to_le_bytes());
+                for x in extent {
+                    params.extend_from_slice(&x.to_le_bytes());
+                }
+                params.extend_from_slice(&(cell_size as f32).to_le_bytes());
+                for p in phase {
+                    params.extend_from_slice(&p.to_le_bytes());
+                }
+                params.extend_from_slice(&round.to_le_bytes());
+                params.extend_from_slice(&((min_distance * min_distance) as f32).to_le_bytes());
+                params.extend_from_slice(&seed.to_le_bytes());
+                // cells to check in each direction
+                params.extend_from_slice(&2_u32.to_le_bytes());
+                params.extend_from_slice(&0_u32.to_le_bytes());
+                params.resize(start + stride as usize, 0);
+            }
+        }
+        if params.is_empty() {
+            return Ok(samples);
+        }
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("bluenoisers parameters"),
+                contents: &params,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bluenoisers"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &params,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(PARAMS_SIZE),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: samples.buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            let mut offset = 0;
+            for _ in 0..rounds {
+                for phase in phases.iter() {
+                    let invocations: u32 = phase
+                        .iter()
+                        .zip(cell_count.iter())
+                        .map(|(p, c)| (c - p).div_ceil(3))
+                        .product();
+                    let groups = invocations.div_ceil(WORKGROUP_SIZE);
+                    pass.set_bind_group(0, &bind_group, &[offset]);
+                    pass.dispatch_workgroups(
+                        groups.min(MAX_WORKGROUPS),
+                        groups.div_ceil(MAX_WORKGROUPS),
+                        1,
+                    );
+                    offset += stride as u32;
+                }
+            }
+        }
+        self.queue.submit([encoder.finish()]);
+        Ok(samples)
+    }
+
+    /// Copies `samples` back from the GPU, blocking until they are available.
+    ///
+    /// The samples are returned in the order of their cells.
+    pub fn read(&self, samples: &GpuSamples) -> Result<Vec<Point>, GpuError> {
+        let size = samples.buffer.size();
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("bluenoisers readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&samples.buffer, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        staging.map_async(wgpu::MapMode::Read, .., move |result| {
+            // the receiver only goes away if polling failed
+            let _ = sender.send(result);
+        });
+        // errors would show up again below
+        let _ = self.device.poll(wgpu::PollType::wait_indefinitely());
+        receiver
+            .recv()
+            .expect("mapping callback not called")
+            .map_err(GpuError::Map)?;
+        let view = staging
+            .get_mapped_range(..)
+            .expect("staging buffer is mapped");
+        let floats: Vec<f32> = view
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        drop(view);
+        staging.unmap();
+        Ok(floats
+            .chunks_exact(4)
+            .filter(|cell| cell[3] > 0_f32)
+            .map(|cell| {
+                cell[..samples.dimension]
+                    .iter()
+                    .map(|x| *x as f64)
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::BackgroundGrid;
+
+    #[test]
+    fn gpu_2d() {
+        // there are no GPUs in many CI environments
+        let Ok(sampler) = GpuSampler::with_default_adapter() else {
+            return;
+        };
+        let radius = 2.;
+        let generated = sampler.generate(&[100., 80.], radius, 8, 7).unwrap();
+        let samples = sampler.read(&generated).unwrap();
+        assert!(samples.len() > 400);
+        for (i, s1) in samples.iter().enumerate() {
+            assert!(s1[0] < 100. && s1[1] < 80.);
+            for s2 in samples.iter().skip(i + 1) {
+                // single precision on the GPU
+                assert!(BackgroundGrid::dst_sqr(s1, s2) >= radius * radius * 0.9999);
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_dimension() {
+        let Ok(sampler) = GpuSampler::with_default_adapter() else {
+            return;
+        };
+        assert!(matches!(
+            sampler.generate(&[10.], 1., 1, 0),
+            Err(GpuError::UnsupportedDimension(1))
+        ));
+    }
+}
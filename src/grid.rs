@@ -0,0 +1,1244 @@
+//! The background grid used to accelerate the distance checks.
+
+use crate::config::{ConfigError, Interval, NeighborSearch, Separation, check_domain};
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use crate::{Point, SampleStorage};
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as CellMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::ops::{Deref, DerefMut};
+use smallvec::SmallVec;
+// without std, there is no hasher to randomize a HashMap with
+#[cfg(feature = "std")]
+use std::collections::HashMap as CellMap;
+
+/// Where the cells of a [`BackgroundGrid`] live.
+///
+/// Each cell stores `0` if it is empty or the index of its sample plus one.
+#[derive(Debug)]
+pub(crate) enum CellStorage {
+    /// Cells on the heap, the default.
+    Heap(Vec<usize>),
+    /// Cells in a memory mapped file, for grids which don't fit into RAM.
+    #[cfg(feature = "memmap")]
+    Mapped(memmap2::MmapMut),
+}
+
+impl CellStorage {
+    #[cfg(feature = "memmap")]
+    fn mapped(file: &std::fs::File, cell_count: usize) -> std::io::Result<CellStorage> {
+        if cell_count == 0 {
+            // empty mappings are not supported on all platforms
+            return Ok(CellStorage::Heap(Vec::new()));
+        }
+        let byte_count = cell_count
+            .checked_mul(std::mem::size_of::<usize>())
+            .ok_or_else(|| std::io::Error::other("grid too large to be mapped"))?;
+        // truncate first so that all the cells are zero, i.e. empty
+        file.set_len(0)?;
+        file.set_len(byte_count as u64)?;
+        // SAFETY: the file was sized for us above and nobody else is supposed
+        // to touch it while the grid is alive.
+        let map = unsafe { memmap2::MmapMut::map_mut(file)? };
+        Ok(CellStorage::Mapped(map))
+    }
+}
+
+impl Deref for CellStorage {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        match self {
+            CellStorage::Heap(data) => data,
+            #[cfg(feature = "memmap")]
+            CellStorage::Mapped(map) => {
+                // SAFETY: mappings are page aligned and the length is a
+                // multiple of `size_of::<usize>()`, see `CellStorage::mapped`.
+                unsafe {
+                    std::slice::from_raw_parts(
+                        map.as_ptr() as *const usize,
+                        map.len() / std::mem::size_of::<usize>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl DerefMut for CellStorage {
+    fn deref_mut(&mut self) -> &mut [usize] {
+        match self {
+            CellStorage::Heap(data) => data,
+            #[cfg(feature = "memmap")]
+            CellStorage::Mapped(map) => {
+                // SAFETY: see `deref`
+                unsafe {
+                    std::slice::from_raw_parts_mut(
+                        map.as_mut_ptr() as *mut usize,
+                        map.len() / std::mem::size_of::<usize>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// A copy of the state of a background grid, for visualization and
+/// debugging, see [`PoissonDiskSet::grid_snapshot`](crate::PoissonDiskSet::grid_snapshot).
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridSnapshot {
+    /// The edge length of the cells.
+    pub cell_size: f64,
+    /// The number of cells along each axis, or `None` for the sparse grid
+    /// used above six dimensions, which has no fixed extent.
+    pub cell_count: Option<Vec<usize>>,
+    /// The cells holding samples, with the first axis changing fastest.
+    pub occupied: Vec<OccupiedCell>,
+}
+
+/// A cell of a [`GridSnapshot`] which holds samples.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OccupiedCell {
+    /// The coordinates of the cell, in cells.
+    pub cell: Vec<usize>,
+    /// The indices of its samples. Cells of dense grids hold at most one.
+    pub samples: Vec<usize>,
+}
+
+impl GridSnapshot {
+    /// The fraction of cells which hold a sample, or `None` for sparse grids.
+    pub fn fill_ratio(&self) -> Option<f64> {
+        let total: usize = self.cell_count.as_ref()?.iter().product();
+        Some(self.occupied.len() as f64 / total.max(1) as f64)
+    }
+
+    /// Whether each cell holds a sample, with the first axis changing
+    /// fastest, e.g. to draw the occupancy of a 2D grid as an image. `None`
+    /// for sparse grids.
+    pub fn occupancy(&self) -> Option<Vec<bool>> {
+        let cell_count = self.cell_count.as_ref()?;
+        let mut occupancy = vec![false; cell_count.iter().product()];
+        for occupied in self.occupied.iter() {
+            let idx = occupied
+                .cell
+                .iter()
+                .zip(cell_count.iter())
+                .rev()
+                .fold(0, |accu, (c, count)| accu * count + c);
+            occupancy[idx] = true;
+        }
+        Some(occupancy)
+    }
+}
+
+/// Grids with more dimensions than this use [`SparseCells`]. Above it, the
+/// number of cells and of neighbors to check per sample grow too fast.
+pub(crate) const SPARSE_DIMENSION: usize = 6;
+
+/// Cells of edge length `min_distance` which only exist if they hold a
+/// sample, for grids in high dimensions.
+///
+/// Since the cells are as large as the minimal distance, only the directly
+/// adjacent cells can hold conflicting samples, and of those only the ones
+/// which are closer to the sample than the minimal distance are visited.
+/// Their number grows much slower with the dimension than the *3ⁿ* adjacent
+/// cells.
+#[derive(Debug, Default)]
+pub(crate) struct SparseCells {
+    /// The indices of the samples in each occupied cell, plus one.
+    cells: CellMap<SmallVec<[usize; 8]>, SmallVec<[usize; 2]>>,
+}
+
+impl SparseCells {
+    fn cell_id(position: &[f64], cell_size: f64) -> SmallVec<[usize; 8]> {
+        position.iter().map(|x| (*x / cell_size) as usize).collect()
+    }
+
+    fn insert(&mut self, position: &[f64], cell_size: f64, sample_id: usize) {
+        self.cells
+            .entry(SparseCells::cell_id(position, cell_size))
+            .or_default()
+            .push(sample_id);
+    }
+
+    #[cfg(feature = "std")]
+    fn remove(&mut self, position: &[f64], cell_size: f64, sample_id: usize) {
+        let cell = SparseCells::cell_id(position, cell_size);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|id| *id != sample_id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// The indices of the samples in the cells which may hold samples less
+    /// than `radius` away from `position`, ascending. These are the cells of
+    /// the box around the ball, or all occupied ones if there are fewer.
+    fn around(&self, position: &[f64], radius: f64, cell_size: f64) -> Vec<usize> {
+        let cell_of = |x: f64| (x.max(0_f64) / cell_size) as usize;
+        let min_cell: SmallVec<[usize; 8]> = position.iter().map(|x| cell_of(x - radius)).collect();
+        let max_cell: SmallVec<[usize; 8]> = position.iter().map(|x| cell_of(x + radius)).collect();
+        let box_cells = min_cell
+            .iter()
+            .zip(max_cell.iter())
+            .map(|(min, max)| (max - min + 1) as f64)
+            .product::<f64>();
+        let mut found: Vec<usize> = Vec::new();
+        if box_cells > self.cells.len() as f64 {
+            found.extend(self.cells.values().flatten().map(|id| id - 1));
+        } else {
+            // all the cells from min_cell to max_cell, like an odometer
+            let mut cell = min_cell.clone();
+            loop {
+                if let Some(ids) = self.cells.get(&cell) {
+                    found.extend(ids.iter().map(|id| id - 1));
+                }
+                let Some(axis) = (0..cell.len()).find(|axis| cell[*axis] < max_cell[*axis]) else {
+                    break;
+                };
+                cell[axis] += 1;
+                cell[..axis].copy_from_slice(&min_cell[..axis]);
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    fn has_conflict<S: SampleStorage>(
+        &self,
+        position: &[f64],
+        min_dst_sqr: f64,
+        cell_size: f64,
+        samples: &S,
+    ) -> bool {
+        let mut cell = SparseCells::cell_id(position, cell_size);
+        self.visit(
+            &mut cell,
+            0,
+            0_f64,
+            position,
+            min_dst_sqr,
+            cell_size,
+            samples,
+        )
+    }
+
+    /// Visits the cells which differ from `cell` in the axes from `axis` on
+    /// and are closer than the minimal distance, given that the offsets in
+    /// the axes before already account for `dst_sqr`.
+    #[allow(clippy::too_many_arguments)]
+    fn visit<S: SampleStorage>(
+        &self,
+        cell: &mut SmallVec<[usize; 8]>,
+        axis: usize,
+        dst_sqr: f64,
+        position: &[f64],
+        min_dst_sqr: f64,
+        cell_size: f64,
+        samples: &S,
+    ) -> bool {
+        if axis == position.len() {
+            return self.cells.get(cell).is_some_and(|ids| {
+                ids.iter().any(|id| {
+                    BackgroundGrid::dst_sqr(position, samples.sample(id - 1)) < min_dst_sqr
+                })
+            });
+        }
+        let own = cell[axis];
+        if self.visit(
+            cell,
+            axis + 1,
+            dst_sqr,
+            position,
+            min_dst_sqr,
+            cell_size,
+            samples,
+        ) {
+            return true;
+        }
+        let below = position[axis] - own as f64 * cell_size;
+        let above = (own + 1) as f64 * cell_size - position[axis];
+        let mut conflict = false;
+        if own > 0 && dst_sqr + below * below < min_dst_sqr {
+            cell[axis] = own - 1;
+            conflict = self.visit(
+                cell,
+                axis + 1,
+                dst_sqr + below * below,
+                position,
+                min_dst_sqr,
+                cell_size,
+                samples,
+            );
+        }
+        if !conflict && dst_sqr + above * above < min_dst_sqr {
+            cell[axis] = own + 1;
+            conflict = self.visit(
+                cell,
+                axis + 1,
+                dst_sqr + above * above,
+                position,
+                min_dst_sqr,
+                cell_size,
+                samples,
+            );
+        }
+        cell[axis] = own;
+        conflict
+    }
+}
+
+/// One bit per cell of a dense grid, set if the cell holds a sample.
+///
+/// This lets the neighbor scan skip 64 empty cells at once without touching
+/// the cells themselves, which are 64 times larger. Early during generation
+/// almost all cells are empty.
+#[derive(Debug, Default)]
+pub(crate) struct Occupancy {
+    words: Vec<u64>,
+}
+
+impl Occupancy {
+    fn new(cell_count: usize) -> Occupancy {
+        Occupancy {
+            words: vec![0; cell_count.div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, idx: usize) {
+        self.words[idx / 64] |= 1 << (idx % 64);
+    }
+
+    #[cfg(feature = "std")]
+    fn clear(&mut self, idx: usize) {
+        self.words[idx / 64] &= !(1 << (idx % 64));
+    }
+
+    /// The first occupied cell from `first` to `last`, inclusive.
+    fn next_set(&self, first: usize, last: usize) -> Option<usize> {
+        if first > last {
+            return None;
+        }
+        let mut word_idx = first / 64;
+        let mut word = self.words[word_idx] & (!0 << (first % 64));
+        loop {
+            if word != 0 {
+                let idx = word_idx * 64 + word.trailing_zeros() as usize;
+                return (idx <= last).then_some(idx);
+            }
+            word_idx += 1;
+            if word_idx * 64 > last {
+                return None;
+            }
+            word = self.words[word_idx];
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BackgroundGrid {
+    pub(crate) data: CellStorage,
+    /// Which cells of `data` are not empty.
+    pub(crate) occupancy: Occupancy,
+    /// Replaces `data` in high dimensions, see [`SPARSE_DIMENSION`].
+    pub(crate) sparse: Option<SparseCells>,
+    pub(crate) dimensions: Vec<f64>,
+    /// Which borders of each axis belong to the domain, empty if all axes
+    /// are half-open, see [`BlueNoiseConfig::bounds`](crate::BlueNoiseConfig::bounds).
+    pub(crate) bounds: Vec<Interval>,
+    /// Which cells are visited to find conflicts, see
+    /// [`BlueNoiseConfig::neighbor_search`](crate::BlueNoiseConfig::neighbor_search).
+    pub(crate) search: NeighborSearch,
+    /// Whether samples exactly the minimal distance apart conflict, see
+    /// [`BackgroundGrid::separate`].
+    pub(crate) separation: Separation,
+    /// Samples whose squared distance is less than this conflict.
+    pub(crate) min_dst_sqr: f64,
+    pub(crate) cell_size: f64,
+    pub(crate) cell_count: Vec<usize>,
+    pub(crate) cell_multiplicators: Vec<usize>,
+}
+
+/// Fails if a dense grid for a domain of size `dimensions` with samples at
+/// least `min_distance` apart would have more cells than can be addressed.
+///
+/// The cells are counted in floating point, which can't overflow, and each
+/// takes a `usize`, so the limit is `isize::MAX` bytes like for any
+/// allocation.
+pub(crate) fn check_size(dimensions: &[f64], min_distance: f64) -> Result<(), ConfigError> {
+    let cell_size = min_distance / (dimensions.len() as f64).sqrt();
+    // a flat axis has one cell
+    let cells: f64 = dimensions
+        .iter()
+        .map(|x| (x / cell_size).ceil().max(1_f64))
+        .product();
+    if cells * size_of::<usize>() as f64 > isize::MAX as f64 {
+        return Err(too_large(cells));
+    }
+    Ok(())
+}
+
+fn too_large(cells: f64) -> ConfigError {
+    ConfigError::DomainTooLarge {
+        cells,
+        bytes: cells * size_of::<usize>() as f64,
+    }
+}
+
+impl BackgroundGrid {
+    /// Creates an empty grid. Above [`SPARSE_DIMENSION`] dimensions, the
+    /// grid uses [`SparseCells`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domain is not valid, see [`BackgroundGrid::try_new`].
+    pub fn new(dimensions: Vec<f64>, min_distance: f64) -> BackgroundGrid {
+        BackgroundGrid::try_new(dimensions, min_distance).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates an empty grid, or fails if there are no dimensions, a size is
+    /// negative or not finite, `min_distance` is not positive and finite, or
+    /// the cells don't fit into memory.
+    pub fn try_new(dimensions: Vec<f64>, min_distance: f64) -> Result<BackgroundGrid, ConfigError> {
+        check_domain(&dimensions, min_distance)?;
+        if dimensions.len() > SPARSE_DIMENSION {
+            return Ok(BackgroundGrid::new_sparse(dimensions, min_distance));
+        }
+        check_size(&dimensions, min_distance)?;
+        let mut grid = BackgroundGrid::without_storage(dimensions, min_distance);
+        let mut data = Vec::new();
+        // the size may still be more than the system can allocate
+        data.try_reserve_exact(grid.data_size())
+            .map_err(|_| too_large(grid.data_size() as f64))?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            cells = grid.data_size(),
+            cell_size = grid.cell_size,
+            "allocating background grid"
+        );
+        data.resize(grid.data_size(), 0);
+        grid.data = CellStorage::Heap(data);
+        grid.occupancy = Occupancy::new(grid.data_size());
+        Ok(grid)
+    }
+
+    /// Creates a grid whose cells are stored in `file` instead of on the
+    /// heap. The previous content of `file` is discarded.
+    ///
+    /// Fails like [`try_new`](BackgroundGrid::try_new), with the
+    /// [`ConfigError`] as the inner error. Above [`SPARSE_DIMENSION`]
+    /// dimensions, the grid uses [`SparseCells`] on the heap as well, and
+    /// `file` is not used.
+    #[cfg(feature = "memmap")]
+    pub fn new_mapped(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        file: &std::fs::File,
+    ) -> std::io::Result<BackgroundGrid> {
+        check_domain(&dimensions, min_distance).map_err(std::io::Error::other)?;
+        if dimensions.len() > SPARSE_DIMENSION {
+            return Ok(BackgroundGrid::new_sparse(dimensions, min_distance));
+        }
+        check_size(&dimensions, min_distance).map_err(std::io::Error::other)?;
+        let mut grid = BackgroundGrid::without_storage(dimensions, min_distance);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            cells = grid.data_size(),
+            cell_size = grid.cell_size,
+            "mapping background grid"
+        );
+        grid.data = CellStorage::mapped(file, grid.data_size())?;
+        grid.occupancy = Occupancy::new(grid.data_size());
+        Ok(grid)
+    }
+
+    /// Creates a grid with [`SparseCells`] in any dimension. It has no dense
+    /// layout, i.e. no `cell_count` and `cell_multiplicators`.
+    pub(crate) fn new_sparse(dimensions: Vec<f64>, min_distance: f64) -> BackgroundGrid {
+        assert!(min_distance > 0.0);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(cell_size = min_distance, "using sparse background grid");
+        BackgroundGrid {
+            data: CellStorage::Heap(Vec::new()),
+            occupancy: Occupancy::default(),
+            sparse: Some(SparseCells::default()),
+            dimensions,
+            bounds: Vec::new(),
+            search: NeighborSearch::Compact,
+            separation: Separation::AtLeast,
+            min_dst_sqr: min_distance * min_distance,
+            cell_size: min_distance,
+            cell_count: Vec::new(),
+            cell_multiplicators: Vec::new(),
+        }
+    }
+
+    /// Creates a grid without any cells; only its layout is valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the domain has more cells than can be addressed, see
+    /// [`check_size`].
+    pub(crate) fn without_storage(dimensions: Vec<f64>, min_distance: f64) -> BackgroundGrid {
+        assert!(min_distance > 0.0);
+        if let Err(error) = check_size(&dimensions, min_distance) {
+            panic!("{error}");
+        }
+        let dimension = dimensions.len();
+        let cell_size = min_distance / (dimension as f64).sqrt();
+        let cell_count: Vec<usize> = dimensions
+            .iter()
+            .map(|x| ((x / cell_size).ceil() as usize).max(1))
+            .collect();
+        let mut cell_multiplicators = Vec::new();
+        let mut multi_accu = 1_usize;
+        for cell_count in cell_count.iter().take(dimension) {
+            cell_multiplicators.push(multi_accu);
+            multi_accu *= cell_count;
+        }
+        BackgroundGrid {
+            data: CellStorage::Heap(Vec::new()),
+            occupancy: Occupancy::default(),
+            sparse: None,
+            dimensions,
+            bounds: Vec::new(),
+            search: NeighborSearch::Compact,
+            separation: Separation::AtLeast,
+            min_dst_sqr: min_distance * min_distance,
+            cell_size,
+            cell_count,
+            cell_multiplicators,
+        }
+    }
+
+    pub(crate) fn data_size(&self) -> usize {
+        self.cell_count
+            .iter()
+            .try_fold(1_usize, |accu, count| accu.checked_mul(*count))
+            .expect("the number of cells was checked")
+    }
+
+    /// Whether `position` is inside of the domain, including the borders
+    /// which are closed.
+    pub(crate) fn contains(&self, position: &[f64]) -> bool {
+        if self.bounds.is_empty() {
+            return position
+                .iter()
+                .zip(self.dimensions.iter())
+                .all(|(x, dim)| Interval::ClosedOpen.contains(*x, *dim));
+        }
+        position
+            .iter()
+            .zip(self.dimensions.iter())
+            .zip(self.bounds.iter())
+            .all(|((x, dim), bounds)| bounds.contains(*x, *dim))
+    }
+
+    pub fn dst_sqr(x: &[f64], y: &[f64]) -> f64 {
+        debug_assert_eq!(x.len(), y.len());
+        x.iter().zip(y.iter()).fold(0_f64, |accu, (xx, yx)| {
+            let diff = xx - yx;
+            accu + diff * diff
+        })
+    }
+
+    pub(crate) fn calc_idx(&self, cell_id: &[usize]) -> usize {
+        self.cell_multiplicators
+            .iter()
+            .zip(cell_id.iter())
+            .skip(1)
+            .fold(cell_id[0], |accu, (multi, cell)| accu + multi * cell)
+    }
+
+    pub fn insert<S: SampleStorage>(
+        &mut self,
+        sample_position: Point,
+        samples: &mut S,
+    ) -> Result<usize, ()> {
+        let samp_idx = self.check(&sample_position, samples)?;
+        if let Some(sparse) = &mut self.sparse {
+            sparse.insert(&sample_position, self.cell_size, samples.len() + 1);
+            samples.push(sample_position);
+            return Ok(samples.len());
+        }
+        samples.push(sample_position);
+        debug_assert_eq!(self.data[samp_idx], 0);
+        self.data[samp_idx] = samples.len();
+        self.occupancy.set(samp_idx);
+        Ok(samples.len())
+    }
+
+    /// Puts the sample with the index `id - 1` into the cell of `position`,
+    /// which [`check`](BackgroundGrid::check) must have allowed.
+    #[cfg(feature = "std")]
+    pub(crate) fn put(&mut self, position: &[f64], id: usize) {
+        if let Some(sparse) = &mut self.sparse {
+            sparse.insert(position, self.cell_size, id);
+            return;
+        }
+        let idx = self.cell_index(position);
+        debug_assert_eq!(self.data[idx], 0);
+        self.data[idx] = id;
+        self.occupancy.set(idx);
+    }
+
+    /// Takes the sample with the index `id - 1` at `position` out of its
+    /// cell, so it doesn't conflict with anything anymore.
+    #[cfg(feature = "std")]
+    pub(crate) fn remove(&mut self, position: &[f64], id: usize) {
+        if let Some(sparse) = &mut self.sparse {
+            sparse.remove(position, self.cell_size, id);
+            return;
+        }
+        let idx = self.cell_index(position);
+        debug_assert_eq!(self.data[idx], id);
+        self.data[idx] = 0;
+        self.occupancy.clear(idx);
+    }
+
+    /// Checks whether a sample could be inserted at `sample_position` and
+    /// returns the index of its cell if so. With [`SparseCells`], the index is
+    /// always `0`.
+    pub fn check<S: SampleStorage>(
+        &self,
+        sample_position: &[f64],
+        samples: &S,
+    ) -> Result<usize, ()> {
+        if !self.contains(sample_position) {
+            return Err(());
+        }
+        let dimension = self.dimensions.len();
+        debug_assert_eq!(sample_position.len(), dimension);
+        match self.search {
+            NeighborSearch::Compact => {}
+            NeighborSearch::Conservative => {
+                return self.check_conservative(sample_position, samples);
+            }
+            NeighborSearch::Exhaustive => {
+                let conflict = (0..samples.len()).any(|i| {
+                    BackgroundGrid::dst_sqr(sample_position, samples.sample(i)) < self.min_dst_sqr
+                });
+                return if conflict {
+                    Err(())
+                } else {
+                    Ok(self.cell_index(sample_position))
+                };
+            }
+        }
+        if let Some(sparse) = &self.sparse {
+            return if sparse.has_conflict(
+                sample_position,
+                self.min_dst_sqr,
+                self.cell_size,
+                samples,
+            ) {
+                Err(())
+            } else {
+                Ok(0)
+            };
+        }
+        match dimension {
+            2 => return self.check_fixed::<S, 2>(sample_position.try_into().unwrap(), samples),
+            3 => return self.check_fixed::<S, 3>(sample_position.try_into().unwrap(), samples),
+            _ => (),
+        }
+        let cell_id: SmallVec<[usize; 4]> = sample_position
+            .iter()
+            .zip(self.cell_count.iter())
+            // a closed upper border is in the last cell
+            .map(|(x, count)| ((*x / self.cell_size) as usize).min(count - 1))
+            .collect();
+        let samp_idx = self.calc_idx(&cell_id);
+        debug_assert!(
+            cell_id
+                .iter()
+                .zip(self.cell_count.iter())
+                .all(|(cid, cc)| cid < cc)
+        );
+        let cell_offs = self.cell_offs();
+        let min_cell: SmallVec<[usize; 4]> = cell_id
+            .iter()
+            .map(|x| x.saturating_sub(cell_offs))
+            .collect();
+        let max_cell: SmallVec<[usize; 4]> = cell_id
+            .iter()
+            .zip(self.cell_count.iter())
+            .map(|(x, size_x)| min(x + cell_offs, size_x - 1))
+            .collect();
+        debug_assert!(
+            min_cell
+                .iter()
+                .zip(max_cell.iter())
+                .zip(cell_id.iter())
+                .all(|((cmin, cmax), c)| cmin <= c && c <= cmax)
+        );
+        // Rows along the first axis are contiguous, so their empty cells can
+        // be skipped using the occupancy bits.
+        let row_length = max_cell[0] - min_cell[0];
+        let mut indices = min_cell.clone();
+        let mut checked_own_idx = false;
+        loop {
+            debug_assert!(
+                min_cell
+                    .iter()
+                    .zip(max_cell.iter())
+                    .zip(indices.iter())
+                    .all(|((cmin, cmax), c)| cmin <= c && c <= cmax)
+            );
+            let row_start = self.calc_idx(&indices);
+            let row_end = row_start + row_length;
+            if (row_start..=row_end).contains(&samp_idx) {
+                checked_own_idx = true;
+            }
+            let mut idx = row_start;
+            while let Some(occupied) = self.occupancy.next_set(idx, row_end) {
+                debug_assert_ne!(self.data[occupied], 0);
+                let other_sample = samples.sample(self.data[occupied] - 1);
+                if BackgroundGrid::dst_sqr(sample_position, other_sample) < self.min_dst_sqr {
+                    return Err(());
+                }
+                idx = occupied + 1;
+            }
+            // loop exit check
+            if indices[1..] == max_cell[1..] {
+                break;
+            }
+            // iterate rows
+            for i in 1..dimension {
+                if indices[i] == max_cell[i] {
+                    indices[i] = min_cell[i];
+                } else {
+                    indices[i] += 1;
+                    break;
+                }
+            }
+        }
+        // no collission found
+        debug_assert!(
+            checked_own_idx,
+            "Didn't check own idx.\n\tMin cells: {:?}\n\tMax cells: \
+                               {:?}\n\tself cells: {:?}",
+            min_cell, max_cell, cell_id
+        );
+        Ok(samp_idx)
+    }
+
+    /// How many cells in each direction have to be checked for conflicts.
+    ///
+    /// A conflicting sample is less than the minimal distance away along
+    /// every axis, i.e. less than `sqrt(n)` cell edges in `n` dimensions, so
+    /// its cell is at most `ceil(sqrt(n))` cells away, in exact arithmetic;
+    /// see [`NeighborSearch::Conservative`] for a range which also holds
+    /// with rounding.
+    fn cell_offs(&self) -> usize {
+        (self.min_dst_sqr.sqrt() / self.cell_size).ceil() as usize
+    }
+
+    /// Sets whether samples exactly the minimal distance apart conflict.
+    pub(crate) fn separate(&mut self, separation: Separation) {
+        if separation != self.separation {
+            // `<` the next larger number is `<=` the number itself
+            self.min_dst_sqr = match separation {
+                Separation::AtLeast => self.min_dst_sqr.next_down(),
+                Separation::MoreThan => self.min_dst_sqr.next_up(),
+            };
+            self.separation = separation;
+        }
+    }
+
+    /// The index of the cell of a position inside of the domain in `data`,
+    /// zero for a sparse grid.
+    fn cell_index(&self, position: &[f64]) -> usize {
+        if self.sparse.is_some() {
+            return 0;
+        }
+        let cell_id: SmallVec<[usize; 4]> = position
+            .iter()
+            .zip(self.cell_count.iter())
+            .map(|(x, count)| ((*x / self.cell_size) as usize).min(count - 1))
+            .collect();
+        self.calc_idx(&cell_id)
+    }
+
+    /// Same as [`check`](BackgroundGrid::check), but visits all the cells
+    /// between the ones of the corners of the box around the ball of the
+    /// minimal distance, which is slightly enlarged to absorb the rounding
+    /// of its square root. As the cell of a coordinate never decreases when
+    /// the coordinate grows, no sample closer than the minimal distance can
+    /// be in a cell outside of this range, whatever the rounding.
+    fn check_conservative<S: SampleStorage>(
+        &self,
+        sample_position: &[f64],
+        samples: &S,
+    ) -> Result<usize, ()> {
+        let radius = self.min_dst_sqr.sqrt() * (1_f64 + 1e-9);
+        let cell_of = |x: f64| (x.max(0_f64) / self.cell_size) as usize;
+        let min_cell: SmallVec<[usize; 8]> = sample_position
+            .iter()
+            .map(|x| cell_of(x - radius))
+            .collect();
+        let max_cell: SmallVec<[usize; 8]> = match &self.sparse {
+            Some(_) => sample_position
+                .iter()
+                .map(|x| cell_of(x + radius))
+                .collect(),
+            None => sample_position
+                .iter()
+                .zip(self.cell_count.iter())
+                .map(|(x, count)| cell_of(x + radius).min(count - 1))
+                .collect(),
+        };
+        let conflicts = |ids: &[usize]| {
+            ids.iter().any(|id| {
+                BackgroundGrid::dst_sqr(sample_position, samples.sample(id - 1)) < self.min_dst_sqr
+            })
+        };
+        // all the cells from min_cell to max_cell, like an odometer
+        let mut cell = min_cell.clone();
+        loop {
+            let conflict = match &self.sparse {
+                Some(sparse) => sparse.cells.get(&cell).is_some_and(|ids| conflicts(ids)),
+                None => {
+                    let id = self.data[self.calc_idx(&cell)];
+                    id != 0 && conflicts(&[id])
+                }
+            };
+            if conflict {
+                return Err(());
+            }
+            let Some(axis) = (0..cell.len()).find(|axis| cell[*axis] < max_cell[*axis]) else {
+                return Ok(self.cell_index(sample_position));
+            };
+            cell[axis] += 1;
+            cell[..axis].copy_from_slice(&min_cell[..axis]);
+        }
+    }
+
+    /// Same as [`check`](BackgroundGrid::check) in `D` dimensions, for the
+    /// common cases of 2 and 3 dimensions. The position must be inside of
+    /// the domain.
+    #[inline]
+    fn check_fixed<S: SampleStorage, const D: usize>(
+        &self,
+        sample_position: [f64; D],
+        samples: &S,
+    ) -> Result<usize, ()> {
+        debug_assert!(D == 2 || D == 3);
+        let cell_offs = self.cell_offs();
+        let mut min_cell = [0_usize; D];
+        let mut max_cell = [0_usize; D];
+        let mut samp_idx = 0;
+        for i in 0..D {
+            let cell = ((sample_position[i] / self.cell_size) as usize).min(self.cell_count[i] - 1);
+            samp_idx += cell * self.cell_multiplicators[i];
+            min_cell[i] = cell.saturating_sub(cell_offs);
+            max_cell[i] = min(cell + cell_offs, self.cell_count[i] - 1);
+        }
+        let (min_z, max_z, multi_z) = if D == 3 {
+            (
+                min_cell[D - 1],
+                max_cell[D - 1],
+                self.cell_multiplicators[D - 1],
+            )
+        } else {
+            (0, 0, 0)
+        };
+        let row_length = max_cell[0] - min_cell[0];
+        for z in min_z..=max_z {
+            for y in min_cell[1]..=max_cell[1] {
+                // see `check` for the use of the occupancy bits
+                let row_start = min_cell[0] + y * self.cell_multiplicators[1] + z * multi_z;
+                let row_end = row_start + row_length;
+                let mut idx = row_start;
+                while let Some(occupied) = self.occupancy.next_set(idx, row_end) {
+                    let other_sample = samples.sample(self.data[occupied] - 1);
+                    let mut dst_sqr = 0_f64;
+                    for i in 0..D {
+                        let diff = sample_position[i] - other_sample[i];
+                        dst_sqr += diff * diff;
+                    }
+                    if dst_sqr < self.min_dst_sqr {
+                        return Err(());
+                    }
+                    idx = occupied + 1;
+                }
+            }
+        }
+        Ok(samp_idx)
+    }
+
+    /// The indices of the samples which are less than `radius` away from
+    /// `query`, in no particular order.
+    pub(crate) fn within<'a, S: SampleStorage>(
+        &'a self,
+        query: &[f64],
+        radius: f64,
+        samples: &'a S,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let query = Point::from_slice(query);
+        let radius_sqr = radius * radius;
+        let dimension = self.dimensions.len();
+        // the samples which are in the grid, without the removed ones
+        let sparse = match &self.sparse {
+            Some(sparse) if radius > 0_f64 => sparse.around(&query, radius, self.cell_size),
+            _ => Vec::new(),
+        };
+        // the box of cells around the ball, if it intersects the domain
+        let cell_box = if self.sparse.is_some() || samples.is_empty() || radius <= 0_f64 {
+            None
+        } else {
+            let outside = query
+                .iter()
+                .zip(self.dimensions.iter())
+                .any(|(x, dim)| *x + radius < 0_f64 || *x - radius >= *dim);
+            let cell_of =
+                |x: f64, size_x: usize| ((x.max(0_f64) / self.cell_size) as usize).min(size_x - 1);
+            (!outside).then(|| {
+                let min_cell: SmallVec<[usize; 4]> = query
+                    .iter()
+                    .zip(self.cell_count.iter())
+                    .map(|(x, size_x)| cell_of(x - radius, *size_x))
+                    .collect();
+                let max_cell: SmallVec<[usize; 4]> = query
+                    .iter()
+                    .zip(self.cell_count.iter())
+                    .map(|(x, size_x)| cell_of(x + radius, *size_x))
+                    .collect();
+                (min_cell, max_cell)
+            })
+        };
+        let mut next_cell = cell_box.as_ref().map(|(min_cell, _)| min_cell.clone());
+        let cells = core::iter::from_fn(move || {
+            let (min_cell, max_cell) = cell_box.as_ref()?;
+            let indices = next_cell.as_mut()?;
+            let idx = self.calc_idx(indices);
+            // iterate indices
+            if indices == max_cell {
+                next_cell = None;
+            } else {
+                for i in 0..dimension {
+                    if indices[i] == max_cell[i] {
+                        indices[i] = min_cell[i];
+                    } else {
+                        indices[i] += 1;
+                        break;
+                    }
+                }
+            }
+            Some(idx)
+        })
+        .filter_map(|idx| self.data[idx].checked_sub(1));
+        sparse
+            .into_iter()
+            .chain(cells)
+            .filter(move |i| BackgroundGrid::dst_sqr(&query, samples.sample(*i)) < radius_sqr)
+    }
+
+    /// Finds the `k` samples closest to `query`, which may be outside of the
+    /// domain. Returns their indices in `samples` and their distances, sorted
+    /// by distance.
+    #[cfg(feature = "std")]
+    pub(crate) fn knn<S: SampleStorage>(
+        &self,
+        query: &[f64],
+        k: usize,
+        samples: &S,
+    ) -> Vec<(usize, f64)> {
+        let k = k.min(samples.len());
+        if k == 0 {
+            return Vec::new();
+        }
+        if self.sparse.is_some() {
+            // the shells of cells around the query are too large to search
+            let mut found: Vec<(usize, f64)> = (0..samples.len())
+                .map(|i| (i, BackgroundGrid::dst_sqr(query, samples.sample(i))))
+                .collect();
+            found.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+            return found
+                .into_iter()
+                .take(k)
+                .map(|(i, dst_sqr)| (i, dst_sqr.sqrt()))
+                .collect();
+        }
+        let dimension = self.dimensions.len();
+        // the cell of the closest point of the domain
+        let center: SmallVec<[usize; 4]> = query
+            .iter()
+            .zip(self.cell_count.iter())
+            .map(|(x, size_x)| ((x.max(0_f64) / self.cell_size) as usize).min(size_x - 1))
+            .collect();
+        // the closest samples so far with their squared distances, sorted
+        let mut found: Vec<(usize, f64)> = Vec::with_capacity(k + 1);
+        // search shells of cells around the center, i.e. the cells with
+        // a maximal distance of `shell` cells in any direction
+        for shell in 0.. {
+            let min_cell: SmallVec<[usize; 4]> =
+                center.iter().map(|c| c.saturating_sub(shell)).collect();
+            let max_cell: SmallVec<[usize; 4]> = center
+                .iter()
+                .zip(self.cell_count.iter())
+                .map(|(c, size_x)| min(c + shell, size_x - 1))
+                .collect();
+            let mut indices = min_cell.clone();
+            loop {
+                let on_shell = indices
+                    .iter()
+                    .zip(center.iter())
+                    .any(|(i, c)| i.abs_diff(*c) == shell);
+                if on_shell {
+                    let idx = self.calc_idx(&indices);
+                    if self.data[idx] != 0 {
+                        let i = self.data[idx] - 1;
+                        let dst_sqr = BackgroundGrid::dst_sqr(query, samples.sample(i));
+                        let pos = found.partition_point(|(_, other)| *other <= dst_sqr);
+                        if pos < k {
+                            found.insert(pos, (i, dst_sqr));
+                            found.truncate(k);
+                        }
+                    }
+                }
+                // loop exit check
+                if indices == max_cell {
+                    break;
+                }
+                // iterate indices
+                for i in 0..dimension {
+                    if indices[i] == max_cell[i] {
+                        indices[i] = min_cell[i];
+                    } else {
+                        indices[i] += 1;
+                        break;
+                    }
+                }
+            }
+            // All other cells are at least `shell` cells away from the
+            // closest point of the domain, thus from the query too.
+            let bound = shell as f64 * self.cell_size;
+            let covers_grid = min_cell.iter().all(|c| *c == 0)
+                && max_cell
+                    .iter()
+                    .zip(self.cell_count.iter())
+                    .all(|(c, size_x)| c + 1 == *size_x);
+            if covers_grid || (found.len() == k && found[k - 1].1 <= bound * bound) {
+                break;
+            }
+        }
+        found
+            .into_iter()
+            .map(|(i, dst_sqr)| (i, dst_sqr.sqrt()))
+            .collect()
+    }
+
+    /// Copies the state of the grid, see [`GridSnapshot`].
+    pub(crate) fn snapshot(&self) -> GridSnapshot {
+        if let Some(sparse) = &self.sparse {
+            let mut occupied: Vec<OccupiedCell> = sparse
+                .cells
+                .iter()
+                .map(|(cell, ids)| OccupiedCell {
+                    cell: cell.to_vec(),
+                    samples: ids.iter().map(|id| id - 1).collect(),
+                })
+                .collect();
+            occupied.sort_by(|a, b| a.cell.iter().rev().cmp(b.cell.iter().rev()));
+            return GridSnapshot {
+                cell_size: self.cell_size,
+                cell_count: None,
+                occupied,
+            };
+        }
+        let mut occupied = Vec::new();
+        let mut idx = 0;
+        while let Some(cell_idx) = self
+            .occupancy
+            .next_set(idx, self.data.len().saturating_sub(1))
+        {
+            let mut rest = cell_idx;
+            let cell = self
+                .cell_count
+                .iter()
+                .map(|count| {
+                    let c = rest % count;
+                    rest /= count;
+                    c
+                })
+                .collect();
+            occupied.push(OccupiedCell {
+                cell,
+                samples: vec![self.data[cell_idx] - 1],
+            });
+            idx = cell_idx + 1;
+        }
+        GridSnapshot {
+            cell_size: self.cell_size,
+            cell_count: Some(self.cell_count.clone()),
+            occupied,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+
+    #[test]
+    fn grid_corners() {
+        let mut grid = BackgroundGrid::new(vec![35_f64, 9_f64], 4.0);
+        let mut samples: Vec<Point> = Vec::new();
+        assert_eq!(grid.cell_count.len(), 2);
+        assert_eq!(grid.insert(smallvec![0., 9.], &mut samples), Err(()));
+        assert_eq!(samples.len(), 0);
+        assert_eq!(grid.insert(smallvec![0., 0.], &mut samples), Ok(1));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(grid.insert(smallvec![34., 0.], &mut samples), Ok(2));
+        assert_eq!(samples.len(), 2);
+        assert_eq!(grid.insert(smallvec![0., 8.], &mut samples), Ok(3));
+        assert_eq!(samples.len(), 3);
+        assert_eq!(grid.insert(smallvec![34., 8.], &mut samples), Ok(4));
+        assert_eq!(samples.len(), 4);
+    }
+
+    #[test]
+    fn separation() {
+        let mut samples: Vec<Point> = Vec::new();
+        let mut grid = BackgroundGrid::new(vec![4., 4.], 1.);
+        grid.insert(smallvec![1., 1.], &mut samples).unwrap();
+        // exactly the minimal distance apart
+        assert!(grid.check(&[2., 1.], &samples).is_ok());
+        grid.separate(Separation::MoreThan);
+        assert!(grid.check(&[2., 1.], &samples).is_err());
+        assert!(grid.check(&[1., 2.], &samples).is_err());
+        assert!(grid.check(&[2.5, 1.], &samples).is_ok());
+        grid.separate(Separation::AtLeast);
+        assert_eq!(grid.min_dst_sqr, 1.);
+        assert!(grid.check(&[2., 1.], &samples).is_ok());
+    }
+
+    #[test]
+    fn close_minimal_distance() {
+        // with cells of 0.3 / √2, the samples are two cells apart but closer
+        // than the minimal distance
+        let mut samples: Vec<Point> = Vec::new();
+        let mut grid = BackgroundGrid::new(vec![1., 2.], 0.3);
+        grid.insert(smallvec![0.2, 1.], &mut samples).unwrap();
+        assert!(grid.check(&[0.49, 1.], &samples).is_err());
+        assert!(grid.check(&[0.51, 1.], &samples).is_ok());
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut grid = BackgroundGrid::new(vec![35_f64, 9_f64], 4.0);
+        let mut samples: Vec<Point> = Vec::new();
+        assert_eq!(grid.snapshot().fill_ratio(), Some(0.));
+        grid.insert(smallvec![34., 8.], &mut samples).unwrap();
+        grid.insert(smallvec![0., 0.], &mut samples).unwrap();
+        let snapshot = grid.snapshot();
+        assert_eq!(snapshot.cell_count, Some(vec![13, 4]));
+        assert_eq!(
+            snapshot.occupied,
+            vec![
+                OccupiedCell {
+                    cell: vec![0, 0],
+                    samples: vec![1]
+                },
+                OccupiedCell {
+                    cell: vec![12, 2],
+                    samples: vec![0]
+                },
+            ]
+        );
+        let occupancy = snapshot.occupancy().unwrap();
+        assert_eq!(occupancy.iter().filter(|o| **o).count(), 2);
+        assert!(occupancy[0] && occupancy[12 + 2 * 13]);
+        let mut sparse = BackgroundGrid::new(vec![10_f64; 7], 2.0);
+        sparse.insert(smallvec![1.; 7], &mut samples).unwrap();
+        let snapshot = sparse.snapshot();
+        assert_eq!(snapshot.cell_count, None);
+        assert_eq!(snapshot.occupied[0].cell, vec![0; 7]);
+        assert_eq!(snapshot.occupied[0].samples, vec![2]);
+    }
+
+    #[test]
+    fn occupancy() {
+        let mut occupancy = Occupancy::new(200);
+        assert_eq!(occupancy.next_set(0, 199), None);
+        occupancy.set(3);
+        occupancy.set(130);
+        assert_eq!(occupancy.next_set(0, 199), Some(3));
+        assert_eq!(occupancy.next_set(3, 3), Some(3));
+        assert_eq!(occupancy.next_set(4, 199), Some(130));
+        assert_eq!(occupancy.next_set(4, 129), None);
+        assert_eq!(occupancy.next_set(131, 199), None);
+    }
+
+    #[test]
+    fn sparse_grid() {
+        let mut grid = BackgroundGrid::new(vec![10_f64; 9], 2.0);
+        assert!(grid.sparse.is_some() && grid.data.is_empty());
+        let mut samples: Vec<Point> = Vec::new();
+        let mut position: Point = smallvec![3.9; 9];
+        assert_eq!(grid.insert(position.clone(), &mut samples), Ok(1));
+        // in the adjacent cell and close enough
+        position[4] = 4.1;
+        assert_eq!(grid.insert(position.clone(), &mut samples), Err(()));
+        // in the adjacent cell along several axes, but far away
+        let mut far: Point = smallvec![3.9; 9];
+        far[..3].copy_from_slice(&[5.1; 3]);
+        assert_eq!(grid.insert(far, &mut samples), Ok(2));
+        position[4] = 5.9;
+        assert_eq!(grid.insert(position.clone(), &mut samples), Ok(3));
+        assert_eq!(grid.insert(smallvec![10.; 9], &mut samples), Err(()));
+    }
+
+    #[test]
+    fn neighbor_search() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(9);
+        // dense in 2, 3 and 4 dimensions, and sparse
+        for dimensions in [vec![12.; 2], vec![6.; 3], vec![4.; 4], vec![3.; 7]] {
+            let mut grids = [
+                NeighborSearch::Compact,
+                NeighborSearch::Conservative,
+                NeighborSearch::Exhaustive,
+            ]
+            .map(|search| {
+                let mut grid = BackgroundGrid::new(dimensions.clone(), 1.);
+                grid.search = search;
+                (grid, Vec::<Point>::new())
+            });
+            for _ in 0..2000 {
+                let position: Point = dimensions.iter().map(|x| rng.gen_range(0. ..*x)).collect();
+                let inserted = grids
+                    .each_mut()
+                    .map(|(grid, samples)| grid.insert(position.clone(), samples));
+                assert!(inserted.iter().all(|id| *id == inserted[0]));
+            }
+            assert!(grids[0].1.len() > 20);
+        }
+    }
+
+    #[cfg(feature = "memmap")]
+    #[test]
+    fn mapped_grid() {
+        let file = tempfile::tempfile().unwrap();
+        let mut grid = BackgroundGrid::new_mapped(vec![35_f64, 9_f64], 4.0, &file).unwrap();
+        let mut samples: Vec<Point> = Vec::new();
+        assert!(grid.data.iter().all(|cell| *cell == 0));
+        assert_eq!(grid.insert(smallvec![0., 0.], &mut samples), Ok(1));
+        assert_eq!(grid.insert(smallvec![1., 1.], &mut samples), Err(()));
+        assert_eq!(grid.insert(smallvec![34., 8.], &mut samples), Ok(2));
+        assert_eq!(file.metadata().unwrap().len() as usize, grid.data.len() * 8);
+        for (dimensions, min_distance) in [(vec![f64::NAN, 9.], 4.), (vec![35., 9.], 0.)] {
+            let error = BackgroundGrid::new_mapped(dimensions, min_distance, &file).unwrap_err();
+            assert!(error.get_ref().unwrap().is::<ConfigError>());
+        }
+        let sparse = BackgroundGrid::new_mapped(vec![3.; 7], 1., &file).unwrap();
+        assert!(sparse.sparse.is_some());
+    }
+}
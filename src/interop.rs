@@ -0,0 +1,511 @@
+//! Conversions between samples and the types of other math crates.
+
+#[cfg(any(
+    feature = "cgmath",
+    feature = "glam",
+    feature = "mint",
+    feature = "nalgebra",
+    feature = "ndarray"
+))]
+use crate::PoissonDiskSet;
+#[cfg(feature = "ndarray")]
+use crate::{Point, Violation};
+
+/// Copies `samples` into an array with one row per sample.
+///
+/// ```
+/// let samples = bluenoisers::blue_noise(vec![10., 5.], 1., 30);
+/// let array = bluenoisers::to_array(&samples);
+/// assert_eq!(array.dim(), (samples.len(), 2));
+/// assert_eq!(array[[3, 1]], samples[3][1]);
+/// ```
+///
+/// An empty slice gives an array of shape `(0, 0)`.
+///
+/// Requires the `ndarray` feature.
+///
+/// # Panics
+///
+/// Panics if the samples don't all have the same number of coordinates.
+#[cfg(feature = "ndarray")]
+pub fn to_array<P: AsRef<[f64]>>(samples: &[P]) -> ndarray::Array2<f64> {
+    let dimension = samples.first().map_or(0, |s| s.as_ref().len());
+    let mut array = ndarray::Array2::zeros((samples.len(), dimension));
+    for (mut row, sample) in array.rows_mut().into_iter().zip(samples) {
+        let sample = sample.as_ref();
+        assert_eq!(
+            sample.len(),
+            dimension,
+            "all samples must have the same number of coordinates"
+        );
+        row.assign(&ndarray::ArrayView1::from(sample));
+    }
+    array
+}
+
+#[cfg(feature = "ndarray")]
+impl PoissonDiskSet {
+    /// Copies the samples into an array with one row per sample and one
+    /// column per dimension, see [`to_array`].
+    ///
+    /// Requires the `ndarray` feature.
+    pub fn to_array(&self) -> ndarray::Array2<f64> {
+        if self.is_empty() {
+            return ndarray::Array2::zeros((0, self.dimensions().len()));
+        }
+        to_array(self.samples())
+    }
+
+    /// Builds a set from the rows of `points`, see
+    /// [`from_points`](PoissonDiskSet::from_points).
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    /// use ndarray::array;
+    ///
+    /// let points = array![[1., 1.], [4., 2.]];
+    /// let set = PoissonDiskSet::from_array(vec![5., 5.], 2., points.view()).unwrap();
+    /// assert_eq!(set.to_array(), points);
+    /// ```
+    ///
+    /// Requires the `ndarray` feature.
+    pub fn from_array(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        points: ndarray::ArrayView2<f64>,
+    ) -> Result<PoissonDiskSet, Vec<Violation>> {
+        let points = points
+            .rows()
+            .into_iter()
+            .map(|row| row.iter().copied().collect::<Point>());
+        PoissonDiskSet::from_points(dimensions, min_distance, points.collect())
+    }
+}
+
+/// A cgmath point which samples can be converted into, see [`to_cgmath`].
+///
+/// Requires the `cgmath` feature.
+#[cfg(feature = "cgmath")]
+pub trait CgmathPoint: Sized {
+    /// The number of coordinates.
+    const DIMENSION: usize;
+
+    /// Converts a sample with [`DIMENSION`](CgmathPoint::DIMENSION)
+    /// coordinates.
+    fn from_sample(sample: &[f64]) -> Self;
+}
+
+#[cfg(feature = "cgmath")]
+impl CgmathPoint for cgmath::Point2<f64> {
+    const DIMENSION: usize = 2;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        cgmath::Point2::new(sample[0], sample[1])
+    }
+}
+
+#[cfg(feature = "cgmath")]
+impl CgmathPoint for cgmath::Point3<f64> {
+    const DIMENSION: usize = 3;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        cgmath::Point3::new(sample[0], sample[1], sample[2])
+    }
+}
+
+/// Converts `samples` into cgmath points, `Point2<f64>` or `Point3<f64>`.
+///
+/// ```
+/// use cgmath::Point2;
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 5.], 1., 30);
+/// let points: Vec<Point2<f64>> = bluenoisers::to_cgmath(&samples);
+/// assert_eq!(points[3].y, samples[3][1]);
+/// ```
+///
+/// Requires the `cgmath` feature.
+///
+/// # Panics
+///
+/// Panics if a sample doesn't have as many coordinates as the points.
+#[cfg(feature = "cgmath")]
+pub fn to_cgmath<V: CgmathPoint, P: AsRef<[f64]>>(samples: &[P]) -> Vec<V> {
+    samples
+        .iter()
+        .map(|sample| {
+            let sample = sample.as_ref();
+            assert_eq!(
+                sample.len(),
+                V::DIMENSION,
+                "the samples must have as many coordinates as the points"
+            );
+            V::from_sample(sample)
+        })
+        .collect()
+}
+
+#[cfg(feature = "cgmath")]
+impl PoissonDiskSet {
+    /// Converts the samples into cgmath points, see [`to_cgmath`].
+    ///
+    /// Requires the `cgmath` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension of the set differs from the points'.
+    pub fn to_cgmath<V: CgmathPoint>(&self) -> Vec<V> {
+        assert_eq!(
+            self.dimensions().len(),
+            V::DIMENSION,
+            "the dimension of the set must be the points'"
+        );
+        to_cgmath(self.samples())
+    }
+}
+
+/// A glam vector which samples can be converted into, see [`to_glam`].
+///
+/// Requires the `glam` feature.
+#[cfg(feature = "glam")]
+pub trait GlamVector: Sized {
+    /// The number of coordinates.
+    const DIMENSION: usize;
+
+    /// Converts a sample with [`DIMENSION`](GlamVector::DIMENSION)
+    /// coordinates, possibly losing precision.
+    fn from_sample(sample: &[f64]) -> Self;
+}
+
+#[cfg(feature = "glam")]
+impl GlamVector for glam::Vec2 {
+    const DIMENSION: usize = 2;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        glam::Vec2::new(sample[0] as f32, sample[1] as f32)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl GlamVector for glam::Vec3 {
+    const DIMENSION: usize = 3;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        glam::Vec3::new(sample[0] as f32, sample[1] as f32, sample[2] as f32)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl GlamVector for glam::DVec2 {
+    const DIMENSION: usize = 2;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        glam::DVec2::new(sample[0], sample[1])
+    }
+}
+
+#[cfg(feature = "glam")]
+impl GlamVector for glam::DVec3 {
+    const DIMENSION: usize = 3;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        glam::DVec3::new(sample[0], sample[1], sample[2])
+    }
+}
+
+/// Converts `samples` into glam vectors: `Vec2`, `Vec3`, `DVec2` or `DVec3`.
+///
+/// ```
+/// use glam::Vec2;
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 5.], 1., 30);
+/// let positions: Vec<Vec2> = bluenoisers::to_glam(&samples);
+/// assert_eq!(positions[3].y, samples[3][1] as f32);
+/// ```
+///
+/// Requires the `glam` feature.
+///
+/// # Panics
+///
+/// Panics if a sample doesn't have as many coordinates as the vectors.
+#[cfg(feature = "glam")]
+pub fn to_glam<V: GlamVector, P: AsRef<[f64]>>(samples: &[P]) -> Vec<V> {
+    samples
+        .iter()
+        .map(|sample| {
+            let sample = sample.as_ref();
+            assert_eq!(
+                sample.len(),
+                V::DIMENSION,
+                "the samples must have as many coordinates as the vectors"
+            );
+            V::from_sample(sample)
+        })
+        .collect()
+}
+
+#[cfg(feature = "glam")]
+impl PoissonDiskSet {
+    /// Converts the samples into glam vectors, see [`to_glam`].
+    ///
+    /// Requires the `glam` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension of the set differs from the vectors'.
+    pub fn to_glam<V: GlamVector>(&self) -> Vec<V> {
+        assert_eq!(
+            self.dimensions().len(),
+            V::DIMENSION,
+            "the dimension of the set must be the vectors'"
+        );
+        to_glam(self.samples())
+    }
+}
+
+/// A mint type which samples can be converted into, see [`to_mint`].
+///
+/// Requires the `mint` feature.
+#[cfg(feature = "mint")]
+pub trait MintVector: Sized {
+    /// The number of coordinates.
+    const DIMENSION: usize;
+
+    /// Converts a sample with [`DIMENSION`](MintVector::DIMENSION)
+    /// coordinates.
+    fn from_sample(sample: &[f64]) -> Self;
+}
+
+#[cfg(feature = "mint")]
+impl MintVector for mint::Point2<f64> {
+    const DIMENSION: usize = 2;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        mint::Point2 {
+            x: sample[0],
+            y: sample[1],
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl MintVector for mint::Point3<f64> {
+    const DIMENSION: usize = 3;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        mint::Point3 {
+            x: sample[0],
+            y: sample[1],
+            z: sample[2],
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl MintVector for mint::Vector2<f64> {
+    const DIMENSION: usize = 2;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        mint::Vector2 {
+            x: sample[0],
+            y: sample[1],
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl MintVector for mint::Vector3<f64> {
+    const DIMENSION: usize = 3;
+
+    fn from_sample(sample: &[f64]) -> Self {
+        mint::Vector3 {
+            x: sample[0],
+            y: sample[1],
+            z: sample[2],
+        }
+    }
+}
+
+/// Converts `samples` into mint points or vectors, which most math crates
+/// convert from, e.g. with `Into`.
+///
+/// ```
+/// use mint::Point2;
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 5.], 1., 30);
+/// let points: Vec<Point2<f64>> = bluenoisers::to_mint(&samples);
+/// assert_eq!(points[3].y, samples[3][1]);
+/// ```
+///
+/// Requires the `mint` feature.
+///
+/// # Panics
+///
+/// Panics if a sample doesn't have as many coordinates as the points.
+#[cfg(feature = "mint")]
+pub fn to_mint<V: MintVector, P: AsRef<[f64]>>(samples: &[P]) -> Vec<V> {
+    samples
+        .iter()
+        .map(|sample| {
+            let sample = sample.as_ref();
+            assert_eq!(
+                sample.len(),
+                V::DIMENSION,
+                "the samples must have as many coordinates as the points"
+            );
+            V::from_sample(sample)
+        })
+        .collect()
+}
+
+#[cfg(feature = "mint")]
+impl PoissonDiskSet {
+    /// Converts the samples into mint points or vectors, see [`to_mint`].
+    ///
+    /// Requires the `mint` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dimension of the set differs from the points'.
+    pub fn to_mint<V: MintVector>(&self) -> Vec<V> {
+        assert_eq!(
+            self.dimensions().len(),
+            V::DIMENSION,
+            "the dimension of the set must be the points'"
+        );
+        to_mint(self.samples())
+    }
+}
+
+/// Converts `samples` into nalgebra points of dimension `D`, e.g.
+/// `Point2<f64>` or `Point3<f64>`.
+///
+/// ```
+/// use nalgebra::Point2;
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 5.], 1., 30);
+/// let points: Vec<Point2<f64>> = bluenoisers::to_nalgebra(&samples);
+/// assert_eq!(points[3].y, samples[3][1]);
+/// ```
+///
+/// Requires the `nalgebra` feature.
+///
+/// # Panics
+///
+/// Panics if a sample doesn't have `D` coordinates.
+#[cfg(feature = "nalgebra")]
+pub fn to_nalgebra<P: AsRef<[f64]>, const D: usize>(samples: &[P]) -> Vec<nalgebra::Point<f64, D>> {
+    samples
+        .iter()
+        .map(|sample| {
+            let sample: [f64; D] = sample
+                .as_ref()
+                .try_into()
+                .expect("the samples must have `D` coordinates");
+            nalgebra::Point::from(sample)
+        })
+        .collect()
+}
+
+#[cfg(feature = "nalgebra")]
+impl PoissonDiskSet {
+    /// Converts the samples into nalgebra points of dimension `D`, see
+    /// [`to_nalgebra`].
+    ///
+    /// Requires the `nalgebra` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not `D`-dimensional.
+    pub fn to_nalgebra<const D: usize>(&self) -> Vec<nalgebra::Point<f64, D>> {
+        assert_eq!(
+            self.dimensions().len(),
+            D,
+            "the set must be `D`-dimensional"
+        );
+        to_nalgebra(self.samples())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray() {
+        use crate::PoissonDiskSet;
+
+        let set = PoissonDiskSet::new(vec![6., 5., 4.], 1., 30);
+        let array = set.to_array();
+        assert_eq!(array.dim(), (set.len(), 3));
+        for (row, sample) in array.rows().into_iter().zip(set.samples()) {
+            assert_eq!(row.to_vec(), sample.to_vec());
+        }
+        // a column major view of the same points
+        let transposed = array.t().to_owned();
+        let loaded = PoissonDiskSet::from_array(vec![6., 5., 4.], 1., transposed.t()).unwrap();
+        assert_eq!(loaded.samples(), set.samples());
+        assert!(PoissonDiskSet::from_array(vec![6., 5., 4.], 2., array.view()).is_err());
+
+        let empty = PoissonDiskSet::from_points(vec![1., 1.], 1., Vec::new()).unwrap();
+        assert_eq!(empty.to_array().dim(), (0, 2));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra() {
+        use crate::PoissonDiskSet;
+        use nalgebra::{Point3, distance};
+
+        let set = PoissonDiskSet::new(vec![6., 5., 4.], 1., 30);
+        let points: Vec<Point3<f64>> = set.to_nalgebra();
+        assert_eq!(points.len(), set.len());
+        assert_eq!(points[1].coords.as_slice(), &set.samples()[1][..]);
+        assert!(distance(&points[0], &points[1]) >= 1.);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam() {
+        use crate::PoissonDiskSet;
+        use glam::{DVec2, DVec3, Vec3};
+
+        let set = PoissonDiskSet::new(vec![6., 5., 4.], 1., 30);
+        let single: Vec<Vec3> = set.to_glam();
+        let double: Vec<DVec3> = set.to_glam();
+        for ((single, double), sample) in single.iter().zip(double.iter()).zip(set.samples()) {
+            assert_eq!(double.to_array()[..], sample[..]);
+            assert_eq!(*single, double.as_vec3());
+        }
+        let flat = PoissonDiskSet::new(vec![6., 5.], 1., 30);
+        let points: Vec<DVec2> = flat.to_glam();
+        assert!(points[0].distance(points[1]) >= 1.);
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn mint() {
+        use crate::PoissonDiskSet;
+        use mint::{Point3, Vector3};
+
+        let set = PoissonDiskSet::new(vec![6., 5., 4.], 1., 30);
+        let points: Vec<Point3<f64>> = set.to_mint();
+        let vectors: Vec<Vector3<f64>> = set.to_mint();
+        for ((point, vector), sample) in points.iter().zip(vectors.iter()).zip(set.samples()) {
+            assert_eq!([point.x, point.y, point.z][..], sample[..]);
+            assert_eq!(<[f64; 3]>::from(*vector), <[f64; 3]>::from(*point));
+        }
+    }
+
+    #[cfg(feature = "cgmath")]
+    #[test]
+    fn cgmath() {
+        use crate::PoissonDiskSet;
+        use cgmath::{MetricSpace, Point2};
+
+        let set = PoissonDiskSet::new(vec![6., 5.], 1., 30);
+        let points: Vec<Point2<f64>> = set.to_cgmath();
+        for (point, sample) in points.iter().zip(set.samples()) {
+            assert_eq!([point.x, point.y][..], sample[..]);
+        }
+        assert!(points[0].distance(points[1]) >= 1.);
+    }
+}
@@ -0,0 +1,159 @@
+//! Runtime checks of the invariants of generation, for the `validate`
+//! feature.
+
+use crate::{BlueNoiseIterator, PoissonDiskSet, SampleStorage, Violation, validate_with_bounds};
+use std::fmt;
+
+/// An invariant which was broken during generation, see
+/// [`BlueNoiseIterator::try_into_set`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvariantError {
+    /// Right after inserting the sample with this index, the background grid
+    /// and the samples disagreed.
+    Insert {
+        /// The index of the sample.
+        sample: usize,
+        /// What was wrong.
+        message: String,
+    },
+    /// The finished samples violate the Poisson disk property.
+    Violations(Vec<Violation>),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantError::Insert { sample, message } => {
+                write!(f, "inserting sample {sample} broke the grid: {message}")
+            }
+            InvariantError::Violations(violations) => {
+                write!(f, "the samples have {} violations", violations.len())?;
+                if let Some(first) = violations.first() {
+                    write!(f, ", the first is {first:?}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvariantError {}
+
+impl<S: SampleStorage> BlueNoiseIterator<S> {
+    /// Checks the sample with the id `id` right after it was inserted, and
+    /// remembers the first problem for [`verify`](BlueNoiseIterator::verify).
+    pub(crate) fn verify_insert(&mut self, id: usize) {
+        if self.invariant_error.is_some() {
+            return;
+        }
+        let index = id - 1;
+        let position = self.samples.sample(index);
+        let message = if position.len() != self.dimensions.len() || !self.bggrid.contains(position)
+        {
+            Some("the sample is outside of the domain".to_string())
+        } else if self.bggrid.check(position, &self.samples).is_ok() {
+            Some("the grid would accept another sample at the same position".to_string())
+        } else {
+            // found by visiting cells, independently of `check`
+            self.bggrid
+                .within(position, self.min_distance, &self.samples)
+                .find(|other| *other != index)
+                .map(|other| format!("sample {other} is closer than the minimal distance"))
+        };
+        if let Some(message) = message {
+            self.invariant_error = Some(InvariantError::Insert {
+                sample: index,
+                message,
+            });
+        }
+    }
+
+    /// Checks the samples so far: reports the first broken invariant after
+    /// an insert, or else checks the distances between all the samples with
+    /// [`validate_with_bounds`].
+    ///
+    /// Requires the `validate` feature.
+    pub fn verify(&self) -> Result<(), InvariantError> {
+        if let Some(error) = &self.invariant_error {
+            return Err(error.clone());
+        }
+        let samples: Vec<&[f64]> = (0..self.samples.len())
+            .map(|i| self.samples.sample(i))
+            .collect();
+        validate_with_bounds(
+            &samples,
+            self.min_distance,
+            &self.dimensions,
+            &self.bggrid.bounds,
+        )
+        .map_err(InvariantError::Violations)
+    }
+}
+
+impl BlueNoiseIterator {
+    /// Like [`into_set`](BlueNoiseIterator::into_set), but fails if an
+    /// invariant was broken, see [`verify`](BlueNoiseIterator::verify).
+    ///
+    /// With the `validate` feature, the grid and the samples are checked
+    /// after every insert, also in release builds, which makes generation
+    /// about twice as slow.
+    ///
+    /// ```
+    /// let set = bluenoisers::blue_noise_iter(vec![30., 30.], 1., 30)
+    ///     .try_into_set()
+    ///     .unwrap();
+    /// assert!(set.len() > 100);
+    /// ```
+    ///
+    /// Requires the `validate` feature.
+    pub fn try_into_set(mut self) -> Result<PoissonDiskSet, InvariantError> {
+        while self.next_ref().is_some() {}
+        self.verify()?;
+        Ok(self.into_set())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlueNoiseConfig, Interval, Point, blue_noise_iter};
+
+    #[test]
+    fn verify() {
+        for (dimensions, min_distance) in [
+            (vec![20., 20.], 1.),
+            (vec![5., 5.], 0.3),
+            (vec![8., 8., 8.], 1.),
+            (vec![3., 3., 3.], 0.5),
+            (vec![4.; 4], 1.),
+            (vec![2.5; 7], 1.),
+        ] {
+            let set = blue_noise_iter(dimensions, min_distance, 30).try_into_set();
+            assert!(set.is_ok(), "{}", set.unwrap_err());
+        }
+        let mut it = blue_noise_iter(vec![20., 20.], 1., 30);
+        while it.next_ref().is_some() {}
+        assert_eq!(it.verify(), Ok(()));
+        // corrupt the samples behind the back of the grid
+        let copy = it.samples[0].clone();
+        it.samples.push(copy);
+        assert!(matches!(
+            it.verify(),
+            Err(InvariantError::Violations(violations)) if violations.len() == 1
+        ));
+        it.samples[1] = Point::from_slice(&[-1., 0.]);
+        it.verify_insert(2);
+        assert!(matches!(
+            it.verify(),
+            Err(InvariantError::Insert { sample: 1, .. })
+        ));
+        // a sample on a closed border is inside
+        let mut it = BlueNoiseConfig::new(vec![20., 20.], 1.)
+            .bounds(vec![Interval::Closed; 2])
+            .seed(1)
+            .iter();
+        assert!(it.seed(Point::from_slice(&[20., 20.])));
+        while it.next_ref().is_some() {}
+        assert_eq!(it.verify(), Ok(()));
+    }
+}
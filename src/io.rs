@@ -0,0 +1,477 @@
+//! Loading point sets from files.
+
+use crate::{Point, PoissonDiskSet, Violation};
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// Errors when loading a point set.
+#[derive(Debug)]
+pub enum ImportError {
+    /// Reading failed.
+    Io(io::Error),
+    /// The file is not in the expected format.
+    Malformed(String),
+    /// The points are not a valid set, see [`validate`](crate::validate).
+    Invalid(Vec<Violation>),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "reading points failed: {e}"),
+            ImportError::Malformed(message) => write!(f, "malformed point file: {message}"),
+            ImportError::Invalid(violations) => {
+                write!(
+                    f,
+                    "the points are not valid, {} violations",
+                    violations.len()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> ImportError {
+        ImportError::Io(e)
+    }
+}
+
+/// Reads points from CSV, one point per line with its coordinates separated
+/// by commas. All lines must have the same number of coordinates.
+///
+/// Empty lines and lines starting with `#` are skipped, as is a header in the
+/// first remaining line if it isn't numeric.
+///
+/// ```
+/// let csv = "x,y\n1.5,2\n3,4.25\n";
+/// let points = bluenoisers::read_csv(csv.as_bytes()).unwrap();
+/// assert_eq!(points.len(), 2);
+/// assert_eq!(points[1][..], [3., 4.25]);
+/// ```
+///
+/// Requires the `io` feature.
+pub fn read_csv<R: BufRead>(reader: R) -> Result<Vec<Point>, ImportError> {
+    let mut points: Vec<Point> = Vec::new();
+    let mut first_line = true;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let point: Result<Point, _> = line.split(',').map(|x| x.trim().parse()).collect();
+        let header = std::mem::replace(&mut first_line, false);
+        let point = match point {
+            Ok(point) => point,
+            Err(_) if header => continue,
+            Err(e) => {
+                return Err(ImportError::Malformed(format!("line {}: {e}", number + 1)));
+            }
+        };
+        if let Some(first) = points.first()
+            && first.len() != point.len()
+        {
+            return Err(ImportError::Malformed(format!(
+                "line {}: expected {} coordinates, found {}",
+                number + 1,
+                first.len(),
+                point.len()
+            )));
+        }
+        points.push(point);
+    }
+    Ok(points)
+}
+
+/// Reads the vertex positions from a PLY file, in ASCII or binary format.
+///
+/// The points are 3-dimensional if the vertices have a `z` property and
+/// 2-dimensional otherwise. Other properties and elements are ignored.
+///
+/// ```
+/// let ply = "ply
+/// format ascii 1.0
+/// element vertex 2
+/// property float x
+/// property float y
+/// property uchar red
+/// end_header
+/// 1 2 255
+/// 3.5 4 0
+/// ";
+/// let points = bluenoisers::read_ply(ply.as_bytes()).unwrap();
+/// assert_eq!(points.len(), 2);
+/// assert_eq!(points[1][..], [3.5, 4.]);
+/// ```
+///
+/// Requires the `io` feature.
+pub fn read_ply<R: BufRead>(mut reader: R) -> Result<Vec<Point>, ImportError> {
+    let header = PlyHeader::read(&mut reader)?;
+    let mut points = Vec::new();
+    for element in &header.elements {
+        let axes: Vec<usize> = if element.name == "vertex" {
+            let find = |name: &str| {
+                element
+                    .properties
+                    .iter()
+                    .position(|(property, _)| property == name)
+            };
+            match (find("x"), find("y"), find("z")) {
+                (Some(x), Some(y), Some(z)) => vec![x, y, z],
+                (Some(x), Some(y), None) => vec![x, y],
+                _ => {
+                    return Err(ImportError::Malformed(
+                        "the vertices have no x and y properties".to_string(),
+                    ));
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        let mut values = vec![0_f64; element.properties.len()];
+        for _ in 0..element.count {
+            header.read_item(&mut reader, element, &mut values)?;
+            if !axes.is_empty() {
+                points.push(axes.iter().map(|axis| values[*axis]).collect());
+            }
+        }
+        if !axes.is_empty() {
+            // the remaining elements don't matter
+            break;
+        }
+    }
+    Ok(points)
+}
+
+impl PoissonDiskSet {
+    /// Loads a set from CSV, see [`read_csv`] and
+    /// [`from_points`](PoissonDiskSet::from_points).
+    ///
+    /// Requires the `io` feature.
+    pub fn from_csv<R: BufRead>(
+        reader: R,
+        dimensions: Vec<f64>,
+        min_distance: f64,
+    ) -> Result<PoissonDiskSet, ImportError> {
+        PoissonDiskSet::from_points(dimensions, min_distance, read_csv(reader)?)
+            .map_err(ImportError::Invalid)
+    }
+
+    /// Loads a set from the vertices of a PLY file, see [`read_ply`] and
+    /// [`from_points`](PoissonDiskSet::from_points).
+    ///
+    /// Requires the `io` feature.
+    pub fn from_ply<R: BufRead>(
+        reader: R,
+        dimensions: Vec<f64>,
+        min_distance: f64,
+    ) -> Result<PoissonDiskSet, ImportError> {
+        PoissonDiskSet::from_points(dimensions, min_distance, read_ply(reader)?)
+            .map_err(ImportError::Invalid)
+    }
+}
+
+/// The scalar types of PLY properties.
+#[derive(Clone, Copy, Debug)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                if big_endian {
+                    <$t>::from_be_bytes(bytes) as f64
+                } else {
+                    <$t>::from_le_bytes(bytes) as f64
+                }
+            }};
+        }
+        match self {
+            Scalar::I8 => decode!(i8),
+            Scalar::U8 => decode!(u8),
+            Scalar::I16 => decode!(i16),
+            Scalar::U16 => decode!(u16),
+            Scalar::I32 => decode!(i32),
+            Scalar::U32 => decode!(u32),
+            Scalar::F32 => decode!(f32),
+            Scalar::F64 => decode!(f64),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(Scalar),
+    /// The type of the length, then the type of the entries.
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, Property)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug)]
+struct PlyHeader {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+impl PlyHeader {
+    fn read<R: BufRead>(reader: &mut R) -> Result<PlyHeader, ImportError> {
+        let malformed = |message: &str| ImportError::Malformed(message.to_string());
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.trim_end() != "ply" {
+            return Err(malformed("not a PLY file"));
+        }
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(malformed("the header doesn't end"));
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["end_header"] => break,
+                ["format", name, _] => {
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::LittleEndian,
+                        "binary_big_endian" => Format::BigEndian,
+                        _ => return Err(malformed("unknown format")),
+                    });
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count
+                        .parse()
+                        .map_err(|_| malformed("invalid element count"))?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", length, entry, name] => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| malformed("property without element"))?;
+                    let scalar =
+                        |name| Scalar::parse(name).ok_or_else(|| malformed("unknown type"));
+                    element.properties.push((
+                        name.to_string(),
+                        Property::List(scalar(length)?, scalar(entry)?),
+                    ));
+                }
+                ["property", scalar, name] => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| malformed("property without element"))?;
+                    let scalar = Scalar::parse(scalar).ok_or_else(|| malformed("unknown type"))?;
+                    element
+                        .properties
+                        .push((name.to_string(), Property::Scalar(scalar)));
+                }
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => return Err(malformed("unknown header line")),
+            }
+        }
+        Ok(PlyHeader {
+            format: format.ok_or_else(|| malformed("no format"))?,
+            elements,
+        })
+    }
+
+    /// Reads one item of `element` and stores the values of its scalar
+    /// properties in `values`. Lists are skipped.
+    fn read_item<R: BufRead>(
+        &self,
+        reader: &mut R,
+        element: &Element,
+        values: &mut [f64],
+    ) -> Result<(), ImportError> {
+        let truncated = || ImportError::Malformed(format!("truncated {}", element.name));
+        if self.format == Format::Ascii {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(truncated());
+            }
+            let mut words = line.split_whitespace();
+            let mut next = || -> Result<f64, ImportError> {
+                words
+                    .next()
+                    .ok_or_else(truncated)?
+                    .parse()
+                    .map_err(|e| ImportError::Malformed(format!("{}: {e}", element.name)))
+            };
+            for ((_, property), value) in element.properties.iter().zip(values.iter_mut()) {
+                match property {
+                    Property::Scalar(_) => *value = next()?,
+                    Property::List(_, _) => {
+                        for _ in 0..next()? as usize {
+                            next()?;
+                        }
+                    }
+                }
+            }
+            return Ok(());
+        }
+        let big_endian = self.format == Format::BigEndian;
+        let mut buffer = [0_u8; 8];
+        let mut next = |scalar: Scalar| -> Result<f64, ImportError> {
+            let bytes = &mut buffer[..scalar.size()];
+            reader.read_exact(bytes).map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => truncated(),
+                _ => ImportError::Io(e),
+            })?;
+            Ok(scalar.decode(bytes, big_endian))
+        };
+        for ((_, property), value) in element.properties.iter().zip(values.iter_mut()) {
+            match property {
+                Property::Scalar(scalar) => *value = next(*scalar)?,
+                Property::List(length, entry) => {
+                    for _ in 0..next(*length)? as usize {
+                        next(*entry)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let mut csv = String::from("# exported points\nx, y\n");
+        for sample in set.samples() {
+            csv.push_str(&format!("{}, {}\n\n", sample[0], sample[1]));
+        }
+        let loaded = PoissonDiskSet::from_csv(csv.as_bytes(), vec![20., 10.], 1.).unwrap();
+        assert_eq!(loaded.samples(), set.samples());
+
+        assert!(matches!(
+            read_csv("1,2\n3,4,5\n".as_bytes()),
+            Err(ImportError::Malformed(_))
+        ));
+        assert!(matches!(
+            read_csv("1,2\n3,a\n".as_bytes()),
+            Err(ImportError::Malformed(_))
+        ));
+        assert!(matches!(
+            PoissonDiskSet::from_csv("1,2\n1,2.5\n".as_bytes(), vec![5., 5.], 1.),
+            Err(ImportError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn ply() {
+        let set = PoissonDiskSet::new(vec![6., 5., 4.], 1., 30);
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut ply = format!(
+                "ply\nformat {format} 1.0\ncomment made by hand\n\
+                 element camera 1\nproperty list uchar int ids\n\
+                 element vertex {}\nproperty double z\nproperty uchar red\n\
+                 property double x\nproperty double y\nend_header\n",
+                set.len()
+            )
+            .into_bytes();
+            // a camera with two ids
+            ply.push(2);
+            for id in [7_i32, 8] {
+                ply.extend(if big_endian {
+                    id.to_be_bytes()
+                } else {
+                    id.to_le_bytes()
+                });
+            }
+            for sample in set.samples() {
+                let bytes = |x: f64| {
+                    if big_endian {
+                        x.to_be_bytes()
+                    } else {
+                        x.to_le_bytes()
+                    }
+                };
+                ply.extend(bytes(sample[2]));
+                ply.push(255);
+                ply.extend(bytes(sample[0]));
+                ply.extend(bytes(sample[1]));
+            }
+            let loaded = PoissonDiskSet::from_ply(&ply[..], vec![6., 5., 4.], 1.).unwrap();
+            assert_eq!(loaded.samples(), set.samples());
+            assert!(matches!(
+                read_ply(&ply[..ply.len() - 1]),
+                Err(ImportError::Malformed(_))
+            ));
+        }
+        let ascii = "ply\nformat ascii 1.0\nelement face 1\nproperty list uchar int vertex_indices\n\
+                     element vertex 1\nproperty float x\nproperty float y\nend_header\n\
+                     3 0 1 2\n0.5 0.25\n";
+        assert_eq!(
+            read_ply(ascii.as_bytes()).unwrap()[..],
+            [Point::from_slice(&[0.5, 0.25])]
+        );
+        assert!(matches!(
+            read_ply(
+                "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n1\n"
+                    .as_bytes()
+            ),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+}
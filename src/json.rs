@@ -0,0 +1,634 @@
+//! JSON files of point sets together with how they were generated.
+
+use crate::{
+    BlueNoiseConfig, ImportError, Interval, NeighborSearch, Point, PoissonDiskSet, Separation,
+};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The name of the algorithm behind [`BlueNoiseConfig`].
+const ALGORITHM: &str = "bridson";
+/// The names of the [`Interval`]s, as serde names them.
+const INTERVALS: [(Interval, &str); 4] = [
+    (Interval::ClosedOpen, "ClosedOpen"),
+    (Interval::Closed, "Closed"),
+    (Interval::Open, "Open"),
+    (Interval::OpenClosed, "OpenClosed"),
+];
+/// The names of the [`NeighborSearch`] strategies.
+const SEARCHES: [(NeighborSearch, &str); 3] = [
+    (NeighborSearch::Compact, "Compact"),
+    (NeighborSearch::Conservative, "Conservative"),
+    (NeighborSearch::Exhaustive, "Exhaustive"),
+];
+/// The names of the [`Separation`]s.
+const SEPARATIONS: [(Separation, &str); 2] = [
+    (Separation::AtLeast, "AtLeast"),
+    (Separation::MoreThan, "MoreThan"),
+];
+
+/// The name of `value` in `names`.
+fn name_of<T: PartialEq>(names: &[(T, &'static str)], value: &T) -> &'static str {
+    names
+        .iter()
+        .find(|(v, _)| v == value)
+        .map(|(_, name)| *name)
+        .expect("every value has a name")
+}
+
+/// The value named `name` in `names`, if it is a string.
+fn named<T: Copy>(names: &[(T, &str)], name: &Json) -> Option<T> {
+    let name = name.as_str()?;
+    names.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+/// Writes `samples` together with the `config` they were generated with as
+/// JSON to `path`, so that they can be reproduced if the configuration is
+/// seeded.
+///
+/// The document records the crate and its version, the algorithm, the
+/// configuration and the samples:
+///
+/// ```json
+/// {
+///   "generator": "bluenoisers",
+///   "version": "2.0.0",
+///   "algorithm": "bridson",
+///   "config": {"dimensions": [2, 1], "min_distance": 0.5, "k_abort": 30, "seed": 7},
+///   "samples": [[0.25, 0.5], [1.25, 0.75]]
+/// }
+/// ```
+///
+/// The seed is `null` for configurations seeded from the operating system.
+/// Domains which [wrap](BlueNoiseConfig::wrap) around also have a `"wrap"`
+/// array in the configuration, with a boolean per axis, and
+/// configurations with the
+/// [border correction](BlueNoiseConfig::border_correction) or in the
+/// [reproducible](BlueNoiseConfig::reproducible) mode have
+/// `"border_correction": true` or `"reproducible": true`. The other options
+/// are only written if they are set, named as serde names them:
+///
+/// ```json
+/// "config": {
+///   "dimensions": [2, 1], "min_distance": 0.5, "k_abort": 30, "seed": 7,
+///   "bounds": ["Closed", "ClosedOpen"], "neighbor_search": "Conservative",
+///   "separation": "MoreThan", "max_distance": 0.65
+/// }
+/// ```
+///
+/// [`read_json`] reads the document back.
+///
+/// ```no_run
+/// use bluenoisers::{BlueNoiseConfig, export_json};
+///
+/// let config = BlueNoiseConfig::new(vec![64., 64.], 2.).seed(7);
+/// export_json("samples.json", &config.generate(), &config).unwrap();
+/// ```
+///
+/// Requires the `io` feature.
+pub fn export_json<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    config: &BlueNoiseConfig,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_json(&mut writer, samples, config)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples together with the `config` they were generated
+    /// with as JSON, see [`export_json`].
+    ///
+    /// Requires the `io` feature.
+    pub fn export_json(&self, path: impl AsRef<Path>, config: &BlueNoiseConfig) -> io::Result<()> {
+        export_json(path, self.samples(), config)
+    }
+
+    /// Loads a set from JSON written by [`export_json`], see [`read_json`]
+    /// and [`from_points`](PoissonDiskSet::from_points).
+    ///
+    /// Requires the `io` feature.
+    pub fn from_json<R: Read>(reader: R) -> Result<PoissonDiskSet, ImportError> {
+        let (config, samples) = read_json(reader)?;
+        PoissonDiskSet::from_points_with_bounds(
+            config.dimensions,
+            config.min_distance,
+            config.bounds,
+            samples,
+        )
+        .map_err(ImportError::Invalid)
+    }
+}
+
+fn write_json<P: AsRef<[f64]>, W: Write>(
+    writer: &mut W,
+    samples: &[P],
+    config: &BlueNoiseConfig,
+) -> io::Result<()> {
+    let array = |values: &[f64]| {
+        let values: Vec<String> = values.iter().map(f64::to_string).collect();
+        format!("[{}]", values.join(", "))
+    };
+    writeln!(writer, "{{")?;
+    writeln!(writer, r#"  "generator": "bluenoisers","#)?;
+    writeln!(writer, r#"  "version": "{}","#, env!("CARGO_PKG_VERSION"))?;
+    writeln!(writer, r#"  "algorithm": "{ALGORITHM}","#)?;
+    let wrap = if config.wrap.is_empty() {
+        String::new()
+    } else {
+        let flags: Vec<String> = config.wrap.iter().map(bool::to_string).collect();
+        format!(r#", "wrap": [{}]"#, flags.join(", "))
+    };
+    let border_correction = if config.border_correction {
+        r#", "border_correction": true"#
+    } else {
+        ""
+    };
+    let reproducible = if config.reproducible {
+        r#", "reproducible": true"#
+    } else {
+        ""
+    };
+    let mut options = String::new();
+    if !config.bounds.is_empty() {
+        let names: Vec<String> = config
+            .bounds
+            .iter()
+            .map(|bounds| format!(r#""{}""#, name_of(&INTERVALS, bounds)))
+            .collect();
+        options += &format!(r#", "bounds": [{}]"#, names.join(", "));
+    }
+    if let Some(search) = &config.search {
+        options += &format!(r#", "neighbor_search": "{}""#, name_of(&SEARCHES, search));
+    }
+    if let Some(separation) = &config.separation {
+        options += &format!(r#", "separation": "{}""#, name_of(&SEPARATIONS, separation));
+    }
+    if let Some(max_distance) = config.max_distance {
+        options += &format!(r#", "max_distance": {max_distance}"#);
+    }
+    writeln!(
+        writer,
+        r#"  "config": {{"dimensions": {}, "min_distance": {}, "k_abort": {}, "seed": {}{wrap}{border_correction}{reproducible}{options}}},"#,
+        array(&config.dimensions),
+        config.min_distance,
+        config.k_abort,
+        config
+            .seed
+            .map_or("null".to_string(), |seed| seed.to_string()),
+    )?;
+    write!(writer, r#"  "samples": ["#)?;
+    for (index, sample) in samples.iter().enumerate() {
+        let separator = if index == 0 { "" } else { "," };
+        write!(writer, "{separator}\n    {}", array(sample.as_ref()))?;
+    }
+    if !samples.is_empty() {
+        write!(writer, "\n  ")?;
+    }
+    writeln!(writer, "]")?;
+    writeln!(writer, "}}")
+}
+
+/// Reads the configuration and the samples from JSON written by
+/// [`export_json`].
+///
+/// ```
+/// let json = r#"{
+///     "algorithm": "bridson",
+///     "config": {"dimensions": [2, 1], "min_distance": 0.5, "k_abort": 30, "seed": 7},
+///     "samples": [[0.25, 0.5], [1.25, 0.75]]
+/// }"#;
+/// let (config, samples) = bluenoisers::read_json(json.as_bytes()).unwrap();
+/// assert_eq!(config, bluenoisers::BlueNoiseConfig::new(vec![2., 1.], 0.5).seed(7));
+/// assert_eq!(samples[1][..], [1.25, 0.75]);
+/// ```
+///
+/// Fails with [`ImportError::Malformed`] if the document is not JSON, if the
+/// configuration or samples are missing or if the algorithm is not the one
+/// of [`BlueNoiseConfig`]. Other members are ignored.
+///
+/// Requires the `io` feature.
+pub fn read_json<R: Read>(mut reader: R) -> Result<(BlueNoiseConfig, Vec<Point>), ImportError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let document = Json::parse(&text).map_err(ImportError::Malformed)?;
+    let malformed = |message: &str| ImportError::Malformed(message.to_string());
+    if let Some(algorithm) = document.get("algorithm")
+        && algorithm.as_str() != Some(ALGORITHM)
+    {
+        return Err(malformed("unknown algorithm"));
+    }
+    let config = document
+        .get("config")
+        .ok_or_else(|| malformed("no config"))?;
+    let numbers =
+        |value: &Json| -> Option<Vec<f64>> { value.as_array()?.iter().map(Json::as_f64).collect() };
+    let dimensions = config
+        .get("dimensions")
+        .and_then(numbers)
+        .ok_or_else(|| malformed("no dimensions"))?;
+    let min_distance = config
+        .get("min_distance")
+        .and_then(Json::as_f64)
+        .ok_or_else(|| malformed("no min_distance"))?;
+    let mut result = BlueNoiseConfig::new(dimensions, min_distance);
+    if let Some(k_abort) = config.get("k_abort") {
+        let k_abort = k_abort
+            .as_integer()
+            .ok_or_else(|| malformed("k_abort is not an integer"))?;
+        result = result.k_abort(k_abort as usize);
+    }
+    match config.get("seed") {
+        None | Some(Json::Null) => {}
+        Some(seed) => {
+            let seed = seed
+                .as_integer()
+                .ok_or_else(|| malformed("the seed is not an integer"))?;
+            result = result.seed(seed);
+        }
+    }
+    if let Some(wrap) = config.get("wrap") {
+        let wrap = wrap
+            .as_array()
+            .and_then(|flags| {
+                flags
+                    .iter()
+                    .map(Json::as_bool)
+                    .collect::<Option<Vec<bool>>>()
+            })
+            .filter(|flags| flags.len() == result.dimensions.len())
+            .ok_or_else(|| malformed("wrap is not a boolean per dimension"))?;
+        if wrap
+            .iter()
+            .zip(result.dimensions.iter())
+            .any(|(wrap, dim)| *wrap && *dim < 2_f64 * result.min_distance)
+        {
+            return Err(malformed("a wrapped axis is too short"));
+        }
+        result = result.wrap(wrap);
+    }
+    match config.get("border_correction") {
+        None => {}
+        Some(Json::Bool(correct)) => result = result.border_correction(*correct),
+        Some(_) => return Err(malformed("border_correction is not a boolean")),
+    }
+    match config.get("reproducible") {
+        None | Some(Json::Bool(false)) => {}
+        Some(Json::Bool(true)) if result.seed.is_some() => result.reproducible = true,
+        Some(Json::Bool(true)) => return Err(malformed("reproducible without a seed")),
+        Some(_) => return Err(malformed("reproducible is not a boolean")),
+    }
+    if let Some(bounds) = config.get("bounds") {
+        let bounds = bounds
+            .as_array()
+            .and_then(|names| {
+                names
+                    .iter()
+                    .map(|name| named(&INTERVALS, name))
+                    .collect::<Option<Vec<Interval>>>()
+            })
+            .filter(|bounds| bounds.len() == result.dimensions.len())
+            .ok_or_else(|| malformed("bounds is not an interval per dimension"))?;
+        if bounds
+            .iter()
+            .zip(result.wrap.iter())
+            .any(|(bounds, wrap)| *wrap && *bounds != Interval::ClosedOpen)
+        {
+            return Err(malformed("a wrapped axis is not half-open"));
+        }
+        result = result.bounds(bounds);
+    }
+    if let Some(search) = config.get("neighbor_search") {
+        let search =
+            named(&SEARCHES, search).ok_or_else(|| malformed("unknown neighbor_search"))?;
+        result = result.neighbor_search(search);
+    }
+    if let Some(separation) = config.get("separation") {
+        let separation =
+            named(&SEPARATIONS, separation).ok_or_else(|| malformed("unknown separation"))?;
+        result = result.separation(separation);
+    }
+    if let Some(max_distance) = config.get("max_distance") {
+        let max_distance = max_distance
+            .as_f64()
+            .filter(|max_distance| max_distance.is_finite() && *max_distance > result.min_distance)
+            .ok_or_else(|| malformed("max_distance is not more than min_distance"))?;
+        result = result.max_distance(max_distance);
+    }
+    let samples = document
+        .get("samples")
+        .and_then(Json::as_array)
+        .ok_or_else(|| malformed("no samples"))?
+        .iter()
+        .map(|sample| {
+            numbers(sample)
+                .filter(|sample| sample.len() == result.dimensions.len())
+                .map(Point::from_vec)
+                .ok_or_else(|| malformed("a sample is not a point in the domain"))
+        })
+        .collect::<Result<Vec<Point>, ImportError>>()?;
+    Ok((result, samples))
+}
+
+/// A parsed JSON value.
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    /// The number, and its text to read integers exactly.
+    Number(f64, String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            at: 0,
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.at != parser.text.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(x, _) => Some(*x),
+            _ => None,
+        }
+    }
+
+    fn as_integer(&self) -> Option<u64> {
+        match self {
+            Json::Number(_, text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// A recursive descent parser of JSON.
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{message} at byte {}", self.at)
+    }
+
+    fn whitespace(&mut self) {
+        while self
+            .text
+            .get(self.at)
+            .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.at += 1;
+        }
+    }
+
+    /// Skips whitespace and consumes `c` if it is next.
+    fn eat(&mut self, c: u8) -> bool {
+        self.whitespace();
+        let found = self.text.get(self.at) == Some(&c);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        let rest = &self.text[self.at..];
+        for (word, value) in [
+            ("null", Json::Null),
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+        ] {
+            if rest.starts_with(word.as_bytes()) {
+                self.at += word.len();
+                return Ok(value);
+            }
+        }
+        match rest.first() {
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.at += 1;
+                let mut values = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        values.push(self.value()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return Err(self.error("expected `,` or `]`"));
+                        }
+                    }
+                }
+                Ok(Json::Array(values))
+            }
+            Some(b'{') => {
+                self.at += 1;
+                let mut members = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.whitespace();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return Err(self.error("expected `:`"));
+                        }
+                        members.push((key, self.value()?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        if !self.eat(b',') {
+                            return Err(self.error("expected `,` or `}`"));
+                        }
+                    }
+                }
+                Ok(Json::Object(members))
+            }
+            Some(c) if *c == b'-' || c.is_ascii_digit() => {
+                let length = rest
+                    .iter()
+                    .position(|c| !matches!(c, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                    .unwrap_or(rest.len());
+                let text = std::str::from_utf8(&rest[..length]).expect("ASCII is UTF-8");
+                let number = text.parse().map_err(|_| self.error("invalid number"))?;
+                self.at += length;
+                Ok(Json::Number(number, text.to_string()))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.text.get(self.at) != Some(&b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.at += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.text.get(self.at) {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    self.at += 1;
+                    let escaped = match self.text.get(self.at) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self
+                                .text
+                                .get(self.at + 1..self.at + 5)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                            self.at += 4;
+                            // surrogate pairs are not needed for the names
+                            // and values of point sets
+                            hex.and_then(char::from_u32).unwrap_or('\u{fffd}')
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut buffer = [0_u8; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                Some(c) => bytes.push(*c),
+            }
+            self.at += 1;
+        }
+        self.at += 1;
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let json = Json::parse(r#" {"a": [1, -2.5e1, true, null], "b\nä": {}} "#).unwrap();
+        assert_eq!(
+            json.get("a").unwrap().as_array().unwrap()[1].as_f64(),
+            Some(-25.)
+        );
+        assert_eq!(json.get("b\nä"), Some(&Json::Object(Vec::new())));
+        assert_eq!(
+            json.get("a").unwrap().as_array().unwrap()[0].as_integer(),
+            Some(1)
+        );
+        for invalid in ["", "[1,]", "{\"a\" 1}", "[1] 2", "\"open", "-"] {
+            assert!(Json::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.json");
+        let config = BlueNoiseConfig::new(vec![12., 8.], 1.)
+            .k_abort(20)
+            .seed(u64::MAX);
+        let set = config.generate_set();
+        set.export_json(&path, &config).unwrap();
+        let (read, samples) = read_json(File::open(&path).unwrap()).unwrap();
+        assert_eq!(read, config);
+        assert_eq!(samples, set.samples());
+        assert_eq!(read.generate(), samples);
+        let loaded = PoissonDiskSet::from_json(File::open(&path).unwrap()).unwrap();
+        assert_eq!(loaded.samples(), set.samples());
+
+        let wrapped = config
+            .clone()
+            .wrap(vec![true, false])
+            .border_correction(true);
+        export_json(&path, &wrapped.generate(), &wrapped).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(r#""wrap": [true, false]"#));
+        let (read, samples) = read_json(text.as_bytes()).unwrap();
+        assert_eq!(read, wrapped);
+        assert_eq!(read.generate(), samples);
+        let invalid = text.replace("[true, false]", "[true]");
+        assert!(read_json(invalid.as_bytes()).is_err());
+
+        let options = BlueNoiseConfig::new(vec![20., 20.], 1.)
+            .seed(5)
+            .bounds(vec![Interval::Closed, Interval::OpenClosed])
+            .neighbor_search(NeighborSearch::Conservative)
+            .separation(Separation::MoreThan)
+            .max_distance(1.3);
+        export_json(&path, &options.generate(), &options).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(r#""bounds": ["Closed", "OpenClosed"]"#));
+        let (read, samples) = read_json(text.as_bytes()).unwrap();
+        assert_eq!(read, options);
+        assert_eq!(read.generate(), samples);
+        for invalid in [
+            text.replace(r#""Closed", "OpenClosed""#, r#""Closed""#),
+            text.replace("Conservative", "Lazy"),
+            text.replace("1.3", "0.5"),
+        ] {
+            assert!(read_json(invalid.as_bytes()).is_err());
+        }
+
+        let unseeded = BlueNoiseConfig::new(vec![1.], 1.);
+        export_json(&path, &[[0.5]], &unseeded).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(r#""seed": null"#));
+        assert_eq!(read_json(text.as_bytes()).unwrap().0, unseeded);
+
+        let other = text.replace(ALGORITHM, "lloyd");
+        assert!(matches!(
+            read_json(other.as_bytes()),
+            Err(ImportError::Malformed(_))
+        ));
+        let too_close = text.replace("[0.5]", "[0.5], [0.75]");
+        assert!(matches!(
+            PoissonDiskSet::from_json(too_close.as_bytes()),
+            Err(ImportError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,423 @@
+//! LAS and LAZ files of point sets, for GIS and lidar tools.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The size of the header of a LAS 1.2 file.
+const HEADER_SIZE: u16 = 227;
+/// The size of the header of a variable length record.
+const VLR_HEADER_SIZE: usize = 54;
+/// The size of a point of point data record format 0.
+const POINT_SIZE: u16 = 20;
+
+/// How [`export_las`] writes a point set: as a LAS 1.2 file with one point
+/// of point data record format 0 per sample. 2D samples get a z coordinate
+/// of zero.
+///
+/// The samples are usually generated in a local frame. The offset moves
+/// them to where they belong, e.g. to the easting and northing of a survey
+/// area, so GIS tools show them in place.
+///
+/// ```
+/// use bluenoisers::LasFormat;
+///
+/// let samples = [[1., 2., 0.5], [3.25, 4., 0.]];
+/// let format = LasFormat::new().offset([500_000., 5_400_000., 300.]).epsg(32633);
+/// let mut las = Vec::new();
+/// format.write(&mut las, &samples).unwrap();
+/// assert_eq!(&las[..4], b"LASF");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct LasFormat {
+    offset: [f64; 3],
+    scale: f64,
+    epsg: Option<u16>,
+    classes: Option<Vec<u8>>,
+}
+
+impl Default for LasFormat {
+    fn default() -> LasFormat {
+        LasFormat::new()
+    }
+}
+
+impl LasFormat {
+    /// Creates a format without offset, with millimeter precision if the
+    /// samples are in meters, without coordinate system and with all points
+    /// unclassified.
+    pub fn new() -> LasFormat {
+        LasFormat {
+            offset: [0.; 3],
+            scale: 0.001,
+            epsg: None,
+            classes: None,
+        }
+    }
+
+    /// Adds `offset` to the coordinates of every sample.
+    pub fn offset(mut self, offset: [f64; 3]) -> LasFormat {
+        self.offset = offset;
+        self
+    }
+
+    /// Stores coordinates as multiples of `scale`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` is not positive.
+    pub fn scale(mut self, scale: f64) -> LasFormat {
+        assert!(scale > 0., "the scale must be positive");
+        self.scale = scale;
+        self
+    }
+
+    /// Tags the coordinates, after adding the offset, with the projected
+    /// coordinate system with the EPSG code `code`, e.g. `32633` for UTM
+    /// zone 33N. Projected systems are the ones in which the minimal
+    /// distance is a length.
+    pub fn epsg(mut self, code: u16) -> LasFormat {
+        self.epsg = Some(code);
+        self
+    }
+
+    /// Gives each point a classification, with the meanings of the ASPRS
+    /// standard, e.g. `2` for ground.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a class is larger than 31, the largest class of point data
+    /// record format 0.
+    pub fn classes(mut self, classes: Vec<u8>) -> LasFormat {
+        assert!(
+            classes.iter().all(|class| *class < 32),
+            "the classes must be less than 32"
+        );
+        self.classes = Some(classes);
+        self
+    }
+
+    /// Writes `samples` as a LAS file to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample has less than two or more than three coordinates,
+    /// if the classes are not one per sample or if a sample is too far away
+    /// from the offset for the scale.
+    pub fn write<P: AsRef<[f64]>, W: Write>(&self, mut writer: W, samples: &[P]) -> io::Result<()> {
+        let points = self.points(samples);
+        writer.write_all(&self.header(samples.len(), &points, &[]))?;
+        writer.write_all(&points)
+    }
+
+    /// Writes `samples` as a LAZ file, the compressed form of a LAS file, to
+    /// `writer`.
+    ///
+    /// Requires the `laz` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`write`](LasFormat::write).
+    #[cfg(feature = "laz")]
+    pub fn write_laz<P: AsRef<[f64]>, W: Write>(
+        &self,
+        mut writer: W,
+        samples: &[P],
+    ) -> io::Result<()> {
+        let items = laz::LazItemRecordBuilder::default_for_point_format_id(0, 0)
+            .map_err(io::Error::other)?;
+        let vlr = laz::LazVlr::from_laz_items(items);
+        let mut data = Vec::new();
+        vlr.write_to(&mut data)?;
+        let laszip = Vlr {
+            user_id: laz::LazVlr::USER_ID,
+            record_id: laz::LazVlr::RECORD_ID,
+            description: laz::LazVlr::DESCRIPTION,
+            data,
+        };
+        let points = self.points(samples);
+        // the compressor stores the position of its chunk table relative to
+        // the start of the file
+        let mut file = io::Cursor::new(self.header(samples.len(), &points, &[laszip]));
+        file.set_position(file.get_ref().len() as u64);
+        laz::compress_buffer(&mut file, &points, vlr).map_err(io::Error::other)?;
+        writer.write_all(file.get_ref())
+    }
+
+    /// The points in point data record format 0.
+    fn points<P: AsRef<[f64]>>(&self, samples: &[P]) -> Vec<u8> {
+        if let Some(classes) = &self.classes {
+            assert_eq!(
+                classes.len(),
+                samples.len(),
+                "there must be one of the classes per sample"
+            );
+        }
+        let mut points = Vec::with_capacity(samples.len() * POINT_SIZE as usize);
+        for (index, sample) in samples.iter().enumerate() {
+            let sample = sample.as_ref();
+            assert!(
+                (2..=3).contains(&sample.len()),
+                "only 2D and 3D samples can be exported"
+            );
+            for axis in 0..3 {
+                let x = sample.get(axis).copied().unwrap_or(0.);
+                let stored = (x / self.scale).round();
+                assert!(
+                    stored >= i32::MIN as f64 && stored <= i32::MAX as f64,
+                    "the samples are too far away from the offset for the scale"
+                );
+                points.extend_from_slice(&(stored as i32).to_le_bytes());
+            }
+            // intensity
+            points.extend_from_slice(&0_u16.to_le_bytes());
+            // the first and only return
+            points.push(0b0000_1001);
+            points.push(self.classes.as_ref().map_or(0, |classes| classes[index]));
+            // scan angle, user data and point source
+            points.extend_from_slice(&[0; 4]);
+        }
+        points
+    }
+
+    /// The header and the variable length records in front of `points`.
+    fn header(&self, count: usize, points: &[u8], vlrs: &[Vlr]) -> Vec<u8> {
+        let mut vlrs = vlrs.to_vec();
+        if let Some(code) = self.epsg {
+            // a GeoTIFF key directory with version 1.1.0 and two keys: that
+            // the model is projected and which system it uses
+            let keys: [u16; 12] = [1, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, code];
+            vlrs.push(Vlr {
+                user_id: "LASF_Projection",
+                record_id: 34735,
+                description: "GeoKeyDirectoryTag",
+                data: keys.iter().flat_map(|key| key.to_le_bytes()).collect(),
+            });
+        }
+
+        // the bounds of the stored coordinates
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for point in points.chunks_exact(POINT_SIZE as usize) {
+            for axis in 0..3 {
+                let stored = i32::from_le_bytes(point[4 * axis..4 * axis + 4].try_into().unwrap());
+                let x = self.offset[axis] + stored as f64 * self.scale;
+                min[axis] = min[axis].min(x);
+                max[axis] = max[axis].max(x);
+            }
+        }
+        if points.is_empty() {
+            min = self.offset;
+            max = self.offset;
+        }
+
+        let count = u32::try_from(count).expect("LAS 1.2 files hold less than 2³² points");
+        let vlrs_size: usize = vlrs
+            .iter()
+            .map(|vlr| VLR_HEADER_SIZE + vlr.data.len())
+            .sum();
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize + vlrs_size);
+        header.extend_from_slice(b"LASF");
+        // file source, global encoding and GUID
+        header.extend_from_slice(&[0; 2 + 2 + 16]);
+        // version 1.2
+        header.extend_from_slice(&[1, 2]);
+        header.extend_from_slice(&text::<32>("OTHER"));
+        header.extend_from_slice(&text::<32>(concat!(
+            "bluenoisers ",
+            env!("CARGO_PKG_VERSION")
+        )));
+        // day and year of creation are unknown
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+        header.extend_from_slice(&(HEADER_SIZE as u32 + vlrs_size as u32).to_le_bytes());
+        header.extend_from_slice(&(vlrs.len() as u32).to_le_bytes());
+        // compressed points are flagged by the highest bit of the format
+        let compressed = vlrs.iter().any(|vlr| vlr.record_id == 22204);
+        header.push(if compressed { 0x80 } else { 0 });
+        header.extend_from_slice(&POINT_SIZE.to_le_bytes());
+        header.extend_from_slice(&count.to_le_bytes());
+        // by return, all points are first returns
+        header.extend_from_slice(&count.to_le_bytes());
+        header.extend_from_slice(&[0; 4 * 4]);
+        for value in [self.scale; 3]
+            .into_iter()
+            .chain(self.offset)
+            .chain([max[0], min[0], max[1], min[1], max[2], min[2]])
+        {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        debug_assert_eq!(header.len(), HEADER_SIZE as usize);
+
+        for vlr in &vlrs {
+            // reserved
+            header.extend_from_slice(&[0; 2]);
+            header.extend_from_slice(&text::<16>(vlr.user_id));
+            header.extend_from_slice(&vlr.record_id.to_le_bytes());
+            header.extend_from_slice(&(vlr.data.len() as u16).to_le_bytes());
+            header.extend_from_slice(&text::<32>(vlr.description));
+            header.extend_from_slice(&vlr.data);
+        }
+        header
+    }
+}
+
+/// A variable length record.
+#[derive(Clone, Debug)]
+struct Vlr {
+    user_id: &'static str,
+    record_id: u16,
+    description: &'static str,
+    data: Vec<u8>,
+}
+
+/// `text` padded with zeros to `N` bytes.
+fn text<const N: usize>(text: &str) -> [u8; N] {
+    let mut bytes = [0; N];
+    bytes[..text.len()].copy_from_slice(text.as_bytes());
+    bytes
+}
+
+/// Writes the 2D or 3D `samples` as a LAS file to `path`, or as a LAZ file
+/// if `path` ends with `.laz`, see [`LasFormat`].
+///
+/// ```no_run
+/// use bluenoisers::{LasFormat, export_las};
+///
+/// let samples = bluenoisers::blue_noise(vec![1000., 1000., 10.], 25., 30);
+/// let format = LasFormat::new().offset([500_000., 5_400_000., 300.]).epsg(32633);
+/// export_las("plan.las", &samples, &format).unwrap();
+/// ```
+///
+/// Writing LAZ files requires the `laz` feature, without it they fail with
+/// [`io::ErrorKind::Unsupported`].
+///
+/// # Panics
+///
+/// Panics if a sample has less than two or more than three coordinates, if
+/// the classes are not one per sample or if a sample is too far away from
+/// the offset for the scale.
+pub fn export_las<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    format: &LasFormat,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let laz = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("laz"));
+    if laz && cfg!(not(feature = "laz")) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "writing LAZ files requires the laz feature",
+        ));
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    #[cfg(feature = "laz")]
+    if laz {
+        format.write_laz(&mut writer, samples)?;
+        return writer.flush();
+    }
+    format.write(&mut writer, samples)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D or 3D set as a LAS or LAZ file, see
+    /// [`export_las`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2- or 3-dimensional, if the classes of
+    /// `format` are not one per sample or if a sample is too far away from
+    /// the offset for the scale.
+    pub fn export_las(&self, path: impl AsRef<Path>, format: &LasFormat) -> io::Result<()> {
+        assert!(
+            (2..=3).contains(&self.dimensions().len()),
+            "only 2D and 3D sets can be exported"
+        );
+        export_las(path, self.samples(), format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f64_at(bytes: &[u8], at: usize) -> f64 {
+        f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn write() {
+        let samples = [[1., 2., 0.5], [3.25, 4., 0.]];
+        let format = LasFormat::new()
+            .offset([1000., 2000., 10.])
+            .scale(0.25)
+            .epsg(32633)
+            .classes(vec![2, 5]);
+        let mut las = Vec::new();
+        format.write(&mut las, &samples).unwrap();
+        assert_eq!(&las[..4], b"LASF");
+        assert_eq!(las[24..26], [1, 2]);
+        let offset = u32_at(&las, 96) as usize;
+        // one VLR with the two GeoTIFF keys
+        assert_eq!(u32_at(&las, 100), 1);
+        assert_eq!(offset, 227 + 54 + 24);
+        assert_eq!(&las[227 + 2..227 + 17], b"LASF_Projection");
+        assert_eq!(&las[offset - 2..offset], &32633_u16.to_le_bytes());
+        assert_eq!(las.len(), offset + 2 * 20);
+        assert_eq!(u32_at(&las, 107), 2);
+        assert_eq!(f64_at(&las, 131), 0.25);
+        assert_eq!(f64_at(&las, 155), 1000.);
+        // max x, min x, max y, min y, max z, min z
+        let bounds: Vec<f64> = (0..6).map(|i| f64_at(&las, 179 + 8 * i)).collect();
+        assert_eq!(bounds, [1003.25, 1001., 2004., 2002., 10.5, 10.]);
+        let second = &las[offset + 20..];
+        assert_eq!(u32_at(second, 0), 13);
+        assert_eq!(u32_at(second, 4), 16);
+        assert_eq!(second[15], 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let format = LasFormat::new().scale(1e-6);
+        format.write(Vec::new(), &[[1e4, 0.]]).unwrap();
+    }
+
+    #[test]
+    fn export_las() {
+        let dir = tempfile::tempdir().unwrap();
+        let set = PoissonDiskSet::new(vec![6., 4., 2.], 1., 30);
+        let path = dir.path().join("plan.las");
+        set.export_las(&path, &LasFormat::new()).unwrap();
+        let las = std::fs::read(&path).unwrap();
+        assert_eq!(u32_at(&las, 107) as usize, set.len());
+        assert_eq!(las.len(), 227 + 20 * set.len());
+
+        let result = set.export_las(dir.path().join("plan.laz"), &LasFormat::new());
+        #[cfg(not(feature = "laz"))]
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        #[cfg(feature = "laz")]
+        {
+            result.unwrap();
+            let laz = std::fs::read(dir.path().join("plan.laz")).unwrap();
+            assert_eq!(laz[104], 0x80);
+            let offset = u32_at(&laz, 96) as usize;
+            let vlr = laz::LazVlr::from_buffer(&laz[227 + 54..offset]).unwrap();
+            let mut points = vec![0; 20 * set.len()];
+            // the position of the chunk table is relative to the file
+            let mut file = io::Cursor::new(&laz[..]);
+            file.set_position(offset as u64);
+            let mut decompressor = laz::LasZipDecompressor::new(file, vlr).unwrap();
+            decompressor.decompress_many(&mut points).unwrap();
+            assert_eq!(points, las[227..]);
+        }
+    }
+}
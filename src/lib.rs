@@ -5,260 +5,847 @@
 //! For background information see
 //! [*Fast Poisson Disk Sampling in Arbitrary Dimensions*](https://www.cs.ubc.ca/~rbridson/docs/bridson-siggraph07-poissondisk.pdf)
 //! by Robert Bridson.
+//!
+//! Above six dimensions, the dense background grid of the paper is replaced
+//! by a sparse one with larger cells, since its size and the number of cells
+//! to check per candidate grow exponentially with the dimension.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs
+//! `alloc`, e.g. to generate samples or a [`DitherMask`] on microcontrollers.
+//! The core generator is kept: [`BlueNoiseConfig`], [`BlueNoiseIterator`],
+//! [`blue_noise_with_rng`], the sample storages and encodings. There is no
+//! operating system to seed the random number generator from, so a seed or
+//! a random number generator has to be passed. The floating point functions
+//! then come from `libm`, so seeded patterns may differ from the ones
+//! generated with `std`.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 
-use rand::Rng;
-use std::cmp::min;
+extern crate alloc;
 
-#[derive(Debug)]
-struct BackgroundGrid {
-    data: Vec<usize>,
-    dimensions: Vec<f64>,
-    min_dst_sqr: f64,
-    cell_size: f64,
-    cell_count: Vec<usize>,
-    cell_multiplicators: Vec<usize>,
-}
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+mod audio;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod cells;
+#[cfg(feature = "serde")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod concurrent;
+mod config;
+#[cfg(feature = "std")]
+mod coverage;
+#[cfg(feature = "std")]
+mod csv;
+#[cfg(feature = "delaunay")]
+mod delaunay;
+mod encoding;
+#[cfg(feature = "std")]
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fixed;
+#[cfg(feature = "std")]
+mod gltf;
+#[cfg(feature = "wgpu")]
+mod gpu;
+mod grid;
+mod interop;
+#[cfg(feature = "validate")]
+mod invariants;
+#[cfg(feature = "io")]
+mod io;
+#[cfg(feature = "io")]
+mod json;
+#[cfg(feature = "std")]
+mod las;
+mod latin;
+mod mask;
+#[cfg(not(any(feature = "std", test)))]
+mod math;
+#[cfg(feature = "std")]
+mod npy;
+#[cfg(feature = "std")]
+mod obj;
+mod palette;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pixel;
+#[cfg(feature = "plot")]
+mod plot;
+#[cfg(feature = "std")]
+mod ply;
+#[cfg(feature = "image")]
+mod png;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod raster;
+#[cfg(feature = "bevy")]
+mod scatter;
+#[cfg(feature = "std")]
+mod set;
+#[cfg(feature = "std")]
+mod spawn;
+mod sphere;
+mod stats;
+#[cfg(feature = "image")]
+mod stipple;
+mod storage;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "std")]
+mod svg;
+#[cfg(feature = "std")]
+mod synthesis;
+mod table;
+#[cfg(feature = "std")]
+mod tiled;
+#[cfg(feature = "std")]
+mod transport;
+#[cfg(feature = "std")]
+mod usd;
+#[cfg(feature = "std")]
+mod validate;
+mod variable;
+#[cfg(feature = "std")]
+mod voronoi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-impl BackgroundGrid {
-    pub fn new(dimensions: Vec<f64>, min_distance: f64) -> BackgroundGrid {
-        assert!(min_distance > 0.0);
-        let dimension = dimensions.len();
-        let cell_size = min_distance / (dimension as f64).sqrt();
-        let cell_count: Vec<usize> = dimensions
-            .iter()
-            .map(|x| (x / cell_size).ceil() as usize)
-            .collect();
-        let data_size = cell_count.iter().product::<usize>();
-        let mut cell_multiplicators = Vec::new();
-        let mut multi_accu = 1_usize;
-        for cell_count in cell_count.iter().take(dimension) {
-            cell_multiplicators.push(multi_accu);
-            multi_accu *= cell_count;
-        }
-        BackgroundGrid {
-            data: vec![0; data_size],
-            dimensions,
-            min_dst_sqr: min_distance * min_distance,
-            cell_size,
-            cell_count,
-            cell_multiplicators,
-        }
-    }
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+pub use audio::AudioNoise;
+#[cfg(feature = "std")]
+pub use cache::PatternCache;
+#[cfg(feature = "std")]
+pub use cells::CellLists;
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentGrid;
+#[cfg(feature = "rayon")]
+pub use config::blue_noise_batch;
+pub use config::{BlueNoiseConfig, ConfigError, Interval, NeighborSearch, Separation};
+#[cfg(feature = "std")]
+pub use csv::{CsvFormat, export_csv};
+#[cfg(feature = "half")]
+pub use encoding::F16;
+pub use encoding::{Encoding, F32, UNorm16, VertexLayout};
+#[cfg(feature = "std")]
+pub use events::event_times;
+pub use fixed::{FixedPoint, blue_noise_fixed};
+#[cfg(feature = "std")]
+pub use gltf::export_gltf;
+#[cfg(feature = "wgpu")]
+pub use gpu::{GpuError, GpuSampler, GpuSamples};
+use grid::BackgroundGrid;
+pub use grid::{GridSnapshot, OccupiedCell};
+#[cfg(feature = "ndarray")]
+pub use interop::to_array;
+#[cfg(feature = "nalgebra")]
+pub use interop::to_nalgebra;
+#[cfg(feature = "cgmath")]
+pub use interop::{CgmathPoint, to_cgmath};
+#[cfg(feature = "glam")]
+pub use interop::{GlamVector, to_glam};
+#[cfg(feature = "mint")]
+pub use interop::{MintVector, to_mint};
+#[cfg(feature = "validate")]
+pub use invariants::InvariantError;
+#[cfg(feature = "io")]
+pub use io::{ImportError, read_csv, read_ply};
+#[cfg(feature = "io")]
+pub use json::{export_json, read_json};
+#[cfg(feature = "std")]
+pub use las::{LasFormat, export_las};
+pub use latin::blue_noise_latin;
+pub use mask::DitherMask;
+#[cfg(not(any(feature = "std", test)))]
+use math::Float;
+#[cfg(feature = "std")]
+pub use npy::{export_npy, export_npz};
+#[cfg(feature = "std")]
+pub use obj::{ObjPrimitive, export_obj};
+pub use palette::color_palette;
+#[cfg(feature = "rayon")]
+pub use parallel::{
+    blue_noise_parallel, blue_noise_parallel_seamless, blue_noise_parallel_seamless_seeded,
+    blue_noise_parallel_seeded,
+};
+pub use pixel::{PixelSamples, pixel_pattern};
+#[cfg(feature = "plot")]
+pub use plot::{PlotError, plot_points, plot_rdf, plot_spectrum};
+#[cfg(feature = "std")]
+pub use ply::export_ply;
+#[cfg(feature = "image")]
+pub use png::{PngStyle, Splat, export_png};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "std")]
+pub use raster::CellFeature;
+#[cfg(feature = "bevy")]
+pub use scatter::{BlueNoisePlugin, BlueNoiseScatter, ScatterPoint, ScatterPoints};
+#[cfg(feature = "std")]
+pub use set::{Crop, Placement, PoissonDiskSet, recycle};
+use smallvec::SmallVec;
+#[cfg(feature = "std")]
+pub use spawn::{Batch, blue_noise_spawn};
+pub use sphere::{blue_noise_directions, blue_noise_rotations};
+pub use stats::GenerationStats;
+#[cfg(feature = "image")]
+pub use stipple::stipple;
+// std::time::Instant panics in browsers
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use std::time::Instant;
+#[cfg(feature = "bumpalo")]
+pub use storage::BumpSamples;
+pub use storage::SampleStorage;
+#[cfg(feature = "futures")]
+pub use stream::BlueNoiseStream;
+#[cfg(feature = "std")]
+pub use svg::{SvgStyle, export_svg};
+#[cfg(feature = "std")]
+pub use synthesis::{NoiseColor, colored_noise, synthesize_spectrum};
+pub use table::{PatternTable, TableError, TableFormat, encode_table};
+#[cfg(feature = "rayon")]
+pub use tiled::blue_noise_tiled_parallel;
+#[cfg(feature = "std")]
+pub use tiled::{Tiling, blue_noise_tiled, blue_noise_tiled_seeded};
+#[cfg(feature = "std")]
+pub use transport::{Transport, transport};
+#[cfg(feature = "std")]
+pub use usd::{UsdPoints, export_usd};
+#[cfg(feature = "std")]
+pub use validate::{
+    MergePolicy, Violation, dedup, find_conflicts, merge, merge_dedup, refill, repair,
+    resolve_conflicts, validate, validate_with_bounds,
+};
+pub use variable::{SampleUpdate, blue_noise_variable, blue_noise_variable_update};
+#[cfg(feature = "voronoi")]
+pub use voronoi::Polygon;
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+use web_time::Instant;
+
+/// A single sample.
+///
+/// Up to four dimensions the coordinates are stored inline, so the common 2D
+/// and 3D cases don't need a heap allocation per sample. It dereferences to
+/// `[f64]`.
+pub type Point = SmallVec<[f64; 4]>;
 
-    pub fn dst_sqr(x: &[f64], y: &[f64]) -> f64 {
-        debug_assert_eq!(x.len(), y.len());
-        x.iter().zip(y.iter()).fold(0_f64, |accu, (xx, yx)| {
-            let diff = xx - yx;
-            accu + diff * diff
+fn polar_to_cartesian(radius: f64, angles: &[f64]) -> Point {
+    let sines: SmallVec<[f64; 4]> = angles.iter().map(|x| x.sin()).collect();
+    (0..angles.len() + 1)
+        .map(|i| {
+            sines.iter().take(i).fold(radius, |accu, sine| accu * sine)
+                * match angles.get(i) {
+                    Some(ang) => ang.cos(),
+                    None => 1_f64,
+                }
         })
+        .collect()
+}
+
+/// A random coordinate from `lower` up to but excluding `upper`, or `lower`
+/// if there is no room between them, as along an axis of size 0.
+pub(crate) fn coordinate<R: Rng>(rng: &mut R, lower: f64, upper: f64) -> f64 {
+    if lower < upper {
+        rng.gen_range(lower..upper)
+    } else {
+        lower
     }
+}
 
-    fn calc_idx(&self, cell_id: &[usize]) -> usize {
-        self.cell_multiplicators
-            .iter()
-            .zip(cell_id.iter())
-            .skip(1)
-            .fold(cell_id[0], |accu, (multi, cell)| accu + multi * cell)
+/// Creates a random candidate at least `min_distance` and less than
+/// `max_distance` away from `center`, usually one and two times the minimal
+/// distance of the samples.
+fn candidate<R: Rng>(center: &[f64], min_distance: f64, max_distance: f64, rng: &mut R) -> Point {
+    let dimension = center.len();
+    let mut radius = rng.gen_range(min_distance..max_distance);
+    // in one dimension, there are no angles but two directions
+    if dimension == 1 && rng.gen_bool(0.5) {
+        radius = -radius;
     }
+    let angles: SmallVec<[f64; 4]> = (0..dimension - 1)
+        .map(|_| rng.gen_range(0_f64..2_f64 * core::f64::consts::PI))
+        .collect();
+    let samp_offs = polar_to_cartesian(radius, &angles);
+    debug_assert_eq!(samp_offs.len(), dimension);
+    // if polar_to_cartesian would return an iterator, this might be
+    // more efficient
+    samp_offs
+        .into_iter()
+        .zip(center.iter())
+        .map(|(offs, x)| x + offs)
+        .collect()
+}
 
-    pub fn insert(
-        &mut self,
-        sample_position: Vec<f64>,
-        samples: &mut Vec<Vec<f64>>,
-    ) -> Result<usize, ()> {
-        if sample_position
-            .iter()
-            .zip(self.dimensions.iter())
-            .any(|(samp_x, dim_x)| *samp_x < 0_f64 || samp_x >= dim_x)
-        {
-            return Err(());
-        }
-        let dimension = self.dimensions.len();
-        debug_assert_eq!(sample_position.len(), dimension);
-        let cell_id: Vec<usize> = sample_position
-            .iter()
-            .map(|x| (*x / self.cell_size) as usize)
-            .collect();
-        let samp_idx = self.calc_idx(&cell_id);
-        debug_assert!(
-            cell_id
-                .iter()
-                .zip(self.cell_count.iter())
-                .all(|(cid, cc)| cid < cc)
-        );
-        let cell_offs = (self.min_dst_sqr / self.cell_size).ceil() as usize;
-        let min_cell: Vec<usize> = cell_id
-            .iter()
-            .map(|x| x.saturating_sub(cell_offs))
-            .collect();
-        let max_cell: Vec<usize> = cell_id
+/// Like [`candidate`], but uniform in the shell between `min_distance` and
+/// `max_distance` and without trigonometry, so the result is the same on all
+/// platforms: offsets are drawn from the enclosing cube until one falls into
+/// the shell.
+fn candidate_in_shell<R: Rng>(
+    center: &[f64],
+    min_distance: f64,
+    max_distance: f64,
+    rng: &mut R,
+) -> Point {
+    let outer = max_distance;
+    loop {
+        let offset: Point = center
             .iter()
-            .zip(self.cell_count.iter())
-            .map(|(x, size_x)| min(x + cell_offs, size_x - 1))
+            .map(|_| rng.gen_range(-outer..outer))
             .collect();
-        debug_assert!(
-            min_cell
+        let squared: f64 = offset.iter().map(|x| x * x).sum();
+        if (min_distance * min_distance..outer * outer).contains(&squared) {
+            return offset
                 .iter()
-                .zip(max_cell.iter())
-                .zip(cell_id.iter())
-                .all(|((cmin, cmax), c)| cmin <= c && c <= cmax)
-        );
-        let mut indices = min_cell.clone();
-        let mut checked_own_idx = false;
-        loop {
-            debug_assert!(
-                min_cell
-                    .iter()
-                    .zip(max_cell.iter())
-                    .zip(indices.iter())
-                    .all(|((cmin, cmax), c)| cmin <= c && c <= cmax)
-            );
-            let idx = self.calc_idx(&indices);
-            if idx == samp_idx {
-                checked_own_idx = true;
-            }
-            match self.data[idx] {
-                0 => (),
-                other_id => {
-                    let other_sample = &samples[other_id - 1];
-                    if BackgroundGrid::dst_sqr(&sample_position, other_sample) < self.min_dst_sqr {
-                        return Err(());
-                    }
-                }
-            }
-            // loop exit check
-            if indices == max_cell {
-                break;
-            }
-            // iterate indices
-            for i in 0..dimension {
-                if indices[i] == max_cell[i] {
-                    indices[i] = min_cell[i];
-                } else {
-                    indices[i] += 1;
-                    break;
-                }
-            }
+                .zip(center.iter())
+                .map(|(o, x)| x + o)
+                .collect();
         }
-        // no collission found
-        debug_assert!(
-            checked_own_idx,
-            "Didn't check own idx.\n\tMin cells: {:?}\n\tMax cells: \
-                               {:?}\n\tself cells: {:?}",
-            min_cell, max_cell, cell_id
-        );
-        samples.push(sample_position);
-        debug_assert_eq!(self.data[samp_idx], 0);
-        self.data[samp_idx] = samples.len();
-        Ok(samples.len())
     }
 }
 
-fn polar_to_cartesian(radius: f64, angles: Vec<f64>) -> Vec<f64> {
-    let sines: Vec<f64> = angles.iter().map(|x| x.sin()).collect();
-    (0..angles.len() + 1)
-        .map(|i| {
-            sines.iter().take(i).fold(radius, |accu, sine| accu * sine)
-                * match angles.get(i) {
-                    Some(ang) => ang.cos(),
-                    None => 1_f64,
-                }
-        })
-        .collect()
+/// The random number generator of new iterators, seeded from the operating
+/// system.
+#[cfg(feature = "std")]
+fn unseeded_rng() -> ChaCha8Rng {
+    ChaCha8Rng::from_entropy()
 }
 
+/// Without an operating system, all public ways to create an iterator replace
+/// this by a seeded one.
+#[cfg(not(feature = "std"))]
+fn unseeded_rng() -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(0)
+}
+
+/// Creates a candidate around a sample, see [`candidate`].
+type Candidate = fn(&[f64], f64, f64, &mut ChaCha8Rng) -> Point;
+
+/// Finds the index of the first candidate which can be inserted.
+#[cfg(feature = "rayon")]
+type FirstValid<S> = fn(&BackgroundGrid, &S, &[Point]) -> Option<usize>;
+
 /// The iterator struct returned by [`blue_noise_iter`].
-pub struct BlueNoiseIterator {
+///
+/// `S` is where the generated samples are kept, see [`SampleStorage`].
+pub struct BlueNoiseIterator<S = Vec<Point>> {
     dimensions: Vec<f64>,
     min_distance: f64,
+    /// How far from their sample candidates may be, see
+    /// [`BlueNoiseConfig::max_distance`].
+    max_distance: f64,
+    /// The next cell of the background grid to fill gaps in once growth
+    /// stops, `None` if gaps are left, see [`BlueNoiseIterator::fill_gap`].
+    gap_cell: Option<usize>,
     k_abort: usize,
-    samples: Vec<Vec<f64>>,
+    samples: S,
     bggrid: BackgroundGrid,
     active: Vec<usize>,
     active_idx: usize,
     next_active: Vec<usize>,
+    /// If set, new samples are only accepted inside this half-open box.
+    region: Option<(Vec<f64>, Vec<f64>)>,
+    /// The axes along which the domain wraps around, empty if it doesn't
+    /// wrap at all, see [`BlueNoiseConfig::wrap`].
+    wrap: Vec<bool>,
+    /// The axes shorter than the minimal distance, empty if there are none.
+    /// Candidates are only spread out along the other axes, and placed
+    /// anywhere along these.
+    flat: Vec<bool>,
+    /// Whether candidates are generated without trigonometry, see
+    /// [`BlueNoiseConfig::reproducible`].
+    reproducible: bool,
+    rng: ChaCha8Rng,
+    stats: GenerationStats,
+    /// The first invariant which was found broken after an insert.
+    #[cfg(feature = "validate")]
+    invariant_error: Option<InvariantError>,
+    /// If set, finds the first acceptable candidate of a batch in parallel,
+    /// see [`BlueNoiseIterator::parallel_candidates`].
+    #[cfg(feature = "rayon")]
+    first_valid: Option<FirstValid<S>>,
 }
 
 impl BlueNoiseIterator {
     fn new(dimensions: Vec<f64>, min_distance: f64, k_abort: usize) -> BlueNoiseIterator {
+        BlueNoiseIterator::try_new(dimensions, min_distance, k_abort)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    fn try_new(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        k_abort: usize,
+    ) -> Result<BlueNoiseIterator, ConfigError> {
+        let bggrid = BackgroundGrid::try_new(dimensions.clone(), min_distance)?;
+        Ok(BlueNoiseIterator::with_grid(
+            dimensions,
+            min_distance,
+            k_abort,
+            bggrid,
+            Vec::new(),
+        ))
+    }
+}
+
+impl<S: SampleStorage> BlueNoiseIterator<S> {
+    fn with_grid(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        k_abort: usize,
+        bggrid: BackgroundGrid,
+        samples: S,
+    ) -> BlueNoiseIterator<S> {
+        assert!(samples.is_empty(), "the sample storage must be empty");
+        let flat: Vec<bool> = dimensions.iter().map(|x| *x < min_distance).collect();
+        let flat = if flat.contains(&true) {
+            flat
+        } else {
+            Vec::new()
+        };
         BlueNoiseIterator {
-            dimensions: dimensions.clone(),
+            dimensions,
             min_distance,
+            max_distance: 2_f64 * min_distance,
+            gap_cell: None,
             k_abort,
-            samples: Vec::new(),
-            bggrid: BackgroundGrid::new(dimensions, min_distance),
+            samples,
+            bggrid,
             active: Vec::new(),
             active_idx: 0,
             next_active: Vec::new(),
+            region: None,
+            wrap: Vec::new(),
+            flat,
+            reproducible: false,
+            rng: unseeded_rng(),
+            stats: GenerationStats::default(),
+            #[cfg(feature = "validate")]
+            invariant_error: None,
+            #[cfg(feature = "rayon")]
+            first_valid: None,
         }
     }
-}
 
-impl Iterator for BlueNoiseIterator {
-    type Item = Vec<f64>;
+    /// Inserts a sample which is not reported by the iterator, but which new
+    /// samples have to respect and which serves as a starting point for them.
+    ///
+    /// Returns whether the sample could be inserted.
+    #[cfg(feature = "std")]
+    fn seed(&mut self, position: Point) -> bool {
+        match self.bggrid.insert(position, &mut self.samples) {
+            Ok(id) => {
+                self.next_active.push(id);
+                self.stats.samples += 1;
+                #[cfg(feature = "validate")]
+                self.verify_insert(id);
+                true
+            }
+            Err(()) => false,
+        }
+    }
 
-    fn next(&mut self) -> Option<Vec<f64>> {
+    /// Creates a candidate around `center` with `generate` along the axes
+    /// which aren't [flat](BlueNoiseIterator::flat), and at a random position
+    /// along the flat ones. A thin slab is thereby sampled like the plane
+    /// instead of losing most candidates across its faces.
+    fn candidate_around(&mut self, center: &[f64], generate: Candidate) -> Point {
+        if self.flat.is_empty() {
+            return generate(center, self.min_distance, self.max_distance, &mut self.rng);
+        }
+        let spread: Point = center
+            .iter()
+            .zip(self.flat.iter())
+            .filter(|(_, flat)| !**flat)
+            .map(|(x, _)| *x)
+            .collect();
+        let mut spread =
+            generate(&spread, self.min_distance, self.max_distance, &mut self.rng).into_iter();
+        self.dimensions
+            .iter()
+            .zip(self.flat.iter())
+            .map(|(dim, flat)| {
+                if *flat {
+                    coordinate(&mut self.rng, 0_f64, *dim)
+                } else {
+                    spread.next().unwrap()
+                }
+            })
+            .collect()
+    }
+
+    /// A random position in the region, or in the domain if there is none.
+    fn random_position(&mut self) -> Point {
+        match &self.region {
+            Some((lower, upper)) => lower
+                .iter()
+                .zip(upper.iter())
+                .map(|(lo, hi)| coordinate(&mut self.rng, *lo, *hi))
+                .collect(),
+            None => self
+                .dimensions
+                .iter()
+                .map(|x| coordinate(&mut self.rng, 0_f64, *x))
+                .collect(),
+        }
+    }
+
+    /// Moves a position which left the domain across a wrapped border back
+    /// in at the opposite border.
+    fn wrap_around(&self, position: &mut [f64]) {
+        let axes = position.iter_mut().zip(self.dimensions.iter());
+        for ((x, dim), wrap) in axes.zip(self.wrap.iter()) {
+            if *wrap && *x < 0_f64 {
+                *x += dim;
+            } else if *wrap && *x >= *dim {
+                *x -= dim;
+            }
+        }
+    }
+
+    /// Whether a sample at `position` would be too close to one of the
+    /// samples at the opposite side of a wrapped border.
+    fn conflicts_across_borders(&self, position: &[f64]) -> bool {
+        // the wrapped axes along which the position is close to a border,
+        // and by how much its image beyond the opposite border is moved
+        let shifts: SmallVec<[(usize, f64); 4]> = position
+            .iter()
+            .zip(self.dimensions.iter())
+            .zip(self.wrap.iter())
+            .enumerate()
+            .filter(|(_, (_, wrap))| **wrap)
+            .filter_map(|(axis, ((x, dim), _))| {
+                if *x < self.min_distance {
+                    Some((axis, *dim))
+                } else if *x >= dim - self.min_distance {
+                    Some((axis, -dim))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        // every combination of the shifts gives an image, e.g. three near a
+        // corner in 2D
+        (1_usize..1 << shifts.len()).any(|combination| {
+            let mut image = Point::from_slice(position);
+            for (bit, (axis, shift)) in shifts.iter().enumerate() {
+                if combination & 1 << bit != 0 {
+                    image[*axis] += shift;
+                }
+            }
+            self.bggrid
+                .within(&image, self.min_distance, &self.samples)
+                .next()
+                .is_some()
+        })
+    }
+
+    /// Inserts a sample into the next gap in which a position is farther
+    /// than `max_distance` from all samples, looking through the cells of
+    /// the grid from [`gap_cell`](BlueNoiseIterator::gap_cell) on.
+    ///
+    /// The positions in a cell with a sample are at most the minimal
+    /// distance from it, since that is the diagonal of the cells, so only
+    /// the empty cells matter. Each is probed on a lattice whose boxes have
+    /// a half diagonal of `max_distance` minus the minimal distance: a probe
+    /// which can't take a sample is closer than the minimal distance to one,
+    /// so the positions around it are closer than `max_distance`. Sparse
+    /// grids don't keep their empty cells, so nothing is filled in more
+    /// than six dimensions.
+    fn fill_gap(&mut self) -> Option<usize> {
+        let first = self.gap_cell.take()?;
+        if self.bggrid.sparse.is_some() {
+            return None;
+        }
         let dimension = self.dimensions.len();
-        // we don't need to store the RNG because it is one per thread and
-        // lazyli initialized
-        let mut rng = rand::thread_rng();
+        let cell_size = self.bggrid.cell_size;
+        let spacing = 2_f64 * (self.max_distance - self.min_distance) / (dimension as f64).sqrt();
+        for idx in first..self.bggrid.data.len() {
+            if self.bggrid.data[idx] != 0 {
+                continue;
+            }
+            // the part of the cell inside of the domain, split into
+            // `steps` boxes of `step` along each axis
+            let mut rest = idx;
+            let mut lower = Point::new();
+            let mut step = Point::new();
+            let mut steps: SmallVec<[usize; 4]> = SmallVec::new();
+            for (count, dim) in self.bggrid.cell_count.iter().zip(self.dimensions.iter()) {
+                let lo = (rest % count) as f64 * cell_size;
+                rest /= count;
+                let extent = cell_size.min(dim - lo);
+                let n = ((extent / spacing).ceil() as usize).max(1);
+                lower.push(lo);
+                step.push(extent / n as f64);
+                steps.push(n);
+            }
+            let mut probe_id: SmallVec<[usize; 4]> = smallvec::smallvec![0; dimension];
+            loop {
+                let probe: Point = lower
+                    .iter()
+                    .zip(step.iter())
+                    .zip(probe_id.iter())
+                    .map(|((lo, step), i)| lo + (*i as f64 + 0.5_f64) * step)
+                    .collect();
+                if self.in_region(&probe)
+                    && !self.conflicts_across_borders(&probe)
+                    && let Ok(id) = self.bggrid.insert(probe, &mut self.samples)
+                {
+                    self.gap_cell = Some(idx + 1);
+                    self.stats.filled += 1;
+                    return Some(id);
+                }
+                // the next probe, like an odometer
+                let Some(axis) = (0..dimension).find(|axis| probe_id[*axis] + 1 < steps[*axis])
+                else {
+                    break;
+                };
+                probe_id[axis] += 1;
+                probe_id[..axis].fill(0);
+            }
+        }
+        None
+    }
+
+    fn in_region(&self, position: &[f64]) -> bool {
+        match &self.region {
+            Some((lower, upper)) => position
+                .iter()
+                .zip(lower.iter().zip(upper.iter()))
+                // a flat axis only contains its lower border
+                .all(|(x, (lo, hi))| (lo <= x && x < hi) || (lo == hi && x == lo)),
+            None => self.bggrid.contains(position),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<S: SampleStorage + Sync> BlueNoiseIterator<S> {
+    /// Checks all the `k_abort` candidates around an active sample in
+    /// parallel instead of one after another.
+    ///
+    /// The first acceptable candidate is used, like in the sequential case.
+    /// As all the candidates are created up front, this does more work in
+    /// total, but it pays off for a large `k_abort` and in high dimensions,
+    /// where the single checks are expensive. Domains which
+    /// [wrap](BlueNoiseConfig::wrap) around are always checked sequentially.
+    ///
+    /// Requires the `rayon` feature.
+    pub fn parallel_candidates(mut self) -> BlueNoiseIterator<S> {
+        fn first_valid<S: SampleStorage + Sync>(
+            bggrid: &BackgroundGrid,
+            samples: &S,
+            candidates: &[Point],
+        ) -> Option<usize> {
+            use rayon::prelude::*;
+            candidates
+                .par_iter()
+                .position_first(|samp| bggrid.check(samp, samples).is_ok())
+        }
+        self.first_valid = Some(first_valid::<S>);
+        self
+    }
+}
+
+impl<S: SampleStorage> BlueNoiseIterator<S> {
+    /// Generates the next sample like [`next`](Iterator::next), but returns
+    /// a reference to its coordinates in the storage instead of a copy.
+    ///
+    /// This saves one allocation per sample for consumers which only read
+    /// the coordinates:
+    ///
+    /// ```
+    /// let mut it = bluenoisers::blue_noise_iter(vec![20., 20.], 1., 30);
+    /// let mut sum = 0.;
+    /// while let Some(sample) = it.next_ref() {
+    ///     sum += sample[0];
+    /// }
+    /// assert!(sum > 0.);
+    /// ```
+    pub fn next_ref(&mut self) -> Option<&[f64]> {
+        let id = self.advance()?;
+        Some(self.samples.sample(id - 1))
+    }
+
+    /// The counters collected so far, see [`GenerationStats`]. This can be
+    /// called at any point during generation.
+    pub fn stats(&self) -> GenerationStats {
+        self.stats
+    }
+
+    /// Copies the state of the background grid, see [`GridSnapshot`]. This
+    /// can be called at any point during generation.
+    pub fn grid_snapshot(&self) -> GridSnapshot {
+        self.bggrid.snapshot()
+    }
+
+    /// Generates all the remaining samples.
+    ///
+    /// Parts of the domain may not be reachable by growing from the samples
+    /// so far, e.g. if they were [seeded](BlueNoiseIterator::seed), so growth
+    /// is restarted at random positions until `k_abort` of them in a row are
+    /// too close to existing samples.
+    #[cfg(feature = "std")]
+    fn exhaust(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("exhaust", seeded = self.samples.len()).entered();
+        loop {
+            while self.next_ref().is_some() {}
+            let start = Instant::now();
+            let restarted = (0..self.k_abort).any(|_| {
+                let dart = self.random_position();
+                self.stats.darts += 1;
+                !self.conflicts_across_borders(&dart) && self.seed(dart)
+            });
+            self.stats.dart_time += start.elapsed();
+            #[cfg(feature = "tracing")]
+            tracing::trace!(restarted, samples = self.samples.len(), "random restart");
+            if !restarted {
+                return;
+            }
+        }
+    }
+
+    /// Generates the next sample and returns its id, i.e. its index in the
+    /// storage plus one.
+    fn advance(&mut self) -> Option<usize> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let id = self.grow();
+        #[cfg(feature = "std")]
+        {
+            self.stats.growth_time += start.elapsed();
+        }
+        if id.is_some() {
+            self.stats.samples += 1;
+        }
+        #[cfg(feature = "validate")]
+        if let Some(id) = id {
+            self.verify_insert(id);
+        }
+        id
+    }
+
+    fn grow(&mut self) -> Option<usize> {
         // first sample
         if self.samples.is_empty() {
-            let initial_sample: Vec<f64> = self
-                .dimensions
-                .iter()
-                .map(|x| rng.gen_range(0_f64..*x))
-                .collect();
+            // only an open lower border can reject a random position
+            let initial_sample = loop {
+                let position = self.random_position();
+                if self.bggrid.contains(&position) {
+                    break position;
+                }
+            };
             let initial_sample_id = self
                 .bggrid
-                .insert(initial_sample.clone(), &mut self.samples)
+                .insert(initial_sample, &mut self.samples)
                 .unwrap();
             debug_assert_eq!(initial_sample_id, 1);
             self.active.push(initial_sample_id);
-            return Some(initial_sample);
-        }
-        // if active_idx has iterated completely, use the next_active list and
-        // start over
-        if self.active_idx >= self.active.len() {
-            self.active_idx = 0;
-            self.active = self.next_active.clone();
-            self.next_active = Vec::new();
+            return Some(initial_sample_id);
         }
-        if self.active.is_empty() {
-            return None;
-        }
-        let current_id = self.active[self.active_idx];
-        let current_samp = self.samples[current_id - 1].clone();
-        for _ in 0..self.k_abort {
-            let radius = rng.gen_range(self.min_distance..2_f64 * self.min_distance);
-            let angles = (0..dimension - 1)
-                .map(|_| rng.gen_range(0_f64..2_f64 * std::f64::consts::PI))
-                .collect();
-            let samp_offs = polar_to_cartesian(radius, angles);
-            debug_assert_eq!(samp_offs.len(), dimension);
-            // if polar_to_cartesian would return an iterator, this might be
-            // more efficient
-            let samp = samp_offs
-                .into_iter()
-                .zip(current_samp.iter())
-                .map(|(offs, x)| x + offs)
-                .collect();
-            match self.bggrid.insert(samp, &mut self.samples) {
-                Ok(new_samp_id) => {
+        loop {
+            // if active_idx has iterated completely, use the next_active list
+            // and start over
+            if self.active_idx >= self.active.len() {
+                self.active_idx = 0;
+                self.active = core::mem::take(&mut self.next_active);
+                if !self.active.is_empty() {
+                    self.stats.rounds += 1;
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        round = self.stats.rounds,
+                        active = self.active.len(),
+                        samples = self.samples.len(),
+                        "starting round"
+                    );
+                }
+            }
+            if self.active.is_empty() {
+                let id = self.fill_gap()?;
+                self.next_active.push(id);
+                return Some(id);
+            }
+            let current_id = self.active[self.active_idx];
+            let current_samp = Point::from_slice(self.samples.sample(current_id - 1));
+            self.active_idx += 1;
+            // a domain which is flat along all axes has room for one sample
+            if !self.flat.is_empty() && self.flat.iter().all(|flat| *flat) {
+                self.stats.retired += 1;
+                continue;
+            }
+            let candidate: Candidate = if self.reproducible {
+                candidate_in_shell
+            } else {
+                candidate
+            };
+            #[cfg(feature = "rayon")]
+            if let Some(first_valid) = self.first_valid
+                && self.wrap.is_empty()
+            {
+                let mut candidates: Vec<Point> = Vec::with_capacity(self.k_abort);
+                for _ in 0..self.k_abort {
+                    let samp = self.candidate_around(&current_samp, candidate);
+                    if self.in_region(&samp) {
+                        candidates.push(samp);
+                    }
+                }
+                let outside = self.k_abort - candidates.len();
+                self.stats.rejected_outside += outside;
+                let found = first_valid(&self.bggrid, &self.samples, &candidates);
+                // the candidates after the first valid one don't count
+                let tried = found.map_or(candidates.len(), |i| i + 1);
+                self.stats.candidates += outside + tried;
+                self.stats.rejected_too_close += found.map_or(tried, |i| i);
+                if let Some(i) = found {
+                    let samp = candidates.swap_remove(i);
+                    let new_samp_id = self.bggrid.insert(samp, &mut self.samples).unwrap();
                     self.next_active.push(current_id);
                     self.next_active.push(new_samp_id);
-                    self.active_idx += 1;
-                    return Some(self.samples[new_samp_id - 1].clone());
+                    return Some(new_samp_id);
+                }
+                self.stats.retired += 1;
+                continue;
+            }
+            for _ in 0..self.k_abort {
+                let mut samp = self.candidate_around(&current_samp, candidate);
+                self.stats.candidates += 1;
+                self.wrap_around(&mut samp);
+                if !self.in_region(&samp) {
+                    self.stats.rejected_outside += 1;
+                    continue;
                 }
-                Err(_) => {
-                    // wait for the next iteration
+                if self.conflicts_across_borders(&samp) {
+                    self.stats.rejected_too_close += 1;
+                    continue;
+                }
+                if let Ok(new_samp_id) = self.bggrid.insert(samp, &mut self.samples) {
+                    self.next_active.push(current_id);
+                    self.next_active.push(new_samp_id);
+                    return Some(new_samp_id);
                 }
+                self.stats.rejected_too_close += 1;
             }
+            self.stats.retired += 1;
         }
-        self.active_idx += 1;
-        self.next()
+    }
+}
+
+impl<S: SampleStorage> Iterator for BlueNoiseIterator<S> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let id = self.advance()?;
+        Some(Point::from_slice(self.samples.sample(id - 1)))
     }
 }
 
@@ -278,11 +865,66 @@ impl Iterator for BlueNoiseIterator {
 /// The samples returned are in order of generation.
 /// Each sample is at most *2 × `min_distance`* away from a previous sample
 /// (except the first sample, of course).
-pub fn blue_noise(dimensions: Vec<f64>, min_distance: f64, k_abort: usize) -> Vec<Vec<f64>> {
+#[cfg(feature = "std")]
+pub fn blue_noise(dimensions: Vec<f64>, min_distance: f64, k_abort: usize) -> Vec<Point> {
     // this method avoids copying the samples once more vs a simple it.collect()
     let mut it = BlueNoiseIterator::new(dimensions, min_distance, k_abort);
     // force generation of all the samples
-    for _ in it.by_ref() {}
+    while it.next_ref().is_some() {}
+    it.samples
+}
+
+/// Like [`blue_noise`], but returns the coordinates in a compact encoding,
+/// sample after sample.
+///
+/// The samples are encoded as they are generated, so no full precision copy
+/// of the result is made. The returned buffer can e.g. be uploaded to the GPU
+/// as is.
+///
+/// ```
+/// use bluenoisers::{UNorm16, blue_noise_encoded};
+///
+/// let coordinates = blue_noise_encoded::<UNorm16>(vec![64., 64.], 4., 30);
+/// assert_eq!(coordinates.len() % 2, 0);
+/// ```
+#[cfg(feature = "std")]
+pub fn blue_noise_encoded<E: Encoding>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+) -> Vec<E::Scalar> {
+    let mut encoded = Vec::new();
+    for sample in BlueNoiseIterator::new(dimensions.clone(), min_distance, k_abort) {
+        encoded.extend(
+            sample
+                .iter()
+                .zip(dimensions.iter())
+                .map(|(x, extent)| E::encode(*x, *extent)),
+        );
+    }
+    encoded
+}
+
+/// Like [`blue_noise`], but draws the seed of the generator from `rng`
+/// instead of the operating system, which is needed without the `std`
+/// feature. The same seed yields the same samples.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let samples = bluenoisers::blue_noise_with_rng(vec![64., 64.], 4., 30, &mut rng);
+/// assert!(samples.len() > 100);
+/// ```
+pub fn blue_noise_with_rng<R: RngCore>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<Point> {
+    let mut it = BlueNoiseIterator::new(dimensions, min_distance, k_abort);
+    it.rng = ChaCha8Rng::seed_from_u64(rng.next_u64());
+    while it.next_ref().is_some() {}
     it.samples
 }
 
@@ -290,6 +932,7 @@ pub fn blue_noise(dimensions: Vec<f64>, min_distance: f64, k_abort: usize) -> Ve
 ///
 /// This is useful for pipelined processing or when you only need to `take` some
 /// amount of samples Otherwise this is the same as `blue_noise`
+#[cfg(feature = "std")]
 pub fn blue_noise_iter(
     dimensions: Vec<f64>,
     min_distance: f64,
@@ -298,24 +941,80 @@ pub fn blue_noise_iter(
     BlueNoiseIterator::new(dimensions, min_distance, k_abort)
 }
 
-#[test]
-fn grid_corners() {
-    let mut grid = BackgroundGrid::new(vec![35_f64, 9_f64], 4.0);
-    let mut samples = Vec::new();
-    assert_eq!(grid.cell_count.len(), 2);
-    assert_eq!(grid.insert(vec![0., 9.], &mut samples), Err(()));
-    assert_eq!(samples.len(), 0);
-    assert_eq!(grid.insert(vec![0., 0.], &mut samples), Ok(1));
-    assert_eq!(samples.len(), 1);
-    assert_eq!(grid.insert(vec![34., 0.], &mut samples), Ok(2));
-    assert_eq!(samples.len(), 2);
-    assert_eq!(grid.insert(vec![0., 8.], &mut samples), Ok(3));
-    assert_eq!(samples.len(), 3);
-    assert_eq!(grid.insert(vec![34., 8.], &mut samples), Ok(4));
-    assert_eq!(samples.len(), 4);
+/// Like [`blue_noise_iter`], but keeps the background grid in a memory mapped
+/// file instead of on the heap.
+///
+/// The grid has one cell per *`min_distance` / √n* in each of the `n`
+/// dimensions, so for huge domains with small distances it easily exceeds the
+/// available RAM. Mapping it lets the operating system page it in and out as
+/// needed. Since samples are generated close to each other, the access pattern
+/// stays mostly local.
+///
+/// The file at `grid_path` is created if necessary and its content is
+/// replaced. It is not removed afterwards, this is up to the caller once the
+/// iterator has been dropped.
+///
+/// Requires the `memmap` feature.
+#[cfg(feature = "memmap")]
+pub fn blue_noise_iter_mmap<P: AsRef<std::path::Path>>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    grid_path: P,
+) -> std::io::Result<BlueNoiseIterator> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(grid_path)?;
+    let bggrid = BackgroundGrid::new_mapped(dimensions.clone(), min_distance, &file)?;
+    Ok(BlueNoiseIterator::with_grid(
+        dimensions,
+        min_distance,
+        k_abort,
+        bggrid,
+        Vec::new(),
+    ))
 }
 
-#[cfg(test)]
+/// Like [`blue_noise`], but puts the samples into `storage` instead of a
+/// freshly allocated `Vec`.
+///
+/// This allows to generate samples into memory managed by the caller, e.g. an
+/// arena which is reset every frame, see [`SampleStorage`].
+///
+/// # Panics
+///
+/// Panics if `storage` is not empty.
+#[cfg(feature = "std")]
+pub fn blue_noise_with_storage<S: SampleStorage>(
+    storage: S,
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+) -> S {
+    let bggrid = BackgroundGrid::new(dimensions.clone(), min_distance);
+    let mut it = BlueNoiseIterator::with_grid(dimensions, min_distance, k_abort, bggrid, storage);
+    while it.next_ref().is_some() {}
+    it.samples
+}
+
+/// Like [`blue_noise`], but allocates the samples in `bump`.
+///
+/// Requires the `bumpalo` feature.
+#[cfg(feature = "bumpalo")]
+pub fn blue_noise_in(
+    bump: &bumpalo::Bump,
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+) -> BumpSamples<'_> {
+    let storage = BumpSamples::new_in(dimensions.len(), bump);
+    blue_noise_with_storage(storage, dimensions, min_distance, k_abort)
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     extern crate rand;
@@ -330,6 +1029,10 @@ mod tests {
     fn sanity_6d() {
         sanity_nd(6, 6., 6.5);
     }
+    #[test]
+    fn sanity_8d() {
+        sanity_nd(8, 7., 7.5);
+    }
     fn sanity_nd(dimension: usize, minr: f64, maxr: f64) {
         let mut rng = rand::thread_rng();
         let radius = 3.;
@@ -355,6 +1058,59 @@ mod tests {
             assert!(mindst < 2_f64 * radius); // not nicely spread in the room
         }
     }
+    #[test]
+    fn stats() {
+        let check = |it: BlueNoiseIterator| {
+            let set = it.into_set();
+            let stats = set.stats();
+            assert_eq!(stats.samples, set.len());
+            let rejected = stats.rejected_outside + stats.rejected_too_close;
+            assert_eq!(stats.candidates - rejected, set.len() - 1);
+            // in the end, every sample is retired after `k_abort` rejections
+            assert_eq!(stats.retired, set.len());
+            assert!(rejected >= 30 * set.len());
+            assert!(stats.rejected_outside > 0);
+            assert!(stats.rounds > 1);
+            assert_eq!(stats.darts, 0);
+        };
+        check(blue_noise_iter(vec![30., 20.], 1., 30));
+        #[cfg(feature = "rayon")]
+        check(blue_noise_iter(vec![30., 20.], 1., 30).parallel_candidates());
+        let mut points = blue_noise(vec![30., 20.], 1., 30);
+        points.truncate(10);
+        let mut it = BlueNoiseIterator::new(vec![30., 20.], 1., 30);
+        for point in points {
+            it.seed(point);
+        }
+        it.exhaust();
+        assert!(it.stats().darts >= 30);
+        assert_eq!(it.stats().samples, it.samples.len());
+    }
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_candidates() {
+        let samples: Vec<Point> = blue_noise_iter(vec![20., 20., 20.], 3., 60)
+            .parallel_candidates()
+            .collect();
+        assert!(samples.len() > 50);
+        for (i, s1) in samples.iter().enumerate() {
+            for s2 in samples.iter().skip(i + 1) {
+                assert!(super::BackgroundGrid::dst_sqr(s1, s2) >= 9.);
+            }
+        }
+    }
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn bump_allocated() {
+        let bump = bumpalo::Bump::new();
+        let samples = blue_noise_in(&bump, vec![20., 30.], 2., 30);
+        assert!(samples.len() > 10);
+        for (i, s1) in samples.iter().enumerate() {
+            for s2 in samples.iter().skip(i + 1) {
+                assert!(super::BackgroundGrid::dst_sqr(s1, s2) >= 4.);
+            }
+        }
+    }
     fn get_image(radius: f64, size: usize) -> Vec<Vec<bool>> {
         let samples = blue_noise(vec![size as f64, size as f64], radius, 30);
         let mut image = vec![vec![false; size]; size];
@@ -376,13 +1132,12 @@ mod tests {
                             if dx == 0 && dy == 0 {
                                 continue;
                             }
-                            image.get((y + dy) as usize).map(|line| {
-                                if let Some(&true) = line.get((x + dx) as usize) {
-                                    // the -1 is to accomodate for rounding
-                                    // errors
-                                    assert!(dx * dx + dy * dy >= (radius - 1) * (radius - 1));
-                                }
-                            });
+                            if let Some(line) = image.get((y + dy) as usize)
+                                && let Some(&true) = line.get((x + dx) as usize)
+                            {
+                                // the -1 is to accomodate for rounding errors
+                                assert!(dx * dx + dy * dy >= (radius - 1) * (radius - 1));
+                            }
                         }
                     }
                 }
@@ -0,0 +1,302 @@
+//! Blue noise threshold textures for ordered dithering.
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The standard deviation of the Gaussian filter in pixels, as proposed by
+/// Ulichney.
+const SIGMA: f64 = 1.5;
+
+/// A tileable threshold texture, also known as blue noise mask: comparing
+/// the values of an image against it dithers the image with blue noise.
+///
+/// Every pixel has a distinct rank, and the pixels with a rank below any
+/// `n` form a blue noise pattern of `n` dots, which also wraps around the
+/// borders.
+///
+/// ```
+/// use bluenoisers::DitherMask;
+///
+/// let mask = DitherMask::void_and_cluster(16, 16, 7);
+/// let thresholds = mask.to_u8();
+/// // dither a mid gray
+/// let dots = thresholds.iter().filter(|t| **t < 128).count();
+/// assert_eq!(dots, 16 * 16 / 2);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DitherMask {
+    width: usize,
+    height: usize,
+    ranks: Vec<u32>,
+}
+
+impl DitherMask {
+    /// Generates a mask of `width` × `height` pixels with Ulichney's
+    /// void-and-cluster method. The same `seed` yields the same mask.
+    ///
+    /// This takes time quadratic in the number of pixels, about a second for
+    /// 128 × 128 pixels in a release build.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mask is empty or has more than 2³² pixels.
+    pub fn void_and_cluster(width: usize, height: usize, seed: u64) -> DitherMask {
+        let count = width
+            .checked_mul(height)
+            .filter(|count| *count > 0 && *count <= u32::MAX as usize)
+            .expect("the mask must have between one and 2³² pixels");
+        let mut energy = Energy::new(width, height);
+        let mut ones = vec![false; count];
+
+        // a random initial pattern of a tenth of the pixels
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let initial = (count / 10).max(1);
+        let mut placed = 0;
+        while placed < initial {
+            let pixel = rng.gen_range(0..count);
+            if !ones[pixel] {
+                ones[pixel] = true;
+                energy.toggle(pixel, 1_f64);
+                placed += 1;
+            }
+        }
+        // move dots from the tightest clusters to the largest voids until
+        // that doesn't change anything anymore
+        loop {
+            let cluster = energy.tightest_cluster(&ones);
+            ones[cluster] = false;
+            energy.toggle(cluster, -1_f64);
+            let void = energy.largest_void(&ones);
+            ones[void] = true;
+            energy.toggle(void, 1_f64);
+            if void == cluster {
+                break;
+            }
+        }
+
+        let mut ranks = vec![0_u32; count];
+        // the dots of the initial pattern get the ranks below it, removing
+        // the tightest clusters first
+        let mut remaining = ones.clone();
+        let mut remaining_energy = energy.clone();
+        for rank in (0..initial).rev() {
+            let cluster = remaining_energy.tightest_cluster(&remaining);
+            remaining[cluster] = false;
+            remaining_energy.toggle(cluster, -1_f64);
+            ranks[cluster] = rank as u32;
+        }
+        // and the other pixels the ranks above it, filling the largest
+        // voids first; since the energy of the zeros is the total energy
+        // minus that of the ones, this also covers the second half, where
+        // the ones are the majority
+        for rank in initial..count {
+            let void = energy.largest_void(&ones);
+            ones[void] = true;
+            energy.toggle(void, 1_f64);
+            ranks[void] = rank as u32;
+        }
+        DitherMask {
+            width,
+            height,
+            ranks,
+        }
+    }
+
+    /// The width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The rank of each pixel, row by row, from `0` to the number of pixels
+    /// minus one.
+    pub fn ranks(&self) -> &[u32] {
+        &self.ranks
+    }
+
+    /// The thresholds in `[0, 1)`, row by row, each rank at the center of
+    /// its interval.
+    pub fn to_f32(&self) -> Vec<f32> {
+        let count = self.ranks.len() as f64;
+        self.ranks
+            .iter()
+            .map(|rank| ((*rank as f64 + 0.5) / count) as f32)
+            .collect()
+    }
+
+    /// The thresholds as 8 bit values, row by row, with all values equally
+    /// often if the number of pixels is a multiple of 256.
+    pub fn to_u8(&self) -> Vec<u8> {
+        let count = self.ranks.len() as u64;
+        self.ranks
+            .iter()
+            .map(|rank| (*rank as u64 * 256 / count) as u8)
+            .collect()
+    }
+
+    /// The thresholds as 16 bit values, row by row, with all values equally
+    /// often if the number of pixels is a multiple of 65536.
+    pub fn to_u16(&self) -> Vec<u16> {
+        let count = self.ranks.len() as u64;
+        self.ranks
+            .iter()
+            .map(|rank| (*rank as u64 * 65536 / count) as u16)
+            .collect()
+    }
+
+    /// The thresholds of [`to_u8`](Self::to_u8) as an 8 bit grayscale
+    /// image.
+    ///
+    /// Requires the `image` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width or the height doesn't fit in a `u32`.
+    #[cfg(feature = "image")]
+    pub fn to_gray_image(&self) -> image::GrayImage {
+        let (width, height) = self.image_size();
+        image::GrayImage::from_raw(width, height, self.to_u8())
+            .expect("the buffer matches the size")
+    }
+
+    /// The thresholds of [`to_u16`](Self::to_u16) as a 16 bit grayscale
+    /// image, for dithering to more than 256 levels.
+    ///
+    /// Requires the `image` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width or the height doesn't fit in a `u32`.
+    #[cfg(feature = "image")]
+    pub fn to_gray16_image(&self) -> image::ImageBuffer<image::Luma<u16>, Vec<u16>> {
+        let (width, height) = self.image_size();
+        image::ImageBuffer::from_raw(width, height, self.to_u16())
+            .expect("the buffer matches the size")
+    }
+
+    #[cfg(feature = "image")]
+    fn image_size(&self) -> (u32, u32) {
+        let size = |n: usize| u32::try_from(n).expect("the mask is too large for an image");
+        (size(self.width), size(self.height))
+    }
+}
+
+/// The sum of toroidally wrapped Gaussians centered at the dots of a binary
+/// pattern, at every pixel.
+#[derive(Clone, Debug)]
+struct Energy {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+    /// The Gaussian for offsets from `-radius` to `radius` along both axes.
+    kernel: Vec<f64>,
+    radius: usize,
+}
+
+impl Energy {
+    fn new(width: usize, height: usize) -> Energy {
+        let radius = (3_f64 * SIGMA).ceil() as usize;
+        let side = 2 * radius + 1;
+        let kernel = (0..side * side)
+            .map(|i| {
+                let dx = (i % side) as f64 - radius as f64;
+                let dy = (i / side) as f64 - radius as f64;
+                (-(dx * dx + dy * dy) / (2_f64 * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+        Energy {
+            width,
+            height,
+            values: vec![0_f64; width * height],
+            kernel,
+            radius,
+        }
+    }
+
+    /// Adds `sign` times the Gaussian around `pixel`.
+    fn toggle(&mut self, pixel: usize, sign: f64) {
+        let (x, y) = (pixel % self.width, pixel / self.width);
+        let side = 2 * self.radius + 1;
+        for (i, weight) in self.kernel.iter().enumerate() {
+            // shifted by a multiple of the size to stay positive
+            let wx = (x + self.width * side + i % side - self.radius) % self.width;
+            let wy = (y + self.height * side + i / side - self.radius) % self.height;
+            self.values[wy * self.width + wx] += sign * weight;
+        }
+    }
+
+    /// The dot with the highest energy.
+    fn tightest_cluster(&self, ones: &[bool]) -> usize {
+        (0..ones.len())
+            .filter(|pixel| ones[*pixel])
+            .max_by(|a, b| self.values[*a].total_cmp(&self.values[*b]))
+            .expect("there are dots")
+    }
+
+    /// The pixel without dot with the lowest energy.
+    fn largest_void(&self, ones: &[bool]) -> usize {
+        (0..ones.len())
+            .filter(|pixel| !ones[*pixel])
+            .min_by(|a, b| self.values[*a].total_cmp(&self.values[*b]))
+            .expect("there are pixels without dots")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn void_and_cluster() {
+        let mask = DitherMask::void_and_cluster(32, 24, 1);
+        assert_eq!(mask, DitherMask::void_and_cluster(32, 24, 1));
+        assert_ne!(mask, DitherMask::void_and_cluster(32, 24, 2));
+        let mut ranks = mask.ranks().to_vec();
+        ranks.sort_unstable();
+        assert!(ranks.iter().enumerate().all(|(i, rank)| i as u32 == *rank));
+
+        // the dots of every level are spread out, so a dot has no neighbor
+        // at low levels, wrapping around the borders
+        let level: Vec<bool> = mask
+            .ranks()
+            .iter()
+            .map(|r| (*r as usize) < 32 * 24 / 8)
+            .collect();
+        for y in 0..24 {
+            for x in 0..32 {
+                if level[y * 32 + x] {
+                    for (dx, dy) in [(1, 0), (0, 1), (31, 0), (0, 23)] {
+                        assert!(!level[(y + dy) % 24 * 32 + (x + dx) % 32]);
+                    }
+                }
+            }
+        }
+
+        let thresholds = mask.to_u8();
+        assert_eq!(thresholds.iter().filter(|t| **t < 64).count(), 32 * 24 / 4);
+        assert_eq!(*mask.to_u16().iter().max().unwrap(), 65450);
+        assert!(mask.to_f32().iter().all(|t| (0. ..1.).contains(t)));
+        assert_eq!(DitherMask::void_and_cluster(1, 1, 0).ranks(), [0]);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn images() {
+        let mask = DitherMask::void_and_cluster(20, 10, 3);
+        let gray = mask.to_gray_image();
+        assert_eq!(gray.dimensions(), (20, 10));
+        let rank = mask.ranks()[3 * 20 + 7];
+        assert_eq!(gray.get_pixel(7, 3).0, [(rank * 256 / 200) as u8]);
+        let gray16 = mask.to_gray16_image();
+        assert_eq!(gray16.dimensions(), (20, 10));
+        assert_eq!(gray16.into_raw(), mask.to_u16());
+    }
+}
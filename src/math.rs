@@ -0,0 +1,49 @@
+//! The floating point functions of `std` which are missing in `core`,
+//! implemented with `libm` for `no_std` builds.
+
+/// Methods with the names of the inherent ones of `f64` in `std`, so the
+/// code calling them is the same with and without `std`.
+pub(crate) trait Float {
+    fn ceil(self) -> Self;
+    fn cos(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn round(self) -> Self;
+    fn sin(self) -> Self;
+    fn sqrt(self) -> Self;
+}
+
+impl Float for f64 {
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn cos(self) -> f64 {
+        libm::cos(self)
+    }
+
+    fn exp(self) -> f64 {
+        libm::exp(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn powf(self, n: f64) -> f64 {
+        libm::pow(self, n)
+    }
+
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+
+    fn sin(self) -> f64 {
+        libm::sin(self)
+    }
+
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+}
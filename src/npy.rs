@@ -0,0 +1,208 @@
+//! NumPy `.npy` and `.npz` files of point sets.
+
+use crate::PoissonDiskSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Writes `samples` as a NumPy `.npy` file to `path`, holding a C ordered
+/// `float64` array with one row per sample, which `numpy.load` reads.
+///
+/// ```no_run
+/// let samples = bluenoisers::blue_noise(vec![10., 10.], 1., 30);
+/// bluenoisers::export_npy("samples.npy", &samples).unwrap();
+/// ```
+///
+/// An empty set gives an array of shape `(0, 0)`.
+///
+/// # Panics
+///
+/// Panics if the samples don't all have the same number of coordinates.
+pub fn export_npy<P: AsRef<[f64]>>(path: impl AsRef<Path>, samples: &[P]) -> io::Result<()> {
+    fs::write(path, npy(samples))
+}
+
+/// Writes several named arrays of samples as a NumPy `.npz` archive to
+/// `path`, e.g. one per class of samples. The arrays are stored as in
+/// [`export_npy`] under their name, without compression.
+///
+/// ```no_run
+/// let coarse = bluenoisers::blue_noise(vec![10., 10.], 2., 30);
+/// let fine = bluenoisers::blue_noise(vec![10., 10.], 1., 30);
+/// bluenoisers::export_npz("levels.npz", &[("coarse", &coarse), ("fine", &fine)]).unwrap();
+/// ```
+///
+/// In Python, `numpy.load("levels.npz")["fine"]` is then the second array.
+///
+/// # Panics
+///
+/// Panics if the samples of an array don't all have the same number of
+/// coordinates or if the archive would be larger than 4 GiB.
+pub fn export_npz<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    arrays: &[(&str, &[P])],
+) -> io::Result<()> {
+    let files: Vec<(String, Vec<u8>)> = arrays
+        .iter()
+        .map(|(name, samples)| (format!("{name}.npy"), npy(samples)))
+        .collect();
+    fs::write(path, zip(&files))
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples as a NumPy `.npy` file, see [`export_npy`].
+    pub fn export_npy(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        export_npy(path, self.samples())
+    }
+}
+
+/// The bytes of an `.npy` file, in version 1.0 of the format.
+fn npy<P: AsRef<[f64]>>(samples: &[P]) -> Vec<u8> {
+    let dimension = samples.first().map_or(0, |s| s.as_ref().len());
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {dimension}), }}",
+        samples.len()
+    );
+    // the data starts at a multiple of 64 bytes after the magic string, the
+    // version and the length of the header, and the header ends with a
+    // newline
+    let prefix = 6 + 2 + 2;
+    let padding = (64 - (prefix + header.len() + 1) % 64) % 64;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+    let mut bytes = Vec::with_capacity(prefix + header.len() + 8 * dimension * samples.len());
+    bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    for sample in samples {
+        let sample = sample.as_ref();
+        assert_eq!(
+            sample.len(),
+            dimension,
+            "all samples must have the same number of coordinates"
+        );
+        for x in sample {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// The bytes of a ZIP archive storing `files` without compression.
+fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    // 1980-01-01, the earliest date ZIP can store, in MS-DOS format
+    const DATE: u16 = 0x21;
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = u32::try_from(archive.len()).expect("the archive is too large");
+        let size = u32::try_from(data.len()).expect("the archive is too large");
+        // the fields shared by the local header and the central directory:
+        // version needed, flags, method, time, date, checksum, sizes and
+        // the length of the name and of the extra field
+        let mut common = Vec::new();
+        for value in [20_u16, 0, 0, 0, DATE] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc32(data), size, size] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [name.len() as u16, 0] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+
+        archive.extend_from_slice(&0x04034b50_u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x02014b50_u32.to_le_bytes());
+        // version made by
+        directory.extend_from_slice(&20_u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        // comment length, disk, internal and external attributes
+        for value in [0_u16, 0, 0, 0, 0] {
+            directory.extend_from_slice(&value.to_le_bytes());
+        }
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let offset = u32::try_from(archive.len()).expect("the archive is too large");
+    let count = files.len() as u16;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x06054b50_u32.to_le_bytes());
+    for value in [0, 0, count, count] {
+        archive.extend_from_slice(&value.to_le_bytes());
+    }
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&offset.to_le_bytes());
+    // comment length
+    archive.extend_from_slice(&0_u16.to_le_bytes());
+    archive
+}
+
+/// The CRC-32 checksum of `data` as used by ZIP.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npy() {
+        let bytes = super::npy(&[[1., 2., 3.], [4., 5., 6.5]]);
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_length) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }"));
+        assert!(header.ends_with(" \n"));
+        let data = &bytes[10 + header_length..];
+        assert_eq!(data.len(), 6 * 8);
+        assert_eq!(f64::from_le_bytes(data[40..].try_into().unwrap()), 6.5);
+        let empty: [[f64; 2]; 0] = [];
+        assert!(String::from_utf8_lossy(&super::npy(&empty)).contains("'shape': (0, 0)"));
+    }
+
+    #[test]
+    fn npz() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("levels.npz");
+        let coarse = [[1., 2.]];
+        let fine = [[0.5, 0.5], [1.5, 0.5]];
+        export_npz(&path, &[("coarse", &coarse[..]), ("fine", &fine[..])]).unwrap();
+        let archive = fs::read(&path).unwrap();
+        let word = |at: usize| u32::from_le_bytes(archive[at..at + 4].try_into().unwrap());
+        assert_eq!(word(0), 0x04034b50);
+        // the end of the central directory points to its start
+        let end = archive.len() - 22;
+        assert_eq!(word(end), 0x06054b50);
+        assert_eq!(
+            u16::from_le_bytes([archive[end + 10], archive[end + 11]]),
+            2
+        );
+        let directory = word(end + 16) as usize;
+        assert_eq!(word(directory), 0x02014b50);
+        // the second local file starts where the first one ends
+        let first = super::npy(&coarse);
+        let second = 30 + "coarse.npy".len() + first.len();
+        assert_eq!(word(second), 0x04034b50);
+        assert_eq!(&archive[second + 30..second + 38], b"fine.npy");
+        assert_eq!(&archive[second + 38..directory], &super::npy(&fine)[..]);
+        assert_eq!(word(second + 14), crc32(&super::npy(&fine)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Wavefront OBJ files of 2D and 3D point sets.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// What [`export_obj`] writes for every sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjPrimitive {
+    /// Just a vertex.
+    Vertices,
+    /// A square face of edge length `size` parallel to the xy plane, for
+    /// viewers which don't show bare vertices.
+    Quads {
+        /// The edge length.
+        size: f64,
+    },
+    /// A line of length `size` along each axis, crossing at the sample.
+    Crosses {
+        /// The length of the lines.
+        size: f64,
+    },
+}
+
+/// Writes the 2D or 3D `samples` as a Wavefront OBJ file to `path`. 2D
+/// samples get a z coordinate of zero.
+///
+/// ```no_run
+/// use bluenoisers::{ObjPrimitive, export_obj};
+///
+/// let samples = bluenoisers::blue_noise(vec![10., 10., 10.], 1., 30);
+/// export_obj("samples.obj", &samples, ObjPrimitive::Crosses { size: 0.2 }).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if a sample has less than two or more than three coordinates.
+pub fn export_obj<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    primitive: ObjPrimitive,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_obj(&mut writer, samples, primitive)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D or 3D set as a Wavefront OBJ file, see
+    /// [`export_obj`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2- or 3-dimensional.
+    pub fn export_obj(&self, path: impl AsRef<Path>, primitive: ObjPrimitive) -> io::Result<()> {
+        assert!(
+            (2..=3).contains(&self.dimensions().len()),
+            "only 2D and 3D sets can be exported"
+        );
+        export_obj(path, self.samples(), primitive)
+    }
+}
+
+fn write_obj<P: AsRef<[f64]>, W: Write>(
+    writer: &mut W,
+    samples: &[P],
+    primitive: ObjPrimitive,
+) -> io::Result<()> {
+    writeln!(writer, "# {} samples", samples.len())?;
+    // OBJ indices start at one
+    let mut next = 1_usize;
+    for sample in samples {
+        let sample = sample.as_ref();
+        assert!(
+            (2..=3).contains(&sample.len()),
+            "only 2D and 3D samples can be exported"
+        );
+        let position = [
+            sample[0],
+            sample[1],
+            sample.get(2).copied().unwrap_or(0_f64),
+        ];
+        let vertex = |writer: &mut W, offset: [f64; 3]| {
+            writeln!(
+                writer,
+                "v {} {} {}",
+                position[0] + offset[0],
+                position[1] + offset[1],
+                position[2] + offset[2]
+            )
+        };
+        match primitive {
+            ObjPrimitive::Vertices => {
+                vertex(writer, [0_f64; 3])?;
+                next += 1;
+            }
+            ObjPrimitive::Quads { size } => {
+                let h = size / 2_f64;
+                for offset in [
+                    [-h, -h, 0_f64],
+                    [h, -h, 0_f64],
+                    [h, h, 0_f64],
+                    [-h, h, 0_f64],
+                ] {
+                    vertex(writer, offset)?;
+                }
+                writeln!(writer, "f {} {} {} {}", next, next + 1, next + 2, next + 3)?;
+                next += 4;
+            }
+            ObjPrimitive::Crosses { size } => {
+                let h = size / 2_f64;
+                for axis in 0..sample.len() {
+                    let mut offset = [0_f64; 3];
+                    offset[axis] = -h;
+                    vertex(writer, offset)?;
+                    offset[axis] = h;
+                    vertex(writer, offset)?;
+                    writeln!(writer, "l {} {}", next, next + 1)?;
+                    next += 2;
+                }
+            }
+        }
+    }
+    if primitive == ObjPrimitive::Vertices && !samples.is_empty() {
+        // a point element, so that importers keep the vertices
+        write!(writer, "p")?;
+        for index in 1..next {
+            write!(writer, " {index}")?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_obj() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.obj");
+        let lines = |prefix: &str| {
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .filter(|line| line.starts_with(prefix))
+                .map(str::to_string)
+                .collect::<Vec<String>>()
+        };
+        let set = PoissonDiskSet::new(vec![5., 5., 5.], 1., 30);
+        set.export_obj(&path, ObjPrimitive::Vertices).unwrap();
+        let vertices = lines("v ");
+        assert_eq!(vertices.len(), set.len());
+        let first = &set.samples()[0];
+        assert_eq!(
+            vertices[0],
+            format!("v {} {} {}", first[0], first[1], first[2])
+        );
+        assert_eq!(lines("p ")[0].split(' ').count(), set.len() + 1);
+
+        set.export_obj(&path, ObjPrimitive::Crosses { size: 0.2 })
+            .unwrap();
+        assert_eq!(lines("v ").len(), 6 * set.len());
+        assert_eq!(lines("l ").len(), 3 * set.len());
+        assert_eq!(
+            lines("l ").last().unwrap(),
+            &format!("l {} {}", 6 * set.len() - 1, 6 * set.len())
+        );
+
+        super::export_obj(&path, &[[1., 2.]], ObjPrimitive::Quads { size: 1. }).unwrap();
+        assert_eq!(
+            lines("v "),
+            ["v 0.5 1.5 0", "v 1.5 1.5 0", "v 1.5 2.5 0", "v 0.5 2.5 0"]
+        );
+        assert_eq!(lines("f "), ["f 1 2 3 4"]);
+    }
+}
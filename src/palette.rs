@@ -0,0 +1,145 @@
+//! Color palettes whose colors are as distinct as possible.
+
+use crate::BlueNoiseConfig;
+use crate::grid::BackgroundGrid;
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use alloc::vec::Vec;
+use rand::Rng;
+
+/// The bounding box of the sRGB gamut in Oklab, `a` and `b` shifted to start
+/// at zero.
+const OKLAB_SIZE: [f64; 3] = [1., 0.52, 0.52];
+const OKLAB_OFFSET: [f64; 3] = [0., -0.24, -0.32];
+
+/// Generates a palette of `count` colors which are as far apart as blue
+/// noise allows in Oklab, a perceptual color space, as gamma encoded sRGB in
+/// `[0, 1]`, e.g. for the lines of a plot or the labels of a segmentation.
+///
+/// The colors are a Poisson disk set in the part of the bounding box of the
+/// sRGB gamut which is inside of the gamut, with the largest minimal distance
+/// that gives at least `count` colors, found by bisection. Of the surplus,
+/// the colors closest to another one are removed. So the palette is not
+/// ordered, and unlike a palette of maximal distance, it has no preference
+/// for the corners of the gamut, like pure red or black.
+///
+/// Each step of the bisection generates with the same seed, drawn once from
+/// `rng`, so a seeded `rng` gives the same palette.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let palette = bluenoisers::color_palette(8, &mut rng);
+/// assert_eq!(palette.len(), 8);
+/// let bytes: Vec<[u8; 3]> = palette
+///     .iter()
+///     .map(|color| color.map(|x| (x * 255.).round() as u8))
+///     .collect();
+/// ```
+pub fn color_palette<R: Rng>(count: usize, rng: &mut R) -> Vec<[f64; 3]> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let seed = rng.next_u64();
+    let in_gamut = |min_distance: f64| -> Vec<[f64; 3]> {
+        BlueNoiseConfig::new(OKLAB_SIZE.to_vec(), min_distance)
+            .seed(seed)
+            .generate()
+            .iter()
+            .filter_map(|lab| {
+                let lab: [f64; 3] = core::array::from_fn(|i| lab[i] + OKLAB_OFFSET[i]);
+                let rgb = oklab_to_linear_srgb(lab);
+                rgb.iter()
+                    .all(|x| (0_f64..=1_f64).contains(x))
+                    .then_some(lab)
+            })
+            .collect()
+    };
+    // at least `count` colors with `lower`, less with `upper`
+    let mut upper = 2_f64;
+    let mut lower = upper / 2_f64;
+    let mut colors = in_gamut(lower);
+    while colors.len() < count {
+        upper = lower;
+        lower /= 2_f64;
+        colors = in_gamut(lower);
+    }
+    for _ in 0..30 {
+        if colors.len() == count {
+            break;
+        }
+        let middle = (lower + upper) / 2_f64;
+        let candidate = in_gamut(middle);
+        if candidate.len() < count {
+            upper = middle;
+        } else {
+            lower = middle;
+            colors = candidate;
+        }
+    }
+    while colors.len() > count {
+        let closest = closest_pair(&colors);
+        colors.swap_remove(closest);
+    }
+    colors
+        .iter()
+        .map(|lab| oklab_to_linear_srgb(*lab).map(gamma_encode))
+        .collect()
+}
+
+/// The index of one of the two colors which are closest to each other.
+fn closest_pair(colors: &[[f64; 3]]) -> usize {
+    let mut closest = (f64::INFINITY, 0);
+    for (i, a) in colors.iter().enumerate() {
+        for b in &colors[..i] {
+            let dst_sqr = BackgroundGrid::dst_sqr(a, b);
+            if dst_sqr < closest.0 {
+                closest = (dst_sqr, i);
+            }
+        }
+    }
+    closest.1
+}
+
+/// Converts from Oklab to linear sRGB, see
+/// <https://bottosson.github.io/posts/oklab>.
+fn oklab_to_linear_srgb([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let cube = |x: f64| x * x * x;
+    let l_ = cube(l + 0.396_337_777_4 * a + 0.215_803_757_3 * b);
+    let m_ = cube(l - 0.105_561_345_8 * a - 0.063_854_172_8 * b);
+    let s_ = cube(l - 0.089_484_177_5 * a - 1.291_485_548 * b);
+    [
+        4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+        -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701 * s_,
+    ]
+}
+
+/// The sRGB transfer function.
+fn gamma_encode(x: f64) -> f64 {
+    if x <= 0.003_130_8 {
+        12.92 * x
+    } else {
+        // without rounding above one
+        (1.055 * x.powf(1_f64 / 2.4) - 0.055).min(1_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn palette() {
+        for count in [1, 2, 8, 50] {
+            let generate = || color_palette(count, &mut ChaCha8Rng::seed_from_u64(4));
+            let palette = generate();
+            assert_eq!(palette, generate());
+            assert_eq!(palette.len(), count);
+            assert!(palette.iter().flatten().all(|x| (0. ..=1.).contains(x)));
+        }
+    }
+}
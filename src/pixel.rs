@@ -0,0 +1,387 @@
+//! Optimal patterns of a few samples per pixel.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Above this many samples, [`pixel_pattern`] searches locally instead of
+/// exhaustively.
+const EXHAUSTIVE: usize = 24;
+
+/// Finds the pattern of `count` samples in the unit square, e.g. for
+/// multisample antialiasing or the first samples of a ray tracer, which is
+/// best for tiling the pixels of an image.
+///
+/// The samples have perfect projections: they are on a `count` by `count`
+/// grid like rooks on a chess board which don't attack each other, i.e.
+/// there is exactly one sample in each of `count` equal columns and rows.
+/// Of those patterns, the one whose samples are farthest apart, across the
+/// borders to the neighboring pixels too, is chosen, and of those the one
+/// with the fewest pairs of samples at that distance. The samples are at
+/// the centers of their cells, in order of their columns.
+///
+/// Up to 24 samples, the search is exhaustive, so the pattern is optimal,
+/// and takes at most half a second. Beyond that, it starts from lattices,
+/// where each sample is a fixed number of rows above the one of the previous
+/// column, and swaps rows while that improves the pattern, which is fast but
+/// not optimal. Either way, the search is deterministic, so the same count
+/// always gives the same pattern, which is meant to be computed once, e.g.
+/// for a table, see [`encode_table`](crate::encode_table).
+///
+/// ```
+/// let pattern = bluenoisers::pixel_pattern(8);
+/// // one sample in each column and row of eight
+/// let mut rows: Vec<usize> = pattern.iter().map(|p| (p[1] * 8.) as usize).collect();
+/// rows.sort();
+/// assert!(rows.iter().copied().eq(0..8));
+/// assert_eq!(pattern[3], [0.4375, 0.1875]);
+/// ```
+pub fn pixel_pattern(count: usize) -> Vec<[f64; 2]> {
+    let rows = if count <= EXHAUSTIVE {
+        exhaustive(count)
+    } else {
+        local(count)
+    };
+    let center = |cell: usize| (cell as f64 + 0.5) / count as f64;
+    rows.iter()
+        .enumerate()
+        .map(|(column, row)| [center(column), center(*row)])
+        .collect()
+}
+
+/// A different set of samples in the unit square for every pixel and frame
+/// of a renderer, without storing them.
+///
+/// Every set is the same base pattern, by default the one of
+/// [`pixel_pattern`], rotated or mirrored by one of the eight symmetries of
+/// the square and moved by an offset, wrapping around at the borders. Both
+/// are drawn from a hash of the seed, the pixel and the frame, so a set is
+/// computed independently of all others, e.g. in a shader, and always the
+/// same. Since the moves are isometries of the torus, every set keeps the
+/// distances of the base pattern across the borders of the pixel, and with
+/// the default base also has one sample in each column and row, while the
+/// sets of neighboring pixels and frames are uncorrelated.
+///
+/// ```
+/// use bluenoisers::PixelSamples;
+///
+/// let sets = PixelSamples::new(8, 42);
+/// let first = sets.samples(640, 360, 0);
+/// assert_eq!(first, sets.samples(640, 360, 0));
+/// assert_ne!(first, sets.samples(640, 360, 1));
+/// assert_eq!(first[5], sets.sample(640, 360, 0, 5));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PixelSamples {
+    pattern: Vec<[f64; 2]>,
+    seed: u64,
+}
+
+impl PixelSamples {
+    /// Sets of `count` samples each, moves of [`pixel_pattern`]. The same
+    /// `seed` yields the same sets.
+    pub fn new(count: usize, seed: u64) -> PixelSamples {
+        PixelSamples::from_pattern(pixel_pattern(count), seed)
+    }
+
+    /// Sets which are moves of `pattern`, whose samples have to be in
+    /// `[0, 1)²`, e.g. blue noise generated with
+    /// [`wrap`](crate::BlueNoiseConfig::wrap) and scaled to the unit square.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample of `pattern` is outside of `[0, 1)²`.
+    pub fn from_pattern(pattern: Vec<[f64; 2]>, seed: u64) -> PixelSamples {
+        assert!(
+            pattern.iter().flatten().all(|x| (0_f64..1_f64).contains(x)),
+            "the samples must be in [0, 1)²"
+        );
+        PixelSamples { pattern, seed }
+    }
+
+    /// The base pattern.
+    pub fn pattern(&self) -> &[[f64; 2]] {
+        &self.pattern
+    }
+
+    /// The number of samples per set.
+    pub fn len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    /// Whether the sets have no samples.
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// The set of pixel `(x, y)` in `frame`, in `[0, 1)²` relative to the
+    /// pixel.
+    pub fn samples(&self, x: u32, y: u32, frame: u32) -> Vec<[f64; 2]> {
+        let moved = self.moved(x, y, frame);
+        self.pattern.iter().map(|sample| moved(*sample)).collect()
+    }
+
+    /// The sample `index` of the set of pixel `(x, y)` in `frame`, without
+    /// computing the others.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`len`](PixelSamples::len).
+    pub fn sample(&self, x: u32, y: u32, frame: u32, index: usize) -> [f64; 2] {
+        self.moved(x, y, frame)(self.pattern[index])
+    }
+
+    /// The move of the pattern of pixel `(x, y)` in `frame`.
+    fn moved(&self, x: u32, y: u32, frame: u32) -> impl Fn([f64; 2]) -> [f64; 2] + use<> {
+        let hash = mix(mix(mix(self.seed, x as u64), y as u64), frame as u64);
+        // the upper 53 bits as a number in [0, 1)
+        let unit = |bits: u64| (bits >> 11) as f64 / (1_u64 << 53) as f64;
+        let offset = [unit(mix(hash, 1)), unit(mix(hash, 2))];
+        move |[mut u, mut v]| {
+            if hash & 1 != 0 {
+                core::mem::swap(&mut u, &mut v);
+            }
+            if hash & 2 != 0 {
+                u = 1_f64 - u;
+            }
+            if hash & 4 != 0 {
+                v = 1_f64 - v;
+            }
+            let wrapped = |x: f64, offset: f64| {
+                let x = x + offset;
+                // a sample at 0 is at 1 after mirroring
+                if x >= 1_f64 { x - 1_f64 } else { x }
+            };
+            [wrapped(u, offset[0]), wrapped(v, offset[1])]
+        }
+    }
+}
+
+/// How good the rows of the samples of the columns are: the smallest
+/// squared distance in cells over the torus and how many pairs are that
+/// close, negated so that larger is better.
+fn score(rows: &[usize], count: usize) -> (usize, isize) {
+    let mut closest = (usize::MAX, 0_isize);
+    for i in 0..rows.len() {
+        for j in 0..i {
+            let dst_sqr = torus_dst_sqr(i, j, rows[i], rows[j], count);
+            if dst_sqr < closest.0 {
+                closest = (dst_sqr, -1);
+            } else if dst_sqr == closest.0 {
+                closest.1 -= 1;
+            }
+        }
+    }
+    closest
+}
+
+fn torus_dst_sqr(
+    column_a: usize,
+    column_b: usize,
+    row_a: usize,
+    row_b: usize,
+    count: usize,
+) -> usize {
+    let wrapped = |a: usize, b: usize| {
+        let d = a.abs_diff(b);
+        d.min(count - d)
+    };
+    let dx = wrapped(column_a, column_b);
+    let dy = wrapped(row_a, row_b);
+    dx * dx + dy * dy
+}
+
+/// The best rows by branch and bound over all permutations. Since the
+/// pattern tiles, it can be moved so the first sample is in the first row.
+fn exhaustive(count: usize) -> Vec<usize> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let mut best = local(count);
+    let mut best_score = score(&best, count);
+    let mut rows = vec![0];
+    let mut used = vec![false; count];
+    used[0] = true;
+    // the score of the samples placed so far, which only gets worse
+    let mut scores = vec![(usize::MAX, 0_isize)];
+    branch(
+        count,
+        &mut rows,
+        &mut used,
+        &mut scores,
+        &mut best,
+        &mut best_score,
+    );
+    best
+}
+
+fn branch(
+    count: usize,
+    rows: &mut Vec<usize>,
+    used: &mut Vec<bool>,
+    scores: &mut Vec<(usize, isize)>,
+    best: &mut Vec<usize>,
+    best_score: &mut (usize, isize),
+) {
+    let column = rows.len();
+    if column == count {
+        let score = *scores.last().unwrap();
+        if score > *best_score {
+            *best_score = score;
+            best.clone_from(rows);
+        }
+        return;
+    }
+    for row in 0..count {
+        if used[row] {
+            continue;
+        }
+        let mut score = *scores.last().unwrap();
+        for (other, other_row) in rows.iter().enumerate() {
+            let dst_sqr = torus_dst_sqr(column, other, row, *other_row, count);
+            if dst_sqr < score.0 {
+                score = (dst_sqr, -1);
+            } else if dst_sqr == score.0 {
+                score.1 -= 1;
+            }
+        }
+        if score <= *best_score {
+            continue;
+        }
+        rows.push(row);
+        used[row] = true;
+        scores.push(score);
+        branch(count, rows, used, scores, best, best_score);
+        scores.pop();
+        used[row] = false;
+        rows.pop();
+    }
+}
+
+/// Good rows by local search: starting from each lattice, where the sample
+/// of a column is a fixed number of rows above the one of the previous
+/// column, swaps the rows of two columns while that improves the pattern.
+fn local(count: usize) -> Vec<usize> {
+    let mut best: Vec<usize> = (0..count).collect();
+    let mut best_score = score(&best, count);
+    for step in 1..count.max(1) {
+        let mut rows: Vec<usize> = (0..count).map(|column| column * step % count).collect();
+        let mut seen = vec![false; count];
+        if !rows
+            .iter()
+            .all(|row| !core::mem::replace(&mut seen[*row], true))
+        {
+            // not a permutation
+            continue;
+        }
+        let mut current = score(&rows, count);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..count {
+                for j in 0..i {
+                    rows.swap(i, j);
+                    let swapped = score(&rows, count);
+                    if swapped > current {
+                        current = swapped;
+                        improved = true;
+                    } else {
+                        rows.swap(i, j);
+                    }
+                }
+            }
+        }
+        if current > best_score {
+            best_score = current;
+            best = rows;
+        }
+    }
+    best
+}
+
+/// Combines a seed with a value into a new seed, with SplitMix64.
+fn mix(seed: u64, value: u64) -> u64 {
+    let mut z = (seed ^ value.rotate_left(32)).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The best score of all permutations of `rows[fixed..]`.
+    fn brute_force(rows: &mut Vec<usize>, fixed: usize) -> (usize, isize) {
+        if fixed == rows.len() {
+            return score(rows, rows.len());
+        }
+        (fixed..rows.len())
+            .map(|i| {
+                rows.swap(fixed, i);
+                let best = brute_force(rows, fixed + 1);
+                rows.swap(fixed, i);
+                best
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn pixel() {
+        for count in [0, 1, 2, 3, 4, 5, 6, 7, 8, 16, 30] {
+            let pattern = pixel_pattern(count);
+            assert_eq!(pattern.len(), count);
+            let rows: Vec<usize> = pattern
+                .iter()
+                .enumerate()
+                .map(|(column, p)| {
+                    assert_eq!(p[0], (column as f64 + 0.5) / count as f64);
+                    (p[1] * count as f64) as usize
+                })
+                .collect();
+            let mut sorted = rows.clone();
+            sorted.sort();
+            assert!(sorted.iter().copied().eq(0..count));
+            if count <= 8 {
+                let best = brute_force(&mut (0..count).collect(), 0);
+                assert_eq!(score(&rows, count), best);
+            }
+        }
+    }
+    #[test]
+    fn pixel_samples() {
+        let torus_dst_sqr = |a: &[f64; 2], b: &[f64; 2]| {
+            let wrapped = |d: f64| d.abs().min(1. - d.abs());
+            wrapped(a[0] - b[0]).powi(2) + wrapped(a[1] - b[1]).powi(2)
+        };
+        let closest = |set: &[[f64; 2]]| {
+            let mut closest = f64::INFINITY;
+            for (i, a) in set.iter().enumerate() {
+                for b in &set[..i] {
+                    closest = closest.min(torus_dst_sqr(a, b));
+                }
+            }
+            closest
+        };
+        let sets = PixelSamples::new(16, 7);
+        let base = closest(sets.pattern());
+        let mut seen = Vec::new();
+        for key in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1), (u32::MAX, 3, 9)] {
+            let set = sets.samples(key.0, key.1, key.2);
+            assert_eq!(set, PixelSamples::new(16, 7).samples(key.0, key.1, key.2));
+            assert!(set.iter().flatten().all(|x| (0. ..1.).contains(x)));
+            assert!((closest(&set) - base).abs() < 1e-12);
+            for axis in 0..2 {
+                let mut strata: Vec<usize> = set.iter().map(|p| (p[axis] * 16.) as usize).collect();
+                strata.sort();
+                assert!(strata.iter().copied().eq(0..16));
+            }
+            assert!(!seen.contains(&set));
+            seen.push(set);
+        }
+        assert_ne!(
+            sets.samples(0, 0, 0),
+            PixelSamples::new(16, 8).samples(0, 0, 0)
+        );
+    }
+}
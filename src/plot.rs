@@ -0,0 +1,227 @@
+//! Quick looks at point sets and their statistics.
+
+use crate::PoissonDiskSet;
+use crate::analysis::{RadialDistribution, Spectrum};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::fmt;
+use std::path::Path;
+
+/// Errors when plotting.
+#[derive(Debug)]
+pub struct PlotError(String);
+
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "plotting failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for PlotError {}
+
+type DrawResult<DB> = Result<(), DrawingAreaErrorKind<<DB as DrawingBackend>::ErrorType>>;
+
+/// Something which can be drawn with any backend.
+trait Figure {
+    /// The size of the image in pixels.
+    fn size(&self) -> (u32, u32);
+
+    fn draw<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) -> DrawResult<DB>;
+}
+
+/// Draws `figure` into an SVG file if `path` ends with `.svg` and into a
+/// bitmap otherwise, whose format is chosen by the extension.
+fn render<F: Figure>(figure: &F, path: &Path) -> Result<(), PlotError> {
+    let error = |e: &dyn fmt::Display| PlotError(e.to_string());
+    let svg = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+    if svg {
+        let area = SVGBackend::new(path, figure.size()).into_drawing_area();
+        area.fill(&WHITE).map_err(|e| error(&e))?;
+        figure.draw(&area).map_err(|e| error(&e))?;
+        area.present().map_err(|e| error(&e))
+    } else {
+        let area = BitMapBackend::new(path, figure.size()).into_drawing_area();
+        area.fill(&WHITE).map_err(|e| error(&e))?;
+        figure.draw(&area).map_err(|e| error(&e))?;
+        area.present().map_err(|e| error(&e))
+    }
+}
+
+/// The longer side of plots of domains and spectra, in pixels.
+const SIZE: u32 = 800;
+
+struct Points<'a, P> {
+    points: &'a [P],
+    dimensions: [f64; 2],
+}
+
+impl<P: AsRef<[f64]>> Figure for Points<'_, P> {
+    fn size(&self) -> (u32, u32) {
+        let [width, height] = self.dimensions;
+        if width >= height {
+            (SIZE, ((SIZE as f64 * height / width).ceil() as u32).max(1))
+        } else {
+            (((SIZE as f64 * width / height).ceil() as u32).max(1), SIZE)
+        }
+    }
+
+    fn draw<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        let (width, height) = self.size();
+        let scale = [
+            width as f64 / self.dimensions[0],
+            height as f64 / self.dimensions[1],
+        ];
+        for point in self.points {
+            let point = point.as_ref();
+            let pixel = ((point[0] * scale[0]) as i32, (point[1] * scale[1]) as i32);
+            area.draw(&Circle::new(pixel, 2, BLACK.filled()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Plots the 2D `points` in a domain of size `dimensions` as dots, with the
+/// origin at the top left. The file is SVG if `path` ends with `.svg` and
+/// PNG otherwise.
+///
+/// ```no_run
+/// let samples = bluenoisers::blue_noise(vec![100., 50.], 1., 30);
+/// bluenoisers::plot_points(&samples, [100., 50.], "samples.png").unwrap();
+/// ```
+///
+/// Requires the `plot` feature.
+///
+/// # Panics
+///
+/// Panics if a point has less than two coordinates.
+pub fn plot_points<P: AsRef<[f64]>>(
+    points: &[P],
+    dimensions: [f64; 2],
+    path: impl AsRef<Path>,
+) -> Result<(), PlotError> {
+    render(&Points { points, dimensions }, path.as_ref())
+}
+
+impl PoissonDiskSet {
+    /// Plots the samples of a 2D set, see [`plot_points`].
+    ///
+    /// Requires the `plot` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2-dimensional.
+    pub fn plot(&self, path: impl AsRef<Path>) -> Result<(), PlotError> {
+        let dimensions: [f64; 2] = self
+            .dimensions()
+            .try_into()
+            .expect("only 2D sets can be plotted");
+        plot_points(self.samples(), dimensions, path)
+    }
+}
+
+impl Figure for Spectrum {
+    fn size(&self) -> (u32, u32) {
+        let side = self.size.max(1) as u32;
+        let side = side * (SIZE / side).max(1);
+        (side, side)
+    }
+
+    fn draw<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        let scale = (self.size().0 / self.size.max(1) as u32) as i32;
+        for (i, value) in self.image().iter().enumerate() {
+            let (x, y) = ((i % self.size) as i32, (i / self.size) as i32);
+            let gray = (value * 255_f64).round() as u8;
+            area.draw(&Rectangle::new(
+                [(x * scale, y * scale), ((x + 1) * scale, (y + 1) * scale)],
+                RGBColor(gray, gray, gray).filled(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// Plots `spectrum` as a grayscale image with the DC term in the middle, see
+/// [`Spectrum::image`]. The file is SVG if `path` ends with `.svg` and PNG
+/// otherwise.
+///
+/// Requires the `plot` feature.
+pub fn plot_spectrum(spectrum: &Spectrum, path: impl AsRef<Path>) -> Result<(), PlotError> {
+    render(spectrum, path.as_ref())
+}
+
+impl Figure for RadialDistribution {
+    fn size(&self) -> (u32, u32) {
+        (SIZE, SIZE / 2)
+    }
+
+    fn draw<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        let max_radius = self.radius.last().copied().unwrap_or(1_f64) * 1.05;
+        let max_g = self.g.iter().copied().fold(1_f64, f64::max) * 1.1;
+        let mut chart = ChartBuilder::on(area)
+            .margin(10)
+            .build_cartesian_2d(0_f64..max_radius, 0_f64..max_g)?;
+        chart.draw_series(LineSeries::new(
+            [(0_f64, 1_f64), (max_radius, 1_f64)],
+            RGBColor(160, 160, 160),
+        ))?;
+        chart.draw_series(LineSeries::new(
+            self.radius.iter().copied().zip(self.g.iter().copied()),
+            BLACK.stroke_width(2),
+        ))?;
+        Ok(())
+    }
+}
+
+/// Plots the radial distribution function `rdf` as a curve, with a gray line
+/// at *g(r) = 1*. The plot has no labels, since they would need fonts. The
+/// file is SVG if `path` ends with `.svg` and PNG otherwise.
+///
+/// Requires the `plot` feature.
+pub fn plot_rdf(rdf: &RadialDistribution, path: impl AsRef<Path>) -> Result<(), PlotError> {
+    render(rdf, path.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{EdgeCorrection, periodogram, rdf};
+
+    #[test]
+    fn plots() {
+        let dir = tempfile::tempdir().unwrap();
+        let set = PoissonDiskSet::new(vec![40., 20.], 1., 30);
+        let spectrum = periodogram(set.samples(), [40., 20.], 32);
+        let rdf = rdf(
+            set.samples(),
+            &[40., 20.],
+            0.1,
+            4.,
+            EdgeCorrection::Translation,
+        );
+        for extension in ["png", "svg"] {
+            let file = |name: &str| dir.path().join(format!("{name}.{extension}"));
+            set.plot(file("points")).unwrap();
+            plot_spectrum(&spectrum, file("spectrum")).unwrap();
+            plot_rdf(&rdf, file("rdf")).unwrap();
+            for name in ["points", "spectrum", "rdf"] {
+                let bytes = std::fs::read(file(name)).unwrap();
+                if extension == "png" {
+                    assert_eq!(&bytes[1..4], b"PNG");
+                } else {
+                    assert!(String::from_utf8(bytes).unwrap().contains("<svg"));
+                }
+            }
+        }
+        let png = image_size(&std::fs::read(dir.path().join("points.png")).unwrap());
+        assert_eq!(png, (800, 400));
+        assert!(plot_rdf(&rdf, dir.path().join("missing").join("rdf.png")).is_err());
+    }
+
+    /// The width and height stored in the header of a PNG file.
+    fn image_size(png: &[u8]) -> (u32, u32) {
+        let word = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+        (word(16), word(20))
+    }
+}
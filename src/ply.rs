@@ -0,0 +1,93 @@
+//! PLY files of 2D and 3D point sets.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes the 2D or 3D `samples` as the vertices of an ASCII PLY file to
+/// `path`, with the properties `x`, `y` and, for 3D samples, `z` in double
+/// precision. `read_ply` (`io` feature) reads them back.
+///
+/// ```no_run
+/// let samples = bluenoisers::blue_noise(vec![10., 10., 10.], 1., 30);
+/// bluenoisers::export_ply("samples.ply", &samples).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if the samples have less than two or more than three coordinates
+/// or not all the same number.
+pub fn export_ply<P: AsRef<[f64]>>(path: impl AsRef<Path>, samples: &[P]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_ply(&mut writer, samples)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D or 3D set as a PLY file, see
+    /// [`export_ply`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2- or 3-dimensional.
+    pub fn export_ply(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        assert!(
+            (2..=3).contains(&self.dimensions().len()),
+            "only 2D and 3D sets can be exported"
+        );
+        export_ply(path, self.samples())
+    }
+}
+
+fn write_ply<P: AsRef<[f64]>, W: Write>(writer: &mut W, samples: &[P]) -> io::Result<()> {
+    let dimension = samples.first().map_or(2, |sample| sample.as_ref().len());
+    assert!(
+        (2..=3).contains(&dimension),
+        "only 2D and 3D samples can be exported"
+    );
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    writeln!(writer, "comment {} samples", samples.len())?;
+    writeln!(writer, "element vertex {}", samples.len())?;
+    for axis in &["x", "y", "z"][..dimension] {
+        writeln!(writer, "property double {axis}")?;
+    }
+    writeln!(writer, "end_header")?;
+    for sample in samples {
+        let sample = sample.as_ref();
+        assert_eq!(
+            sample.len(),
+            dimension,
+            "all samples must have the same dimension"
+        );
+        let coordinates: Vec<String> = sample.iter().map(f64::to_string).collect();
+        writeln!(writer, "{}", coordinates.join(" "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_ply() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.ply");
+        super::export_ply(&path, &[[1., 2.], [3.5, 0.25]]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "ply\nformat ascii 1.0\ncomment 2 samples\nelement vertex 2\n\
+             property double x\nproperty double y\nend_header\n1 2\n3.5 0.25\n"
+        );
+
+        let set = PoissonDiskSet::new(vec![5., 5., 5.], 1., 30);
+        set.export_ply(&path).unwrap();
+        #[cfg(feature = "io")]
+        {
+            let reader = io::BufReader::new(File::open(&path).unwrap());
+            assert_eq!(crate::read_ply(reader).unwrap(), set.samples());
+        }
+    }
+}
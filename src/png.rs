@@ -0,0 +1,226 @@
+//! Raster images of 2D point sets.
+
+use crate::PoissonDiskSet;
+use std::io;
+use std::path::Path;
+
+/// How a sample is drawn into a raster image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Splat {
+    /// A disk of `radius` pixels at full intensity. The pixel containing the
+    /// sample is always set, so a radius of zero gives a single pixel per
+    /// sample, as needed for masks.
+    Dot {
+        /// The radius in pixels.
+        radius: f64,
+    },
+    /// A Gaussian with the standard deviation `sigma` in pixels and a peak
+    /// of full intensity, cut off at three standard deviations. Overlapping
+    /// Gaussians add up and saturate.
+    Gaussian {
+        /// The standard deviation in pixels.
+        sigma: f64,
+    },
+}
+
+/// How [`export_png`] draws a point set.
+///
+/// ```
+/// use bluenoisers::{PngStyle, Splat};
+///
+/// let style = PngStyle::new([1., 1.], [256, 256])
+///     .splat(Splat::Gaussian { sigma: 1.5 })
+///     .tileable(true);
+/// let samples = bluenoisers::blue_noise(vec![1., 1.], 0.05, 30);
+/// let pixels = style.render(&samples);
+/// assert_eq!(pixels.len(), 256 * 256);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PngStyle {
+    dimensions: [f64; 2],
+    resolution: [u32; 2],
+    splat: Splat,
+    tileable: bool,
+}
+
+impl PngStyle {
+    /// Creates a style for points in a domain of size `dimensions`, which is
+    /// mapped to an image with `resolution` pixels along each axis.
+    ///
+    /// Samples are drawn as single pixels and clipped at the borders.
+    pub fn new(dimensions: [f64; 2], resolution: [u32; 2]) -> PngStyle {
+        PngStyle {
+            dimensions,
+            resolution,
+            splat: Splat::Dot { radius: 0_f64 },
+            tileable: false,
+        }
+    }
+
+    /// Sets how samples are drawn.
+    pub fn splat(mut self, splat: Splat) -> PngStyle {
+        self.splat = splat;
+        self
+    }
+
+    /// Wraps the dots around the borders of the image instead of clipping
+    /// them, so that copies of a tileable set fit together without seams.
+    pub fn tileable(mut self, tileable: bool) -> PngStyle {
+        self.tileable = tileable;
+        self
+    }
+
+    /// Renders the 2D `samples` as white on black into 8 bit gray values,
+    /// stored row by row with the origin at the top left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample has less than two coordinates.
+    pub fn render<P: AsRef<[f64]>>(&self, samples: &[P]) -> Vec<u8> {
+        let [width, height] = self.resolution.map(|r| r as usize);
+        let scale = [
+            width as f64 / self.dimensions[0],
+            height as f64 / self.dimensions[1],
+        ];
+        let reach = match self.splat {
+            Splat::Dot { radius } => radius,
+            Splat::Gaussian { sigma } => 3_f64 * sigma,
+        };
+        let mut intensity = vec![0_f64; width * height];
+        for sample in samples {
+            let sample = sample.as_ref();
+            let center = [sample[0] * scale[0], sample[1] * scale[1]];
+            let own = center.map(|c| c.floor() as i64);
+            let first = center.map(|c| (c - reach - 0.5).floor() as i64);
+            let last = center.map(|c| (c + reach - 0.5).ceil() as i64);
+            for y in first[1]..=last[1] {
+                for x in first[0]..=last[0] {
+                    let Some(pixel) = self.pixel(x, y) else {
+                        continue;
+                    };
+                    let dx = x as f64 + 0.5 - center[0];
+                    let dy = y as f64 + 0.5 - center[1];
+                    let dst_sqr = dx * dx + dy * dy;
+                    intensity[pixel] = match self.splat {
+                        Splat::Dot { .. } if [x, y] == own || dst_sqr <= reach * reach => 1_f64,
+                        Splat::Dot { .. } => intensity[pixel],
+                        Splat::Gaussian { sigma } => {
+                            intensity[pixel] + (-dst_sqr / (2_f64 * sigma * sigma)).exp()
+                        }
+                    };
+                }
+            }
+        }
+        intensity
+            .iter()
+            .map(|i| (i.min(1_f64) * 255_f64).round() as u8)
+            .collect()
+    }
+
+    /// The index of the pixel at column `x` and row `y`, wrapped around if
+    /// tileable.
+    fn pixel(&self, x: i64, y: i64) -> Option<usize> {
+        let [width, height] = self.resolution.map(i64::from);
+        let (x, y) = if self.tileable {
+            (x.rem_euclid(width), y.rem_euclid(height))
+        } else if (0..width).contains(&x) && (0..height).contains(&y) {
+            (x, y)
+        } else {
+            return None;
+        };
+        Some((y * width + x) as usize)
+    }
+}
+
+/// Writes the 2D `samples` as an 8 bit grayscale PNG image to `path`, see
+/// [`PngStyle::render`].
+///
+/// ```no_run
+/// use bluenoisers::{PngStyle, Splat, export_png};
+///
+/// let samples = bluenoisers::blue_noise(vec![100., 50.], 1., 30);
+/// let style = PngStyle::new([100., 50.], [800, 400]).splat(Splat::Dot { radius: 2. });
+/// export_png("samples.png", &samples, &style).unwrap();
+/// ```
+///
+/// Requires the `image` feature.
+///
+/// # Panics
+///
+/// Panics if a sample has less than two coordinates.
+pub fn export_png<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    style: &PngStyle,
+) -> io::Result<()> {
+    let [width, height] = style.resolution;
+    image::save_buffer(
+        path,
+        &style.render(samples),
+        width,
+        height,
+        image::ColorType::L8,
+    )
+    .map_err(io::Error::other)
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D set as a PNG image, see [`export_png`].
+    ///
+    /// Requires the `image` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2-dimensional.
+    pub fn export_png(&self, path: impl AsRef<Path>, style: &PngStyle) -> io::Result<()> {
+        assert_eq!(self.dimensions().len(), 2, "only 2D sets can be exported");
+        export_png(path, self.samples(), style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let samples = [[0.5, 5.5], [5., 3.]];
+        let style = PngStyle::new([10., 10.], [10, 10]);
+        let pixels = style.render(&samples);
+        assert_eq!(pixels.iter().filter(|p| **p == 255).count(), 2);
+        assert_eq!(pixels[5 * 10], 255);
+        assert_eq!(pixels[3 * 10 + 5], 255);
+
+        // the dot at the left border only wraps around if tileable
+        let dot = style.clone().splat(Splat::Dot { radius: 1. });
+        let pixels = dot.render(&samples[..1]);
+        assert_eq!(pixels.iter().filter(|p| **p == 255).count(), 4);
+        let pixels = dot.tileable(true).render(&samples[..1]);
+        assert_eq!(pixels.iter().filter(|p| **p == 255).count(), 5);
+        assert_eq!(pixels[5 * 10 + 9], 255);
+
+        let gaussian = style.splat(Splat::Gaussian { sigma: 1. });
+        let pixels = gaussian.render(&samples[1..]);
+        // the sample is on the corner between four pixels
+        for pixel in [2 * 10 + 4, 2 * 10 + 5, 3 * 10 + 4, 3 * 10 + 5] {
+            assert_eq!(pixels[pixel], (255. * (-0.25_f64).exp()).round() as u8);
+        }
+        assert_eq!(pixels[9 * 10], 0);
+    }
+
+    #[test]
+    fn export_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.png");
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let style = PngStyle::new([20., 10.], [80, 40]).splat(Splat::Dot { radius: 1. });
+        set.export_png(&path, &style).unwrap();
+        let image = image::open(&path).unwrap().into_luma8();
+        assert_eq!(image.dimensions(), (80, 40));
+        assert_eq!(image.into_raw(), style.render(set.samples()));
+        assert!(
+            set.export_png(dir.path().join("missing").join("samples.png"), &style)
+                .is_err()
+        );
+    }
+}
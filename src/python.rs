@@ -0,0 +1,259 @@
+//! Python bindings, returning NumPy arrays.
+//!
+//! Build and install the module into the active Python environment with
+//! [maturin](https://www.maturin.rs), which picks up `pyproject.toml`:
+//!
+//! ```text
+//! maturin develop --release
+//! ```
+//!
+//! ```text
+//! >>> import bluenoisers
+//! >>> samples = bluenoisers.blue_noise([64., 64.], 1., seed=7)
+//! >>> samples.shape[1]
+//! 2
+//! ```
+
+use crate::BlueNoiseConfig;
+use crate::analysis::{self, EdgeCorrection};
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+fn config(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    seed: Option<u64>,
+) -> PyResult<BlueNoiseConfig> {
+    if dimensions.is_empty() || dimensions.iter().any(|d| *d <= 0.) {
+        return Err(PyValueError::new_err(
+            "there must be at least one dimension and all sizes must be positive",
+        ));
+    }
+    if min_distance <= 0. || k_abort == 0 {
+        return Err(PyValueError::new_err(
+            "min_distance and k_abort must be positive",
+        ));
+    }
+    let config = BlueNoiseConfig::new(dimensions, min_distance).k_abort(k_abort);
+    Ok(match seed {
+        Some(seed) => config.seed(seed),
+        None => config,
+    })
+}
+
+/// The rows of a NumPy array of points.
+fn rows(points: &PyReadonlyArray2<f64>) -> Vec<Vec<f64>> {
+    points
+        .as_array()
+        .rows()
+        .into_iter()
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+/// The radii and values of a radial distribution function.
+type Profile<'py> = (Bound<'py, PyArray1<f64>>, Bound<'py, PyArray1<f64>>);
+
+fn check_dimension(points: &[Vec<f64>], dimension: usize) -> PyResult<()> {
+    if points.iter().any(|p| p.len() != dimension) {
+        return Err(PyValueError::new_err(format!(
+            "the points must have {dimension} coordinates"
+        )));
+    }
+    Ok(())
+}
+
+/// Generates blue noise samples in a domain of size `dimensions`, as an
+/// array with one row per sample.
+#[pyfunction]
+#[pyo3(signature = (dimensions, min_distance, k_abort = 30, seed = None))]
+fn blue_noise<'py>(
+    py: Python<'py>,
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    seed: Option<u64>,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let config = config(dimensions, min_distance, k_abort, seed)?;
+    let dimension = config.dimensions().len();
+    let samples = py.detach(|| config.generate());
+    let coordinates: Vec<f64> = samples.iter().flatten().copied().collect();
+    let array = Array2::from_shape_vec((samples.len(), dimension), coordinates)
+        .expect("one row per sample");
+    Ok(array.into_pyarray(py))
+}
+
+/// Generates the samples of `blue_noise` one by one, as arrays of their
+/// coordinates.
+#[pyclass(name = "BlueNoiseIterator", module = "bluenoisers")]
+struct PyBlueNoiseIterator {
+    inner: crate::BlueNoiseIterator,
+}
+
+#[pymethods]
+impl PyBlueNoiseIterator {
+    #[new]
+    #[pyo3(signature = (dimensions, min_distance, k_abort = 30, seed = None))]
+    fn new(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        k_abort: usize,
+        seed: Option<u64>,
+    ) -> PyResult<PyBlueNoiseIterator> {
+        let config = config(dimensions, min_distance, k_abort, seed)?;
+        Ok(PyBlueNoiseIterator {
+            inner: config.iter(),
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.inner
+            .next_ref()
+            .map(|sample| PyArray1::from_slice(py, sample))
+    }
+}
+
+/// Generates a tileable blue noise threshold texture with the
+/// void-and-cluster method, as an array of shape `(height, width)` with
+/// distinct thresholds in `[0, 1)`.
+#[pyfunction]
+#[pyo3(signature = (width, height, seed = 0))]
+fn dither_mask<'py>(
+    py: Python<'py>,
+    width: usize,
+    height: usize,
+    seed: u64,
+) -> PyResult<Bound<'py, PyArray2<f32>>> {
+    let count = width.saturating_mul(height);
+    if count == 0 || count > u32::MAX as usize {
+        return Err(PyValueError::new_err(
+            "the mask must have between one and 2**32 pixels",
+        ));
+    }
+    let mask = py.detach(|| crate::DitherMask::void_and_cluster(width, height, seed));
+    let array = Array2::from_shape_vec((height, width), mask.to_f32()).expect("a value per pixel");
+    Ok(array.into_pyarray(py))
+}
+
+/// The power spectrum of 2D `points` in a domain of size `dimensions`, as
+/// an array of shape `(size, size)` with the DC term in the middle.
+#[pyfunction]
+fn periodogram<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<'py, f64>,
+    dimensions: [f64; 2],
+    size: usize,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let points = rows(&points);
+    check_dimension(&points, 2)?;
+    let spectrum = py.detach(|| analysis::periodogram(&points, dimensions, size));
+    let array = Array2::from_shape_vec((size, size), spectrum.power).expect("a power per pixel");
+    Ok(array.into_pyarray(py))
+}
+
+/// Summarizes the spectrum of 2D `points` in a dict with the effective
+/// Nyquist frequency, the low-frequency energy and the peak.
+#[pyfunction]
+fn quality<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<'py, f64>,
+    dimensions: [f64; 2],
+) -> PyResult<Bound<'py, PyDict>> {
+    let points = rows(&points);
+    check_dimension(&points, 2)?;
+    let quality = py.detach(|| analysis::quality(&points, dimensions));
+    let dict = PyDict::new(py);
+    dict.set_item("effective_nyquist", quality.effective_nyquist)?;
+    dict.set_item("low_frequency_energy", quality.low_frequency_energy)?;
+    dict.set_item("peak", quality.peak)?;
+    Ok(dict)
+}
+
+/// The radial distribution function g(r) of `points`, as a tuple of the
+/// bin radii and the values. `correction` is "none", "translation" or
+/// "periodic".
+#[pyfunction]
+#[pyo3(signature = (points, dimensions, bin_width, max_distance, correction = "translation"))]
+fn rdf<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<'py, f64>,
+    dimensions: Vec<f64>,
+    bin_width: f64,
+    max_distance: f64,
+    correction: &str,
+) -> PyResult<Profile<'py>> {
+    let correction = match correction {
+        "none" => EdgeCorrection::None,
+        "translation" => EdgeCorrection::Translation,
+        "periodic" => EdgeCorrection::Periodic,
+        _ => {
+            return Err(PyValueError::new_err(
+                "correction must be \"none\", \"translation\" or \"periodic\"",
+            ));
+        }
+    };
+    if bin_width <= 0. || max_distance <= 0. {
+        return Err(PyValueError::new_err(
+            "bin_width and max_distance must be positive",
+        ));
+    }
+    if correction == EdgeCorrection::Periodic && dimensions.iter().any(|d| 2. * max_distance > *d) {
+        return Err(PyValueError::new_err(
+            "max_distance must be at most half the size of the domain",
+        ));
+    }
+    let points = rows(&points);
+    check_dimension(&points, dimensions.len())?;
+    let g = py.detach(|| analysis::rdf(&points, &dimensions, bin_width, max_distance, correction));
+    Ok((g.radius.into_pyarray(py), g.g.into_pyarray(py)))
+}
+
+/// The star discrepancy of `points` scaled to the unit cube.
+#[pyfunction]
+fn star_discrepancy(
+    py: Python<'_>,
+    points: PyReadonlyArray2<'_, f64>,
+    dimensions: Vec<f64>,
+) -> PyResult<f64> {
+    let points = rows(&points);
+    check_dimension(&points, dimensions.len())?;
+    Ok(py.detach(|| analysis::star_discrepancy(&points, &dimensions)))
+}
+
+/// l2_star_discrepancy(points, dimensions)
+/// --
+///
+/// The L2 star discrepancy of `points` scaled to the unit cube.
+#[pyfunction]
+fn l2_star_discrepancy(
+    py: Python<'_>,
+    points: PyReadonlyArray2<'_, f64>,
+    dimensions: Vec<f64>,
+) -> PyResult<f64> {
+    let points = rows(&points);
+    check_dimension(&points, dimensions.len())?;
+    Ok(py.detach(|| analysis::l2_star_discrepancy(&points, &dimensions)))
+}
+
+/// The `bluenoisers` Python module.
+#[pymodule]
+#[pyo3(name = "bluenoisers")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(blue_noise, m)?)?;
+    m.add_class::<PyBlueNoiseIterator>()?;
+    m.add_function(wrap_pyfunction!(dither_mask, m)?)?;
+    m.add_function(wrap_pyfunction!(periodogram, m)?)?;
+    m.add_function(wrap_pyfunction!(quality, m)?)?;
+    m.add_function(wrap_pyfunction!(rdf, m)?)?;
+    m.add_function(wrap_pyfunction!(star_discrepancy, m)?)?;
+    m.add_function(wrap_pyfunction!(l2_star_discrepancy, m)?)?;
+    Ok(())
+}
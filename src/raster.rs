@@ -0,0 +1,275 @@
+//! Fields sampled on regular grids of pixels or voxels.
+
+use crate::grid::BackgroundGrid;
+use crate::{Point, PoissonDiskSet};
+
+impl PoissonDiskSet {
+    /// Computes the distance to the nearest sample at the center of every
+    /// pixel of a raster with `resolution` pixels along each axis covering
+    /// the domain.
+    ///
+    /// The values are stored with the first axis changing fastest, i.e. row
+    /// by row for 2D images.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![64., 64.], 4., 30);
+    /// let field = set.rasterize_distance_field(&[128, 128]);
+    /// assert_eq!(field.len(), 128 * 128);
+    /// // the samples cover the domain
+    /// assert!(field.iter().all(|distance| *distance < 8.));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution` doesn't have one entry per dimension.
+    pub fn rasterize_distance_field(&self, resolution: &[usize]) -> Vec<f64> {
+        pixel_centers(self.dimensions(), resolution)
+            .map(|center| self.nearest(&center).map_or(f64::INFINITY, |(_, d)| d))
+            .collect()
+    }
+}
+
+impl PoissonDiskSet {
+    /// Estimates the density of the samples, in samples per unit of volume, at
+    /// the center of every pixel of a raster with `resolution` pixels along
+    /// each axis covering the domain.
+    ///
+    /// Each sample is splatted with a Gaussian kernel with the standard
+    /// deviation `bandwidth`, which should be a few times `min_distance` to
+    /// smooth out the individual samples. The kernel is cut off at three
+    /// standard deviations. Close to the borders of the domain, part of the
+    /// kernels fall outside, so the estimate is lower there.
+    ///
+    /// The values are stored with the first axis changing fastest.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![100., 100.], 1., 30);
+    /// let density = set.density_estimate(&[10, 10], 5.);
+    /// // the center pixels are far enough from the borders
+    /// let center = density[5 * 10 + 5];
+    /// let average = set.len() as f64 / (100. * 100.);
+    /// assert!((center / average - 1.).abs() < 0.1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution` doesn't have one entry per dimension or if
+    /// `bandwidth` is not positive.
+    pub fn density_estimate(&self, resolution: &[usize], bandwidth: f64) -> Vec<f64> {
+        assert!(bandwidth > 0_f64, "the bandwidth must be positive");
+        let dimension = self.dimensions().len() as f64;
+        let variance = bandwidth * bandwidth;
+        let normalization = (2_f64 * std::f64::consts::PI * variance).powf(-dimension / 2_f64);
+        pixel_centers(self.dimensions(), resolution)
+            .map(|center| {
+                self.within(&center, 3_f64 * bandwidth)
+                    .map(|i| {
+                        let dst_sqr = BackgroundGrid::dst_sqr(&center, &self.samples()[i]);
+                        normalization * (-dst_sqr / (2_f64 * variance)).exp()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// What a [cellular noise](PoissonDiskSet::worley) texture shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellFeature {
+    /// The distance to the nearest sample, which gives round spots.
+    F1,
+    /// The distance to the second nearest sample.
+    F2,
+    /// The difference between [`F2`](CellFeature::F2) and
+    /// [`F1`](CellFeature::F1), which is zero along the cell borders and
+    /// gives a cracked, cobblestone look.
+    F2MinusF1,
+}
+
+impl PoissonDiskSet {
+    /// Synthesizes a cellular (Worley) noise texture with `resolution` pixels
+    /// along each axis covering the domain.
+    ///
+    /// The values are distances in domain units, stored with the first axis
+    /// changing fastest. Since some pixels are up to *2 × `min_distance`* away
+    /// from their nearest sample, dividing [`F1`](CellFeature::F1) by that
+    /// maps it to `[0, 1)`.
+    ///
+    /// With `tileable`, distances wrap around the borders of the domain, so
+    /// that copies of the texture fit together without seams.
+    ///
+    /// ```
+    /// use bluenoisers::{CellFeature, PoissonDiskSet};
+    ///
+    /// let set = PoissonDiskSet::new(vec![1., 1.], 0.05, 30);
+    /// let texture = set.worley(&[256, 256], CellFeature::F2MinusF1, true);
+    /// let pixels: Vec<u8> = texture
+    ///     .iter()
+    ///     .map(|d| (d / 0.1 * 255.).min(255.) as u8)
+    ///     .collect();
+    /// assert_eq!(pixels.len(), 256 * 256);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution` doesn't have one entry per dimension.
+    pub fn worley(&self, resolution: &[usize], feature: CellFeature, tileable: bool) -> Vec<f64> {
+        let k = match feature {
+            CellFeature::F1 => 1,
+            CellFeature::F2 | CellFeature::F2MinusF1 => 2,
+        };
+        let dimensions = self.dimensions();
+        pixel_centers(dimensions, resolution)
+            .map(|center| {
+                let mut distances: Vec<f64> =
+                    self.knn(&center, k).iter().map(|(_, d)| *d).collect();
+                if tileable {
+                    // the copies of the domain around it, i.e. all the shifts
+                    // by -1, 0 or 1 times its size along each axis but none
+                    let mut shift = vec![-1_i32; dimensions.len()];
+                    loop {
+                        if shift.iter().any(|s| *s != 0) {
+                            let query: Point = center
+                                .iter()
+                                .zip(shift.iter().zip(dimensions.iter()))
+                                .map(|(x, (s, dim))| x + *s as f64 * dim)
+                                .collect();
+                            let to_domain = query
+                                .iter()
+                                .zip(dimensions.iter())
+                                .map(|(x, dim)| (-x).max(x - dim).max(0_f64).powi(2))
+                                .sum::<f64>()
+                                .sqrt();
+                            let worst = distances.get(k - 1).copied().unwrap_or(f64::INFINITY);
+                            if to_domain < worst {
+                                distances.extend(self.knn(&query, k).iter().map(|(_, d)| *d));
+                                distances.sort_by(f64::total_cmp);
+                                distances.truncate(k);
+                            }
+                        }
+                        // iterate shifts
+                        let mut done = true;
+                        for s in shift.iter_mut() {
+                            if *s == 1 {
+                                *s = -1;
+                            } else {
+                                *s += 1;
+                                done = false;
+                                break;
+                            }
+                        }
+                        if done {
+                            break;
+                        }
+                    }
+                }
+                let nth = |n: usize| distances.get(n).copied().unwrap_or(f64::INFINITY);
+                match feature {
+                    CellFeature::F1 => nth(0),
+                    CellFeature::F2 => nth(1),
+                    CellFeature::F2MinusF1 => nth(1) - nth(0),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Iterates over the centers of the pixels of a raster with `resolution`
+/// pixels along each axis covering a domain of size `dimensions`, with the
+/// first axis changing fastest.
+pub(crate) fn pixel_centers<'a>(
+    dimensions: &'a [f64],
+    resolution: &'a [usize],
+) -> impl Iterator<Item = Point> + 'a {
+    assert_eq!(
+        dimensions.len(),
+        resolution.len(),
+        "the resolution must have one entry per dimension"
+    );
+    let count = if resolution.is_empty() {
+        0
+    } else {
+        resolution.iter().product()
+    };
+    (0..count).map(move |mut linear| {
+        resolution
+            .iter()
+            .zip(dimensions.iter())
+            .map(|(res, dim)| {
+                let pixel = linear % res;
+                linear /= res;
+                (pixel as f64 + 0.5) * dim / *res as f64
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_field() {
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let field = set.rasterize_distance_field(&[40, 10]);
+        assert_eq!(field.len(), 400);
+        // the pixel in the second row, fourth column
+        let center = [3.5 * 0.5, 1.5];
+        let expected = set
+            .samples()
+            .iter()
+            .map(|s| BackgroundGrid::dst_sqr(s, &center).sqrt())
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(field[40 + 3], expected);
+        assert!(field.iter().all(|d| *d < 2.));
+    }
+
+    #[test]
+    fn worley() {
+        let set = PoissonDiskSet::new(vec![16., 8.], 1., 30);
+        let f1 = set.worley(&[32, 16], CellFeature::F1, false);
+        assert_eq!(f1, set.rasterize_distance_field(&[32, 16]));
+        let f2 = set.worley(&[32, 16], CellFeature::F2, false);
+        let cracks = set.worley(&[32, 16], CellFeature::F2MinusF1, false);
+        for ((f1, f2), crack) in f1.iter().zip(f2.iter()).zip(cracks.iter()) {
+            assert!(f1 <= f2);
+            assert_eq!(*crack, f2 - f1);
+        }
+        // wrapping can only bring samples closer
+        let tiled = set.worley(&[32, 16], CellFeature::F1, true);
+        assert!(tiled.iter().zip(f1.iter()).all(|(t, f)| t <= f));
+        // away from the borders nothing changes, but at the borders pixels
+        // see the samples on the other side
+        assert_eq!(tiled[32 * 8 + 16], f1[32 * 8 + 16]);
+        let left: Point = Point::from_slice(&[0.25, 4.25]);
+        let expected = set
+            .samples()
+            .iter()
+            .flat_map(|s| {
+                [0., 16., -16.]
+                    .map(|shift| BackgroundGrid::dst_sqr(&[s[0] + shift, s[1]], &left).sqrt())
+            })
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(tiled[32 * 8], expected);
+    }
+
+    #[test]
+    fn density_estimate() {
+        let set = PoissonDiskSet::new(vec![60., 60.], 1., 30);
+        let density = set.density_estimate(&[6, 6], 6.);
+        let average = set.len() as f64 / (60. * 60.);
+        for y in 1..5 {
+            for x in 1..5 {
+                assert!((density[y * 6 + x] / average - 1.).abs() < 0.1);
+            }
+        }
+        // the first pixels are 5 units from the border, so about a fifth of
+        // their kernel is outside, at the corner a fifth along both axes
+        assert!((density[3] / average - 0.8).abs() < 0.1);
+        assert!((density[0] / average - 0.64).abs() < 0.1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Scattering entities with blue noise in Bevy.
+
+use crate::BlueNoiseConfig;
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::prelude::*;
+use bevy::math::{IVec2, Rect, Vec2};
+use bevy::tasks::{AsyncComputeTaskPool, Task, futures::check_ready};
+
+/// Generates the [`ScatterPoints`] of every entity with a
+/// [`BlueNoiseScatter`], whenever the scatter is added or changed.
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bluenoisers::{BlueNoisePlugin, BlueNoiseScatter, ScatterPoints};
+///
+/// fn setup(mut commands: Commands) {
+///     let region = Rect::new(-50., -50., 50., 50.);
+///     commands.spawn(BlueNoiseScatter {
+///         asynchronous: true,
+///         ..BlueNoiseScatter::new(region, 2.)
+///     });
+/// }
+///
+/// fn plant_trees(mut commands: Commands, scatters: Query<&ScatterPoints, Added<ScatterPoints>>) {
+///     for points in &scatters {
+///         points.spawn(&mut commands, |point| {
+///             Transform::from_xyz(point.position.x, 0., point.position.y)
+///                 .with_rotation(Quat::from_rotation_y(point.seed as f32))
+///         });
+///     }
+/// }
+///
+/// App::new()
+///     .add_plugins((MinimalPlugins, BlueNoisePlugin))
+///     .add_systems(Startup, setup)
+///     .add_systems(Update, plant_trees)
+///     .run();
+/// ```
+///
+/// Requires the `bevy` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlueNoisePlugin;
+
+impl Plugin for BlueNoisePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (start_scatters, finish_scatters).chain());
+    }
+}
+
+/// Blue noise positions for a region of the plane, which
+/// [`BlueNoisePlugin`] generates into a [`ScatterPoints`] component on the
+/// same entity.
+///
+/// The plane is split into square chunks of edge length `chunk_size`, and
+/// each chunk gets its own seed derived from `seed` and the coordinates of
+/// the chunk. So the positions of a chunk only depend on `seed`, and
+/// overlapping regions, or the same region in a later session, get the same
+/// positions. The samples of a chunk keep a distance of half the minimal
+/// distance to its borders, so the positions of neighboring chunks don't
+/// conflict, at the cost of a slightly lower density along the seams.
+///
+/// Requires the `bevy` feature.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct BlueNoiseScatter {
+    /// The region to fill, in the local coordinates of the entity.
+    pub region: Rect,
+    /// The minimal distance between two positions.
+    pub min_distance: f32,
+    /// The seed all chunk seeds are derived from.
+    pub seed: u64,
+    /// The edge length of a chunk, which should be several times the
+    /// minimal distance.
+    pub chunk_size: f32,
+    /// Whether to generate on the [`AsyncComputeTaskPool`] instead of in
+    /// the frame the scatter changed.
+    pub asynchronous: bool,
+}
+
+impl BlueNoiseScatter {
+    /// Creates a scatter filling `region`, with seed `0`, chunks of 16 times
+    /// the minimal distance and synchronous generation.
+    pub fn new(region: Rect, min_distance: f32) -> BlueNoiseScatter {
+        BlueNoiseScatter {
+            region,
+            min_distance,
+            seed: 0,
+            chunk_size: 16. * min_distance,
+            asynchronous: false,
+        }
+    }
+
+    /// The seed of the chunk with the given coordinates, in chunks.
+    pub fn chunk_seed(&self, chunk: IVec2) -> u64 {
+        mix(mix(self.seed, chunk.x as u64), chunk.y as u64)
+    }
+
+    /// Generates the positions inside of the chunk with the given
+    /// coordinates, in chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size isn't larger than the minimal distance.
+    pub fn chunk_points(&self, chunk: IVec2) -> Vec<ScatterPoint> {
+        assert!(
+            self.chunk_size > self.min_distance,
+            "the chunk size must be larger than the minimal distance"
+        );
+        let seed = self.chunk_seed(chunk);
+        let inner = (self.chunk_size - self.min_distance) as f64;
+        let offset = chunk.as_vec2() * self.chunk_size + self.min_distance / 2.;
+        BlueNoiseConfig::new(vec![inner, inner], self.min_distance as f64)
+            .seed(seed)
+            .generate()
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| ScatterPoint {
+                position: offset + Vec2::new(sample[0] as f32, sample[1] as f32),
+                chunk,
+                seed: mix(seed, i as u64),
+            })
+            .collect()
+    }
+
+    /// Generates all positions inside of the region, chunk by chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chunk size isn't larger than the minimal distance.
+    pub fn points(&self) -> Vec<ScatterPoint> {
+        if self.region.is_empty() {
+            return Vec::new();
+        }
+        let first = (self.region.min / self.chunk_size).floor().as_ivec2();
+        let last = (self.region.max / self.chunk_size).floor().as_ivec2();
+        let mut points = Vec::new();
+        for y in first.y..=last.y {
+            for x in first.x..=last.x {
+                points.extend(
+                    self.chunk_points(IVec2::new(x, y))
+                        .into_iter()
+                        .filter(|point| self.region.contains(point.position)),
+                );
+            }
+        }
+        points
+    }
+}
+
+/// A position generated by a [`BlueNoiseScatter`].
+///
+/// Requires the `bevy` feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScatterPoint {
+    /// The position in the local coordinates of the scatter's entity.
+    pub position: Vec2,
+    /// The coordinates of the chunk it belongs to, in chunks.
+    pub chunk: IVec2,
+    /// A seed which is as deterministic as the position, e.g. to pick a
+    /// variant or rotation of what is spawned there.
+    pub seed: u64,
+}
+
+/// The positions of a [`BlueNoiseScatter`], inserted by
+/// [`BlueNoisePlugin`] when they are ready.
+///
+/// Requires the `bevy` feature.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct ScatterPoints(pub Vec<ScatterPoint>);
+
+impl ScatterPoints {
+    /// Spawns an entity with the bundle `bundle` returns for each point and
+    /// returns the entities in the order of the points.
+    pub fn spawn<B: Bundle>(
+        &self,
+        commands: &mut Commands,
+        mut bundle: impl FnMut(&ScatterPoint) -> B,
+    ) -> Vec<Entity> {
+        self.0
+            .iter()
+            .map(|point| commands.spawn(bundle(point)).id())
+            .collect()
+    }
+}
+
+/// The generation of an asynchronous scatter in progress.
+#[derive(Component)]
+struct ScatterTask(Task<Vec<ScatterPoint>>);
+
+fn start_scatters(
+    mut commands: Commands,
+    scatters: Query<(Entity, &BlueNoiseScatter), Changed<BlueNoiseScatter>>,
+) {
+    for (entity, scatter) in &scatters {
+        if scatter.asynchronous {
+            let scatter = scatter.clone();
+            let task = AsyncComputeTaskPool::get().spawn(async move { scatter.points() });
+            // replacing an older task cancels it
+            commands.entity(entity).insert(ScatterTask(task));
+        } else {
+            commands
+                .entity(entity)
+                .remove::<ScatterTask>()
+                .insert(ScatterPoints(scatter.points()));
+        }
+    }
+}
+
+fn finish_scatters(mut commands: Commands, mut tasks: Query<(Entity, &mut ScatterTask)>) {
+    for (entity, mut task) in &mut tasks {
+        if let Some(points) = check_ready(&mut task.0) {
+            commands
+                .entity(entity)
+                .remove::<ScatterTask>()
+                .insert(ScatterPoints(points));
+        }
+    }
+}
+
+/// Combines a seed with a value into a new seed, with SplitMix64.
+fn mix(seed: u64, value: u64) -> u64 {
+    let mut z = (seed ^ value.rotate_left(32)).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::tasks::TaskPool;
+
+    #[test]
+    fn chunks() {
+        let scatter = BlueNoiseScatter::new(Rect::new(-15., -5., 25., 12.), 1.);
+        let points = scatter.points();
+        assert!(points.len() > 300);
+        assert!(points.iter().all(|p| scatter.region.contains(p.position)));
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                assert!(a.position.distance(b.position) >= 1.);
+            }
+        }
+        // a region overlapping the first one gets the same points there
+        let other = BlueNoiseScatter::new(Rect::new(0., 0., 40., 40.), 1.);
+        let shared = |points: &[ScatterPoint]| -> Vec<ScatterPoint> {
+            let overlap = Rect::new(0., 0., 25., 12.);
+            points
+                .iter()
+                .copied()
+                .filter(|p| overlap.contains(p.position))
+                .collect()
+        };
+        assert_eq!(shared(&points), shared(&other.points()));
+        let reseeded = BlueNoiseScatter { seed: 1, ..scatter };
+        assert_ne!(reseeded.points(), points);
+        assert_ne!(
+            scatter.chunk_seed(IVec2::new(1, 0)),
+            scatter.chunk_seed(IVec2::new(0, 1))
+        );
+    }
+
+    #[test]
+    fn plugin() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let mut app = App::new();
+        app.add_plugins(BlueNoisePlugin);
+        let scatter = BlueNoiseScatter::new(Rect::new(0., 0., 20., 20.), 1.);
+        let expected = scatter.points();
+        let now = app.world_mut().spawn(scatter.clone()).id();
+        let later = app
+            .world_mut()
+            .spawn(BlueNoiseScatter {
+                asynchronous: true,
+                ..scatter
+            })
+            .id();
+        app.update();
+        let points = |app: &App, entity| app.world().get::<ScatterPoints>(entity).cloned();
+        assert_eq!(points(&app, now).unwrap().0, expected);
+        while points(&app, later).is_none() {
+            std::thread::yield_now();
+            app.update();
+        }
+        assert_eq!(points(&app, later).unwrap().0, expected);
+
+        let spawn = move |mut commands: Commands, scatters: Query<&ScatterPoints>| {
+            scatters.get(now).unwrap().spawn(&mut commands, |point| {
+                bevy::transform::components::Transform::from_xyz(
+                    point.position.x,
+                    0.,
+                    point.position.y,
+                )
+            });
+        };
+        app.world_mut().run_system_once(spawn).unwrap();
+        let mut transforms = app
+            .world_mut()
+            .query::<&bevy::transform::components::Transform>();
+        assert_eq!(transforms.iter(app.world()).count(), expected.len());
+    }
+}
@@ -0,0 +1,872 @@
+//! A finished pattern which can be queried.
+
+#[cfg(feature = "serde")]
+use crate::config::check_domain;
+use crate::grid::{BackgroundGrid, GridSnapshot};
+use crate::validate::domain_violation;
+use crate::{
+    BlueNoiseConfig, BlueNoiseIterator, GenerationStats, Interval, Point, Violation,
+    validate_with_bounds,
+};
+use rand::Rng;
+
+/// How many positions [`recycle`] tries for each replacement.
+const RECYCLE_TRIES: usize = 30;
+
+/// A generated set of samples together with the background grid used to
+/// generate it, which accelerates spatial queries.
+///
+/// ```
+/// use bluenoisers::PoissonDiskSet;
+///
+/// let set = PoissonDiskSet::new(vec![50., 50.], 2., 30);
+/// let (index, distance) = set.nearest(&[25., 25.]).unwrap();
+/// // blue noise covers the domain, so there is always a sample nearby
+/// assert!(distance < 4.);
+/// assert_eq!(set.nearest(&set.samples()[index]), Some((index, 0.)));
+/// ```
+#[derive(Debug)]
+pub struct PoissonDiskSet {
+    grid: BackgroundGrid,
+    samples: Vec<Point>,
+    min_distance: f64,
+    stats: GenerationStats,
+}
+
+impl PoissonDiskSet {
+    /// Generates a set, the arguments are the same as for
+    /// [`blue_noise`](crate::blue_noise).
+    pub fn new(dimensions: Vec<f64>, min_distance: f64, k_abort: usize) -> PoissonDiskSet {
+        BlueNoiseIterator::new(dimensions, min_distance, k_abort).into_set()
+    }
+
+    /// Generates a set as described by `config`.
+    pub fn generate(config: &BlueNoiseConfig) -> PoissonDiskSet {
+        config.iter().into_set()
+    }
+
+    /// Builds a set from existing `points`, e.g. loaded from a file, so that
+    /// they can be queried like a generated set. The arguments are the same as
+    /// for [`blue_noise`](crate::blue_noise).
+    ///
+    /// Fails with all the violations if the points are not valid, see
+    /// [`validate`](crate::validate); [`repair`](crate::repair) fixes that, and
+    /// [`dedup`](crate::dedup) removes nearly coincident points. Also fails
+    /// with [`Violation::DomainTooLarge`] if the background grid doesn't fit
+    /// into memory.
+    ///
+    /// ```
+    /// use bluenoisers::{Point, PoissonDiskSet};
+    ///
+    /// let points: Vec<Point> = vec![Point::from_slice(&[1., 1.]), Point::from_slice(&[4., 2.])];
+    /// let set = PoissonDiskSet::from_points(vec![5., 5.], 2., points).unwrap();
+    /// assert_eq!(set.nearest(&[3., 3.]).unwrap().0, 1);
+    /// ```
+    pub fn from_points(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        points: Vec<Point>,
+    ) -> Result<PoissonDiskSet, Vec<Violation>> {
+        PoissonDiskSet::from_points_with_bounds(dimensions, min_distance, Vec::new(), points)
+    }
+
+    /// Like [`from_points`](PoissonDiskSet::from_points), but for a domain
+    /// with the given `bounds` along each axis, see
+    /// [`BlueNoiseConfig::bounds`] and [`validate_with_bounds`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are `bounds`, but not one per dimension.
+    pub fn from_points_with_bounds(
+        dimensions: Vec<f64>,
+        min_distance: f64,
+        bounds: Vec<Interval>,
+        points: Vec<Point>,
+    ) -> Result<PoissonDiskSet, Vec<Violation>> {
+        validate_with_bounds(&points, min_distance, &dimensions, &bounds)?;
+        let mut grid = BackgroundGrid::try_new(dimensions, min_distance)
+            .map_err(|error| vec![domain_violation(error)])?;
+        if bounds.iter().any(|bounds| *bounds != Interval::ClosedOpen) {
+            grid.bounds = bounds;
+        }
+        let mut samples = Vec::with_capacity(points.len());
+        for point in points {
+            grid.insert(point, &mut samples)
+                .expect("the grid checks less than `validate`");
+        }
+        Ok(PoissonDiskSet {
+            grid,
+            samples,
+            min_distance,
+            stats: GenerationStats::default(),
+        })
+    }
+
+    /// The size of the domain.
+    pub fn dimensions(&self) -> &[f64] {
+        &self.grid.dimensions
+    }
+
+    /// The minimal distance between samples.
+    pub fn min_distance(&self) -> f64 {
+        self.min_distance
+    }
+
+    /// The samples, in order of generation.
+    pub fn samples(&self) -> &[Point] {
+        &self.samples
+    }
+
+    /// The number of samples.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether there are no samples, which only happens for empty domains.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The counters collected while generating the set, see
+    /// [`GenerationStats`]. All zero for sets built from existing points.
+    pub fn stats(&self) -> GenerationStats {
+        self.stats
+    }
+
+    /// Copies the state of the background grid, e.g. to visualize which cells
+    /// are filled.
+    pub fn grid_snapshot(&self) -> GridSnapshot {
+        self.grid.snapshot()
+    }
+
+    /// Consumes the set and returns its samples.
+    pub fn into_samples(self) -> Vec<Point> {
+        self.samples
+    }
+
+    /// Finds the sample closest to `query` and returns its index and its
+    /// distance to `query`, or `None` if the set is empty. `query` may be
+    /// outside of the domain.
+    ///
+    /// Since every point of the domain is less than *2 × `min_distance`*
+    /// away from a sample, this only has to look at a few cells of the grid.
+    /// Above six dimensions, it checks all the samples.
+    pub fn nearest(&self, query: &[f64]) -> Option<(usize, f64)> {
+        self.knn(query, 1).first().copied()
+    }
+
+    /// Finds the `k` samples closest to `query` and returns their indices and
+    /// their distances to `query`, sorted by distance. If the set has less
+    /// than `k` samples, all of them are returned.
+    ///
+    /// The search expands ring by ring of grid cells around `query`, like
+    /// [`nearest`](PoissonDiskSet::nearest).
+    pub fn knn(&self, query: &[f64], k: usize) -> Vec<(usize, f64)> {
+        debug_assert_eq!(query.len(), self.grid.dimensions.len());
+        self.grid.knn(query, k, &self.samples)
+    }
+
+    /// Iterates over the indices of the samples which are less than `radius`
+    /// away from `query`, in no particular order.
+    ///
+    /// Only the grid cells overlapping the ball are visited. Above six
+    /// dimensions, all the samples are checked.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let spawn_points = PoissonDiskSet::new(vec![500., 500.], 10., 30);
+    /// let player = [120., 300.];
+    /// for index in spawn_points.within(&player, 30.) {
+    ///     println!("spawn point {:?} is close", spawn_points.samples()[index]);
+    /// }
+    /// ```
+    pub fn within(&self, query: &[f64], radius: f64) -> impl Iterator<Item = usize> + '_ {
+        debug_assert_eq!(query.len(), self.grid.dimensions.len());
+        self.grid.within(query, radius, &self.samples)
+    }
+
+    /// Extracts the samples inside the box from `min_corner` to `max_corner`,
+    /// e.g. to sample a part of a larger domain on its own. The samples are
+    /// moved so that `min_corner` becomes the origin of the new set.
+    ///
+    /// With `with_border`, the samples just outside the box are collected as
+    /// well, so that sampling can continue inside the box consistently with
+    /// the rest of the set.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![50., 50.], 1., 30);
+    /// let crop = set.crop(&[10., 20.], &[30., 25.], true);
+    /// assert_eq!(crop.set.dimensions(), [20., 5.]);
+    /// for sample in crop.set.samples() {
+    ///     let original = [sample[0] + 10., sample[1] + 20.];
+    ///     assert_eq!(set.nearest(&original).unwrap().1, 0.);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the corners don't have one entry per dimension, or the box
+    /// is empty or infinite along an axis, i.e. `max_corner` is not above
+    /// `min_corner` by a finite amount.
+    pub fn crop(&self, min_corner: &[f64], max_corner: &[f64], with_border: bool) -> Crop {
+        let dimension = self.grid.dimensions.len();
+        assert!(
+            min_corner.len() == dimension && max_corner.len() == dimension,
+            "the corners must have one entry per dimension"
+        );
+        assert!(
+            min_corner
+                .iter()
+                .zip(max_corner)
+                .all(|(lower, upper)| lower < upper && (upper - lower).is_finite()),
+            "the box must be finite and not empty along every axis"
+        );
+        let mut inside = Vec::new();
+        let mut border = Vec::new();
+        let min_dst_sqr = self.min_distance * self.min_distance;
+        for sample in &self.samples {
+            let moved: Point = sample.iter().zip(min_corner).map(|(x, o)| x - o).collect();
+            let to_box = sample
+                .iter()
+                .zip(min_corner.iter().zip(max_corner))
+                .map(|(x, (lower, upper))| (lower - x).max(x - upper).max(0_f64).powi(2))
+                .sum::<f64>();
+            let in_box = sample
+                .iter()
+                .zip(min_corner.iter().zip(max_corner))
+                .all(|(x, (lower, upper))| lower <= x && x < upper);
+            if in_box {
+                inside.push(moved);
+            } else if with_border && to_box < min_dst_sqr {
+                border.push(moved);
+            }
+        }
+        let dimensions = min_corner
+            .iter()
+            .zip(max_corner)
+            .map(|(lower, upper)| upper - lower)
+            .collect();
+        Crop {
+            set: PoissonDiskSet::from_points(dimensions, self.min_distance, inside)
+                .expect("a subset of a valid set is valid"),
+            border,
+        }
+    }
+
+    /// Checks whether a sample at `position` would keep the minimal distance
+    /// to all the samples, without adding it. If not, reports which sample
+    /// is in the way.
+    ///
+    /// ```
+    /// use bluenoisers::{Placement, PoissonDiskSet};
+    ///
+    /// let set = PoissonDiskSet::new(vec![50., 50.], 2., 30);
+    /// let sample = set.samples()[0].clone();
+    /// assert_eq!(set.placement(&sample), Placement::Blocked(0));
+    /// assert_eq!(set.placement(&[-1., 0.]), Placement::Outside);
+    /// ```
+    pub fn placement(&self, position: &[f64]) -> Placement {
+        debug_assert_eq!(position.len(), self.grid.dimensions.len());
+        if !self.grid.contains(position) {
+            return Placement::Outside;
+        }
+        // a little farther, for samples exactly the minimal distance away
+        // which conflict if they must be farther apart
+        let reach = self.min_distance * (1_f64 + 1e-9);
+        self.within(position, reach)
+            .map(|i| (i, BackgroundGrid::dst_sqr(position, &self.samples[i])))
+            .filter(|(_, dst_sqr)| *dst_sqr < self.grid.min_dst_sqr)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(Placement::Free, |(i, _)| Placement::Blocked(i))
+    }
+
+    /// Whether a sample could be added at `position`, see
+    /// [`placement`](PoissonDiskSet::placement).
+    pub fn can_insert(&self, position: &[f64]) -> bool {
+        self.placement(position) == Placement::Free
+    }
+
+    /// Adds a sample at `position` if [`can_insert`](PoissonDiskSet::can_insert)
+    /// allows it. Returns whether it was added; it gets the next index.
+    pub fn try_insert(&mut self, position: Point) -> bool {
+        if !self.can_insert(&position) {
+            return false;
+        }
+        self.grid
+            .insert(position, &mut self.samples)
+            .expect("the grid checks less than `placement`");
+        true
+    }
+
+    /// Like [`try_insert`](PoissonDiskSet::try_insert), but a position at
+    /// most `epsilon` away from a sample is fused with it instead of being
+    /// rejected, e.g. to import points of which some are already in the set.
+    ///
+    /// Returns the index of the new sample or of the one it was fused with,
+    /// or `None` if the position is outside or blocked by a sample farther
+    /// away than `epsilon`.
+    ///
+    /// ```
+    /// use bluenoisers::{Point, PoissonDiskSet};
+    ///
+    /// let mut set = PoissonDiskSet::new(vec![50., 50.], 2., 30);
+    /// let mut nearby = set.samples()[7].clone();
+    /// nearby[0] += 1e-6;
+    /// assert_eq!(set.insert_dedup(nearby, 1e-3), Some(7));
+    /// ```
+    pub fn insert_dedup(&mut self, position: Point, epsilon: f64) -> Option<usize> {
+        match self.placement(&position) {
+            Placement::Free => {
+                let id = self
+                    .grid
+                    .insert(position, &mut self.samples)
+                    .expect("the grid checks less than `placement`");
+                Some(id - 1)
+            }
+            Placement::Blocked(i)
+                if BackgroundGrid::dst_sqr(&position, &self.samples[i]) <= epsilon * epsilon =>
+            {
+                Some(i)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Retires `fraction` of the samples of `set`, chosen at random, and moves
+/// each to a new position less than the minimal distance away which keeps
+/// the minimal distance to all the other samples, e.g. to let sparkles fade
+/// out and in again every frame while the pattern stays blue noise.
+///
+/// A sample keeps its index when it moves, so per sample state like an age
+/// can be kept alongside the set. The number of samples to retire is
+/// rounded at random, so that small fractions of small sets still retire
+/// samples on average.
+///
+/// A few random positions around a retired sample are tried first. In a
+/// dense set, none of them is free for about one sample in eight in 2D and
+/// more often the more dimensions, about three in five in 7D. Then a few
+/// random positions in empty cells of the grid anywhere in the domain are
+/// tried, and finally positions closer to the old one than its closest
+/// neighbor is to the minimal distance, which are always free but may be
+/// very close. So a retired sample only stays where it is if another sample
+/// is exactly the minimal distance away from it, e.g. in a lattice.
+///
+/// Returns the indices of the samples which moved, ascending. Takes time
+/// proportional to their number, not to the size of the set.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, PoissonDiskSet, recycle};
+/// use rand::SeedableRng;
+///
+/// let mut set = PoissonDiskSet::generate(&BlueNoiseConfig::new(vec![50., 50.], 1.).seed(1));
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+/// for _frame in 0..10 {
+///     let moved = recycle(&mut set, 0.05, &mut rng);
+///     // e.g. restart the animation of the moved sparkles
+///     assert!(moved.len() <= set.len() / 10);
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `fraction` is not between 0 and 1.
+pub fn recycle<R: Rng>(set: &mut PoissonDiskSet, fraction: f64, rng: &mut R) -> Vec<usize> {
+    assert!(
+        (0_f64..=1_f64).contains(&fraction),
+        "the fraction must be between 0 and 1"
+    );
+    let len = set.samples.len();
+    let expected = fraction * len as f64;
+    let count = expected as usize + usize::from(rng.gen_bool(expected.fract()));
+    let mut retired = rand::seq::index::sample(rng, len, count.min(len)).into_vec();
+    retired.sort_unstable();
+    let radius = set.min_distance;
+    let mut moved = Vec::new();
+    for i in retired {
+        // one after another, so that the old position is always free again
+        let old = set.samples[i].clone();
+        set.grid.remove(&old, i + 1);
+        let position = (0..RECYCLE_TRIES)
+            .map(|_| ball_point(&old, radius, rng))
+            .find(|position| set.placement(position) == Placement::Free)
+            .or_else(|| {
+                (0..RECYCLE_TRIES)
+                    .filter_map(|_| dart(&set.grid, rng))
+                    .find(|position| set.placement(position) == Placement::Free)
+            })
+            .or_else(|| {
+                // closer to the old position than its closest neighbor is
+                // to the minimal distance, which keeps that from all samples
+                let nearest = set
+                    .within(&old, 2_f64 * radius)
+                    .map(|j| BackgroundGrid::dst_sqr(&old, &set.samples[j]).sqrt())
+                    .fold(2_f64 * radius, f64::min);
+                let free = nearest - radius;
+                (free > 0_f64).then(|| {
+                    (0..RECYCLE_TRIES)
+                        .map(|_| ball_point(&old, free, rng))
+                        .find(|position| set.placement(position) == Placement::Free)
+                })?
+            });
+        match position {
+            Some(position) => {
+                set.grid.put(&position, i + 1);
+                set.samples[i] = position;
+                moved.push(i);
+            }
+            None => set.grid.put(&old, i + 1),
+        }
+    }
+    moved
+}
+
+/// A uniformly distributed point less than `radius` away from `center`.
+fn ball_point<R: Rng>(center: &[f64], radius: f64, rng: &mut R) -> Point {
+    loop {
+        let offset: Point = center
+            .iter()
+            .map(|_| rng.gen_range(-radius..radius))
+            .collect();
+        if offset.iter().map(|o| o * o).sum::<f64>() < radius * radius {
+            return center
+                .iter()
+                .zip(offset.iter())
+                .map(|(x, o)| x + o)
+                .collect();
+        }
+    }
+}
+
+/// A uniformly distributed point in a random empty cell of `grid`, or `None`
+/// if the cell drawn holds a sample. Sparse grids don't keep their empty
+/// cells, so for those the point is anywhere in the domain.
+fn dart<R: Rng>(grid: &BackgroundGrid, rng: &mut R) -> Option<Point> {
+    if grid.sparse.is_some() {
+        return Some(
+            grid.dimensions
+                .iter()
+                .map(|x| crate::coordinate(rng, 0_f64, *x))
+                .collect(),
+        );
+    }
+    let idx = rng.gen_range(0..grid.data.len());
+    if grid.data[idx] != 0 {
+        return None;
+    }
+    let mut rest = idx;
+    Some(
+        grid.cell_count
+            .iter()
+            .zip(grid.dimensions.iter())
+            .map(|(count, dim)| {
+                let lower = (rest % count) as f64 * grid.cell_size;
+                rest /= count;
+                crate::coordinate(rng, lower, (lower + grid.cell_size).min(*dim))
+            })
+            .collect(),
+    )
+}
+
+/// A part of a [`PoissonDiskSet`], see [`PoissonDiskSet::crop`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Crop {
+    /// The samples inside of the window, relative to its minimal corner.
+    pub set: PoissonDiskSet,
+    /// The samples outside of the window but less than the minimal distance
+    /// away from it, relative to its minimal corner. They limit where samples
+    /// can be added to `set` without violating the minimal distance to the
+    /// rest of the original set.
+    pub border: Vec<Point>,
+}
+
+/// Whether a sample could be added to a [`PoissonDiskSet`] at a position, see
+/// [`PoissonDiskSet::placement`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The position keeps the minimal distance to all samples.
+    Free,
+    /// The position is outside of the domain.
+    Outside,
+    /// The sample with this index is too close; if there are several, the
+    /// closest one.
+    Blocked(usize),
+}
+
+impl BlueNoiseIterator {
+    /// Generates the remaining samples and returns the complete set.
+    pub fn into_set(mut self) -> PoissonDiskSet {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("generate", dimensions = ?self.dimensions).entered();
+        while self.next_ref().is_some() {}
+        #[cfg(feature = "tracing")]
+        tracing::debug!(samples = self.samples.len(), stats = ?self.stats, "generated set");
+        PoissonDiskSet {
+            grid: self.bggrid,
+            samples: self.samples,
+            min_distance: self.min_distance,
+            stats: self.stats,
+        }
+    }
+}
+
+impl From<PoissonDiskSet> for Vec<Point> {
+    fn from(set: PoissonDiskSet) -> Vec<Point> {
+        set.samples
+    }
+}
+
+/// The serialized form of a [`PoissonDiskSet`], without the grid, which is
+/// rebuilt when deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SetData<S> {
+    dimensions: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bounds: Vec<Interval>,
+    min_distance: f64,
+    samples: S,
+    stats: GenerationStats,
+}
+
+/// Serializes the size of the domain, the minimal distance, the samples and
+/// the [statistics](PoissonDiskSet::stats).
+///
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PoissonDiskSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SetData {
+            dimensions: self.dimensions().to_vec(),
+            bounds: self.grid.bounds.clone(),
+            min_distance: self.min_distance,
+            samples: &self.samples,
+            stats: self.stats,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes a set like [`from_points`](PoissonDiskSet::from_points), so
+/// it fails if the domain is not valid, or the samples violate the minimal
+/// distance or are outside of the domain.
+///
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PoissonDiskSet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SetData::<Vec<Point>>::deserialize(deserializer)?
+            .into_set()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SetData<Vec<Point>> {
+    /// Rebuilds the set, or describes why the data is not valid.
+    fn into_set(self) -> Result<PoissonDiskSet, String> {
+        check_domain(&self.dimensions, self.min_distance).map_err(|error| error.to_string())?;
+        if !self.bounds.is_empty() && self.bounds.len() != self.dimensions.len() {
+            return Err("there must be an interval per dimension".to_string());
+        }
+        let mut set = PoissonDiskSet::from_points_with_bounds(
+            self.dimensions,
+            self.min_distance,
+            self.bounds,
+            self.samples,
+        )
+        .map_err(|violations| {
+            format!("the samples are not valid, {} violations", violations.len())
+        })?;
+        set.stats = self.stats;
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    /// A reproducible set with a minimal distance of one.
+    fn seeded(dimensions: &[f64], k_abort: usize, seed: u64) -> PoissonDiskSet {
+        PoissonDiskSet::generate(
+            &BlueNoiseConfig::new(dimensions.to_vec(), 1.)
+                .k_abort(k_abort)
+                .seed(seed),
+        )
+    }
+
+    #[test]
+    fn nearest() {
+        for dimensions in [vec![30., 20.], vec![10., 12., 9.], vec![3.; 7]] {
+            let set = seeded(&dimensions, 30, 1);
+            let mut rng = ChaCha8Rng::seed_from_u64(2);
+            for _ in 0..100 {
+                // include queries outside of the domain
+                let query: Point = dimensions
+                    .iter()
+                    .map(|x| rand::Rng::gen_range(&mut rng, -3_f64..x + 3.))
+                    .collect();
+                let expected = set
+                    .samples()
+                    .iter()
+                    .map(|s| BackgroundGrid::dst_sqr(s, &query).sqrt())
+                    .fold(f64::INFINITY, f64::min);
+                let (index, distance) = set.nearest(&query).unwrap();
+                assert_eq!(distance, expected);
+                assert_eq!(
+                    BackgroundGrid::dst_sqr(&set.samples()[index], &query).sqrt(),
+                    distance
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn knn() {
+        for dimensions in [vec![30., 20.], vec![10., 12., 9.], vec![3.; 7]] {
+            let set = seeded(&dimensions, 30, 3);
+            let mut rng = ChaCha8Rng::seed_from_u64(4);
+            for k in [0, 1, 5, 40] {
+                let query: Point = dimensions
+                    .iter()
+                    .map(|x| rand::Rng::gen_range(&mut rng, -3_f64..x + 3.))
+                    .collect();
+                let mut expected: Vec<f64> = set
+                    .samples()
+                    .iter()
+                    .map(|s| BackgroundGrid::dst_sqr(s, &query).sqrt())
+                    .collect();
+                expected.sort_by(f64::total_cmp);
+                expected.truncate(k);
+                let found = set.knn(&query, k);
+                let distances: Vec<f64> = found.iter().map(|(_, d)| *d).collect();
+                assert_eq!(distances, expected);
+                for (index, distance) in found {
+                    assert_eq!(
+                        BackgroundGrid::dst_sqr(&set.samples()[index], &query).sqrt(),
+                        distance
+                    );
+                }
+            }
+        }
+        let tiny = PoissonDiskSet::new(vec![1., 1.], 3., 30);
+        assert_eq!(tiny.knn(&[0.5, 0.5], 3).len(), 1);
+    }
+
+    #[test]
+    fn within() {
+        for dimensions in [vec![30., 20.], vec![10., 12., 9.], vec![3.; 7]] {
+            let set = seeded(&dimensions, 30, 5);
+            let mut rng = ChaCha8Rng::seed_from_u64(6);
+            for radius in [0., 0.5, 2.5, 7.] {
+                let query: Point = dimensions
+                    .iter()
+                    .map(|x| rand::Rng::gen_range(&mut rng, -3_f64..x + 3.))
+                    .collect();
+                let expected: Vec<usize> = (0..set.len())
+                    .filter(|i| {
+                        BackgroundGrid::dst_sqr(&set.samples()[*i], &query) < radius * radius
+                    })
+                    .collect();
+                let mut found: Vec<usize> = set.within(&query, radius).collect();
+                found.sort_unstable();
+                assert_eq!(found, expected);
+            }
+        }
+        let set = seeded(&[30., 20.], 30, 7);
+        assert_eq!(set.within(&[-5., 10.], 4.).count(), 0);
+        assert_eq!(set.within(&[15., 10.], 100.).count(), set.len());
+    }
+
+    #[test]
+    fn placement() {
+        // with a single try per sample, there are gaps left
+        let mut set = seeded(&[30., 20.], 1, 8);
+        let mut rng = ChaCha8Rng::seed_from_u64(9);
+        let mut inserted = 0;
+        for _ in 0..1000 {
+            let position: Point = [35_f64, 25.]
+                .iter()
+                .map(|x| rand::Rng::gen_range(&mut rng, -5_f64..*x))
+                .collect();
+            let inside = (0. ..30.).contains(&position[0]) && (0. ..20.).contains(&position[1]);
+            let nearest = set.nearest(&position).unwrap();
+            match set.placement(&position) {
+                Placement::Outside => assert!(!inside),
+                Placement::Blocked(i) => {
+                    assert!(inside);
+                    assert_eq!(i, nearest.0);
+                    assert!(nearest.1 < 1.);
+                }
+                Placement::Free => {
+                    assert!(inside && nearest.1 >= 1.);
+                    assert!(set.try_insert(position.clone()));
+                    assert_eq!(set.placement(&position), Placement::Blocked(set.len() - 1));
+                    inserted += 1;
+                }
+            }
+        }
+        assert!(inserted > 0);
+        let mut sparse =
+            PoissonDiskSet::generate(&BlueNoiseConfig::new(vec![3.; 7], 2.).k_abort(1).seed(10));
+        let blocked = sparse.samples()[0].clone();
+        assert!(!sparse.try_insert(blocked));
+    }
+
+    #[test]
+    fn recycle() {
+        for dimensions in [vec![30., 20.], vec![6.; 3], vec![3.; 7]] {
+            let mut set = seeded(&dimensions, 30, 11);
+            let before = set.samples().to_vec();
+            let mut rng = ChaCha8Rng::seed_from_u64(1);
+            let moved = super::recycle(&mut set, 0.2, &mut rng);
+            // all the retired samples move, also where neighbors take more
+            // space than there is around them
+            assert!(moved.len() + 1 >= set.len() / 5);
+            assert!(moved.len() <= set.len() / 5 + 1);
+            assert_eq!(set.len(), before.len());
+            for (i, (now, old)) in set.samples().iter().zip(before.iter()).enumerate() {
+                let distance = BackgroundGrid::dst_sqr(now, old).sqrt();
+                assert_eq!(moved.binary_search(&i).is_ok(), distance > 0.);
+            }
+            validate(set.samples(), 1., &dimensions).unwrap();
+            // the grid follows the moves
+            let rebuilt =
+                PoissonDiskSet::from_points(dimensions.clone(), 1., set.samples().to_vec())
+                    .unwrap();
+            for sample in &before {
+                assert_eq!(set.nearest(sample), rebuilt.nearest(sample));
+            }
+            assert!(super::recycle(&mut set, 0., &mut rng).is_empty());
+        }
+        let mut set = seeded(&[30., 20.], 30, 12);
+        let moved = super::recycle(&mut set, 1., &mut ChaCha8Rng::seed_from_u64(13));
+        assert_eq!(moved.len(), set.len());
+        validate(set.samples(), 1., &[30., 20.]).unwrap();
+        // samples exactly the minimal distance from another can't move
+        let lattice: Vec<Point> = (0..10).map(|i| Point::from_slice(&[i as f64])).collect();
+        let mut set = PoissonDiskSet::from_points(vec![10.], 1., lattice).unwrap();
+        let moved = super::recycle(&mut set, 1., &mut ChaCha8Rng::seed_from_u64(14));
+        assert!(moved.len() < set.len());
+    }
+
+    #[test]
+    fn crop() {
+        let set = seeded(&[30., 20.], 30, 1);
+        let crop = set.crop(&[5., 5.], &[15., 12.], true);
+        let inside = set
+            .samples()
+            .iter()
+            .filter(|s| (5. ..15.).contains(&s[0]) && (5. ..12.).contains(&s[1]))
+            .count();
+        assert_eq!(crop.set.len(), inside);
+        assert!(!crop.border.is_empty());
+        for sample in &crop.border {
+            let outside = !(0. ..10.).contains(&sample[0]) || !(0. ..7.).contains(&sample[1]);
+            let near = (-1. ..11.).contains(&sample[0]) && (-1. ..8.).contains(&sample[1]);
+            assert!(outside && near);
+        }
+        // positions which are free in the crop and not blocked by the border
+        // are free in the original set
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let position: Point = [10_f64, 7.]
+                .iter()
+                .map(|x| rand::Rng::gen_range(&mut rng, 0_f64..*x))
+                .collect();
+            let blocked = crop
+                .border
+                .iter()
+                .any(|b| BackgroundGrid::dst_sqr(b, &position) < 1.);
+            let original: Point = position.iter().map(|x| x + 5.).collect();
+            assert_eq!(
+                crop.set.can_insert(&position) && !blocked,
+                set.can_insert(&original)
+            );
+        }
+        assert!(set.crop(&[5., 5.], &[15., 12.], false).border.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "not empty")]
+    fn crop_inverted() {
+        seeded(&[30., 20.], 30, 1).crop(&[5., 12.], &[15., 5.], false);
+    }
+
+    #[test]
+    fn from_points() {
+        for dimensions in [vec![30., 20.], vec![3.; 7]] {
+            let generated = PoissonDiskSet::new(dimensions.clone(), 1., 30);
+            let set =
+                PoissonDiskSet::from_points(dimensions.clone(), 1., generated.samples().to_vec())
+                    .unwrap();
+            assert_eq!(set.samples(), generated.samples());
+            let query = vec![1.5; dimensions.len()];
+            assert_eq!(set.knn(&query, 3), generated.knn(&query, 3));
+            assert!(!set.can_insert(&generated.samples()[0]));
+        }
+        let points = vec![Point::from_slice(&[1., 1.]), Point::from_slice(&[1.5, 1.])];
+        assert_eq!(
+            PoissonDiskSet::from_points(vec![5., 5.], 1., points).unwrap_err(),
+            vec![Violation::TooClose {
+                first: 0,
+                second: 1,
+                distance: 0.5
+            }]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let set = PoissonDiskSet::new(vec![8., 6.], 1., 30);
+        let json = serde_json::to_string(&set).unwrap();
+        let loaded: PoissonDiskSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.samples(), set.samples());
+        assert_eq!(loaded.dimensions(), set.dimensions());
+        assert_eq!(loaded.stats(), set.stats());
+        assert_eq!(loaded.nearest(&[4., 3.]), set.nearest(&[4., 3.]));
+
+        let config = BlueNoiseConfig::new(vec![8., 6.], 1.).seed(3);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BlueNoiseConfig>(&json).unwrap(),
+            config
+        );
+
+        let too_close = format!(
+            r#"{{"dimensions":[8,6],"min_distance":1,"samples":[[1,1],[1.5,1]],"stats":{}}}"#,
+            serde_json::to_string(&GenerationStats::default()).unwrap()
+        );
+        let error = serde_json::from_str::<PoissonDiskSet>(&too_close).unwrap_err();
+        assert!(error.to_string().contains("1 violations"));
+
+        let domain = |dimensions: &str| {
+            format!(
+                r#"{{"dimensions":{dimensions},"min_distance":1,"samples":[],"stats":{}}}"#,
+                serde_json::to_string(&GenerationStats::default()).unwrap()
+            )
+        };
+        // a flat axis is valid, a negative one isn't
+        assert!(serde_json::from_str::<PoissonDiskSet>(&domain("[0,5]")).is_ok());
+        let error = serde_json::from_str::<PoissonDiskSet>(&domain("[-1,5]")).unwrap_err();
+        assert!(error.to_string().contains("axis 0"));
+        // JSON has no NaN
+        let nan = SetData {
+            dimensions: vec![5., f64::NAN],
+            bounds: Vec::new(),
+            min_distance: 1.,
+            samples: Vec::new(),
+            stats: GenerationStats::default(),
+        };
+        assert!(nan.into_set().unwrap_err().contains("axis 1"));
+    }
+}
@@ -0,0 +1,74 @@
+//! Generation on a background thread, streaming the samples over a channel.
+
+use crate::{BlueNoiseConfig, GenerationStats, Point};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+/// Consecutive samples sent by [`blue_noise_spawn`], in order of generation.
+pub type Batch = Vec<Point>;
+
+/// Generates the samples of `config` on a new thread and sends them over a
+/// channel in batches of up to `batch_size`, so an interactive application
+/// can start using the first samples while the rest is still being
+/// generated.
+///
+/// The batches concatenate to the samples of
+/// [`generate`](BlueNoiseConfig::generate). The channel is unbounded, so the
+/// generator never waits for the receiver. If the receiver is dropped,
+/// generation stops early. Joining the handle gives the
+/// [`GenerationStats`], after the last batch has been sent.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, blue_noise_spawn};
+///
+/// let config = BlueNoiseConfig::new(vec![64., 64.], 1.).seed(3);
+/// let (handle, batches) = blue_noise_spawn(config.clone(), 100);
+/// let mut samples = Vec::new();
+/// for batch in batches {
+///     // e.g. upload the batch to the GPU and draw a frame
+///     samples.extend(batch);
+/// }
+/// let stats = handle.join().unwrap();
+/// assert_eq!(stats.samples, samples.len());
+/// assert_eq!(samples, config.generate());
+/// ```
+///
+/// # Panics
+///
+/// Panics if `batch_size` is zero.
+pub fn blue_noise_spawn(
+    config: BlueNoiseConfig,
+    batch_size: usize,
+) -> (JoinHandle<GenerationStats>, Receiver<Batch>) {
+    assert!(batch_size > 0, "the batch size must be positive");
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let mut it = config.iter();
+        loop {
+            let batch: Batch = it.by_ref().take(batch_size).collect();
+            let last = batch.len() < batch_size;
+            if batch.is_empty() || sender.send(batch).is_err() || last {
+                return it.stats();
+            }
+        }
+    });
+    (handle, receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_receiver() {
+        let config = BlueNoiseConfig::new(vec![1000., 1000.], 1.).seed(2);
+        let (handle, batches) = blue_noise_spawn(config, 10);
+        let first = batches.recv().unwrap();
+        assert_eq!(first.len(), 10);
+        drop(batches);
+        // stops soon after the receiver is gone instead of generating the
+        // whole pattern
+        let stats = handle.join().unwrap();
+        assert!(stats.samples < 100_000);
+    }
+}
@@ -0,0 +1,243 @@
+//! Blue noise on spheres, where the distances are angles.
+
+use crate::Point;
+use crate::grid::BackgroundGrid;
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+use rand::Rng;
+
+/// Generates rotations which are at least `min_angle` radians apart, as unit
+/// quaternions `[x, y, z, w]`, e.g. for the approach directions of a grasp
+/// planner or the orientations of scattered instances.
+///
+/// Two rotations are as far apart as the angle of the rotation from one to
+/// the other, `2 acos(|p · q|)` for quaternions `p` and `q`. Since `q` and
+/// `-q` are the same rotation, this is a Poisson disk set on the sphere in
+/// four dimensions with opposite points identified, and every quaternion is
+/// returned with `w >= 0`. The quaternions are in the order of `glam`'s
+/// `DQuat::from_array`.
+///
+/// The first rotation and the candidates around the others are drawn from
+/// `rng` itself, so seeding it makes the rotations reproducible.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let rotations = bluenoisers::blue_noise_rotations(0.5, 30, &mut rng);
+/// let angle = |p: &[f64; 4], q: &[f64; 4]| {
+///     let dot: f64 = p.iter().zip(q.iter()).map(|(p, q)| p * q).sum();
+///     2. * dot.abs().min(1.).acos()
+/// };
+/// assert!(angle(&rotations[0], &rotations[1]) >= 0.5 - 1e-9);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `min_angle` is not in `(0, π]`.
+pub fn blue_noise_rotations<R: Rng>(min_angle: f64, k_abort: usize, rng: &mut R) -> Vec<[f64; 4]> {
+    assert!(
+        0_f64 < min_angle && min_angle <= PI,
+        "the minimal angle must be in (0, π]"
+    );
+    // the quaternions are half the angle of their rotation apart
+    spherical(4, min_angle / 2_f64, true, k_abort, rng)
+        .into_iter()
+        .map(|q| {
+            let sign = if q[3] < 0_f64 { -1_f64 } else { 1_f64 };
+            [sign * q[0], sign * q[1], sign * q[2], sign * q[3]]
+        })
+        .collect()
+}
+
+/// Generates unit vectors in `dimension` dimensions which are at least
+/// `min_angle` radians apart, i.e. blue noise on the sphere *Sⁿ⁻¹*, e.g. for
+/// the gradients of a procedural noise or a bank of ray directions.
+///
+/// The first direction and the candidates, at a random angle along a random
+/// tangent, are drawn from `rng`, so a seeded `rng` gives the same
+/// directions.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let directions = bluenoisers::blue_noise_directions(3, 0.2, 30, &mut rng);
+/// let dot: f64 = directions[0].iter().zip(directions[1].iter()).map(|(a, b)| a * b).sum();
+/// assert!(dot.min(1.).acos() >= 0.2 - 1e-9);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `dimension` is less than two or `min_angle` is not in `(0, π]`.
+pub fn blue_noise_directions<R: Rng>(
+    dimension: usize,
+    min_angle: f64,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<Point> {
+    assert!(dimension >= 2, "there must be at least two dimensions");
+    assert!(
+        0_f64 < min_angle && min_angle <= PI,
+        "the minimal angle must be in (0, π]"
+    );
+    spherical(dimension, min_angle, false, k_abort, rng)
+}
+
+/// Generates unit vectors in `dimension` dimensions which are at least
+/// `min_angle` apart, and if `antipodal` also at least `min_angle` apart from
+/// the opposites of each other.
+///
+/// The samples grow from a random one like in [`blue_noise`](crate::blue_noise),
+/// with candidates between one and two times `min_angle` away along great
+/// circles. The grid is over the sphere moved into `[0, 2]ⁿ`, with the
+/// chord of the minimal angle as minimal distance, and sparse, since only the
+/// cells along the sphere are occupied.
+fn spherical<R: Rng>(
+    dimension: usize,
+    min_angle: f64,
+    antipodal: bool,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<Point> {
+    let min_distance = 2_f64 * (min_angle / 2_f64).sin();
+    // the coordinates of unit vectors are in [-1, 1]
+    let mut bggrid = BackgroundGrid::new_sparse(vec![2_f64.next_up(); dimension], min_distance);
+    let mut shifted: Vec<Point> = Vec::new();
+    let mut try_add = |direction: &[f64], bggrid: &mut BackgroundGrid| {
+        if antipodal {
+            let opposite: Point = direction.iter().map(|x| 1_f64 - x).collect();
+            if bggrid.check(&opposite, &shifted).is_err() {
+                return None;
+            }
+        }
+        let position: Point = direction.iter().map(|x| x + 1_f64).collect();
+        bggrid.insert(position, &mut shifted).ok()
+    };
+
+    let mut samples: Vec<Point> = Vec::new();
+    let first = loop {
+        if let Some(first) = normalized(gaussian(dimension, rng)) {
+            break first;
+        }
+    };
+    try_add(&first, &mut bggrid).expect("the first sample has no neighbors");
+    samples.push(first);
+    let mut active = vec![0];
+    while !active.is_empty() {
+        let active_idx = rng.gen_range(0..active.len());
+        let current = samples[active[active_idx]].clone();
+        let mut found = false;
+        for _ in 0..k_abort {
+            let angle = rng.gen_range(min_angle..2_f64 * min_angle).min(PI);
+            let Some(samp) = along_great_circle(&current, angle, rng) else {
+                continue;
+            };
+            if try_add(&samp, &mut bggrid).is_none() {
+                continue;
+            }
+            samples.push(samp);
+            active.push(samples.len() - 1);
+            found = true;
+            break;
+        }
+        if !found {
+            active.swap_remove(active_idx);
+        }
+    }
+    samples
+}
+
+/// The unit vector `angle` away from the unit vector `center` in a random
+/// direction, or `None` if the drawn direction was degenerate.
+fn along_great_circle<R: Rng>(center: &[f64], angle: f64, rng: &mut R) -> Option<Point> {
+    // a random direction perpendicular to the center
+    let mut tangent = gaussian(center.len(), rng);
+    let dot: f64 = tangent.iter().zip(center.iter()).map(|(t, c)| t * c).sum();
+    for (t, c) in tangent.iter_mut().zip(center.iter()) {
+        *t -= dot * c;
+    }
+    let tangent = normalized(tangent)?;
+    let (sin, cos) = (angle.sin(), angle.cos());
+    normalized(
+        center
+            .iter()
+            .zip(tangent.iter())
+            .map(|(c, t)| cos * c + sin * t)
+            .collect(),
+    )
+}
+
+/// A vector of independent standard normal coordinates, whose direction is
+/// uniform.
+pub(crate) fn gaussian<R: Rng>(dimension: usize, rng: &mut R) -> Point {
+    (0..dimension)
+        .map(|_| {
+            // Box-Muller, with the first number in (0, 1]
+            let u = 1_f64 - rng.r#gen::<f64>();
+            let v: f64 = rng.r#gen();
+            (-2_f64 * u.ln()).sqrt() * (2_f64 * PI * v).cos()
+        })
+        .collect()
+}
+
+/// `vector` scaled to unit length, or `None` if it is too short.
+pub(crate) fn normalized(mut vector: Point) -> Option<Point> {
+    let length = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if length < 1e-12 {
+        return None;
+    }
+    for x in vector.iter_mut() {
+        *x /= length;
+    }
+    Some(vector)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn rotations() {
+        let generate = || blue_noise_rotations(0.35, 30, &mut ChaCha8Rng::seed_from_u64(5));
+        let rotations = generate();
+        assert_eq!(rotations, generate());
+        for (i, p) in rotations.iter().enumerate() {
+            assert!((p.iter().map(|x| x * x).sum::<f64>() - 1.).abs() < 1e-12);
+            assert!(p[3] >= 0.);
+            for q in &rotations[..i] {
+                let dot: f64 = p.iter().zip(q.iter()).map(|(p, q)| p * q).sum();
+                assert!(2. * dot.abs().min(1.).acos() >= 0.35 - 1e-9);
+            }
+        }
+        // balls of half the angle, which cover a fraction (a - sin a) / π of
+        // all rotations each, fill about a third like in three dimensions
+        let a = 0.35_f64 / 2.;
+        let filled = rotations.len() as f64 * (a - a.sin()) / PI;
+        assert!((0.28..0.36).contains(&filled));
+    }
+
+    #[test]
+    fn directions() {
+        for (dimension, min_angle) in [(2, 0.1), (3, 0.15), (8, 0.8)] {
+            let mut rng = ChaCha8Rng::seed_from_u64(2);
+            let directions = blue_noise_directions(dimension, min_angle, 30, &mut rng);
+            for (i, a) in directions.iter().enumerate() {
+                assert_eq!(a.len(), dimension);
+                assert!((a.iter().map(|x| x * x).sum::<f64>() - 1.).abs() < 1e-12);
+                for b in &directions[..i] {
+                    let dot: f64 = a.iter().zip(b.iter()).map(|(a, b)| a * b).sum();
+                    assert!(dot.min(1.).acos() >= min_angle - 1e-9);
+                }
+            }
+        }
+        // on the circle, between half and all of the 62 that fit
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        assert!((31..=62).contains(&blue_noise_directions(2, 0.1, 30, &mut rng).len()));
+    }
+}
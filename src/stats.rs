@@ -0,0 +1,53 @@
+//! Counters collected during generation.
+
+use core::time::Duration;
+
+/// What happened during generation, to tune `k_abort` and the minimal
+/// distance, see [`BlueNoiseIterator::stats`](crate::BlueNoiseIterator::stats).
+///
+/// ```
+/// let mut it = bluenoisers::blue_noise_iter(vec![30., 30.], 1., 30);
+/// let count = it.by_ref().count();
+/// let stats = it.stats();
+/// assert_eq!(stats.samples, count);
+/// // every accepted candidate became a sample, except the first one
+/// assert_eq!(
+///     stats.candidates - stats.rejected_outside - stats.rejected_too_close,
+///     count - 1
+/// );
+/// println!(
+///     "{:.1} candidates per sample",
+///     stats.candidates as f64 / stats.samples as f64
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenerationStats {
+    /// The number of generated and seeded samples.
+    pub samples: usize,
+    /// The number of candidates around active samples which were tried.
+    pub candidates: usize,
+    /// The candidates which were outside of the domain or region.
+    pub rejected_outside: usize,
+    /// The candidates which were too close to an existing sample.
+    pub rejected_too_close: usize,
+    /// The active samples which were retired after `k_abort` rejected
+    /// candidates.
+    pub retired: usize,
+    /// How often the active list was worked through and replaced by the
+    /// samples which stayed or became active.
+    pub rounds: usize,
+    /// The random positions tried to restart growth when exhausting the
+    /// domain, e.g. for tiles and refills.
+    pub darts: usize,
+    /// The samples placed into gaps once growth stopped, to keep every
+    /// position within the maximal distance, see
+    /// [`BlueNoiseConfig::max_distance`](crate::BlueNoiseConfig::max_distance).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filled: usize,
+    /// The time spent growing from active samples. Without the `std`
+    /// feature, there is no clock and the times stay zero.
+    pub growth_time: Duration,
+    /// The time spent on random restarts.
+    pub dart_time: Duration,
+}
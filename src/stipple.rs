@@ -0,0 +1,78 @@
+//! Stippling: dots whose density follows the tone of an image.
+
+use crate::{Point, blue_noise_variable};
+use rand::Rng;
+
+/// Places dots with blue noise spacing on `image`, the more the darker the
+/// image is, e.g. to draw it with a pen plotter.
+///
+/// The dots are in pixel coordinates, in a domain of the size of the image
+/// with the origin at the top left. Where the image is black, dots are
+/// `min_radius` apart; elsewhere the number of dots per area is proportional
+/// to the darkness, up to a spacing of `max_radius`, which is where white
+/// areas end up. See [`blue_noise_variable`] for the spacing.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// // a gradient from black on the left to white on the right
+/// let image = image::GrayImage::from_fn(64, 32, |x, _| image::Luma([(x * 4) as u8]));
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let dots = bluenoisers::stipple(&image, 1., 8., 30, &mut rng);
+/// let left = dots.iter().filter(|p| p[0] < 32.).count();
+/// assert!(left > 2 * (dots.len() - left));
+/// ```
+///
+/// Requires the `image` feature.
+///
+/// # Panics
+///
+/// Panics if the image is empty, `min_radius` is not positive or `max_radius`
+/// is less than `min_radius`.
+pub fn stipple<R: Rng>(
+    image: &image::GrayImage,
+    min_radius: f64,
+    max_radius: f64,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<Point> {
+    let (width, height) = image.dimensions();
+    assert!(width > 0 && height > 0, "the image must not be empty");
+    let radius = |position: &[f64]| {
+        let x = (position[0] as u32).min(width - 1);
+        let y = (position[1] as u32).min(height - 1);
+        let darkness = 1_f64 - f64::from(image.get_pixel(x, y)[0]) / 255_f64;
+        // the density goes with the inverse square of the radius; white gives
+        // an infinite radius, which is clamped
+        min_radius / darkness.sqrt()
+    };
+    blue_noise_variable(
+        vec![f64::from(width), f64::from(height)],
+        min_radius,
+        max_radius,
+        radius,
+        k_abort,
+        rng,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn stipple() {
+        // black, mid gray and white thirds
+        let image =
+            image::GrayImage::from_fn(90, 30, |x, _| image::Luma([(x / 30 * 255 / 2) as u8]));
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let dots = super::stipple(&image, 1., 6., 30, &mut rng);
+        let third = |i: f64| dots.iter().filter(|p| (p[0] / 30.).floor() == i).count() as f64;
+        let [black, gray, white] = [third(0.), third(1.), third(2.)];
+        // half as dark gives about half as many dots
+        assert!((1.6..2.6).contains(&(black / gray)));
+        assert!(white < gray / 10.);
+        assert!(dots.iter().all(|p| p[0] < 90. && p[1] < 30.));
+    }
+}
@@ -0,0 +1,177 @@
+//! Pluggable storage for the generated samples.
+
+use crate::Point;
+use alloc::vec::Vec;
+
+/// Storage the generated samples are put into.
+///
+/// The generator only ever appends samples and reads back the ones it has
+/// already appended, so this can be backed by any allocator the caller
+/// controls, for example a per-frame arena. See [`blue_noise_with_storage`].
+///
+/// [`blue_noise_with_storage`]: crate::blue_noise_with_storage
+pub trait SampleStorage {
+    /// The number of samples stored.
+    fn len(&self) -> usize;
+
+    /// Whether no samples are stored.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The sample at `index`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `index >= self.len()`.
+    fn sample(&self, index: usize) -> &[f64];
+
+    /// Appends a sample.
+    fn push(&mut self, sample: Point);
+}
+
+impl SampleStorage for Vec<Point> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn sample(&self, index: usize) -> &[f64] {
+        &self[index]
+    }
+
+    fn push(&mut self, sample: Point) {
+        Vec::push(self, sample)
+    }
+}
+
+impl SampleStorage for Vec<Vec<f64>> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn sample(&self, index: usize) -> &[f64] {
+        &self[index]
+    }
+
+    fn push(&mut self, sample: Point) {
+        Vec::push(self, sample.into_vec())
+    }
+}
+
+/// Samples stored back to back in a [`bumpalo::Bump`] arena.
+///
+/// Returned by [`blue_noise_in`](crate::blue_noise_in). Requires the
+/// `bumpalo` feature.
+#[cfg(feature = "bumpalo")]
+#[derive(Debug)]
+pub struct BumpSamples<'bump> {
+    dimension: usize,
+    coordinates: bumpalo::collections::Vec<'bump, f64>,
+}
+
+#[cfg(feature = "bumpalo")]
+impl<'bump> BumpSamples<'bump> {
+    /// Creates an empty storage for `dimension`-dimensional samples.
+    pub fn new_in(dimension: usize, bump: &'bump bumpalo::Bump) -> BumpSamples<'bump> {
+        BumpSamples {
+            dimension,
+            coordinates: bumpalo::collections::Vec::new_in(bump),
+        }
+    }
+
+    /// All coordinates, sample after sample.
+    pub fn coordinates(&self) -> &[f64] {
+        &self.coordinates
+    }
+
+    /// Iterates over the samples in order of generation.
+    pub fn iter(&self) -> std::slice::ChunksExact<'_, f64> {
+        self.coordinates.chunks_exact(self.dimension.max(1))
+    }
+
+    /// Iterates over the samples on the threads of the current rayon thread
+    /// pool. Same as `par_iter()` from rayon's `IntoParallelRefIterator`.
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> rayon::slice::ChunksExact<'_, f64> {
+        use rayon::prelude::*;
+        self.coordinates.par_chunks_exact(self.dimension.max(1))
+    }
+
+    /// Splits the samples into chunks of `samples_per_chunk` samples (the
+    /// last one may be shorter) which are processed on the threads of the
+    /// current rayon thread pool. Each chunk holds the coordinates of its
+    /// samples, sample after sample.
+    ///
+    /// This is useful if the work per sample is small and would otherwise
+    /// be dominated by the overhead of splitting.
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_chunks(&self, samples_per_chunk: usize) -> rayon::slice::Chunks<'_, f64> {
+        use rayon::prelude::*;
+        self.coordinates
+            .par_chunks(samples_per_chunk * self.dimension.max(1))
+    }
+}
+
+#[cfg(all(feature = "bumpalo", feature = "rayon"))]
+impl<'a> rayon::iter::IntoParallelIterator for &'a BumpSamples<'_> {
+    type Iter = rayon::slice::ChunksExact<'a, f64>;
+    type Item = &'a [f64];
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(feature = "bumpalo")]
+impl SampleStorage for BumpSamples<'_> {
+    fn len(&self) -> usize {
+        self.coordinates.len() / self.dimension.max(1)
+    }
+
+    fn sample(&self, index: usize) -> &[f64] {
+        &self.coordinates[index * self.dimension..(index + 1) * self.dimension]
+    }
+
+    fn push(&mut self, sample: Point) {
+        debug_assert_eq!(sample.len(), self.dimension);
+        self.coordinates.extend_from_slice(&sample);
+    }
+}
+
+#[cfg(all(test, feature = "bumpalo"))]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+
+    #[test]
+    fn bump_samples() {
+        let bump = bumpalo::Bump::new();
+        let mut samples = BumpSamples::new_in(2, &bump);
+        assert!(samples.is_empty());
+        samples.push(smallvec![1., 2.]);
+        samples.push(smallvec![3., 4.]);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples.sample(1), &[3., 4.]);
+        assert_eq!(samples.coordinates(), &[1., 2., 3., 4.]);
+        assert_eq!(samples.iter().count(), 2);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_bump_samples() {
+        use rayon::prelude::*;
+        let bump = bumpalo::Bump::new();
+        let mut samples = BumpSamples::new_in(3, &bump);
+        for i in 0..100 {
+            samples.push(smallvec![i as f64, 0., 1.]);
+        }
+        let sum: f64 = (&samples).into_par_iter().map(|s| s[0] + s[2]).sum();
+        assert_eq!(sum, 4950. + 100.);
+        let chunks: Vec<usize> = samples.par_chunks(30).map(|c| c.len() / 3).collect();
+        assert_eq!(chunks, vec![30, 30, 30, 10]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Asynchronous generation, for services which must not block their
+//! executor.
+
+use crate::{BlueNoiseIterator, Point, SampleStorage};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A [`Stream`] of batches of samples, created by
+/// [`BlueNoiseIterator::into_stream`].
+///
+/// Every poll generates one batch, in the order of the iterator. Between two
+/// batches the stream returns [`Poll::Pending`] once and wakes itself, so the
+/// executor gets to run other tasks while a large pattern is generated.
+/// The stream doesn't depend on a runtime, so it works with `tokio` as well
+/// as with any other executor.
+///
+/// ```
+/// use bluenoisers::BlueNoiseConfig;
+/// use futures::StreamExt;
+///
+/// let config = BlueNoiseConfig::new(vec![64., 64.], 1.).seed(3);
+/// let batches: Vec<_> = futures::executor::block_on(config.iter().into_stream(256).collect());
+/// assert!(batches.iter().all(|batch| batch.len() <= 256));
+/// assert_eq!(batches.concat(), config.generate());
+/// ```
+///
+/// Requires the `futures` feature.
+pub struct BlueNoiseStream<S = Vec<Point>> {
+    inner: BlueNoiseIterator<S>,
+    batch_size: usize,
+    /// Whether the last poll gave a batch, so this one yields.
+    yielded: bool,
+    done: bool,
+}
+
+impl<S: SampleStorage> BlueNoiseIterator<S> {
+    /// Turns the iterator into a [`BlueNoiseStream`] of batches of up to
+    /// `batch_size` samples.
+    ///
+    /// Requires the `futures` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn into_stream(self, batch_size: usize) -> BlueNoiseStream<S> {
+        assert!(batch_size > 0, "the batch size must be positive");
+        BlueNoiseStream {
+            inner: self,
+            batch_size,
+            yielded: false,
+            done: false,
+        }
+    }
+}
+
+impl<S> BlueNoiseStream<S> {
+    /// The iterator, e.g. for its [`stats`](BlueNoiseIterator::stats).
+    pub fn iter(&self) -> &BlueNoiseIterator<S> {
+        &self.inner
+    }
+}
+
+impl<S: SampleStorage + Unpin> Stream for BlueNoiseStream<S> {
+    type Item = Vec<Point>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<Point>>> {
+        let stream = self.get_mut();
+        if stream.done {
+            return Poll::Ready(None);
+        }
+        if stream.yielded {
+            stream.yielded = false;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let batch: Vec<Point> = stream.inner.by_ref().take(stream.batch_size).collect();
+        if batch.len() < stream.batch_size {
+            stream.done = true;
+        }
+        if batch.is_empty() {
+            return Poll::Ready(None);
+        }
+        stream.yielded = true;
+        Poll::Ready(Some(batch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlueNoiseConfig;
+    use std::task::Waker;
+
+    #[test]
+    fn yields_between_batches() {
+        let config = BlueNoiseConfig::new(vec![8., 8.], 1.).seed(6);
+        let expected = config.generate();
+        let mut stream = config.iter().into_stream(10);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut samples = Vec::new();
+        let mut pending = 0;
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(batch)) => samples.extend(batch),
+                Poll::Ready(None) => break,
+                Poll::Pending => pending += 1,
+            }
+        }
+        assert_eq!(samples, expected);
+        // one yield after every full batch
+        assert_eq!(pending, expected.len() / 10);
+        assert_eq!(stream.iter().stats().samples, expected.len());
+        assert!(Pin::new(&mut stream).poll_next(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,204 @@
+//! Vector graphics of 2D point sets.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// How [`export_svg`] draws a point set.
+///
+/// ```
+/// use bluenoisers::SvgStyle;
+///
+/// let style = SvgStyle::new([100., 50.])
+///     .dot_radius(0.2)
+///     .color("#c03030")
+///     .outline(true)
+///     .circles(0.5);
+/// assert_eq!(style.dimensions(), [100., 50.]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgStyle {
+    dimensions: [f64; 2],
+    width: f64,
+    dot_radius: f64,
+    color: String,
+    outline: bool,
+    circles: Option<f64>,
+}
+
+impl SvgStyle {
+    /// Creates a style for points in a domain of size `dimensions`, which
+    /// becomes the view box of the image.
+    ///
+    /// The image is 800 pixels wide, with black dots of radius 0.1 in domain
+    /// units, without outline and circles.
+    pub fn new(dimensions: [f64; 2]) -> SvgStyle {
+        SvgStyle {
+            dimensions,
+            width: 800_f64,
+            dot_radius: 0.1,
+            color: "black".to_string(),
+            outline: false,
+            circles: None,
+        }
+    }
+
+    /// Sets the width of the image in pixels. The height follows from the
+    /// aspect ratio of the domain.
+    pub fn width(mut self, width: f64) -> SvgStyle {
+        self.width = width;
+        self
+    }
+
+    /// Sets the radius of the dots in domain units.
+    pub fn dot_radius(mut self, radius: f64) -> SvgStyle {
+        self.dot_radius = radius;
+        self
+    }
+
+    /// Sets the color of the dots, outline and circles, as any SVG color,
+    /// e.g. `"red"` or `"#c03030"`.
+    pub fn color(mut self, color: impl Into<String>) -> SvgStyle {
+        self.color = color.into();
+        self
+    }
+
+    /// Draws the border of the domain.
+    pub fn outline(mut self, outline: bool) -> SvgStyle {
+        self.outline = outline;
+        self
+    }
+
+    /// Draws a circle of `radius` domain units around every sample, e.g. half
+    /// the minimal distance to show the disks which don't overlap, or the
+    /// minimal distance to show where no other sample can be.
+    pub fn circles(mut self, radius: f64) -> SvgStyle {
+        self.circles = Some(radius);
+        self
+    }
+
+    /// The size of the domain.
+    pub fn dimensions(&self) -> [f64; 2] {
+        self.dimensions
+    }
+}
+
+/// Writes the 2D `samples` as an SVG image to `path`, with the origin at the
+/// top left.
+///
+/// ```no_run
+/// use bluenoisers::{SvgStyle, export_svg};
+///
+/// let samples = bluenoisers::blue_noise(vec![100., 50.], 1., 30);
+/// let style = SvgStyle::new([100., 50.]).outline(true).circles(0.5);
+/// export_svg("samples.svg", &samples, &style).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if a sample has less than two coordinates.
+pub fn export_svg<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    style: &SvgStyle,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_svg(&mut writer, samples, style)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D set as an SVG image, see [`export_svg`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2-dimensional.
+    pub fn export_svg(&self, path: impl AsRef<Path>, style: &SvgStyle) -> io::Result<()> {
+        assert_eq!(self.dimensions().len(), 2, "only 2D sets can be exported");
+        export_svg(path, self.samples(), style)
+    }
+}
+
+fn write_svg<P: AsRef<[f64]>, W: Write>(
+    writer: &mut W,
+    samples: &[P],
+    style: &SvgStyle,
+) -> io::Result<()> {
+    let [width, height] = style.dimensions;
+    // strokes are a thousandth of the larger side of the domain
+    let stroke = width.max(height) / 1000_f64;
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {width} {height}">"#,
+        style.width,
+        style.width * height / width,
+    )?;
+    if style.outline {
+        writeln!(
+            writer,
+            r#"<rect width="{width}" height="{height}" fill="none" stroke="{}" stroke-width="{stroke}"/>"#,
+            style.color,
+        )?;
+    }
+    if let Some(radius) = style.circles {
+        writeln!(
+            writer,
+            r#"<g fill="none" stroke="{}" stroke-width="{stroke}">"#,
+            style.color,
+        )?;
+        for sample in samples {
+            let sample = sample.as_ref();
+            writeln!(
+                writer,
+                r#"<circle cx="{}" cy="{}" r="{radius}"/>"#,
+                sample[0], sample[1],
+            )?;
+        }
+        writeln!(writer, "</g>")?;
+    }
+    writeln!(writer, r#"<g fill="{}">"#, style.color)?;
+    for sample in samples {
+        let sample = sample.as_ref();
+        writeln!(
+            writer,
+            r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+            sample[0], sample[1], style.dot_radius,
+        )?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_svg() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("samples.svg");
+        let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+        let style = SvgStyle::new([20., 10.]).width(400.).color("red");
+        set.export_svg(&path, &style).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="200" viewBox="0 0 20 10">"#
+        ));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), set.len());
+        assert!(!svg.contains("<rect"));
+        let first = &set.samples()[0];
+        assert!(svg.contains(&format!(
+            r#"<circle cx="{}" cy="{}" r="0.1"/>"#,
+            first[0], first[1]
+        )));
+
+        set.export_svg(&path, &style.outline(true).circles(0.5))
+            .unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(svg.matches("<circle").count(), 2 * set.len());
+        assert_eq!(svg.matches(r#"r="0.5""#).count(), set.len());
+        assert!(svg.contains(r#"<rect width="20" height="10" fill="none" stroke="red""#));
+    }
+}
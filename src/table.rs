@@ -0,0 +1,386 @@
+//! Patterns compiled into the binary, for targets which can't afford to
+//! generate them at run time.
+//!
+//! A build script generates the pattern once and writes it with
+//! [`encode_table`], and the program embeds the bytes with
+//! `include_bytes!` and reads them with [`PatternTable`], which works in
+//! place and without allocating, also without the `std` feature:
+//!
+//! ```text
+//! // build.rs
+//! let config = bluenoisers::BlueNoiseConfig::new(vec![64., 64.], 1.).seed(1);
+//! let bytes = bluenoisers::encode_table(
+//!     &config.generate(), config.dimensions(), config.min_distance(), bluenoisers::TableFormat::F32,
+//! );
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("pattern.bin");
+//! std::fs::write(out, bytes).unwrap();
+//!
+//! // main.rs
+//! static PATTERN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/pattern.bin"));
+//! let table = bluenoisers::PatternTable::new(PATTERN).unwrap();
+//! ```
+
+use crate::Point;
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+use alloc::vec::Vec;
+use core::fmt;
+
+const MAGIC: &[u8; 4] = b"BNTB";
+const VERSION: u8 = 1;
+/// The bytes before the extents of the domain: magic, version, format,
+/// dimension, count and minimal distance.
+const HEADER_SIZE: usize = 20;
+
+/// How the coordinates are stored in a [`PatternTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Double precision floats, exactly as generated.
+    F64,
+    /// Single precision floats, half the size.
+    F32,
+    /// Unsigned 16 bit integers normalized to the extent of each axis, a
+    /// quarter of the size. The coordinates are off by up to `1 / 131070` of
+    /// the extent, so samples may be closer than the minimal distance by
+    /// twice that.
+    UNorm16,
+}
+
+impl TableFormat {
+    /// The number of bytes per coordinate.
+    pub fn size(self) -> usize {
+        match self {
+            TableFormat::F64 => 8,
+            TableFormat::F32 => 4,
+            TableFormat::UNorm16 => 2,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            TableFormat::F64 => 0,
+            TableFormat::F32 => 1,
+            TableFormat::UNorm16 => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<TableFormat> {
+        match code {
+            0 => Some(TableFormat::F64),
+            1 => Some(TableFormat::F32),
+            2 => Some(TableFormat::UNorm16),
+            _ => None,
+        }
+    }
+}
+
+/// Why bytes are not a [`PatternTable`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// The bytes don't start like a table written by [`encode_table`], or
+    /// the header announces more bytes than can be addressed.
+    NotATable,
+    /// The table was written by a newer version of this crate.
+    UnsupportedVersion(u8),
+    /// The format of the coordinates is unknown.
+    UnknownFormat(u8),
+    /// There are no dimensions.
+    NoDimensions,
+    /// There are not as many bytes as the header announces.
+    WrongSize {
+        /// The number of bytes the header announces.
+        expected: usize,
+        /// The number of bytes there are.
+        actual: usize,
+    },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::NotATable => write!(f, "not a pattern table"),
+            TableError::UnsupportedVersion(version) => {
+                write!(f, "unsupported pattern table version {version}")
+            }
+            TableError::UnknownFormat(code) => write!(f, "unknown coordinate format {code}"),
+            TableError::NoDimensions => write!(f, "the pattern table has no dimensions"),
+            TableError::WrongSize { expected, actual } => write!(
+                f,
+                "the pattern table should have {expected} bytes, but has {actual}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TableError {}
+
+/// Writes `samples` in a domain of size `dimensions` whose samples are at
+/// least `min_distance` apart as a table for [`PatternTable`], with the
+/// coordinates in `format`. All numbers are little endian.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, PatternTable, TableFormat, encode_table};
+///
+/// let config = BlueNoiseConfig::new(vec![16., 8.], 1.).seed(1);
+/// let samples = config.generate();
+/// let bytes = encode_table(&samples, config.dimensions(), 1., TableFormat::F64);
+/// let table = PatternTable::new(&bytes).unwrap();
+/// assert_eq!(table.len(), samples.len());
+/// assert!(table.iter().eq(samples));
+/// ```
+///
+/// # Panics
+///
+/// Panics if there are no dimensions or more than `u16::MAX`, more than
+/// `u32::MAX` samples, or a sample doesn't have a coordinate per dimension.
+pub fn encode_table<P: AsRef<[f64]>>(
+    samples: &[P],
+    dimensions: &[f64],
+    min_distance: f64,
+    format: TableFormat,
+) -> Vec<u8> {
+    assert!(!dimensions.is_empty(), "there must be a dimension");
+    let dimension = u16::try_from(dimensions.len()).expect("too many dimensions");
+    let count = u32::try_from(samples.len()).expect("too many samples");
+    let size =
+        HEADER_SIZE + 8 * dimensions.len() + format.size() * dimensions.len() * samples.len();
+    let mut bytes = Vec::with_capacity(size);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.push(format.code());
+    bytes.extend_from_slice(&dimension.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    bytes.extend_from_slice(&min_distance.to_le_bytes());
+    for extent in dimensions {
+        bytes.extend_from_slice(&extent.to_le_bytes());
+    }
+    for sample in samples {
+        let sample = sample.as_ref();
+        assert_eq!(
+            sample.len(),
+            dimensions.len(),
+            "all samples must have a coordinate per dimension"
+        );
+        for (x, extent) in sample.iter().zip(dimensions.iter()) {
+            match format {
+                TableFormat::F64 => bytes.extend_from_slice(&x.to_le_bytes()),
+                TableFormat::F32 => bytes.extend_from_slice(&(*x as f32).to_le_bytes()),
+                TableFormat::UNorm16 => {
+                    let unorm = ((x / extent).clamp(0_f64, 1_f64) * u16::MAX as f64).round();
+                    bytes.extend_from_slice(&(unorm as u16).to_le_bytes());
+                }
+            }
+        }
+    }
+    debug_assert_eq!(bytes.len(), size);
+    bytes
+}
+
+/// A pattern written by [`encode_table`], read in place, e.g. from a
+/// `static` embedded with `include_bytes!`.
+///
+/// The header is checked once by [`new`](PatternTable::new), then the
+/// samples are decoded on access, which needs no allocation up to four
+/// dimensions, since a [`Point`] stores those inline.
+#[derive(Clone, Copy, Debug)]
+pub struct PatternTable<'a> {
+    bytes: &'a [u8],
+    format: TableFormat,
+    dimension: usize,
+    len: usize,
+}
+
+impl<'a> PatternTable<'a> {
+    /// Checks the header and the size of `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Result<PatternTable<'a>, TableError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+            return Err(TableError::NotATable);
+        }
+        if bytes[4] != VERSION {
+            return Err(TableError::UnsupportedVersion(bytes[4]));
+        }
+        let format = TableFormat::from_code(bytes[5]).ok_or(TableError::UnknownFormat(bytes[5]))?;
+        let dimension = usize::from(u16::from_le_bytes([bytes[6], bytes[7]]));
+        if dimension == 0 {
+            return Err(TableError::NoDimensions);
+        }
+        let len = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+        // a corrupt header can announce more bytes than `usize` can count,
+        // e.g. on 32 bit targets
+        let expected = format
+            .size()
+            .checked_mul(dimension)
+            .and_then(|sample_size| sample_size.checked_mul(len))
+            .and_then(|samples_size| samples_size.checked_add(HEADER_SIZE + 8 * dimension))
+            .ok_or(TableError::NotATable)?;
+        if bytes.len() != expected {
+            return Err(TableError::WrongSize {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        Ok(PatternTable {
+            bytes,
+            format,
+            dimension,
+            len,
+        })
+    }
+
+    /// The format of the coordinates.
+    pub fn format(&self) -> TableFormat {
+        self.format
+    }
+
+    /// The number of dimensions.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// The size of the domain along `axis`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `axis` is not less than the number of dimensions.
+    pub fn extent(&self, axis: usize) -> f64 {
+        assert!(axis < self.dimension, "the axis is out of range");
+        read_f64(self.bytes, HEADER_SIZE + 8 * axis)
+    }
+
+    /// The size of the domain.
+    pub fn dimensions(&self) -> impl Iterator<Item = f64> + 'a {
+        let table = *self;
+        (0..self.dimension).map(move |axis| table.extent(axis))
+    }
+
+    /// The minimal distance the samples were generated with.
+    pub fn min_distance(&self) -> f64 {
+        read_f64(self.bytes, 12)
+    }
+
+    /// The number of samples.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The coordinate along `axis` of the sample with `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` or `axis` is out of range.
+    pub fn coordinate(&self, index: usize, axis: usize) -> f64 {
+        assert!(index < self.len, "the index is out of range");
+        let extent = self.extent(axis);
+        let size = self.format.size();
+        let offset = HEADER_SIZE + 8 * self.dimension + size * (index * self.dimension + axis);
+        let bytes = &self.bytes[offset..offset + size];
+        match self.format {
+            TableFormat::F64 => read_f64(bytes, 0),
+            TableFormat::F32 => {
+                let x = f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+                // rounding may have reached the upper bound
+                x.min(extent.next_down())
+            }
+            TableFormat::UNorm16 => {
+                let x = f64::from(u16::from_le_bytes([bytes[0], bytes[1]])) / u16::MAX as f64;
+                // inside of the domain, which doesn't include its upper bound
+                (x * extent).min(extent.next_down())
+            }
+        }
+    }
+
+    /// The sample with `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn sample(&self, index: usize) -> Point {
+        (0..self.dimension)
+            .map(|axis| self.coordinate(index, axis))
+            .collect()
+    }
+
+    /// The samples, in the order they were written.
+    pub fn iter(&self) -> impl Iterator<Item = Point> + 'a {
+        let table = *self;
+        (0..self.len).map(move |index| table.sample(index))
+    }
+}
+
+fn read_f64(bytes: &[u8], offset: usize) -> f64 {
+    let mut le = [0; 8];
+    le.copy_from_slice(&bytes[offset..offset + 8]);
+    f64::from_le_bytes(le)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlueNoiseConfig;
+    use alloc::vec;
+
+    #[test]
+    fn table() {
+        let config = BlueNoiseConfig::new(vec![20., 10., 5.], 1.).seed(4);
+        let samples = config.generate();
+        for (format, error) in [
+            (TableFormat::F64, 0.),
+            (TableFormat::F32, 1e-6),
+            (TableFormat::UNorm16, 20. / 131_070.),
+        ] {
+            let bytes = encode_table(&samples, config.dimensions(), 1., format);
+            let table = PatternTable::new(&bytes).unwrap();
+            assert_eq!(table.format(), format);
+            assert_eq!(table.len(), samples.len());
+            assert_eq!(table.min_distance(), 1.);
+            assert!(table.dimensions().eq([20., 10., 5.]));
+            for (decoded, sample) in table.iter().zip(samples.iter()) {
+                for (axis, (x, y)) in decoded.iter().zip(sample.iter()).enumerate() {
+                    assert!((x - y).abs() <= error);
+                    assert!((0. ..table.extent(axis)).contains(x));
+                }
+            }
+        }
+
+        let bytes = encode_table(&samples, config.dimensions(), 1., TableFormat::F32);
+        assert_eq!(
+            PatternTable::new(&bytes[..bytes.len() - 1]).unwrap_err(),
+            TableError::WrongSize {
+                expected: bytes.len(),
+                actual: bytes.len() - 1
+            }
+        );
+        assert_eq!(
+            PatternTable::new(b"not a table at all, no").unwrap_err(),
+            TableError::NotATable
+        );
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert_eq!(
+            PatternTable::new(&newer).unwrap_err(),
+            TableError::UnsupportedVersion(2)
+        );
+        let empty = encode_table::<Point>(&[], &[4., 4.], 1., TableFormat::UNorm16);
+        assert!(PatternTable::new(&empty).unwrap().is_empty());
+        let mut huge = empty.clone();
+        huge[6..8].copy_from_slice(&u16::MAX.to_le_bytes());
+        huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(PatternTable::new(&huge).is_err());
+
+        // samples right below the upper bound stay inside after rounding
+        let edge = [[20. - 1e-8, 10. - 1e-8, 5. - 1e-8]];
+        for format in [TableFormat::F64, TableFormat::F32, TableFormat::UNorm16] {
+            let bytes = encode_table(&edge, config.dimensions(), 1., format);
+            let table = PatternTable::new(&bytes).unwrap();
+            for (axis, x) in table.sample(0).iter().enumerate() {
+                assert!((0. ..table.extent(axis)).contains(x));
+            }
+        }
+    }
+}
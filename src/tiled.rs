@@ -0,0 +1,509 @@
+//! Tile by tile generation for domains which don't fit into memory.
+
+use crate::{BlueNoiseIterator, Interval, Point};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+
+/// Generates blue noise tile by tile, handing each finished tile to `sink`.
+///
+/// The domain is split into hypercubes of edge length `tile_size` (the last
+/// tile along each axis may be smaller). Tiles are generated one row along
+/// the first axis after another, first the tiles of the row with an even
+/// coordinate along it and then those with an odd one, which grow from the
+/// samples of their finished neighbors. Only the samples close to the
+/// border of a tile are kept in memory as long as one of its neighbors
+/// still has to be generated. Those are the tiles of one layer across the
+/// last axis, so the memory needed is bounded by the size of a row of tiles
+/// in 2D and of a slab of tiles in 3D, instead of the whole domain.
+///
+/// `sink` is called once per tile with the tile's coordinates (in tiles, not
+/// in domain units) and the samples inside of it, in domain units. Tiles are
+/// handed over with the first axis changing fastest. If `sink` returns an
+/// error, generation stops and the error is returned. A typical sink appends
+/// the samples to a file.
+///
+/// The samples of all tiles together satisfy the same distance constraint as
+/// [`blue_noise`](crate::blue_noise).
+///
+/// # Panics
+///
+/// Panics if `tile_size` is less than *2 × `min_distance`*.
+pub fn blue_noise_tiled<F, E>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    tile_size: f64,
+    sink: F,
+) -> Result<(), E>
+where
+    F: FnMut(&[usize], Vec<Point>) -> Result<(), E>,
+{
+    let tiling = Tiling::new(dimensions, min_distance, k_abort, tile_size);
+    tiled(
+        &tiling,
+        |_| ChaCha8Rng::from_rng(rand::thread_rng()).unwrap(),
+        false,
+        sink,
+    )
+}
+
+/// Like [`blue_noise_tiled`], but reproducible: the same `seed` yields the
+/// same samples in the same tiles.
+///
+/// Each tile gets its own random number generator, derived from `seed` and
+/// the tile's position, like with
+/// [`blue_noise_parallel_seeded`](crate::blue_noise_parallel_seeded).
+///
+/// # Panics
+///
+/// Panics if `tile_size` is less than *2 × `min_distance`*.
+pub fn blue_noise_tiled_seeded<F, E>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    tile_size: f64,
+    seed: u64,
+    sink: F,
+) -> Result<(), E>
+where
+    F: FnMut(&[usize], Vec<Point>) -> Result<(), E>,
+{
+    let tiling = Tiling::new(dimensions, min_distance, k_abort, tile_size);
+    tiled(&tiling, seeded(seed), false, sink)
+}
+
+/// Like [`blue_noise_tiled_seeded`], but generates the tiles of a row with
+/// an even coordinate along the first axis on the threads of the current
+/// rayon thread pool, and then those with an odd one. These don't touch
+/// each other, so the samples are the same as with
+/// [`blue_noise_tiled_seeded`] and the same `seed`. `sink` is still called
+/// on the calling thread, in the same order.
+///
+/// Requires the `rayon` feature.
+///
+/// # Panics
+///
+/// Panics if `tile_size` is less than *2 × `min_distance`*.
+#[cfg(feature = "rayon")]
+pub fn blue_noise_tiled_parallel<F, E>(
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    tile_size: f64,
+    seed: u64,
+    sink: F,
+) -> Result<(), E>
+where
+    F: FnMut(&[usize], Vec<Point>) -> Result<(), E>,
+{
+    let tiling = Tiling::new(dimensions, min_distance, k_abort, tile_size);
+    tiled(&tiling, seeded(seed), true, sink)
+}
+
+/// The random number generator of the tile with the given linear index for
+/// `seed`.
+fn seeded(seed: u64) -> impl Fn(usize) -> ChaCha8Rng + Sync {
+    move |tile_idx| {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(tile_idx as u64);
+        rng
+    }
+}
+
+/// Generates the tiles of `tiling` row by row, see [`blue_noise_tiled`].
+/// `tile_rng` creates the random number generator for the tile with the
+/// given linear index. If `parallel` is set, the tiles of a row which don't
+/// touch each other are generated on the threads of the current rayon
+/// thread pool.
+fn tiled<F, E>(
+    tiling: &Tiling,
+    tile_rng: impl Fn(usize) -> ChaCha8Rng + Sync,
+    parallel: bool,
+    mut sink: F,
+) -> Result<(), E>
+where
+    F: FnMut(&[usize], Vec<Point>) -> Result<(), E>,
+{
+    let tile_count = tiling.tile_count();
+    let dimension = tile_count.len();
+    if dimension == 0 || tile_count.contains(&0) {
+        return Ok(());
+    }
+    let linear = |tile: &[usize]| {
+        tile.iter()
+            .zip(tile_count.iter())
+            .rev()
+            .fold(0_usize, |accu, (t, count)| accu * count + t)
+    };
+    // samples which new neighbors have to respect, per finished tile, in a
+    // fixed order so seeded runs give the same samples
+    let mut bands: BTreeMap<Vec<usize>, Vec<Point>> = BTreeMap::new();
+    for row in tiling.tiles().filter(|tile| tile[0] == 0) {
+        let tiles: Vec<Vec<usize>> = (0..tile_count[0])
+            .map(|t| {
+                let mut tile = row.clone();
+                tile[0] = t;
+                tile
+            })
+            .collect();
+        let mut samples: Vec<Vec<Point>> = vec![Vec::new(); tiles.len()];
+        for parity in 0..2 {
+            let generate = |tile: &Vec<usize>| -> Vec<Point> {
+                let ghosts: Vec<Point> = bands
+                    .iter()
+                    .filter(|(neighbor, _)| {
+                        neighbor
+                            .iter()
+                            .zip(tile.iter())
+                            .all(|(n, t)| n.abs_diff(*t) <= 1)
+                    })
+                    .flat_map(|(_, band)| band.iter().cloned())
+                    .collect();
+                tiling.generate(tile, &ghosts, &mut tile_rng(linear(tile)))
+            };
+            let batch = tiles.iter().skip(parity).step_by(2);
+            #[cfg(feature = "rayon")]
+            let filled: Vec<Vec<Point>> = if parallel {
+                use rayon::prelude::*;
+                let batch: Vec<&Vec<usize>> = batch.collect();
+                batch.into_par_iter().map(generate).collect()
+            } else {
+                batch.map(generate).collect()
+            };
+            #[cfg(not(feature = "rayon"))]
+            let filled: Vec<Vec<Point>> = {
+                debug_assert!(!parallel, "parallel tiles need the rayon feature");
+                batch.map(generate).collect()
+            };
+            for (t, filled) in (parity..tiles.len()).step_by(2).zip(filled) {
+                bands.insert(tiles[t].clone(), tiling.ghosts(&tiles[t], &filled));
+                samples[t] = filled;
+            }
+        }
+        for (tile, samples) in tiles.iter().zip(samples) {
+            sink(tile, samples)?;
+        }
+        // a band is not needed anymore once its last neighbor is done
+        let current = linear(tiles.last().expect("rows have tiles"));
+        bands.retain(|finished, _| {
+            let last_neighbor: Vec<usize> = finished
+                .iter()
+                .zip(tile_count.iter())
+                .map(|(f, count)| (f + 1).min(count - 1))
+                .collect();
+            linear(&last_neighbor) > current
+        });
+    }
+    Ok(())
+}
+
+/// The decomposition of a domain into tiles which can be generated
+/// separately, e.g. on different machines, and stitched together seamlessly.
+///
+/// A tile has to respect the samples of its already generated neighbors,
+/// which it gets as *ghosts*: the samples close to the borders of the
+/// neighbors, see [`ghosts`](Tiling::ghosts). Ghosts are plain samples in
+/// domain units, so they can be sent along with the tile's coordinates in
+/// whatever format is convenient.
+///
+/// Tiles of the same [`phase`](Tiling::phase) never touch each other, so a
+/// farm can generate all the tiles of phase 0 in parallel, then all the tiles
+/// of phase 1 with the ghosts of their finished neighbors and so on. The
+/// result is then as if the whole domain was generated at once.
+///
+/// If tiles were generated without the ghosts of all their neighbors, the
+/// seams contain samples which are too close to each other. Those can be
+/// found with [`find_conflicts`](crate::find_conflicts) and removed with [`resolve_conflicts`](crate::resolve_conflicts).
+///
+/// ```
+/// use bluenoisers::{Tiling, find_conflicts};
+///
+/// let tiling = Tiling::new(vec![40., 40.], 1., 30, 10.);
+/// let mut rng = rand::thread_rng();
+/// let mut generated: Vec<(Vec<usize>, Vec<_>)> = Vec::new();
+/// for phase in 0..tiling.phase_count() {
+///     // in a farm, each iteration of this loop could run on its own machine
+///     for tile in tiling.tiles().filter(|tile| tiling.phase(tile) == phase) {
+///         let ghosts: Vec<_> = generated
+///             .iter()
+///             .flat_map(|(finished, samples)| tiling.ghosts(finished, samples))
+///             .collect();
+///         let samples = tiling.generate(&tile, &ghosts, &mut rng);
+///         generated.push((tile, samples));
+///     }
+/// }
+/// let merged: Vec<_> = generated.into_iter().flat_map(|(_, s)| s).collect();
+/// assert!(find_conflicts(&merged, 1.).is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tiling {
+    dimensions: Vec<f64>,
+    min_distance: f64,
+    k_abort: usize,
+    tile_size: f64,
+    tile_count: Vec<usize>,
+}
+
+impl Tiling {
+    /// Splits a domain of size `dimensions` into hypercubes of edge length
+    /// `tile_size`. The other arguments are the same as for
+    /// [`blue_noise`](crate::blue_noise).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tile_size` is less than *2 × `min_distance`*.
+    pub fn new(dimensions: Vec<f64>, min_distance: f64, k_abort: usize, tile_size: f64) -> Tiling {
+        assert!(
+            tile_size >= 2_f64 * min_distance,
+            "tiles must be at least twice as large as the minimal distance"
+        );
+        let tile_count = dimensions
+            .iter()
+            .map(|x| ((x / tile_size).ceil() as usize).max(1))
+            .collect();
+        Tiling {
+            dimensions,
+            min_distance,
+            k_abort,
+            tile_size,
+            tile_count,
+        }
+    }
+
+    /// The number of tiles along each axis.
+    pub fn tile_count(&self) -> &[usize] {
+        &self.tile_count
+    }
+
+    /// All the tiles, with the first axis changing fastest.
+    pub fn tiles(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        let total = if self.tile_count.is_empty() {
+            0
+        } else {
+            self.tile_count.iter().product()
+        };
+        (0..total).map(|mut linear| {
+            self.tile_count
+                .iter()
+                .map(|count| {
+                    let t = linear % count;
+                    linear /= count;
+                    t
+                })
+                .collect()
+        })
+    }
+
+    /// The half-open box covered by `tile`, in domain units.
+    pub fn bounds(&self, tile: &[usize]) -> (Vec<f64>, Vec<f64>) {
+        let lower = tile.iter().map(|t| *t as f64 * self.tile_size).collect();
+        let upper = tile
+            .iter()
+            .zip(self.dimensions.iter())
+            .map(|(t, dim)| ((*t + 1) as f64 * self.tile_size).min(*dim))
+            .collect();
+        (lower, upper)
+    }
+
+    /// The number of phases, *2ⁿ* in `n` dimensions.
+    pub fn phase_count(&self) -> usize {
+        1 << self.dimensions.len()
+    }
+
+    /// The phase of `tile`: one per combination of even and odd tile
+    /// coordinates. Tiles of the same phase don't influence each other.
+    pub fn phase(&self, tile: &[usize]) -> usize {
+        tile.iter()
+            .enumerate()
+            .fold(0_usize, |accu, (i, t)| accu | (t % 2) << i)
+    }
+
+    /// Exports the samples of the finished `tile` which its neighbors have to
+    /// respect: those closer than *2 × `min_distance`* to its border.
+    pub fn ghosts(&self, tile: &[usize], samples: &[Point]) -> Vec<Point> {
+        let margin = 2_f64 * self.min_distance;
+        let (lower, upper) = self.bounds(tile);
+        samples
+            .iter()
+            .filter(|sample| {
+                sample
+                    .iter()
+                    .zip(lower.iter().zip(upper.iter()))
+                    .any(|(x, (lo, hi))| x - lo < margin || hi - x < margin)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Generates the samples inside of `tile`, continuing from the `ghosts`
+    /// of its already generated neighbors.
+    ///
+    /// Ghosts may come from any tile, the ones too far away to matter are
+    /// ignored. They must not be in conflict with each other, which holds if
+    /// the tiles they are from were generated by this method in different
+    /// phases or are not adjacent.
+    ///
+    /// Returns the new samples in domain units, in order of generation.
+    pub fn generate<R: Rng>(&self, tile: &[usize], ghosts: &[Point], rng: &mut R) -> Vec<Point> {
+        let margin = 2_f64 * self.min_distance;
+        let (lower, upper) = self.bounds(tile);
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("tile", ?tile, ?lower, ?upper, ghosts = ghosts.len()).entered();
+        let origin: Vec<f64> = lower.iter().map(|lo| (lo - margin).max(0_f64)).collect();
+        let extent: Vec<f64> = upper
+            .iter()
+            .zip(self.dimensions.iter())
+            .zip(origin.iter())
+            .map(|((hi, dim), orig)| (hi + margin).min(*dim) - orig)
+            .collect();
+        let mut it = BlueNoiseIterator::new(extent.clone(), self.min_distance, self.k_abort);
+        it.rng = ChaCha8Rng::seed_from_u64(rng.next_u64());
+        let to_local = |position: &[f64]| -> Point {
+            position
+                .iter()
+                .zip(origin.iter())
+                .map(|(x, orig)| x - orig)
+                .collect()
+        };
+        let region_lower = to_local(&lower);
+        let region_upper = to_local(&upper);
+        it.region = Some((region_lower.to_vec(), region_upper.to_vec()));
+        for ghost in ghosts {
+            let local = to_local(ghost);
+            if local
+                .iter()
+                .zip(extent.iter())
+                .all(|(x, ext)| Interval::ClosedOpen.contains(*x, *ext))
+            {
+                let inserted = it.seed(local);
+                debug_assert!(inserted, "ghost samples are in conflict");
+            }
+        }
+        let ghost_count = it.samples.len();
+        // parts of the tile may not be reachable from the ghosts
+        it.exhaust();
+        it.samples
+            .drain(ghost_count..)
+            .map(|local| {
+                local
+                    .iter()
+                    .zip(origin.iter())
+                    .map(|(x, orig)| x + orig)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::BackgroundGrid;
+    use crate::{find_conflicts, resolve_conflicts};
+
+    #[test]
+    fn tiles_respect_min_distance() {
+        let dimensions = vec![60_f64, 37_f64];
+        let radius = 2.;
+        let mut samples: Vec<Point> = Vec::new();
+        let mut tiles = Vec::new();
+        blue_noise_tiled(dimensions.clone(), radius, 30, 8., |tile, tile_samples| {
+            for s in tile_samples.iter() {
+                for (x, t) in s.iter().zip(tile.iter()) {
+                    assert_eq!((x / 8.).floor() as usize, *t);
+                }
+            }
+            tiles.push(tile.to_vec());
+            samples.extend(tile_samples);
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+        assert_eq!(tiles.len(), 8 * 5);
+        assert_eq!(tiles[1], vec![1, 0]);
+        for (i, s1) in samples.iter().enumerate() {
+            assert!(
+                s1.iter()
+                    .zip(dimensions.iter())
+                    .all(|(x, d)| 0. <= *x && x < d)
+            );
+            let mut mindst = f64::INFINITY;
+            for (j, s2) in samples.iter().enumerate() {
+                if i != j {
+                    mindst = mindst.min(BackgroundGrid::dst_sqr(s1, s2).sqrt());
+                }
+            }
+            assert!(mindst >= radius);
+            assert!(mindst < 2_f64 * radius);
+        }
+    }
+
+    #[test]
+    fn seeded() {
+        let run = |seed| {
+            let mut tiles: Vec<(Vec<usize>, Vec<Point>)> = Vec::new();
+            blue_noise_tiled_seeded(vec![40., 30.], 1., 30, 8., seed, |tile, samples| {
+                tiles.push((tile.to_vec(), samples));
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+            tiles
+        };
+        let tiles = run(5);
+        assert_eq!(tiles.len(), 5 * 4);
+        assert_eq!(tiles, run(5));
+        assert_ne!(tiles, run(6));
+        let samples: Vec<Point> = tiles.iter().flat_map(|(_, s)| s.iter().cloned()).collect();
+        assert!(find_conflicts(&samples, 1.).is_empty());
+        #[cfg(feature = "rayon")]
+        for threads in [1, 4] {
+            let mut parallel: Vec<(Vec<usize>, Vec<Point>)> = Vec::new();
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| {
+                    blue_noise_tiled_parallel(vec![40., 30.], 1., 30, 8., 5, |tile, samples| {
+                        parallel.push((tile.to_vec(), samples));
+                        Ok::<(), ()>(())
+                    })
+                })
+                .unwrap();
+            assert_eq!(parallel, tiles);
+        }
+    }
+
+    #[test]
+    fn sink_errors_abort() {
+        let mut calls = 0;
+        let result = blue_noise_tiled(vec![40., 40.], 1., 30, 10., |_, _| {
+            calls += 1;
+            if calls == 3 { Err("disk full") } else { Ok(()) }
+        });
+        assert_eq!(result, Err("disk full"));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn seams_without_ghosts() {
+        let radius = 1.;
+        let tiling = Tiling::new(vec![30., 30.], radius, 30, 10.);
+        let mut rng = rand::thread_rng();
+        let mut samples: Vec<Point> = tiling
+            .tiles()
+            .flat_map(|tile| tiling.generate(&tile, &[], &mut rng))
+            .collect();
+        assert_eq!(tiling.tiles().count(), 9);
+        let conflicts = find_conflicts(&samples, radius);
+        assert!(!conflicts.is_empty());
+        for (i, j) in conflicts {
+            assert!(BackgroundGrid::dst_sqr(&samples[i], &samples[j]) < radius * radius);
+        }
+        let count = samples.len();
+        let removed = resolve_conflicts(&mut samples, radius);
+        assert!(removed > 0);
+        assert_eq!(samples.len(), count - removed);
+        assert!(find_conflicts(&samples, radius).is_empty());
+    }
+}
@@ -0,0 +1,628 @@
+//! Matching the samples of two patterns, to animate from one to the other.
+
+use crate::Point;
+use crate::grid::BackgroundGrid;
+use crate::sphere::{gaussian, normalized};
+use core::cmp::{Ordering, Reverse};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use smallvec::SmallVec;
+use std::collections::{BinaryHeap, HashMap};
+
+/// How many of the closest samples [`transport`] considers as the target of
+/// a sample, around where it is and around where it moved to.
+const CANDIDATES: usize = 8;
+/// How many directions [`sliced`] moves the samples along.
+const SLICES: usize = 256;
+
+/// Which samples of one pattern move to which samples of another, computed
+/// by [`transport`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transport {
+    /// The index of a sample of the first pattern and the index of a sample
+    /// of the second one it moves to. Every sample of the larger pattern is in
+    /// exactly one pair, and every sample of the smaller one in at least one.
+    pub pairs: Vec<(usize, usize)>,
+}
+
+impl Transport {
+    /// The positions `t` of the way from `from` to `to`, one per pair, so `0`
+    /// gives the samples of `from` and `1` those of `to`, some of them
+    /// repeated if the patterns have different numbers of samples.
+    pub fn interpolate(&self, from: &[Point], to: &[Point], t: f64) -> Vec<Point> {
+        self.pairs
+            .iter()
+            .map(|(i, j)| {
+                from[*i]
+                    .iter()
+                    .zip(to[*j].iter())
+                    .map(|(a, b)| (1_f64 - t) * a + t * b)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The sum of the squared distances the samples move, which
+    /// [`transport`] minimizes.
+    pub fn cost(&self, from: &[Point], to: &[Point]) -> f64 {
+        self.pairs
+            .iter()
+            .map(|(i, j)| BackgroundGrid::dst_sqr(&from[*i], &to[*j]))
+            .sum()
+    }
+}
+
+/// Matches the samples of `from` with those of `to` so that the samples move
+/// as little as possible from one pattern to the other, e.g. between the
+/// frames of an animated stippling or of a crowd whose density changes,
+/// where [`Transport::interpolate`] moves the samples smoothly instead of
+/// letting them pop.
+///
+/// The matching approximates the optimal transport, which minimizes the sum
+/// of the squared distances. If one pattern has more samples, every sample
+/// of the smaller one gets one of them, and the rest merge with, or split
+/// from, the closest sample of the smaller pattern.
+///
+/// The samples of the larger pattern are first moved until they are
+/// distributed like the others along many directions, which finds where
+/// they go if the patterns differ in density. Then each is matched with one
+/// of the closest samples around where it is or where it moved to, by an
+/// auction. For patterns of blue noise in two dimensions, the cost is
+/// within about two percent of the optimum, and the time grows a little
+/// faster than the number of samples.
+///
+/// ```
+/// use bluenoisers::{BlueNoiseConfig, transport};
+///
+/// let from = BlueNoiseConfig::new(vec![32., 32.], 1.).seed(1).generate();
+/// let to = BlueNoiseConfig::new(vec![32., 32.], 1.2).seed(2).generate();
+/// let transport = transport(&from, &to);
+/// assert_eq!(transport.pairs.len(), from.len());
+/// // halfway between the frames
+/// let frame = transport.interpolate(&from, &to, 0.5);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the samples don't all have the same number of coordinates.
+pub fn transport(from: &[Point], to: &[Point]) -> Transport {
+    if from.is_empty() || to.is_empty() {
+        return Transport::default();
+    }
+    let dimension = from[0].len();
+    assert!(
+        from.iter().chain(to.iter()).all(|p| p.len() == dimension),
+        "all samples must have the same number of coordinates"
+    );
+    let pairs = if from.len() >= to.len() {
+        matching(from, to)
+    } else {
+        matching(to, from)
+            .into_iter()
+            .map(|(j, i)| (i, j))
+            .collect()
+    };
+    Transport { pairs }
+}
+
+/// The pairs of [`transport`] from the samples of `larger`, which has at
+/// least as many as `smaller`.
+fn matching(larger: &[Point], smaller: &[Point]) -> Vec<(usize, usize)> {
+    let moved = sliced(larger, smaller);
+    let spacing = spacing(larger.iter().chain(smaller.iter()), larger.len());
+    let buckets = Buckets::new(smaller, spacing);
+    let mut nearby = Vec::new();
+    let mut candidates: Vec<SmallVec<[usize; 16]>> = larger
+        .iter()
+        .zip(moved.iter())
+        .map(|(sample, moved)| {
+            nearby.clear();
+            for position in [sample, moved] {
+                let start = nearby.len();
+                buckets.around(position, 1, &mut nearby);
+                nearby[start..].sort_by(|a, b| {
+                    let dst_sqr = |j: &usize| BackgroundGrid::dst_sqr(position, &smaller[*j]);
+                    dst_sqr(a).total_cmp(&dst_sqr(b))
+                });
+                nearby.truncate(start + CANDIDATES);
+            }
+            nearby.sort_unstable();
+            nearby.dedup();
+            nearby.iter().copied().collect()
+        })
+        .collect();
+    // with the closest sample left for every sample of `smaller`, in an
+    // order along a space filling curve, the candidates allow a matching
+    let (lower, upper) = bounding_box(smaller.iter());
+    let mut order: Vec<usize> = (0..smaller.len()).collect();
+    order.sort_by_cached_key(|j| morton(&smaller[*j], &lower, &upper));
+    let mut left = Buckets::new(&moved, spacing);
+    for j in order {
+        let i = left.nearest(&smaller[j], &moved);
+        left.remove(&moved[i], i);
+        if !candidates[i].contains(&j) {
+            candidates[i].push(j);
+        }
+    }
+    let closest: Vec<(usize, f64)> = larger
+        .iter()
+        .map(|sample| {
+            let j = buckets.nearest(sample, smaller);
+            (j, BackgroundGrid::dst_sqr(sample, &smaller[j]))
+        })
+        .collect();
+    let assigned = auction(
+        smaller.len(),
+        &candidates,
+        |i, j| BackgroundGrid::dst_sqr(&larger[i], &smaller[j]),
+        |i| closest[i].1,
+    );
+    assigned
+        .into_iter()
+        .enumerate()
+        .map(|(i, j)| (i, j.unwrap_or(closest[i].0)))
+        .collect()
+}
+
+/// Assigns each bidder one of `objects` objects or one of the remaining
+/// `candidates.len() - objects` free places so that every object is taken
+/// and the sum of the costs is close to minimal, with the auction algorithm
+/// of Bertsekas and ε-scaling. Each bidder only bids for its `candidates`,
+/// at `cost`, which must allow an assignment, or for a free place, at
+/// `free_cost`.
+fn auction(
+    objects: usize,
+    candidates: &[SmallVec<[usize; 16]>],
+    cost: impl Fn(usize, usize) -> f64,
+    free_cost: impl Fn(usize) -> f64,
+) -> Vec<Option<usize>> {
+    let bidders = candidates.len();
+    let costs: Vec<SmallVec<[f64; 16]>> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, objects)| objects.iter().map(|j| cost(i, *j)).collect())
+        .collect();
+    let free_costs: Vec<f64> = (0..bidders).map(free_cost).collect();
+    let largest = costs.iter().flatten().fold(0_f64, |a, c| a.max(*c));
+    let mean = free_costs.iter().sum::<f64>() / bidders as f64;
+    // the result costs at most the number of bidders times the last epsilon
+    // more than the optimum
+    let last = mean.max(f64::MIN_POSITIVE) * 1e-3;
+    let mut epsilon = (largest / 4_f64).max(last);
+    let mut prices = vec![0_f64; objects];
+    // the free places are all the same, so only the cheapest ones matter
+    let mut free: BinaryHeap<Reverse<(Price, usize)>> = (0..bidders - objects)
+        .map(|place| Reverse((Price(0_f64), place)))
+        .collect();
+    // which bidder has each object, then each free place
+    let mut owners = vec![usize::MAX; bidders];
+    loop {
+        owners.fill(usize::MAX);
+        let mut unassigned: Vec<usize> = (0..bidders).rev().collect();
+        while let Some(bidder) = unassigned.pop() {
+            // the best and second best value of an object, or free place,
+            // to the bidder
+            let mut best = (f64::NEG_INFINITY, usize::MAX);
+            let mut second = f64::NEG_INFINITY;
+            let mut offer = |value: f64, object: usize| {
+                if value > best.0 {
+                    second = best.0;
+                    best = (value, object);
+                } else if value > second {
+                    second = value;
+                }
+            };
+            for (j, cost) in candidates[bidder].iter().zip(costs[bidder].iter()) {
+                offer(-cost - prices[*j], *j);
+            }
+            let cheapest = free.pop();
+            if let Some(Reverse((price, place))) = cheapest {
+                offer(-free_costs[bidder] - price.0, objects + place);
+                if let Some(Reverse((price, place))) = free.peek() {
+                    offer(-free_costs[bidder] - price.0, objects + place);
+                }
+            }
+            let (value, object) = best;
+            let second = if second.is_finite() {
+                second
+            } else {
+                value - largest - epsilon
+            };
+            let raise = value - second + epsilon;
+            if let Some(Reverse((price, place))) = cheapest {
+                let price = if objects + place == object {
+                    price.0 + raise
+                } else {
+                    price.0
+                };
+                free.push(Reverse((Price(price), place)));
+            }
+            if object < objects {
+                prices[object] += raise;
+            }
+            if owners[object] != usize::MAX {
+                unassigned.push(owners[object]);
+            }
+            owners[object] = bidder;
+        }
+        if epsilon <= last {
+            break;
+        }
+        epsilon = (epsilon / 5_f64).max(last);
+    }
+    let mut assigned = vec![None; bidders];
+    for (object, owner) in owners.iter().enumerate().take(objects) {
+        assigned[*owner] = Some(object);
+    }
+    assigned
+}
+
+/// A price of a free place of [`auction`], ordered so the cheapest is found.
+#[derive(Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Moves the samples of `from` until they are distributed like those of
+/// `to` along [`SLICES`] random directions, one after the other: along each
+/// one, the samples get the position of the sample of `to` of the same
+/// quantile. This approaches the optimal transport one dimension at a time,
+/// which is fast since it only sorts.
+fn sliced(from: &[Point], to: &[Point]) -> Vec<Point> {
+    let dimension = from[0].len();
+    // the same directions every time, so the matching is too
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut moved = from.to_vec();
+    let mut order: Vec<usize> = (0..from.len()).collect();
+    let mut along_from = vec![0_f64; from.len()];
+    let mut along_to = vec![0_f64; to.len()];
+    for _ in 0..SLICES {
+        let Some(direction) = normalized(gaussian(dimension, &mut rng)) else {
+            continue;
+        };
+        let along = |p: &Point| -> f64 { p.iter().zip(direction.iter()).map(|(x, d)| x * d).sum() };
+        for (a, p) in along_from.iter_mut().zip(moved.iter()) {
+            *a = along(p);
+        }
+        for (a, p) in along_to.iter_mut().zip(to.iter()) {
+            *a = along(p);
+        }
+        order.sort_by(|a, b| along_from[*a].total_cmp(&along_from[*b]));
+        along_to.sort_by(f64::total_cmp);
+        for (rank, i) in order.iter().enumerate() {
+            let quantile = ((2 * rank + 1) * to.len()) / (2 * from.len());
+            let step = along_to[quantile] - along_from[*i];
+            for (x, d) in moved[*i].iter_mut().zip(direction.iter()) {
+                *x += step * d;
+            }
+        }
+    }
+    moved
+}
+
+/// The smallest box containing all `points`.
+fn bounding_box<'a>(mut points: impl Iterator<Item = &'a Point>) -> (Point, Point) {
+    let first = points.next().expect("there are samples");
+    let (mut lower, mut upper) = (first.clone(), first.clone());
+    for p in points {
+        for ((lo, hi), x) in lower.iter_mut().zip(upper.iter_mut()).zip(p.iter()) {
+            *lo = lo.min(*x);
+            *hi = hi.max(*x);
+        }
+    }
+    (lower, upper)
+}
+
+/// The distance of `count` samples spread evenly over the bounding box of
+/// `points`, or along its diagonal if that is farther, since samples on a
+/// line or in a plane fill less than the volume of the box, which may be
+/// zero. `1` if all `points` are at one position.
+fn spacing<'a>(points: impl Iterator<Item = &'a Point>, count: usize) -> f64 {
+    let (lower, upper) = bounding_box(points);
+    let extents: SmallVec<[f64; 4]> = lower
+        .iter()
+        .zip(upper.iter())
+        .map(|(lo, hi)| hi - lo)
+        .collect();
+    let diagonal = extents.iter().map(|e| e * e).sum::<f64>().sqrt();
+    if diagonal <= 0_f64 {
+        return 1_f64;
+    }
+    let volume: f64 = extents.iter().product();
+    let even = (volume / count as f64).powf(1_f64 / lower.len() as f64);
+    even.max(diagonal / count as f64)
+}
+
+/// The position of `p` along the Z-order curve through the box from `lower`
+/// to `upper`.
+fn morton(p: &[f64], lower: &[f64], upper: &[f64]) -> u64 {
+    let axes = p.len().min(64);
+    let bits = 64 / axes;
+    let cells = (1_u64 << bits) as f64;
+    let quantized: SmallVec<[u64; 4]> = (0..axes)
+        .map(|axis| {
+            let extent = upper[axis] - lower[axis];
+            let x = if extent > 0_f64 {
+                (p[axis] - lower[axis]) / extent
+            } else {
+                0_f64
+            };
+            ((x * cells) as u64).min((1 << bits) - 1)
+        })
+        .collect();
+    let mut code = 0_u64;
+    for bit in (0..bits).rev() {
+        for q in &quantized {
+            code = (code << 1) | ((q >> bit) & 1);
+        }
+    }
+    code
+}
+
+/// The indices of samples in cubes of a given size.
+struct Buckets {
+    size: f64,
+    cells: HashMap<SmallVec<[i64; 4]>, Vec<usize>>,
+    /// The range of the cells which ever held samples.
+    lowest: SmallVec<[i64; 4]>,
+    highest: SmallVec<[i64; 4]>,
+}
+
+impl Buckets {
+    fn new(points: &[Point], size: f64) -> Buckets {
+        let mut cells: HashMap<SmallVec<[i64; 4]>, Vec<usize>> = HashMap::new();
+        let mut lowest = Buckets::cell(&points[0], size);
+        let mut highest = lowest.clone();
+        for (i, p) in points.iter().enumerate() {
+            let cell = Buckets::cell(p, size);
+            for ((lo, hi), c) in lowest.iter_mut().zip(highest.iter_mut()).zip(cell.iter()) {
+                *lo = (*lo).min(*c);
+                *hi = (*hi).max(*c);
+            }
+            cells.entry(cell).or_default().push(i);
+        }
+        Buckets {
+            size,
+            cells,
+            lowest,
+            highest,
+        }
+    }
+
+    fn cell(p: &[f64], size: f64) -> SmallVec<[i64; 4]> {
+        p.iter().map(|x| (x / size).floor() as i64).collect()
+    }
+
+    fn remove(&mut self, p: &[f64], i: usize) {
+        let cell = Buckets::cell(p, self.size);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|id| *id != i);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Appends the samples in the cubes at most `reach` cubes away from the
+    /// one of `p` along each axis to `found`.
+    fn around(&self, p: &[f64], reach: i64, found: &mut Vec<usize>) {
+        let center = Buckets::cell(p, self.size);
+        let visited = (2 * reach + 1) as f64;
+        if visited.powi(center.len() as i32) > self.cells.len() as f64 {
+            for (cell, ids) in &self.cells {
+                if cell
+                    .iter()
+                    .zip(center.iter())
+                    .all(|(c, o)| c.abs_diff(*o) <= reach as u64)
+                {
+                    found.extend_from_slice(ids);
+                }
+            }
+            return;
+        }
+        // all the cells from `reach` below to `reach` above the center, like
+        // an odometer
+        let mut cell: SmallVec<[i64; 4]> = center.iter().map(|c| c - reach).collect();
+        loop {
+            if let Some(ids) = self.cells.get(&cell) {
+                found.extend_from_slice(ids);
+            }
+            let Some(axis) = (0..cell.len()).find(|axis| cell[*axis] < center[*axis] + reach)
+            else {
+                return;
+            };
+            cell[axis] += 1;
+            for c in 0..axis {
+                cell[c] = center[c] - reach;
+            }
+        }
+    }
+
+    /// The index of the sample of `points` in the cubes which is closest to
+    /// `p`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cubes are empty.
+    fn nearest(&self, p: &[f64], points: &[Point]) -> usize {
+        let center = Buckets::cell(p, self.size);
+        let mut found = Vec::new();
+        for reach in 1.. {
+            found.clear();
+            self.around(p, reach, &mut found);
+            let best = found
+                .iter()
+                .map(|i| (*i, BackgroundGrid::dst_sqr(p, &points[*i])))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b));
+            // the samples in the other cubes are at least `reach` cubes away
+            let bound = reach as f64 * self.size;
+            let everywhere = (0..center.len()).all(|axis| {
+                center[axis] - reach <= self.lowest[axis]
+                    && self.highest[axis] <= center[axis] + reach
+            });
+            match best {
+                Some((i, dst_sqr)) if dst_sqr <= bound * bound || everywhere => return i,
+                None if everywhere => panic!("there are no samples"),
+                _ => {}
+            }
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlueNoiseConfig;
+
+    #[test]
+    fn transport() {
+        let from = BlueNoiseConfig::new(vec![20., 20.], 1.).seed(1).generate();
+        // moving all samples by the same offset is optimal
+        let shifted: Vec<Point> = from
+            .iter()
+            .map(|p| p.iter().map(|x| x + 0.3).collect())
+            .collect();
+        let matched = super::transport(&from, &shifted);
+        assert!(
+            matched
+                .pairs
+                .iter()
+                .enumerate()
+                .all(|(k, pair)| *pair == (k, k))
+        );
+
+        let to = BlueNoiseConfig::new(vec![20., 20.], 1.3).seed(2).generate();
+        for (a, b) in [(&from, &to), (&to, &from)] {
+            let matched = super::transport(a, b);
+            assert_eq!(matched.pairs.len(), from.len());
+            let mut froms = vec![0; a.len()];
+            let mut tos = vec![0; b.len()];
+            for (i, j) in &matched.pairs {
+                froms[*i] += 1;
+                tos[*j] += 1;
+            }
+            let (once, some) = if a.len() > b.len() {
+                (froms, tos)
+            } else {
+                (tos, froms)
+            };
+            assert!(once.iter().all(|c| *c == 1));
+            assert!(some.iter().all(|c| *c >= 1));
+            // exactly the samples of the other pattern in the end
+            let frame = matched.interpolate(a, b, 1.);
+            assert!(frame.iter().all(|p| b.contains(p)));
+            assert!(matched.cost(a, b) < 0.8 * from.len() as f64);
+        }
+        assert_eq!(super::transport(&from, &[]), Transport::default());
+    }
+
+    #[test]
+    fn degenerate() {
+        let point: Vec<Point> = vec![[3., 4.].into_iter().collect()];
+        let matched = super::transport(&point, &point);
+        assert_eq!(matched.pairs, vec![(0, 0)]);
+        let matched = super::transport(&vec![point[0].clone(); 5], &point);
+        assert_eq!(matched.pairs.len(), 5);
+
+        // all on one line, in different numbers
+        let line = |count: usize, step: f64| -> Vec<Point> {
+            (0..count)
+                .map(|i| [i as f64 * step, 1.].into_iter().collect())
+                .collect()
+        };
+        let matched = super::transport(&line(50, 1.), &line(50, 1.));
+        assert!(matched.pairs.iter().all(|(i, j)| i == j));
+        let (from, to) = (line(70, 1.), line(30, 2.3));
+        let matched = super::transport(&from, &to);
+        assert_eq!(matched.pairs.len(), from.len());
+        assert!(matched.cost(&from, &to) < from.len() as f64);
+    }
+
+    /// The cost of the optimal assignment of the rows to the columns of a
+    /// square matrix, with the Hungarian algorithm.
+    fn optimum(costs: &[Vec<f64>]) -> f64 {
+        let n = costs.len();
+        // potentials and the row matched with each column, 1-based with a
+        // column 0 for the row being added
+        let (mut u, mut v) = (vec![0_f64; n + 1], vec![0_f64; n + 1]);
+        let mut row_of = vec![0; n + 1];
+        let mut way = vec![0; n + 1];
+        for row in 1..=n {
+            row_of[0] = row;
+            let mut column = 0;
+            let mut smallest = vec![f64::INFINITY; n + 1];
+            let mut used = vec![false; n + 1];
+            loop {
+                used[column] = true;
+                let i = row_of[column];
+                let (mut delta, mut next) = (f64::INFINITY, 0);
+                for j in 1..=n {
+                    if !used[j] {
+                        let reduced = costs[i - 1][j - 1] - u[i] - v[j];
+                        if reduced < smallest[j] {
+                            smallest[j] = reduced;
+                            way[j] = column;
+                        }
+                        if smallest[j] < delta {
+                            delta = smallest[j];
+                            next = j;
+                        }
+                    }
+                }
+                for j in 0..=n {
+                    if used[j] {
+                        u[row_of[j]] += delta;
+                        v[j] -= delta;
+                    } else {
+                        smallest[j] -= delta;
+                    }
+                }
+                column = next;
+                if row_of[column] == 0 {
+                    break;
+                }
+            }
+            while column != 0 {
+                let previous = way[column];
+                row_of[column] = row_of[previous];
+                column = previous;
+            }
+        }
+        (1..=n).map(|j| costs[row_of[j] - 1][j - 1]).sum()
+    }
+
+    #[test]
+    fn near_optimum() {
+        for seed in 0..3 {
+            let from = BlueNoiseConfig::new(vec![16., 16.], 1.)
+                .seed(seed)
+                .generate();
+            let mut to = BlueNoiseConfig::new(vec![16., 16.], 1.)
+                .seed(seed + 10)
+                .generate();
+            let mut from = from;
+            let count = from.len().min(to.len());
+            from.truncate(count);
+            to.truncate(count);
+            let costs: Vec<Vec<f64>> = from
+                .iter()
+                .map(|a| to.iter().map(|b| BackgroundGrid::dst_sqr(a, b)).collect())
+                .collect();
+            let cost = super::transport(&from, &to).cost(&from, &to);
+            assert!(cost <= 1.02 * optimum(&costs), "{cost} {}", optimum(&costs));
+        }
+    }
+}
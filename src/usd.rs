@@ -0,0 +1,291 @@
+//! USD layers with a Points prim of a 2D or 3D point set.
+
+use crate::PoissonDiskSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// How [`export_usd`] writes a point set: as a `UsdGeomPoints` prim, which
+/// is the default prim of a text (`.usda`) layer with the z axis up. 2D
+/// samples get a z coordinate of zero.
+///
+/// Optionally, the prim has widths, either one for all points or one per
+/// point, and an integer primvar `class` with one value per point, e.g. to
+/// pick what to instance at each point.
+///
+/// ```
+/// use bluenoisers::UsdPoints;
+///
+/// let samples = [[1., 2.], [3.5, 4.]];
+/// let points = UsdPoints::new("Trees").width(0.5).classes(vec![0, 2]);
+/// let mut usda = Vec::new();
+/// points.write(&mut usda, &samples).unwrap();
+/// let usda = String::from_utf8(usda).unwrap();
+/// assert!(usda.contains("def Points \"Trees\""));
+/// assert!(usda.contains("point3f[] points = [(1, 2, 0), (3.5, 4, 0)]"));
+/// assert!(usda.contains("int[] primvars:class = [0, 2]"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsdPoints {
+    name: String,
+    widths: Option<Widths>,
+    classes: Option<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Widths {
+    Constant(f64),
+    Varying(Vec<f64>),
+}
+
+impl UsdPoints {
+    /// Creates a prim named `name`, without widths or classes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid prim name: letters, digits and
+    /// underscores, not starting with a digit.
+    pub fn new(name: impl Into<String>) -> UsdPoints {
+        let name = name.into();
+        assert!(
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && name.chars().next().is_some_and(|c| !c.is_ascii_digit()),
+            "{name:?} is not a valid prim name"
+        );
+        UsdPoints {
+            name,
+            widths: None,
+            classes: None,
+        }
+    }
+
+    /// Gives all points the diameter `width`, e.g. the minimal distance.
+    pub fn width(mut self, width: f64) -> UsdPoints {
+        self.widths = Some(Widths::Constant(width));
+        self
+    }
+
+    /// Gives each point its own diameter.
+    pub fn widths(mut self, widths: Vec<f64>) -> UsdPoints {
+        self.widths = Some(Widths::Varying(widths));
+        self
+    }
+
+    /// Adds the primvar `class` with one value per point.
+    pub fn classes(mut self, classes: Vec<usize>) -> UsdPoints {
+        self.classes = Some(classes);
+        self
+    }
+
+    /// Writes a layer with `samples` to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample has less than two or more than three coordinates,
+    /// if the widths or classes are not one per sample or if a class
+    /// doesn't fit in an `int`.
+    pub fn write<P: AsRef<[f64]>, W: Write>(&self, mut writer: W, samples: &[P]) -> io::Result<()> {
+        let varying_widths = match &self.widths {
+            Some(Widths::Varying(widths)) => Some(widths.len()),
+            _ => None,
+        };
+        for (name, len) in [
+            ("widths", varying_widths),
+            ("classes", self.classes.as_ref().map(Vec::len)),
+        ] {
+            if let Some(len) = len {
+                assert_eq!(
+                    len,
+                    samples.len(),
+                    "there must be one of the {name} per sample"
+                );
+            }
+        }
+        let positions: Vec<[f32; 3]> = samples
+            .iter()
+            .map(|sample| {
+                let sample = sample.as_ref();
+                assert!(
+                    (2..=3).contains(&sample.len()),
+                    "only 2D and 3D samples can be exported"
+                );
+                [
+                    sample[0] as f32,
+                    sample[1] as f32,
+                    sample.get(2).map_or(0., |z| *z as f32),
+                ]
+            })
+            .collect();
+
+        writeln!(writer, "#usda 1.0")?;
+        writeln!(writer, "(")?;
+        writeln!(writer, "    defaultPrim = \"{}\"", self.name)?;
+        writeln!(writer, "    upAxis = \"Z\"")?;
+        writeln!(writer, ")")?;
+        writeln!(writer)?;
+        writeln!(writer, "def Points \"{}\"", self.name)?;
+        writeln!(writer, "{{")?;
+        if !positions.is_empty() {
+            // the bounds of the points grown by their radius
+            let mut min = [f32::INFINITY; 3];
+            let mut max = [f32::NEG_INFINITY; 3];
+            for (index, position) in positions.iter().enumerate() {
+                let radius = match &self.widths {
+                    Some(Widths::Constant(width)) => *width as f32 / 2.,
+                    Some(Widths::Varying(widths)) => widths[index] as f32 / 2.,
+                    None => 0.,
+                };
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(position[axis] - radius);
+                    max[axis] = max[axis].max(position[axis] + radius);
+                }
+            }
+            writeln!(
+                writer,
+                "    float3[] extent = [{}, {}]",
+                vector(&min),
+                vector(&max)
+            )?;
+        }
+        write!(writer, "    point3f[] points = [")?;
+        for (index, position) in positions.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ", ")?;
+            }
+            write!(writer, "{}", vector(position))?;
+        }
+        writeln!(writer, "]")?;
+        match &self.widths {
+            Some(Widths::Constant(width)) => {
+                writeln!(writer, "    float[] widths = [{}] (", *width as f32)?;
+                writeln!(writer, "        interpolation = \"constant\"")?;
+                writeln!(writer, "    )")?;
+            }
+            Some(Widths::Varying(widths)) => {
+                let widths: Vec<String> = widths.iter().map(|w| (*w as f32).to_string()).collect();
+                writeln!(writer, "    float[] widths = [{}] (", widths.join(", "))?;
+                writeln!(writer, "        interpolation = \"vertex\"")?;
+                writeln!(writer, "    )")?;
+            }
+            None => {}
+        }
+        if let Some(classes) = &self.classes {
+            let classes: Vec<String> = classes
+                .iter()
+                .map(|class| {
+                    i32::try_from(*class)
+                        .expect("the classes must fit in an int")
+                        .to_string()
+                })
+                .collect();
+            writeln!(
+                writer,
+                "    int[] primvars:class = [{}] (",
+                classes.join(", ")
+            )?;
+            writeln!(writer, "        interpolation = \"vertex\"")?;
+            writeln!(writer, "    )")?;
+        }
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+fn vector(v: &[f32; 3]) -> String {
+    format!("({}, {}, {})", v[0], v[1], v[2])
+}
+
+/// Writes the 2D or 3D `samples` as a USD text layer to `path`, see
+/// [`UsdPoints`].
+///
+/// ```no_run
+/// use bluenoisers::{UsdPoints, export_usd};
+///
+/// let samples = bluenoisers::blue_noise(vec![100., 100.], 2., 30);
+/// export_usd("scatter.usda", &samples, &UsdPoints::new("Scatter").width(0.5)).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// Panics if a sample has less than two or more than three coordinates or
+/// if the widths or classes are not one per sample.
+pub fn export_usd<P: AsRef<[f64]>>(
+    path: impl AsRef<Path>,
+    samples: &[P],
+    points: &UsdPoints,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    points.write(&mut writer, samples)?;
+    writer.flush()
+}
+
+impl PoissonDiskSet {
+    /// Writes the samples of a 2D or 3D set as a USD text layer, see
+    /// [`export_usd`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2- or 3-dimensional or if the widths or
+    /// classes of `points` are not one per sample.
+    pub fn export_usd(&self, path: impl AsRef<Path>, points: &UsdPoints) -> io::Result<()> {
+        assert!(
+            (2..=3).contains(&self.dimensions().len()),
+            "only 2D and 3D sets can be exported"
+        );
+        export_usd(path, self.samples(), points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write() {
+        let write = |points: &UsdPoints, samples: &[[f64; 3]]| {
+            let mut text = Vec::new();
+            points.write(&mut text, samples).unwrap();
+            String::from_utf8(text).unwrap()
+        };
+        let samples = [[0.5, 1., 2.], [1.5, -1., 0.25]];
+        assert_eq!(
+            write(&UsdPoints::new("P").widths(vec![1., 0.5]), &samples),
+            "#usda 1.0\n\
+             (\n    defaultPrim = \"P\"\n    upAxis = \"Z\"\n)\n\n\
+             def Points \"P\"\n{\n\
+             \x20   float3[] extent = [(0, -1.25, 0), (1.75, 1.5, 2.5)]\n\
+             \x20   point3f[] points = [(0.5, 1, 2), (1.5, -1, 0.25)]\n\
+             \x20   float[] widths = [1, 0.5] (\n        interpolation = \"vertex\"\n    )\n\
+             }\n"
+        );
+        let empty = write(&UsdPoints::new("_1"), &[]);
+        assert!(empty.contains("point3f[] points = []\n"));
+        assert!(!empty.contains("extent"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_name() {
+        UsdPoints::new("1st");
+    }
+
+    #[test]
+    fn export_usd() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scatter.usda");
+        let set = PoissonDiskSet::new(vec![6., 4.], 1., 30);
+        let points = UsdPoints::new("Scatter")
+            .width(set.min_distance())
+            .classes((0..set.len()).map(|i| i % 3).collect());
+        set.export_usd(&path, &points).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("#usda 1.0\n"));
+        let line = text
+            .lines()
+            .find(|line| line.contains("point3f[] points"))
+            .unwrap();
+        assert_eq!(line.matches('(').count(), set.len());
+        assert!(text.contains("float[] widths = [1] (\n        interpolation = \"constant\""));
+        assert!(text.contains("int[] primvars:class = [0, 1, 2, 0"));
+    }
+}
@@ -0,0 +1,801 @@
+//! Checking and restoring the minimal distance in arbitrary point sets.
+
+use crate::config::{ConfigError, Interval, check_domain};
+use crate::grid::BackgroundGrid;
+use crate::{BlueNoiseIterator, Point};
+use std::collections::HashMap;
+
+/// A way in which a point set violates the Poisson disk property, see
+/// [`validate`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Violation {
+    /// Two points are closer than the minimal distance. `first` is the
+    /// smaller index.
+    TooClose {
+        /// The index of one point.
+        first: usize,
+        /// The index of the other point.
+        second: usize,
+        /// Their distance.
+        distance: f64,
+    },
+    /// The point with this index is outside of the domain.
+    Outside(usize),
+    /// The minimal distance is not positive and finite, so the points can't
+    /// be checked against it. This is the only violation reported then.
+    InvalidMinDistance(f64),
+    /// There are no dimensions. This is the only violation reported then.
+    NoDimensions,
+    /// The size of the domain along `axis` is negative or not finite. This is
+    /// the only violation reported then.
+    InvalidDimension {
+        /// The index of the axis.
+        axis: usize,
+        /// Its size.
+        size: f64,
+    },
+    /// The background grid for the domain doesn't fit into memory, see
+    /// [`ConfigError::DomainTooLarge`]. Only reported when building a
+    /// [`PoissonDiskSet`](crate::PoissonDiskSet), since [`validate`] needs no
+    /// grid.
+    DomainTooLarge {
+        /// The number of cells the grid would have.
+        cells: f64,
+        /// The number of bytes they would take.
+        bytes: f64,
+    },
+}
+
+/// The violation for a domain which `check_domain` or
+/// [`BackgroundGrid::try_new`] rejects.
+pub(crate) fn domain_violation(error: ConfigError) -> Violation {
+    match error {
+        ConfigError::NoDimensions => Violation::NoDimensions,
+        ConfigError::InvalidDimension { axis, size } => Violation::InvalidDimension { axis, size },
+        ConfigError::InvalidMinDistance(min_distance) => {
+            Violation::InvalidMinDistance(min_distance)
+        }
+        ConfigError::DomainTooLarge { cells, bytes } => Violation::DomainTooLarge { cells, bytes },
+        ConfigError::ZeroKAbort | ConfigError::InvalidMaxDistance(_) => {
+            unreachable!("only the domain is checked")
+        }
+    }
+}
+
+/// Checks that all `points` are inside of a domain of size `dimensions` (see
+/// [`blue_noise`](crate::blue_noise)) and at least `min_distance` apart.
+///
+/// Returns all the violations, points outside first, then the pairs which
+/// are too close, sorted by their indices, or only the first problem with
+/// the domain or `min_distance`, if there is one. Points outside of the domain,
+/// including those with coordinates which are not finite, are not compared
+/// with the others. Like the background grid of the generator, this only
+/// compares points in neighboring cells, so it takes linear time for sets
+/// which are mostly valid.
+///
+/// ```
+/// use bluenoisers::{Violation, validate};
+///
+/// let points = [[1., 1.], [5., 5.], [5.5, 5.], [11., 3.]];
+/// assert_eq!(
+///     validate(&points, 1., &[10., 10.]),
+///     Err(vec![
+///         Violation::Outside(3),
+///         Violation::TooClose { first: 1, second: 2, distance: 0.5 },
+///     ])
+/// );
+/// assert_eq!(validate(&points[..2], 1., &[10., 10.]), Ok(()));
+/// assert_eq!(
+///     validate(&points, 0., &[10., 10.]),
+///     Err(vec![Violation::InvalidMinDistance(0.)])
+/// );
+/// ```
+pub fn validate<P: AsRef<[f64]>>(
+    points: &[P],
+    min_distance: f64,
+    dimensions: &[f64],
+) -> Result<(), Vec<Violation>> {
+    validate_with_bounds(points, min_distance, dimensions, &[])
+}
+
+/// Like [`validate`], but for a domain with the given `bounds` along each
+/// axis, see [`BlueNoiseConfig::bounds`](crate::BlueNoiseConfig::bounds).
+/// Without `bounds`, all axes are half-open, as for [`validate`].
+///
+/// ```
+/// use bluenoisers::{Interval, Violation, validate, validate_with_bounds};
+///
+/// let corner = [[10., 10.]];
+/// assert_eq!(validate(&corner, 1., &[10., 10.]), Err(vec![Violation::Outside(0)]));
+/// assert_eq!(
+///     validate_with_bounds(&corner, 1., &[10., 10.], &[Interval::Closed; 2]),
+///     Ok(())
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics if there are `bounds`, but not one per dimension.
+pub fn validate_with_bounds<P: AsRef<[f64]>>(
+    points: &[P],
+    min_distance: f64,
+    dimensions: &[f64],
+    bounds: &[Interval],
+) -> Result<(), Vec<Violation>> {
+    assert!(
+        bounds.is_empty() || bounds.len() == dimensions.len(),
+        "there must be an interval per dimension"
+    );
+    if let Err(error) = check_domain(dimensions, min_distance) {
+        return Err(vec![domain_violation(error)]);
+    }
+    let outside: Vec<bool> = points
+        .iter()
+        .map(|point| {
+            let point = point.as_ref();
+            point.len() != dimensions.len()
+                || point
+                    .iter()
+                    .zip(dimensions.iter())
+                    .enumerate()
+                    .any(|(axis, (x, dim))| {
+                        let bounds = bounds.get(axis).copied().unwrap_or_default();
+                        !bounds.contains(*x, *dim)
+                    })
+        })
+        .collect();
+    let mut violations: Vec<Violation> = outside
+        .iter()
+        .enumerate()
+        .filter(|(_, outside)| **outside)
+        .map(|(i, _)| Violation::Outside(i))
+        .collect();
+    violations.extend(
+        conflicts(points, min_distance, |i| !outside[i])
+            .into_iter()
+            .map(|(first, second)| Violation::TooClose {
+                first,
+                second,
+                distance: BackgroundGrid::dst_sqr(points[first].as_ref(), points[second].as_ref())
+                    .sqrt(),
+            }),
+    );
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Returns the pairs of indices of samples which are closer than
+/// `min_distance` to each other, e.g. to validate stitched tiles. Each pair
+/// is listed once, with the smaller index first.
+///
+/// Samples with coordinates which are not finite are never in conflict, and
+/// neither are any samples if `min_distance` is not positive and finite.
+pub fn find_conflicts<P: AsRef<[f64]>>(samples: &[P], min_distance: f64) -> Vec<(usize, usize)> {
+    conflicts(samples, min_distance, |_| true)
+}
+
+/// The conflicts between the samples for which `include` holds, see
+/// [`find_conflicts`].
+fn conflicts<P: AsRef<[f64]>>(
+    samples: &[P],
+    min_distance: f64,
+    include: impl Fn(usize) -> bool,
+) -> Vec<(usize, usize)> {
+    if !(min_distance.is_finite() && min_distance > 0_f64) {
+        return Vec::new();
+    }
+    let min_dst_sqr = min_distance * min_distance;
+    let hash = spatial_hash(samples, min_distance, &include);
+    let mut conflicts = Vec::new();
+    for (i, sample) in samples.iter().enumerate().filter(|(i, _)| include(*i)) {
+        let sample = sample.as_ref();
+        for_each_neighbor(&hash, sample, min_distance, |j| {
+            if i < j && BackgroundGrid::dst_sqr(sample, samples[j].as_ref()) < min_dst_sqr {
+                conflicts.push((i, j));
+            }
+        });
+    }
+    conflicts.sort_unstable();
+    conflicts
+}
+
+/// Removes samples until none of the remaining ones are closer than
+/// `min_distance` to each other, e.g. at the seams of tiles which were
+/// generated without the ghosts of their neighbors.
+///
+/// Earlier samples take precedence over later ones, so concatenating tiles in
+/// a fixed order gives the same result on every machine. The removed samples
+/// leave holes, which can be filled by generating the affected tiles again
+/// with the result as ghosts.
+///
+/// Returns the number of removed samples.
+pub fn resolve_conflicts(samples: &mut Vec<Point>, min_distance: f64) -> usize {
+    if !(min_distance.is_finite() && min_distance > 0_f64) {
+        return 0;
+    }
+    let min_dst_sqr = min_distance * min_distance;
+    let hash = spatial_hash(samples, min_distance, |_| true);
+    let mut keep = vec![true; samples.len()];
+    for (i, sample) in samples.iter().enumerate() {
+        let mut conflict = false;
+        for_each_neighbor(&hash, sample, min_distance, |j| {
+            conflict |=
+                j < i && keep[j] && BackgroundGrid::dst_sqr(sample, &samples[j]) < min_dst_sqr;
+        });
+        keep[i] = !conflict;
+    }
+    let count = samples.len();
+    let mut keep = keep.into_iter();
+    samples.retain(|_| keep.next().unwrap());
+    count - samples.len()
+}
+
+/// Restores the Poisson disk property of `points` by removing points: the
+/// ones outside of the domain and, of the pairs of points which are too
+/// close, one each.
+///
+/// Unlike [`resolve_conflicts`], which keeps the earlier points, this greedily
+/// removes the points with the most conflicts first, which usually removes
+/// fewer points if the violations are clustered. The order of the remaining
+/// points is kept. Call [`refill`] afterwards to fill the holes.
+///
+/// Returns the number of removed points.
+///
+/// # Panics
+///
+/// Panics if `min_distance` is not positive and finite, or a size of the
+/// domain is negative or not finite.
+///
+/// ```
+/// use bluenoisers::{repair, validate};
+/// use smallvec::smallvec;
+///
+/// // the point in the middle is too close to both others
+/// let mut points = vec![smallvec![1., 1.], smallvec![1.8, 1.], smallvec![2.6, 1.]];
+/// assert_eq!(repair(&mut points, 1., &[10., 10.]), 1);
+/// assert_eq!(points.len(), 2);
+/// assert_eq!(validate(&points, 1., &[10., 10.]), Ok(()));
+/// ```
+pub fn repair(points: &mut Vec<Point>, min_distance: f64, dimensions: &[f64]) -> usize {
+    let mut keep = vec![true; points.len()];
+    let mut conflicts: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+    for violation in validate(points, min_distance, dimensions)
+        .err()
+        .unwrap_or_default()
+    {
+        match violation {
+            Violation::Outside(i) => keep[i] = false,
+            Violation::TooClose { first, second, .. } => {
+                conflicts[first].push(second);
+                conflicts[second].push(first);
+            }
+            Violation::InvalidMinDistance(_) => {
+                panic!("the minimal distance must be positive and finite")
+            }
+            Violation::NoDimensions | Violation::InvalidDimension { .. } => {
+                panic!("the domain must have sizes which are finite and not negative")
+            }
+            Violation::DomainTooLarge { .. } => unreachable!("`validate` needs no grid"),
+        }
+    }
+    let mut degree: Vec<usize> = conflicts
+        .iter()
+        .zip(keep.iter())
+        .map(|(others, kept)| {
+            if *kept {
+                others.iter().filter(|o| keep[**o]).count()
+            } else {
+                0
+            }
+        })
+        .collect();
+    // the points with the most conflicts, lazily updated
+    let mut queue: std::collections::BinaryHeap<(usize, usize)> = degree
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| **d > 0)
+        .map(|(i, d)| (*d, i))
+        .collect();
+    while let Some((d, i)) = queue.pop() {
+        if !keep[i] || d != degree[i] || d == 0 {
+            continue;
+        }
+        keep[i] = false;
+        for other in conflicts[i].iter() {
+            if keep[*other] {
+                degree[*other] -= 1;
+                queue.push((degree[*other], *other));
+            }
+        }
+    }
+    let count = points.len();
+    let mut keep = keep.into_iter();
+    points.retain(|_| keep.next().unwrap());
+    count - points.len()
+}
+
+/// Which points [`merge`] keeps when points of both sets are too close.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MergePolicy {
+    /// Keep the points of the first set.
+    PreferA,
+    /// Keep the points of the second set.
+    PreferB,
+    /// Keep the point with fewer conflicts and, if that is a tie, the one
+    /// which is farther from the other points around it, which leaves a more
+    /// even pattern.
+    FartherFromOthers,
+    /// Keep a random one of the points, seeded for reproducibility.
+    Random(u64),
+}
+
+/// Combines the points `a` and `b` into a set in which no points are closer
+/// than `min_distance`, e.g. to layer hand placed points over generated ones.
+///
+/// The points are considered one after the other in the order given by
+/// `policy`, and each is kept unless it is too close to a point which was
+/// kept before. So conflicts within one of the sets are resolved as well.
+/// The result has the remaining points of `a` first and then those of `b`,
+/// each in their original order; [`refill`] closes the gaps.
+///
+/// ```
+/// use bluenoisers::{MergePolicy, merge};
+/// use smallvec::smallvec;
+///
+/// let generated = vec![smallvec![1., 1.], smallvec![2., 1.], smallvec![3., 1.]];
+/// let authored = vec![smallvec![2.2, 1.]];
+/// let merged = merge(&generated, &authored, 1., MergePolicy::PreferB);
+/// assert_eq!(merged.len(), 2);
+/// assert_eq!(merged[1][..], [2.2, 1.]);
+/// ```
+pub fn merge(a: &[Point], b: &[Point], min_distance: f64, policy: MergePolicy) -> Vec<Point> {
+    merge_points(a, b, min_distance, policy, None)
+}
+
+/// Like [`merge`], but first fuses nearly coincident points, which are at
+/// most `epsilon` apart, into the one which comes first in the order of
+/// `policy`, see [`dedup`]. E.g. a point which was exported and imported
+/// again then counts as one with its original and doesn't crowd out its
+/// other neighbors with [`MergePolicy::FartherFromOthers`].
+///
+/// ```
+/// use bluenoisers::{MergePolicy, merge_dedup};
+/// use smallvec::smallvec;
+///
+/// let generated = vec![smallvec![1., 1.], smallvec![2., 1.]];
+/// let imported = vec![smallvec![2., 1.000001], smallvec![4., 1.]];
+/// let merged = merge_dedup(&generated, &imported, 1., MergePolicy::PreferB, 1e-3);
+/// assert_eq!(merged.len(), 3);
+/// assert_eq!(merged[1][..], [2., 1.000001]);
+/// ```
+pub fn merge_dedup(
+    a: &[Point],
+    b: &[Point],
+    min_distance: f64,
+    policy: MergePolicy,
+    epsilon: f64,
+) -> Vec<Point> {
+    merge_points(a, b, min_distance, policy, Some(epsilon))
+}
+
+fn merge_points(
+    a: &[Point],
+    b: &[Point],
+    min_distance: f64,
+    policy: MergePolicy,
+    epsilon: Option<f64>,
+) -> Vec<Point> {
+    let points: Vec<&Point> = a.iter().chain(b.iter()).collect();
+    let min_dst_sqr = min_distance * min_distance;
+    let hash = spatial_hash(&points, min_distance, |_| true);
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    match policy {
+        MergePolicy::PreferB => order.rotate_left(a.len()),
+        MergePolicy::Random(seed) => {
+            use rand::SeedableRng;
+            use rand::seq::SliceRandom;
+            order.shuffle(&mut rand_chacha::ChaCha8Rng::seed_from_u64(seed));
+        }
+        MergePolicy::PreferA | MergePolicy::FartherFromOthers => {}
+    }
+    let fused = match epsilon {
+        Some(epsilon) => fused(&points, &order, epsilon),
+        None => vec![false; points.len()],
+    };
+    order.retain(|i| !fused[*i]);
+    if policy == MergePolicy::FartherFromOthers {
+        // the number of conflicts and the distance to the nearest point
+        // which is not in conflict
+        let crowding: Vec<(usize, f64)> = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let mut conflicts = 0;
+                let mut nearest = f64::INFINITY;
+                for_each_neighbor(&hash, point, min_distance, |j| {
+                    if i == j || fused[j] {
+                        return;
+                    }
+                    let dst_sqr = BackgroundGrid::dst_sqr(point, points[j]);
+                    if dst_sqr < min_dst_sqr {
+                        conflicts += 1;
+                    } else {
+                        nearest = nearest.min(dst_sqr);
+                    }
+                });
+                (conflicts, nearest)
+            })
+            .collect();
+        order.sort_by(|i, j| {
+            let ((conflicts_i, nearest_i), (conflicts_j, nearest_j)) = (crowding[*i], crowding[*j]);
+            conflicts_i
+                .cmp(&conflicts_j)
+                .then(nearest_j.total_cmp(&nearest_i))
+        });
+    }
+    let mut keep = vec![false; points.len()];
+    for i in order {
+        let mut conflict = false;
+        for_each_neighbor(&hash, points[i], min_distance, |j| {
+            conflict |= keep[j] && BackgroundGrid::dst_sqr(points[i], points[j]) < min_dst_sqr;
+        });
+        keep[i] = !conflict;
+    }
+    points
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, kept)| *kept)
+        .map(|(point, _)| point.clone())
+        .collect()
+}
+
+/// Adds samples to the valid set `points` where there is space for them, e.g.
+/// after [`repair`] or to densify hand placed points. The new samples are
+/// appended; the arguments are the same as for [`blue_noise`](crate::blue_noise).
+///
+/// Growth starts from the existing points and from random positions in the
+/// gaps between them, so the result is blue noise like a generated set.
+///
+/// Returns the number of added samples.
+///
+/// # Panics
+///
+/// Panics if a point is in conflict with an earlier one or outside of the
+/// domain, which [`validate`] reports.
+pub fn refill(
+    points: &mut Vec<Point>,
+    min_distance: f64,
+    dimensions: &[f64],
+    k_abort: usize,
+) -> usize {
+    let mut it = BlueNoiseIterator::new(dimensions.to_vec(), min_distance, k_abort);
+    for point in points.iter() {
+        assert!(it.seed(point.clone()), "the points are not valid");
+    }
+    let count = points.len();
+    it.exhaust();
+    *points = it.samples;
+    points.len() - count
+}
+
+/// Removes the points which are at most `epsilon` away from an earlier point
+/// which is kept, fusing clusters of nearly coincident points, e.g. from repeated
+/// imports or rounding, into their first point. With an `epsilon` of zero,
+/// only exact duplicates are removed. The order of the remaining points is
+/// kept.
+///
+/// Returns the number of removed points.
+///
+/// ```
+/// use bluenoisers::dedup;
+/// use smallvec::smallvec;
+///
+/// let mut points = vec![smallvec![1., 1.], smallvec![3., 1.], smallvec![1., 1.0001]];
+/// assert_eq!(dedup(&mut points, 0.001), 1);
+/// assert_eq!(points.len(), 2);
+/// ```
+pub fn dedup(points: &mut Vec<Point>, epsilon: f64) -> usize {
+    let count = points.len();
+    let references: Vec<&Point> = points.iter().collect();
+    let order: Vec<usize> = (0..count).collect();
+    let mut fused = fused(&references, &order, epsilon).into_iter();
+    points.retain(|_| !fused.next().unwrap());
+    count - points.len()
+}
+
+/// Which of `points` are at most `epsilon` away from a point which comes
+/// before them in `order` and is not fused itself.
+fn fused(points: &[&Point], order: &[usize], epsilon: f64) -> Vec<bool> {
+    // any cell size of at least epsilon works
+    let cell_size = if epsilon > 0_f64 { epsilon } else { 1_f64 };
+    let hash = spatial_hash(points, cell_size, |_| true);
+    let epsilon_sqr = epsilon * epsilon;
+    let mut seen = vec![false; points.len()];
+    let mut fused = vec![false; points.len()];
+    for i in order {
+        for_each_neighbor(&hash, points[*i], cell_size, |j| {
+            fused[*i] |= seen[j]
+                && !fused[j]
+                && BackgroundGrid::dst_sqr(points[*i], points[j]) <= epsilon_sqr;
+        });
+        seen[*i] = true;
+    }
+    fused
+}
+
+/// Buckets the indices of the `samples` for which `include` holds into cells
+/// of edge length `cell_size`. Samples with coordinates which are not finite
+/// are left out, they have no neighbors.
+///
+/// Unlike the cells of the background grid, these can hold any number of
+/// samples, so they also work for sets with conflicts.
+fn spatial_hash<P: AsRef<[f64]>>(
+    samples: &[P],
+    cell_size: f64,
+    include: impl Fn(usize) -> bool,
+) -> HashMap<Vec<i64>, Vec<usize>> {
+    let mut hash: HashMap<Vec<i64>, Vec<usize>> = HashMap::new();
+    for (i, sample) in samples.iter().enumerate() {
+        let sample = sample.as_ref();
+        if include(i) && sample.iter().all(|x| x.is_finite()) {
+            hash.entry(hash_cell(sample, cell_size))
+                .or_default()
+                .push(i);
+        }
+    }
+    hash
+}
+
+/// The cell of `position`. Cells beyond the range of `i64` are clamped, which
+/// merges the outermost ones but keeps their neighbors correct.
+fn hash_cell(position: &[f64], cell_size: f64) -> Vec<i64> {
+    position
+        .iter()
+        // `as` saturates
+        .map(|x| (x / cell_size).floor() as i64)
+        .collect()
+}
+
+/// Calls `f` with the indices of all samples in the cells around `position`,
+/// or not at all if `position` has coordinates which are not finite.
+fn for_each_neighbor<F: FnMut(usize)>(
+    hash: &HashMap<Vec<i64>, Vec<usize>>,
+    position: &[f64],
+    cell_size: f64,
+    mut f: F,
+) {
+    if !position.iter().all(|x| x.is_finite()) {
+        return;
+    }
+    let center = hash_cell(position, cell_size);
+    let lower: Vec<i64> = center.iter().map(|c| c.saturating_sub(1)).collect();
+    let upper: Vec<i64> = center.iter().map(|c| c.saturating_add(1)).collect();
+    let mut cell = lower.clone();
+    loop {
+        if let Some(indices) = hash.get(&cell) {
+            indices.iter().for_each(|i| f(*i));
+        }
+        // iterate cells
+        let mut done = true;
+        for i in 0..cell.len() {
+            if cell[i] == upper[i] {
+                cell[i] = lower[i];
+            } else {
+                cell[i] += 1;
+                done = false;
+                break;
+            }
+        }
+        if done {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn generated_sets_are_valid() {
+        let samples = crate::blue_noise(vec![30., 20.], 1., 30);
+        assert_eq!(validate(&samples, 1., &[30., 20.]), Ok(()));
+        let violations = validate(&samples, 1.5, &[20., 20.]).unwrap_err();
+        for violation in violations {
+            match violation {
+                Violation::Outside(i) => assert!(samples[i][0] >= 20.),
+                Violation::TooClose {
+                    first,
+                    second,
+                    distance,
+                } => {
+                    assert!(first < second);
+                    assert!(distance < 1.5);
+                    assert!(samples[first][0] < 20. && samples[second][0] < 20.);
+                }
+                Violation::InvalidMinDistance(_)
+                | Violation::NoDimensions
+                | Violation::InvalidDimension { .. }
+                | Violation::DomainTooLarge { .. } => unreachable!(),
+            }
+        }
+        let ragged = vec![vec![1., 2.], vec![3.]];
+        assert_eq!(
+            validate(&ragged, 1., &[5., 5.]),
+            Err(vec![Violation::Outside(1)])
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        // not finite coordinates are outside and not compared
+        let points = [[f64::INFINITY, 1.], [1., 1.], [f64::NAN, 1.], [1.5, 1.]];
+        assert_eq!(
+            validate(&points, 1., &[10., 10.]),
+            Err(vec![
+                Violation::Outside(0),
+                Violation::Outside(2),
+                Violation::TooClose {
+                    first: 1,
+                    second: 3,
+                    distance: 0.5
+                },
+            ])
+        );
+        assert_eq!(find_conflicts(&points, 1.), vec![(1, 3)]);
+        // cells beyond the range of `i64`
+        let huge = [[1e300, 1.], [1e300, 1.5], [-1e300, 1.], [1., 1.]];
+        assert_eq!(
+            validate(&huge, 1., &[10., 10.]),
+            Err(vec![
+                Violation::Outside(0),
+                Violation::Outside(1),
+                Violation::Outside(2)
+            ])
+        );
+        assert_eq!(find_conflicts(&huge, 1.), vec![(0, 1)]);
+        let mut huge: Vec<Point> = huge.iter().map(|p| Point::from_slice(p)).collect();
+        assert_eq!(merge(&huge, &[], 1., MergePolicy::PreferA).len(), 3);
+        assert_eq!(super::dedup(&mut huge, 1.), 1);
+        // minimal distances which are not positive and finite
+        for min_distance in [0., -1., f64::INFINITY, f64::NAN] {
+            let violations = validate(&points[1..2], min_distance, &[10., 10.]).unwrap_err();
+            assert!(matches!(violations[..], [Violation::InvalidMinDistance(_)]));
+            assert!(find_conflicts(&points, min_distance).is_empty());
+        }
+        assert_eq!(resolve_conflicts(&mut huge, 0.), 0);
+        // loading points goes through `validate`
+        let points = vec![Point::from_slice(&[1., 1.])];
+        assert_eq!(
+            crate::PoissonDiskSet::from_points(vec![10., 10.], 0., points).unwrap_err(),
+            [Violation::InvalidMinDistance(0.)]
+        );
+        // domains which are not valid, also without points
+        assert_eq!(
+            validate::<Point>(&[], 1., &[]),
+            Err(vec![Violation::NoDimensions])
+        );
+        assert!(matches!(
+            crate::PoissonDiskSet::from_points(vec![10., f64::NAN], 1., Vec::new()).unwrap_err()[..],
+            [Violation::InvalidDimension { axis: 1, size }] if size.is_nan()
+        ));
+        assert!(matches!(
+            crate::PoissonDiskSet::from_points(vec![1e12; 3], 1e-3, Vec::new()).unwrap_err()[..],
+            [Violation::DomainTooLarge { .. }]
+        ));
+    }
+
+    #[test]
+    fn merge_policies() {
+        let dimensions = [20., 20.];
+        let generated = crate::blue_noise(dimensions.to_vec(), 1., 30);
+        let authored: Vec<Point> = (1..10)
+            .map(|i| Point::from_slice(&[2. * i as f64, 10.]))
+            .collect();
+        let conflicting = |merged: &[Point], points: &[Point]| {
+            points.iter().filter(|p| !merged.contains(p)).count()
+        };
+        for policy in [
+            MergePolicy::PreferA,
+            MergePolicy::PreferB,
+            MergePolicy::FartherFromOthers,
+            MergePolicy::Random(7),
+        ] {
+            let merged = merge(&generated, &authored, 1., policy);
+            assert_eq!(validate(&merged, 1., &dimensions), Ok(()));
+            assert_eq!(merged, merge(&generated, &authored, 1., policy));
+            match policy {
+                MergePolicy::PreferA => {
+                    assert_eq!(merged[..generated.len()], generated[..]);
+                }
+                MergePolicy::PreferB => {
+                    assert_eq!(merged[merged.len() - authored.len()..], authored[..]);
+                    assert!(conflicting(&merged, &generated) > 0);
+                }
+                _ => {}
+            }
+        }
+        // the middle point is too close to both others
+        let a = vec![Point::from_slice(&[1., 1.])];
+        let b = vec![Point::from_slice(&[1.5, 1.]), Point::from_slice(&[2.4, 1.])];
+        assert_eq!(merge(&a, &b, 1., MergePolicy::PreferB), b[..1]);
+        assert_eq!(
+            merge(&a, &b, 1., MergePolicy::FartherFromOthers),
+            [a[0].clone(), b[1].clone()]
+        );
+        // points of the same set conflicting with each other
+        let doubled = [authored.clone(), authored.clone()].concat();
+        assert_eq!(merge(&doubled, &[], 1., MergePolicy::PreferB), authored);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut points: Vec<Point> = [[1., 1.], [1., 1.], [1.6, 1.], [2.2, 1.], [1., 1.5]]
+            .iter()
+            .map(|p| Point::from_slice(p))
+            .collect();
+        let mut exact = points.clone();
+        assert_eq!(super::dedup(&mut exact, 0.), 1);
+        assert_eq!(exact, points[1..]);
+        // the third point is fused into the first, so the fourth is kept
+        assert_eq!(super::dedup(&mut points, 0.7), 3);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1][..], [2.2, 1.]);
+
+        let generated = crate::blue_noise(vec![20., 20.], 1., 30);
+        let moved: Vec<Point> = generated
+            .iter()
+            .map(|p| p.iter().map(|x| x + 1e-9).collect())
+            .collect();
+        for policy in [MergePolicy::PreferB, MergePolicy::FartherFromOthers] {
+            let merged = merge_dedup(&generated, &moved, 1., policy, 1e-6);
+            assert_eq!(merged.len(), generated.len());
+            assert_eq!(validate(&merged, 1., &[20., 20.]), Ok(()));
+        }
+        assert_eq!(
+            merge_dedup(&generated, &moved, 1., MergePolicy::PreferB, 1e-6),
+            moved
+        );
+    }
+
+    #[test]
+    fn repair_and_refill() {
+        let dimensions = [30., 20.];
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut points = crate::blue_noise_with_rng(dimensions.to_vec(), 1., 30, &mut rng);
+        // jitter a part of the points, which creates clusters of violations
+        for point in points.iter_mut().step_by(5) {
+            for x in point.iter_mut() {
+                *x += rand::Rng::gen_range(&mut rng, -0.5..0.5);
+            }
+        }
+        let mut resolved = points.clone();
+        let count = points.len();
+        let removed = repair(&mut points, 1., &dimensions);
+        assert!(removed > 0);
+        assert_eq!(points.len(), count - removed);
+        assert_eq!(validate(&points, 1., &dimensions), Ok(()));
+        // removing greedily by the number of conflicts is usually better
+        resolved.retain(|p| {
+            p.iter()
+                .zip(dimensions.iter())
+                .all(|(x, d)| (0. ..*d).contains(x))
+        });
+        let resolved_removed = count - resolved.len() + resolve_conflicts(&mut resolved, 1.);
+        assert!(removed <= resolved_removed + resolved_removed / 10);
+
+        let kept = points.clone();
+        let added = refill(&mut points, 1., &dimensions, 30);
+        assert!(added > 0);
+        assert_eq!(points[..kept.len()], kept[..]);
+        assert_eq!(validate(&points, 1., &dimensions), Ok(()));
+        assert!(points.len() as f64 > 0.95 * count as f64);
+    }
+}
@@ -0,0 +1,420 @@
+//! Blue noise whose minimal distance varies over the domain.
+
+use crate::grid::BackgroundGrid;
+use crate::{Point, candidate};
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::Rng;
+
+/// Generates blue noise samples whose spacing follows `radius`, a function
+/// giving the minimal distance at every position of the domain, e.g. to
+/// place more samples where an image is darker or a mesh is more detailed.
+///
+/// The radii are clamped to `[min_radius, max_radius]`, and a radius which is
+/// NaN counts as `max_radius`. Every two samples `p` and `q` are at least the
+/// smaller of `radius(p)` and `radius(q)` apart, so within a region of
+/// constant radius the samples are a plain Poisson disk set. Since neighbors
+/// are looked up within the radius of a candidate, large ratios of
+/// `max_radius` to `min_radius` are slower.
+///
+/// The first sample and all the candidates are drawn from `rng` directly,
+/// so a seeded `rng` gives the same samples.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// // denser towards the left
+/// let radius = |p: &[f64]| 1. + p[0] / 16.;
+/// let samples = bluenoisers::blue_noise_variable(vec![64., 16.], 1., 5., radius, 30, &mut rng);
+/// let left = samples.iter().filter(|p| p[0] < 32.).count();
+/// assert!(left > 2 * (samples.len() - left));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `min_radius` is not positive or `max_radius` is less than
+/// `min_radius`.
+pub fn blue_noise_variable<F, R>(
+    dimensions: Vec<f64>,
+    min_radius: f64,
+    max_radius: f64,
+    radius: F,
+    k_abort: usize,
+    rng: &mut R,
+) -> Vec<Point>
+where
+    F: Fn(&[f64]) -> f64,
+    R: Rng,
+{
+    let radius_at = clamped(radius, min_radius, max_radius);
+    let mut pattern = Pattern::new(dimensions.clone(), min_radius);
+    let first: Point = dimensions
+        .iter()
+        .map(|x| rng.gen_range(0_f64..*x))
+        .collect();
+    let radius = radius_at(&first);
+    assert!(
+        pattern.try_add(first, radius),
+        "an empty pattern accepts any sample inside of the domain"
+    );
+    pattern.grow(vec![0], &radius_at, k_abort, |_| true, rng);
+    pattern.samples
+}
+
+/// Which samples [`blue_noise_variable_update`] removed and added.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleUpdate {
+    /// The indices of the removed samples among the previous ones, ascending.
+    pub removed: Vec<usize>,
+    /// The new samples, in order of generation.
+    pub added: Vec<Point>,
+}
+
+impl SampleUpdate {
+    /// The updated samples: the previous ones which were not removed, in
+    /// their order, followed by the added ones.
+    pub fn apply(&self, previous: &[Point]) -> Vec<Point> {
+        let mut removed = self.removed.iter().peekable();
+        previous
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| removed.next_if_eq(&i).is_none())
+            .map(|(_, sample)| sample.clone())
+            .chain(self.added.iter().cloned())
+            .collect()
+    }
+}
+
+/// Updates `previous`, samples of [`blue_noise_variable`], after the radius
+/// or the size of the domain changed in the half-open boxes `changed`, e.g.
+/// because an artist painted over a part of a radius map.
+///
+/// Only the samples inside of the boxes or outside of the new domain are
+/// removed, and the boxes are filled again, growing from the samples around
+/// them. All the other samples stay where they are, so whatever refers to
+/// them by index, like the instances of a scattering, can be kept, and the
+/// result only differs where something changed. The arguments are the same
+/// as for [`blue_noise_variable`] with the new radius and domain; where the
+/// domain grew must be in `changed` as well. Outside of the boxes the radius
+/// must be the same as before, otherwise the remaining samples may be too
+/// close for it.
+///
+/// Within a quarter of the radius inside of the borders of the boxes, there
+/// are about 20 % less samples than elsewhere, since the remaining samples
+/// around the boxes were placed with their removed neighbors in mind.
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(3);
+/// let samples = bluenoisers::blue_noise_variable(vec![64., 64.], 1., 4., |_| 2., 30, &mut rng);
+/// // denser in the middle, same as before elsewhere
+/// let middle = |p: &[f64]| p.iter().all(|x| (24. ..40.).contains(x));
+/// let radius = |p: &[f64]| if middle(p) { 1. } else { 2. };
+/// let changed = [(vec![24., 24.], vec![40., 40.])];
+/// let update = bluenoisers::blue_noise_variable_update(
+///     vec![64., 64.], 1., 4., radius, 30, &samples, &changed, &mut rng,
+/// );
+/// assert!(update.removed.iter().all(|i| middle(&samples[*i])));
+/// assert!(update.added.len() > 3 * update.removed.len());
+/// let samples = update.apply(&samples);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `min_radius` is not positive or `max_radius` is less than
+/// `min_radius`.
+#[allow(clippy::too_many_arguments)]
+pub fn blue_noise_variable_update<F, R>(
+    dimensions: Vec<f64>,
+    min_radius: f64,
+    max_radius: f64,
+    radius: F,
+    k_abort: usize,
+    previous: &[Point],
+    changed: &[(Vec<f64>, Vec<f64>)],
+    rng: &mut R,
+) -> SampleUpdate
+where
+    F: Fn(&[f64]) -> f64,
+    R: Rng,
+{
+    let radius_at = clamped(radius, min_radius, max_radius);
+    let in_domain = |position: &[f64]| {
+        position
+            .iter()
+            .zip(dimensions.iter())
+            .all(|(x, dim)| 0_f64 <= *x && x < dim)
+    };
+    let in_changed = |position: &[f64]| {
+        changed.iter().any(|(lower, upper)| {
+            position
+                .iter()
+                .zip(lower.iter().zip(upper.iter()))
+                .all(|(x, (lo, hi))| lo <= x && x < hi)
+        })
+    };
+    let mut pattern = Pattern::new(dimensions.clone(), min_radius);
+    let mut removed = Vec::new();
+    for (i, sample) in previous.iter().enumerate() {
+        let kept = in_domain(sample)
+            && !in_changed(sample)
+            && pattern.try_add(sample.clone(), radius_at(sample));
+        if !kept {
+            removed.push(i);
+        }
+    }
+    let kept = pattern.samples.len();
+    // the boxes inside of the domain
+    let boxes: Vec<(Vec<f64>, Vec<f64>)> = changed
+        .iter()
+        .map(|(lower, upper)| {
+            let lower: Vec<f64> = lower.iter().map(|lo| lo.max(0_f64)).collect();
+            let upper: Vec<f64> = upper
+                .iter()
+                .zip(dimensions.iter())
+                .map(|(hi, dim)| hi.min(*dim))
+                .collect();
+            (lower, upper)
+        })
+        .filter(|(lower, upper)| lower.iter().zip(upper.iter()).all(|(lo, hi)| lo < hi))
+        .collect();
+    // the remaining samples whose candidates can reach into a box
+    let active = (0..kept)
+        .filter(|i| {
+            let sample = &pattern.samples[*i];
+            let reach = 2_f64 * pattern.radii[*i];
+            boxes.iter().any(|(lower, upper)| {
+                let dst_sqr: f64 = sample
+                    .iter()
+                    .zip(lower.iter().zip(upper.iter()))
+                    .map(|(x, (lo, hi))| {
+                        let outside = (lo - x).max(x - hi).max(0_f64);
+                        outside * outside
+                    })
+                    .sum();
+                dst_sqr < reach * reach
+            })
+        })
+        .collect();
+    let allowed = |position: &[f64]| in_domain(position) && in_changed(position);
+    pattern.grow(active, &radius_at, k_abort, allowed, rng);
+    // parts of the boxes may not be reachable from the samples so far
+    for (lower, upper) in &boxes {
+        loop {
+            let dart = (0..k_abort).find_map(|_| {
+                let dart: Point = lower
+                    .iter()
+                    .zip(upper.iter())
+                    .map(|(lo, hi)| rng.gen_range(*lo..*hi))
+                    .collect();
+                let radius = radius_at(&dart);
+                pattern
+                    .try_add(dart, radius)
+                    .then(|| pattern.samples.len() - 1)
+            });
+            match dart {
+                Some(dart) => pattern.grow(vec![dart], &radius_at, k_abort, allowed, rng),
+                None => break,
+            }
+        }
+    }
+    SampleUpdate {
+        removed,
+        added: pattern.samples.split_off(kept),
+    }
+}
+
+/// The radius function clamped to `[min_radius, max_radius]`, with NaN as
+/// `max_radius`.
+fn clamped<F: Fn(&[f64]) -> f64>(
+    radius: F,
+    min_radius: f64,
+    max_radius: f64,
+) -> impl Fn(&[f64]) -> f64 {
+    assert!(min_radius > 0_f64, "the minimal radius must be positive");
+    assert!(
+        max_radius >= min_radius,
+        "the maximal radius must be at least the minimal radius"
+    );
+    move |position: &[f64]| {
+        let radius = radius(position);
+        if radius.is_nan() {
+            max_radius
+        } else {
+            radius.clamp(min_radius, max_radius)
+        }
+    }
+}
+
+/// Samples with their radii.
+struct Pattern {
+    // no two samples are closer than the minimal radius, so the cells of its
+    // grid hold one sample at most
+    bggrid: BackgroundGrid,
+    samples: Vec<Point>,
+    radii: Vec<f64>,
+}
+
+impl Pattern {
+    fn new(dimensions: Vec<f64>, min_radius: f64) -> Pattern {
+        Pattern {
+            bggrid: BackgroundGrid::new(dimensions, min_radius),
+            samples: Vec::new(),
+            radii: Vec::new(),
+        }
+    }
+
+    /// Adds a sample at `position` with `radius` unless it is outside of the
+    /// domain or in conflict with a sample, and returns whether it did.
+    fn try_add(&mut self, position: Point, radius: f64) -> bool {
+        // the neighbors closer than the radius of the candidate, which
+        // conflict if they are also closer than their own radius
+        let too_close = self
+            .bggrid
+            .within(&position, radius, &self.samples)
+            .any(|i| {
+                BackgroundGrid::dst_sqr(&position, &self.samples[i]) < self.radii[i] * self.radii[i]
+            });
+        if too_close || self.bggrid.insert(position, &mut self.samples).is_err() {
+            return false;
+        }
+        self.radii.push(radius);
+        true
+    }
+
+    /// Grows from the samples with the indices in `active` until none of them
+    /// has space left around it, placing new samples only where `allowed`.
+    fn grow<R: Rng>(
+        &mut self,
+        mut active: Vec<usize>,
+        radius_at: impl Fn(&[f64]) -> f64,
+        k_abort: usize,
+        allowed: impl Fn(&[f64]) -> bool,
+        rng: &mut R,
+    ) {
+        let dimensions = self.bggrid.dimensions.clone();
+        while !active.is_empty() {
+            let active_idx = rng.gen_range(0..active.len());
+            let current = self.samples[active[active_idx]].clone();
+            let current_radius = self.radii[active[active_idx]];
+            let mut found = false;
+            for _ in 0..k_abort {
+                let samp = candidate(&current, current_radius, 2_f64 * current_radius, rng);
+                if samp
+                    .iter()
+                    .zip(dimensions.iter())
+                    .any(|(x, dim)| *x < 0_f64 || x >= dim)
+                    || !allowed(&samp)
+                {
+                    continue;
+                }
+                let samp_radius = radius_at(&samp);
+                if !self.try_add(samp, samp_radius) {
+                    continue;
+                }
+                active.push(self.samples.len() - 1);
+                found = true;
+                break;
+            }
+            if !found {
+                active.swap_remove(active_idx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn variable_radius() {
+        let radius = |p: &[f64]| if p[1] < 10. { 1. } else { 3. };
+        let generate = |seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            blue_noise_variable(vec![40., 20.], 1., 3., radius, 30, &mut rng)
+        };
+        let samples = generate(5);
+        assert_eq!(samples, generate(5));
+        for (i, p) in samples.iter().enumerate() {
+            assert!(p[0] >= 0. && p[0] < 40. && p[1] >= 0. && p[1] < 20.);
+            for q in &samples[i + 1..] {
+                let distance = BackgroundGrid::dst_sqr(p, q).sqrt();
+                assert!(distance >= radius(p).min(radius(q)));
+            }
+        }
+        // the density is about inversely proportional to the squared radius
+        let dense = samples.iter().filter(|p| p[1] < 10.).count() as f64;
+        let sparse = samples.len() as f64 - dense;
+        assert!((5.0..13.).contains(&(dense / sparse)));
+
+        // a constant radius gives as many samples as plain blue noise
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let constant = blue_noise_variable(vec![40., 20.], 1., 1., |_| f64::NAN, 30, &mut rng);
+        let plain = crate::blue_noise_with_rng(vec![40., 20.], 1., 30, &mut rng);
+        let ratio = constant.len() as f64 / plain.len() as f64;
+        assert!((0.95..1.05).contains(&ratio));
+    }
+
+    #[test]
+    fn update() {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let previous = blue_noise_variable(vec![40., 20.], 1., 3., |_| 2., 30, &mut rng);
+        // denser in a box, and the domain grows along x
+        let dense = |p: &[f64]| (10. ..20.).contains(&p[0]) && p[1] < 10.;
+        let radius = |p: &[f64]| if dense(p) { 1. } else { 2. };
+        let changed = [
+            (vec![10., 0.], vec![20., 10.]),
+            (vec![40., 0.], vec![50., 20.]),
+        ];
+        let update = blue_noise_variable_update(
+            vec![50., 20.],
+            1.,
+            3.,
+            radius,
+            30,
+            &previous,
+            &changed,
+            &mut rng,
+        );
+        let removed: Vec<usize> = (0..previous.len())
+            .filter(|i| dense(&previous[*i]))
+            .collect();
+        assert_eq!(update.removed, removed);
+        let samples = update.apply(&previous);
+        assert_eq!(
+            samples.len(),
+            previous.len() - removed.len() + update.added.len()
+        );
+        for (i, p) in samples.iter().enumerate() {
+            for q in &samples[i + 1..] {
+                let distance = BackgroundGrid::dst_sqr(p, q).sqrt();
+                assert!(distance >= radius(p).min(radius(q)));
+            }
+        }
+        let grown = update.added.iter().filter(|p| p[0] >= 40.).count();
+        let filled = update.added.iter().filter(|p| dense(p)).count();
+        assert_eq!(grown + filled, update.added.len());
+        // 10 x 20 new units with radius 2, and about four times as many
+        // samples in the dense box as before
+        assert!((25..45).contains(&grown));
+        assert!(filled > 3 * removed.len());
+
+        // nothing changed, nothing to do
+        let unchanged = blue_noise_variable_update(
+            vec![40., 20.],
+            1.,
+            3.,
+            |_| 2.,
+            30,
+            &previous,
+            &[],
+            &mut rng,
+        );
+        assert_eq!(unchanged, SampleUpdate::default());
+    }
+}
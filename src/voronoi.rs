@@ -0,0 +1,140 @@
+//! Voronoi diagrams of 2D sets.
+
+use crate::PoissonDiskSet;
+
+/// A polygon, as its corners in counter-clockwise order.
+pub type Polygon = Vec<[f64; 2]>;
+
+impl PoissonDiskSet {
+    /// Computes the Voronoi diagram of the set, clipped to the domain.
+    ///
+    /// Returns one cell per sample, in the order of the samples. Each cell is
+    /// the part of the domain which is closer to its sample than to any of
+    /// the others.
+    ///
+    /// Each cell is cut out of the domain by the bisectors to the samples
+    /// around it, which are found using the background grid. Since there is
+    /// a sample less than *2 × `min_distance`* away from every point of the
+    /// domain, only few of them are needed.
+    ///
+    /// ```
+    /// use bluenoisers::PoissonDiskSet;
+    ///
+    /// let set = PoissonDiskSet::new(vec![20., 10.], 1., 30);
+    /// let cells = set.voronoi();
+    /// let area: f64 = cells
+    ///     .iter()
+    ///     .map(|cell| {
+    ///         (0..cell.len())
+    ///             .map(|i| {
+    ///                 let (a, b) = (cell[i], cell[(i + 1) % cell.len()]);
+    ///                 a[0] * b[1] - b[0] * a[1]
+    ///             })
+    ///             .sum::<f64>()
+    ///             / 2.
+    ///     })
+    ///     .sum();
+    /// assert!((area - 200.).abs() < 1e-6);
+    /// ```
+    ///
+    /// Requires the `voronoi` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set is not 2-dimensional.
+    #[cfg(feature = "voronoi")]
+    pub fn voronoi(&self) -> Vec<Polygon> {
+        assert_eq!(
+            self.dimensions().len(),
+            2,
+            "Voronoi diagrams are only supported in 2D"
+        );
+        (0..self.len())
+            .map(|index| self.voronoi_cell(index))
+            .collect()
+    }
+
+    /// The Voronoi cell of the sample with the given index, see
+    /// [`voronoi`](PoissonDiskSet::voronoi). The set must be 2-dimensional.
+    pub(crate) fn voronoi_cell(&self, index: usize) -> Polygon {
+        debug_assert_eq!(self.dimensions().len(), 2);
+        let (width, height) = (self.dimensions()[0], self.dimensions()[1]);
+        let sample = &self.samples()[index];
+        let site = [sample[0], sample[1]];
+        let mut radius = 4_f64 * self.min_distance();
+        loop {
+            // the cell is final once all samples are considered which could
+            // cut off its farthest corner
+            let mut cell = vec![[0., 0.], [width, 0.], [width, height], [0., height]];
+            for other in self.within(&site, radius) {
+                if other != index {
+                    let other = &self.samples()[other];
+                    cell = clip(&cell, site, [other[0], other[1]]);
+                }
+            }
+            let reach = cell
+                .iter()
+                .map(|corner| {
+                    ((corner[0] - site[0]).powi(2) + (corner[1] - site[1]).powi(2)).sqrt()
+                })
+                .fold(0_f64, f64::max);
+            if 2_f64 * reach < radius || radius > width + height {
+                return cell;
+            }
+            radius = 2_f64 * reach * 1.01;
+        }
+    }
+}
+
+/// Clips the convex `polygon` to the half-plane of points closer to `site`
+/// than to `other`.
+fn clip(polygon: &[[f64; 2]], site: [f64; 2], other: [f64; 2]) -> Polygon {
+    let normal = [other[0] - site[0], other[1] - site[1]];
+    let middle = [(site[0] + other[0]) / 2., (site[1] + other[1]) / 2.];
+    // positive outside of the half-plane
+    let side = |p: [f64; 2]| (p[0] - middle[0]) * normal[0] + (p[1] - middle[1]) * normal[1];
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let (side_current, side_next) = (side(*current), side(next));
+        if side_current <= 0. {
+            clipped.push(*current);
+        }
+        if (side_current <= 0.) != (side_next <= 0.) {
+            let t = side_current / (side_current - side_next);
+            clipped.push([
+                current[0] + t * (next[0] - current[0]),
+                current[1] + t * (next[1] - current[1]),
+            ]);
+        }
+    }
+    clipped
+}
+
+#[cfg(all(test, feature = "voronoi"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_contain_their_samples() {
+        let set = PoissonDiskSet::new(vec![30., 20.], 1., 30);
+        let cells = set.voronoi();
+        assert_eq!(cells.len(), set.len());
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let query = [
+                rand::Rng::gen_range(&mut rng, 0_f64..30.),
+                rand::Rng::gen_range(&mut rng, 0_f64..20.),
+            ];
+            // exactly the cell of the nearest sample contains the query
+            let (nearest, _) = set.nearest(&query).unwrap();
+            let inside = |cell: &Polygon| {
+                (0..cell.len()).all(|i| {
+                    let (a, b) = (cell[i], cell[(i + 1) % cell.len()]);
+                    (b[0] - a[0]) * (query[1] - a[1]) - (b[1] - a[1]) * (query[0] - a[0]) >= -1e-9
+                })
+            };
+            assert!(inside(&cells[nearest]));
+        }
+    }
+}
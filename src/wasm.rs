@@ -0,0 +1,95 @@
+//! A JavaScript interface for WebAssembly, e.g. to scatter objects
+//! client-side in a web application. Build the module and generate its
+//! JavaScript bindings with the matching version of
+//! [`wasm-bindgen-cli`](https://crates.io/crates/wasm-bindgen-cli):
+//!
+//! ```text
+//! cargo rustc --release --lib --crate-type cdylib --target wasm32-unknown-unknown --features wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/bluenoisers.wasm
+//! ```
+//!
+//! ```text
+//! import init, { blueNoise } from "./pkg/bluenoisers.js";
+//! await init();
+//! // x0, y0, x1, y1, ...
+//! const samples = blueNoise(new Float64Array([64, 64]), 1, 30, 7);
+//! ```
+//!
+//! Samples are returned as flat `Float64Array`s of their coordinates.
+//! Without a seed, the random number generator is seeded from
+//! `crypto.getRandomValues`.
+
+use crate::BlueNoiseConfig;
+use js_sys::Float64Array;
+use wasm_bindgen::prelude::*;
+
+fn config(
+    dimensions: &[f64],
+    min_distance: f64,
+    k_abort: Option<usize>,
+    seed: Option<u32>,
+) -> Result<BlueNoiseConfig, JsError> {
+    if dimensions.is_empty() || dimensions.iter().any(|d| !(d.is_finite() && *d > 0.)) {
+        return Err(JsError::new(
+            "there must be at least one dimension and all sizes must be positive",
+        ));
+    }
+    if !(min_distance.is_finite() && min_distance > 0.) || k_abort == Some(0) {
+        return Err(JsError::new("minDistance and kAbort must be positive"));
+    }
+    let config =
+        BlueNoiseConfig::new(dimensions.to_vec(), min_distance).k_abort(k_abort.unwrap_or(30));
+    Ok(match seed {
+        Some(seed) => config.seed(seed.into()),
+        None => config,
+    })
+}
+
+/// Generates blue noise samples in a domain of size `dimensions`, with the
+/// coordinates of all samples one after the other. `kAbort` defaults to 30.
+/// The same seed yields the same samples as in Rust.
+///
+/// Throws if there are no dimensions or a size, `minDistance` or `kAbort`
+/// is not positive.
+#[wasm_bindgen(js_name = blueNoise)]
+pub fn blue_noise(
+    dimensions: &[f64],
+    #[wasm_bindgen(js_name = minDistance)] min_distance: f64,
+    #[wasm_bindgen(js_name = kAbort)] k_abort: Option<usize>,
+    seed: Option<u32>,
+) -> Result<Float64Array, JsError> {
+    let samples = config(dimensions, min_distance, k_abort, seed)?.generate();
+    let coordinates: Vec<f64> = samples.iter().flatten().copied().collect();
+    Ok(Float64Array::from(&coordinates[..]))
+}
+
+/// Generates the samples of [`blue_noise`] one by one, e.g. to spread the
+/// work over several frames.
+#[wasm_bindgen]
+pub struct BlueNoiseIterator {
+    inner: crate::BlueNoiseIterator,
+}
+
+#[wasm_bindgen]
+impl BlueNoiseIterator {
+    /// Takes the arguments of [`blue_noise`] and throws for the same ones.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        dimensions: &[f64],
+        #[wasm_bindgen(js_name = minDistance)] min_distance: f64,
+        #[wasm_bindgen(js_name = kAbort)] k_abort: Option<usize>,
+        seed: Option<u32>,
+    ) -> Result<BlueNoiseIterator, JsError> {
+        let config = config(dimensions, min_distance, k_abort, seed)?;
+        Ok(BlueNoiseIterator {
+            inner: config.iter(),
+        })
+    }
+
+    /// Generates the coordinates of the next sample, or `undefined` when all
+    /// samples have been generated.
+    #[wasm_bindgen(js_name = nextSample)]
+    pub fn next_sample(&mut self) -> Option<Float64Array> {
+        self.inner.next_ref().map(Float64Array::from)
+    }
+}